@@ -14,7 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{difficulty::bitcoin_retarget, error::ConsensusError, memory_pool::MemoryPool, MerkleTreeLedger};
+use crate::{
+    difficulty::bitcoin_retarget,
+    error::ConsensusError,
+    memory_pool::{Entry, MemoryPool, SelectionPolicy},
+    MerkleTreeLedger,
+};
 use snarkos_profiler::{end_timer, start_timer};
 use snarkos_storage::BlockPath;
 use snarkvm_algorithms::{CRH, SNARK};
@@ -35,11 +40,13 @@ use snarkvm_dpc::{
     DPCComponents,
     DPCScheme,
     Program,
+    Record,
 };
 use snarkvm_objects::{
     dpc::DPCTransactions,
     AleoAmount,
     Block,
+    BlockError,
     BlockHeader,
     BlockHeaderHash,
     LedgerScheme,
@@ -52,9 +59,16 @@ use snarkvm_utilities::{to_bytes, FromBytes, ToBytes};
 
 use chrono::Utc;
 use rand::Rng;
+use std::collections::HashSet;
 
 pub const TWO_HOURS_UNIX: i64 = 7200;
 
+/// The default maximum number of seconds a block's timestamp may be ahead of the local time.
+pub const MAX_FUTURE_BLOCK_TIME: i64 = TWO_HOURS_UNIX;
+
+/// The maximum number of stored orphans connected to the canon chain in a single pass.
+pub const MAX_ORPHANS_PER_PASS: usize = 50;
+
 /// Calculate a block reward that halves every 4 years * 365 days * 24 hours * 100 blocks/hr = 3,504,000 blocks.
 pub fn get_block_reward(block_num: u32) -> AleoAmount {
     let expected_blocks_per_hour: u32 = 100;
@@ -78,10 +92,16 @@ pub struct ConsensusParameters {
     pub network_id: Network,
     /// The maximum permitted block size (in bytes).
     pub max_block_size: usize,
+    /// The soft target for the size of mined blocks (in bytes); it can't exceed `max_block_size`.
+    pub block_size_target: usize,
+    /// The policy for selecting the memory pool transactions included in mined blocks.
+    pub selection_policy: SelectionPolicy,
     /// The maximum permitted nonce value.
     pub max_nonce: u32,
     /// The anticipated number of seconds for finding a new block.
     pub target_block_time: i64,
+    /// The maximum number of seconds a block's timestamp may be ahead of the local time.
+    pub max_future_block_time: i64,
     /// The PoSW consensus verifier (read-only mode, no proving key loaded).
     pub verifier: PoswMarlin,
     /// The authorized inner SNARK IDs.
@@ -99,6 +119,23 @@ impl ConsensusParameters {
         )
     }
 
+    /// Returns the size (in bytes) that newly mined blocks should not exceed.
+    pub fn block_size_target(&self) -> usize {
+        std::cmp::min(self.block_size_target, self.max_block_size)
+    }
+
+    /// Checks that the timestamp of the given block header isn't too far ahead of the local time;
+    /// this doesn't depend on the parent, so it also applies to orphans.
+    pub fn verify_timestamp(&self, header: &BlockHeader) -> Result<(), ConsensusError> {
+        let future_timelimit = Utc::now().timestamp() + self.max_future_block_time;
+
+        if header.time > future_timelimit {
+            Err(ConsensusError::FuturisticTimestamp(future_timelimit, header.time))
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn is_genesis(block_header: &BlockHeader) -> bool {
         block_header.previous_block_hash == BlockHeaderHash([0u8; 32])
     }
@@ -106,7 +143,7 @@ impl ConsensusParameters {
     /// Verify all fields in a block header.
     /// 1. The parent hash points to the tip of the chain.
     /// 2. Transactions hash to merkle root.
-    /// 3. The timestamp is less than 2 hours into the future.
+    /// 3. The timestamp is at most `max_future_block_time` seconds into the future.
     /// 4. The timestamp is greater than parent timestamp.
     /// 5. The header is greater than or equal to target difficulty.
     /// 6. The nonce is within the limit.
@@ -119,8 +156,6 @@ impl ConsensusParameters {
     ) -> Result<(), ConsensusError> {
         let hash_result = header.to_difficulty_hash();
 
-        let now = Utc::now().timestamp();
-        let future_timelimit: i64 = now + TWO_HOURS_UNIX;
         let expected_difficulty = self.get_block_difficulty(parent_header, header.time);
 
         if parent_header.get_hash() != header.previous_block_hash {
@@ -134,8 +169,8 @@ impl ConsensusParameters {
             return Err(ConsensusError::PedersenMerkleRoot(
                 header.pedersen_merkle_root_hash.to_string(),
             ));
-        } else if header.time > future_timelimit {
-            return Err(ConsensusError::FuturisticTimestamp(future_timelimit, header.time));
+        } else if let Err(e) = self.verify_timestamp(header) {
+            return Err(e);
         } else if header.time < parent_header.time {
             return Err(ConsensusError::TimestampInvalid(header.time, parent_header.time));
         } else if hash_result > header.difficulty_target {
@@ -311,12 +346,9 @@ impl ConsensusParameters {
                     self.process_block(parameters, &storage, memory_pool, block)?;
 
                     // Attempt to fast forward the block state if the node already stores
-                    // the children of the new canon block.
-                    let (_, child_path) = storage.longest_child_path(block.header.get_hash())?;
-                    for child_block_hash in child_path {
-                        let new_block = storage.get_block(&child_block_hash)?;
-                        self.process_block(parameters, &storage, memory_pool, &new_block)?;
-                    }
+                    // the children of the new canon block; any remaining ones are left
+                    // for subsequent calls to `resolve_orphans`.
+                    self.resolve_orphans(parameters, storage, memory_pool, MAX_ORPHANS_PER_PASS)?;
                 }
                 BlockPath::SideChain(side_chain_path) => {
                     debug!(
@@ -324,15 +356,25 @@ impl ConsensusParameters {
                         side_chain_path.new_block_number
                     );
 
-                    // If the side chain is now longer than the canon chain,
+                    // If the side chain now carries more work than the canon chain,
                     // perform a fork to the side chain.
-                    if side_chain_path.new_block_number > storage.get_current_block_height() {
+                    let side_chain_work = self.side_chain_work(storage, &side_chain_path.path, block)?;
+                    let canon_chain_work = self.canon_chain_work(storage, side_chain_path.shared_block_number)?;
+
+                    if side_chain_work > canon_chain_work {
                         debug!(
-                            "Determined side chain is longer than canon chain by {} blocks",
-                            side_chain_path.new_block_number - storage.get_current_block_height()
+                            "Determined side chain has more work than canon chain ({} > {})",
+                            side_chain_work, canon_chain_work
                         );
                         warn!("A valid fork has been detected. Performing a fork to the side chain.");
 
+                        // Collect the canon blocks that are about to be reverted
+                        let current_block_height = storage.get_current_block_height();
+                        let mut reverted_blocks = vec![];
+                        for block_height in (side_chain_path.shared_block_number + 1)..=current_block_height {
+                            reverted_blocks.push(storage.get_block(&storage.get_block_hash(block_height)?)?);
+                        }
+
                         // Fork to superior side chain
                         storage.revert_for_fork(&side_chain_path)?;
 
@@ -346,8 +388,27 @@ impl ConsensusParameters {
                                 }
                             }
                         }
+
+                        // Return the transactions of the reverted blocks to the memory pool;
+                        // the ones already included in the new canon chain are rejected.
+                        for reverted_block in reverted_blocks {
+                            for transaction in reverted_block.transactions.0 {
+                                if transaction.value_balance.is_negative() {
+                                    continue;
+                                }
+
+                                let entry = Entry {
+                                    size_in_bytes: to_bytes![transaction]?.len(),
+                                    transaction,
+                                };
+
+                                if let Err(error) = memory_pool.insert(storage, entry) {
+                                    debug!("Failed to return a reverted transaction to the memory pool: {}", error);
+                                }
+                            }
+                        }
                     } else {
-                        // If the sidechain is not longer than the main canon chain, simply store the block
+                        // If the sidechain doesn't have more work than the main canon chain, simply store the block
                         storage.insert_only(block)?;
                     }
                 }
@@ -357,6 +418,130 @@ impl ConsensusParameters {
         Ok(())
     }
 
+    /// Connects up to `max_count` stored orphans that extend the canon chain, following its
+    /// longest stored continuation; returns the number of such orphans that remain to be connected.
+    pub fn resolve_orphans(
+        &self,
+        parameters: &PublicParameters<Components>,
+        storage: &MerkleTreeLedger,
+        memory_pool: &mut MemoryPool<Tx>,
+        max_count: usize,
+    ) -> Result<usize, ConsensusError> {
+        let canon_hash = storage.get_block_hash(storage.get_current_block_height())?;
+
+        // The first block of the path is the current canon block itself.
+        let (_, child_path) = storage.longest_child_path(canon_hash)?;
+        let orphans = child_path.len().saturating_sub(1);
+
+        for child_block_hash in child_path.iter().skip(1).take(max_count) {
+            let new_block = storage.get_block(child_block_hash)?;
+            self.process_block(parameters, storage, memory_pool, &new_block)?;
+        }
+
+        Ok(orphans.saturating_sub(max_count))
+    }
+
+    /// Returns the commitments of the records that are unspent as of the given block height.
+    ///
+    /// Serial numbers can't be linked to commitments without the owner's private key, so a record
+    /// is only considered spent if it is stored on this node and owned by the given account;
+    /// all the other commitments created up to the given height are reported as unspent.
+    ///
+    /// This walks every block up to the given height and derives a serial number for every
+    /// matching record, so its cost grows linearly with the length of the chain.
+    pub fn record_set_at_height(
+        &self,
+        parameters: &PublicParameters<Components>,
+        storage: &MerkleTreeLedger,
+        private_key: &AccountPrivateKey<Components>,
+        height: u32,
+    ) -> Result<Vec<Vec<u8>>, ConsensusError> {
+        if height > storage.get_current_block_height() {
+            return Err(BlockError::InvalidBlockNumber(height).into());
+        }
+
+        let address = AccountAddress::<Components>::from_private_key(
+            parameters.account_signature_parameters(),
+            parameters.account_commitment_parameters(),
+            parameters.account_encryption_parameters(),
+            private_key,
+        )?;
+
+        let mut created_commitments = vec![];
+        let mut serial_numbers = HashSet::new();
+
+        for block_height in 0..=height {
+            let block_hash = storage.get_block_hash(block_height)?;
+
+            for transaction in storage.get_block_transactions(&block_hash)?.0 {
+                for commitment in transaction.new_commitments() {
+                    created_commitments.push(to_bytes![commitment]?);
+                }
+
+                for serial_number in transaction.old_serial_numbers() {
+                    serial_numbers.insert(to_bytes![serial_number]?);
+                }
+            }
+        }
+
+        let mut record_set = Vec::with_capacity(created_commitments.len());
+        for commitment in created_commitments {
+            if let Some(record) = storage.get_record::<DPCRecord<Components>>(&commitment)? {
+                if record.owner() == &address {
+                    let (serial_number, _) =
+                        InstantiatedDPC::generate_sn(&parameters.system_parameters, &record, private_key)?;
+
+                    if serial_numbers.contains(&to_bytes![serial_number]?) {
+                        continue;
+                    }
+                }
+            }
+
+            record_set.push(commitment);
+        }
+
+        Ok(record_set)
+    }
+
+    /// Returns the amount of work represented by the given block header; the lower
+    /// the difficulty target, the more work is required to mine the block.
+    pub fn block_work(header: &BlockHeader) -> u128 {
+        u64::MAX as u128 / (header.difficulty_target as u128 + 1)
+    }
+
+    /// Returns the total work of the canon blocks above the given height.
+    fn canon_chain_work(&self, storage: &MerkleTreeLedger, shared_block_number: u32) -> Result<u128, ConsensusError> {
+        let mut work = 0u128;
+        for block_height in (shared_block_number + 1)..=storage.get_current_block_height() {
+            let header = storage.get_block_header(&storage.get_block_hash(block_height)?)?;
+            work = work.saturating_add(Self::block_work(&header));
+        }
+
+        Ok(work)
+    }
+
+    /// Returns the total work of the side chain blocks in the given path, which ends with the given block.
+    fn side_chain_work(
+        &self,
+        storage: &MerkleTreeLedger,
+        path: &[BlockHeaderHash],
+        block: &Block<Tx>,
+    ) -> Result<u128, ConsensusError> {
+        let block_hash = block.header.get_hash();
+
+        let mut work = 0u128;
+        for hash in path {
+            let block_work = if *hash == block_hash {
+                Self::block_work(&block.header)
+            } else {
+                Self::block_work(&storage.get_block_header(hash)?)
+            };
+            work = work.saturating_add(block_work);
+        }
+
+        Ok(work)
+    }
+
     /// Generate a coinbase transaction given candidate block transactions
     #[allow(clippy::too_many_arguments)]
     pub fn create_coinbase_transaction<R: Rng>(
@@ -597,6 +782,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_block_work() {
+        let h1 = DATA.block_1.header.clone();
+
+        let mut h2 = h1.clone();
+        h2.difficulty_target /= 2;
+
+        assert!(ConsensusParameters::block_work(&h2) > ConsensusParameters::block_work(&h1));
+        assert_eq!(
+            ConsensusParameters::block_work(&BlockHeader {
+                difficulty_target: u64::MAX,
+                ..h1
+            }),
+            0
+        );
+    }
+
     #[test]
     fn verify_header() {
         // mine a PoSW proof
@@ -604,8 +806,11 @@ mod tests {
 
         let consensus: ConsensusParameters = ConsensusParameters {
             max_block_size: 1_000_000usize,
+            block_size_target: 1_000_000usize,
+            selection_policy: SelectionPolicy::default(),
             max_nonce: std::u32::MAX - 1,
             target_block_time: 2i64, //unix seconds
+            max_future_block_time: MAX_FUTURE_BLOCK_TIME,
             network_id: Network::Mainnet,
             verifier: posw,
             authorized_inner_snark_ids: vec![],
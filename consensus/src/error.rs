@@ -16,7 +16,7 @@
 
 use snarkos_storage::error::StorageError;
 use snarkvm_algorithms::errors::CRHError;
-use snarkvm_dpc::DPCError;
+use snarkvm_dpc::{AccountError, DPCError};
 use snarkvm_objects::{BlockError, TransactionError};
 use snarkvm_posw::error::PoswError;
 
@@ -25,6 +25,9 @@ use std::fmt::Debug;
 /// Possible block verification errors
 #[derive(Debug, Error)]
 pub enum ConsensusError {
+    #[error("{}", _0)]
+    AccountError(AccountError),
+
     #[error("UTXO has already been spent {:?} index: {:?}", _0, _1)]
     AlreadySpent(Vec<u8>, u32),
 
@@ -52,7 +55,7 @@ pub enum ConsensusError {
     #[error("{}", _0)]
     DPCError(DPCError),
 
-    #[error("timestamp more than 2 hours into the future {:?} actual {:?}", _0, _1)]
+    #[error("timestamp too far into the future; limit {:?} actual {:?}", _0, _1)]
     FuturisticTimestamp(i64, i64),
 
     #[error("invalid block {:?}", _0)]
@@ -107,6 +110,27 @@ pub enum ConsensusError {
     PreExistingBlock,
 }
 
+impl ConsensusError {
+    /// Returns `true` if the error means that the processed block is invalid, as opposed to it
+    /// being already known or the node failing to process it on its own end.
+    pub fn is_invalid_block(&self) -> bool {
+        !matches!(
+            self,
+            ConsensusError::PreExistingBlock
+                | ConsensusError::StorageError(_)
+                | ConsensusError::Crate(..)
+                | ConsensusError::Message(_)
+                | ConsensusError::NoGenesisBlock
+        )
+    }
+}
+
+impl From<AccountError> for ConsensusError {
+    fn from(error: AccountError) -> Self {
+        ConsensusError::AccountError(error)
+    }
+}
+
 impl From<BlockError> for ConsensusError {
     fn from(error: BlockError) -> Self {
         ConsensusError::BlockError(error)
@@ -52,7 +52,7 @@ pub mod miner;
 pub use miner::Miner;
 
 pub mod memory_pool;
-pub use memory_pool::MemoryPool;
+pub use memory_pool::{MemoryPool, SelectionPolicy};
 
 use snarkos_storage::Ledger;
 use snarkvm_dpc::base_dpc::instantiated::{CommitmentMerkleParameters, Tx};
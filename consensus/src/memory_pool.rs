@@ -28,15 +28,91 @@ use snarkvm_utilities::{
     to_bytes,
 };
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// The default maximum size of the memory pool in bytes.
+pub const DEFAULT_MAX_MEMORY_POOL_SIZE: usize = 300_000_000;
+
+/// The number of registered record spends beyond which the ones not used by the memory pool entries are pruned.
+pub const MAX_RECORD_SPENDS: usize = 10_000;
+
+/// Returns the fee paid by the given transaction, i.e. its value balance.
+#[inline]
+pub fn transaction_fee<T: Transaction>(transaction: &T) -> i64 {
+    transaction.value_balance().0
+}
+
+/// The policies for selecting the memory pool transactions that are included in a new block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionPolicy {
+    /// The transactions paying the highest fees come first, which maximizes the revenue of the miner.
+    HighestFee,
+    /// The transactions are selected in the order in which they entered the memory pool.
+    Fifo,
+    /// The transactions are selected in the order in which they entered the memory pool, except that
+    /// a transaction is never placed before the ones it depends on.
+    DependencyOrdered,
+}
+
+impl SelectionPolicy {
+    /// Returns the name of the policy, as used in the configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HighestFee => "highest_fee",
+            Self::Fifo => "fifo",
+            Self::DependencyOrdered => "dependency_ordered",
+        }
+    }
+}
+
+impl Default for SelectionPolicy {
+    fn default() -> Self {
+        Self::HighestFee
+    }
+}
+
+impl fmt::Display for SelectionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for SelectionPolicy {
+    type Err = ConsensusError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "highest_fee" => Ok(Self::HighestFee),
+            "fifo" => Ok(Self::Fifo),
+            "dependency_ordered" => Ok(Self::DependencyOrdered),
+            _ => Err(ConsensusError::Message(format!(
+                "unknown transaction selection policy: {}",
+                name
+            ))),
+        }
+    }
+}
 
 /// Stores a transaction and it's size in the memory pool.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Entry<T: Transaction> {
     pub size_in_bytes: usize,
     pub transaction: T,
 }
 
+impl<T: Transaction> Entry<T> {
+    /// Returns the fee paid by the transaction, i.e. its value balance.
+    #[inline]
+    pub fn fee(&self) -> i64 {
+        transaction_fee(&self.transaction)
+    }
+}
+
 /// Stores transactions received by the server.
 /// Transaction entries will eventually be fetched by the miner and assembled into blocks.
 #[derive(Debug, Clone)]
@@ -45,6 +121,19 @@ pub struct MemoryPool<T: Transaction> {
     pub transactions: HashMap<Vec<u8>, Entry<T>>,
     /// The total size in bytes of the current memory pool.
     pub total_size_in_bytes: usize,
+    /// The maximum size in bytes of the memory pool; the lowest-fee entries are evicted beyond it.
+    pub max_size_in_bytes: usize,
+    /// The mapping of unconfirmed transaction IDs to the order in which they were inserted.
+    insertion_order: HashMap<Vec<u8>, u64>,
+    /// The order that will be assigned to the next inserted transaction.
+    next_insertion: u64,
+    /// The mapping of unconfirmed transaction IDs to the time at which they were inserted.
+    insertion_times: HashMap<Vec<u8>, Instant>,
+    /// The unconfirmed transaction IDs ordered by fee, and then by insertion order.
+    fee_index: BTreeSet<(i64, u64, Vec<u8>)>,
+    /// The mapping of serial numbers to the commitments of the records they spend; a serial number can't be
+    /// linked to its record without the key of the record's owner, so only the registered spends are known.
+    record_spends: HashMap<Vec<u8>, Vec<u8>>,
 }
 
 const BLOCK_HEADER_SIZE: usize = BlockHeader::size();
@@ -57,8 +146,25 @@ impl<T: Transaction> MemoryPool<T> {
         Self::default()
     }
 
-    /// Load the memory pool from previously stored state in storage
-    pub fn from_storage<P: LoadableMerkleParameters>(storage: &Ledger<T, P>) -> Result<Self, ConsensusError> {
+    /// Sets the maximum size of the memory pool in bytes, evicting the lowest-fee entries if it's exceeded.
+    pub fn with_max_size(mut self, max_size_in_bytes: usize) -> Self {
+        self.max_size_in_bytes = max_size_in_bytes;
+
+        while self.total_size_in_bytes > self.max_size_in_bytes {
+            match self.lowest_fee_entry() {
+                Some(transaction_id) => {
+                    self.remove_by_hash(&transaction_id).ok();
+                }
+                None => break,
+            }
+        }
+
+        self
+    }
+
+    /// Load the memory pool from previously stored state in storage; every stored transaction
+    /// is re-validated against the current ledger, and the ones that now conflict are dropped.
+    pub fn load<P: LoadableMerkleParameters>(storage: &Ledger<T, P>) -> Result<Self, ConsensusError> {
         let mut memory_pool = Self::new();
 
         if let Ok(serialized_transactions) = storage.get_memory_pool() {
@@ -69,7 +175,9 @@ impl<T: Transaction> MemoryPool<T> {
                         transaction,
                         size_in_bytes: size,
                     };
-                    memory_pool.insert(storage, entry)?;
+                    if let Err(error) = memory_pool.insert(storage, entry) {
+                        debug!("Dropping a stored memory pool transaction: {}", error);
+                    }
                 }
             }
         }
@@ -110,6 +218,11 @@ impl<T: Transaction> MemoryPool<T> {
             return Ok(None);
         }
 
+        // An entry that can't fit even in an empty memory pool is rejected before anything is evicted.
+        if entry.size_in_bytes > self.max_size_in_bytes {
+            return Ok(None);
+        }
+
         let mut holding_serial_numbers = vec![];
         let mut holding_commitments = vec![];
         let mut holding_memos = Vec::with_capacity(self.transactions.len());
@@ -136,38 +249,99 @@ impl<T: Transaction> MemoryPool<T> {
             return Ok(None);
         }
 
+        // Pick the lowest-fee entries that need to be evicted to make room for the new one; nothing
+        // is evicted unless the new entry pays at least as much as each of them.
+        let fee = entry.fee();
+        let required_size = (self.total_size_in_bytes + entry.size_in_bytes).saturating_sub(self.max_size_in_bytes);
+        let mut evicted_size = 0;
+        let mut evicted = vec![];
+        for (evicted_fee, _, transaction_id) in &self.fee_index {
+            if evicted_size >= required_size {
+                break;
+            }
+            if fee < *evicted_fee {
+                return Ok(None);
+            }
+            evicted_size += self.transactions[transaction_id].size_in_bytes;
+            evicted.push(transaction_id.clone());
+        }
+
+        for transaction_id in evicted {
+            self.remove_by_hash(&transaction_id)?;
+        }
+
         let transaction_id = entry.transaction.transaction_id()?.to_vec();
 
         self.total_size_in_bytes += entry.size_in_bytes;
         self.transactions.insert(transaction_id.clone(), entry);
+        self.insertion_order.insert(transaction_id.clone(), self.next_insertion);
+        self.insertion_times.insert(transaction_id.clone(), Instant::now());
+        self.fee_index
+            .insert((fee, self.next_insertion, transaction_id.clone()));
+        self.next_insertion += 1;
 
         Ok(Some(transaction_id))
     }
 
+    /// Returns the id of the entry paying the lowest fee; the oldest one is chosen among equal fees.
+    fn lowest_fee_entry(&self) -> Option<Vec<u8>> {
+        self.fee_index
+            .iter()
+            .next()
+            .map(|(_, _, transaction_id)| transaction_id.clone())
+    }
+
     /// Cleanse the memory pool of outdated transactions.
     #[inline]
     pub fn cleanse<P: LoadableMerkleParameters>(&mut self, storage: &Ledger<T, P>) -> Result<(), ConsensusError> {
-        let mut new_memory_pool = Self::new();
+        let mut new_memory_pool = Self::new().with_max_size(self.max_size_in_bytes);
 
-        for (_, entry) in self.clone().transactions.iter() {
+        // Re-insert the entries in their original order, so that it's retained for evictions.
+        let mut entries: Vec<_> = self.transactions.iter().collect();
+        entries.sort_by_key(|(transaction_id, _)| self.insertion_order.get(*transaction_id));
+
+        for (transaction_id, entry) in entries {
             new_memory_pool.insert(&storage, entry.clone())?;
+
+            // Re-validation doesn't make a transaction any younger.
+            if let Some(inserted_at) = self.insertion_times.get(transaction_id) {
+                if let Some(new_inserted_at) = new_memory_pool.insertion_times.get_mut(transaction_id) {
+                    *new_inserted_at = *inserted_at;
+                }
+            }
         }
 
-        self.total_size_in_bytes = new_memory_pool.total_size_in_bytes;
-        self.transactions = new_memory_pool.transactions;
+        *self = new_memory_pool;
 
         Ok(())
     }
 
+    /// Removes the entries that were inserted more than `max_age` ago, returning their ids.
+    pub fn expire(&mut self, max_age: Duration) -> Vec<Vec<u8>> {
+        self.expire_at(max_age, Instant::now())
+    }
+
+    fn expire_at(&mut self, max_age: Duration, now: Instant) -> Vec<Vec<u8>> {
+        let expired: Vec<_> = self
+            .insertion_times
+            .iter()
+            .filter(|(_, inserted_at)| now.saturating_duration_since(**inserted_at) > max_age)
+            .map(|(transaction_id, _)| transaction_id.clone())
+            .collect();
+
+        for transaction_id in &expired {
+            self.remove_by_hash(transaction_id).ok();
+        }
+
+        expired
+    }
+
     /// Removes transaction from memory pool or error.
     #[inline]
     pub fn remove(&mut self, entry: &Entry<T>) -> Result<Option<Vec<u8>>, ConsensusError> {
         if self.contains(entry) {
-            self.total_size_in_bytes -= entry.size_in_bytes;
-
             let transaction_id = entry.transaction.transaction_id()?.to_vec();
-
-            self.transactions.remove(&transaction_id);
+            self.remove_by_hash(&transaction_id)?;
 
             return Ok(Some(transaction_id));
         }
@@ -178,12 +352,15 @@ impl<T: Transaction> MemoryPool<T> {
     /// Removes transaction from memory pool based on the transaction id.
     #[inline]
     pub fn remove_by_hash(&mut self, transaction_id: &[u8]) -> Result<Option<Entry<T>>, ConsensusError> {
-        match self.transactions.clone().get(transaction_id) {
+        match self.transactions.remove(transaction_id) {
             Some(entry) => {
                 self.total_size_in_bytes -= entry.size_in_bytes;
-                self.transactions.remove(transaction_id);
+                self.insertion_times.remove(transaction_id);
+                if let Some(order) = self.insertion_order.remove(transaction_id) {
+                    self.fee_index.remove(&(entry.fee(), order, transaction_id.to_vec()));
+                }
 
-                Ok(Some(entry.clone()))
+                Ok(Some(entry))
             }
             None => Ok(None),
         }
@@ -198,38 +375,252 @@ impl<T: Transaction> MemoryPool<T> {
         }
     }
 
-    /// Get candidate transactions for a new block.
+    /// Registers that the given serial number spends the record with the given commitment, so that the entry
+    /// spending it can be linked to the one creating the record.
+    pub fn register_record_spend(&mut self, serial_number: Vec<u8>, commitment: Vec<u8>) {
+        if self.record_spends.len() >= MAX_RECORD_SPENDS {
+            let pooled_serial_numbers = self
+                .transactions
+                .values()
+                .flat_map(|entry| entry.transaction.old_serial_numbers())
+                .filter_map(|serial_number| to_bytes![serial_number].ok())
+                .collect::<HashSet<_>>();
+
+            self.record_spends
+                .retain(|serial_number, _| pooled_serial_numbers.contains(serial_number));
+        }
+
+        self.record_spends.insert(serial_number, commitment);
+    }
+
+    /// Returns the mapping of the ids of the entries to the ids of the entries they depend on, i.e. which
+    /// create the records spent by their registered serial numbers; it's built from the current contents.
+    pub fn dependencies(&self) -> HashMap<Vec<u8>, Vec<Vec<u8>>> {
+        let mut creators = HashMap::new();
+        for (transaction_id, entry) in &self.transactions {
+            for commitment in entry.transaction.new_commitments() {
+                if let Ok(commitment) = to_bytes![commitment] {
+                    creators.insert(commitment, transaction_id);
+                }
+            }
+        }
+
+        self.transactions
+            .iter()
+            .map(|(transaction_id, entry)| {
+                let parents = entry
+                    .transaction
+                    .old_serial_numbers()
+                    .iter()
+                    .filter_map(|serial_number| to_bytes![serial_number].ok())
+                    .filter_map(|serial_number| self.record_spends.get(&serial_number))
+                    .filter_map(|commitment| creators.get(commitment))
+                    .filter(|parent_id| **parent_id != transaction_id)
+                    .map(|parent_id| (*parent_id).clone())
+                    .collect();
+
+                (transaction_id.clone(), parents)
+            })
+            .collect()
+    }
+
+    /// Returns the ids of the entries the given one depends on, directly or through other entries, nearest
+    /// first; `None` is returned if the entry isn't in the memory pool.
+    pub fn ancestors(&self, transaction_id: &[u8]) -> Option<Vec<Vec<u8>>> {
+        if !self.transactions.contains_key(transaction_id) {
+            return None;
+        }
+
+        Some(Self::walk_dependencies(transaction_id, &self.dependencies()))
+    }
+
+    /// Returns the ids of the entries that depend on the given one, directly or through other entries,
+    /// nearest first; `None` is returned if the entry isn't in the memory pool.
+    pub fn descendants(&self, transaction_id: &[u8]) -> Option<Vec<Vec<u8>>> {
+        if !self.transactions.contains_key(transaction_id) {
+            return None;
+        }
+
+        Some(Self::walk_dependencies(
+            transaction_id,
+            &Self::invert_dependencies(&self.dependencies()),
+        ))
+    }
+
+    /// Returns the number of entries per fee bucket, as pairs of the lowest fee of a bucket and its count;
+    /// a bucket spans the fees from its bound up to the next one, and only the non-empty buckets are returned.
+    /// The bounds must be ascending; fees below the first bound are counted in the first bucket.
+    pub fn fee_histogram(&self, bucket_bounds: &[u64]) -> Vec<(u64, usize)> {
+        let mut counts = vec![0usize; bucket_bounds.len()];
+
+        for entry in self.transactions.values() {
+            let fee = entry.fee().max(0) as u64;
+            let bucket = bucket_bounds
+                .iter()
+                .rposition(|&bound| bound <= fee)
+                .unwrap_or_default();
+            if let Some(count) = counts.get_mut(bucket) {
+                *count += 1;
+            }
+        }
+
+        bucket_bounds
+            .iter()
+            .copied()
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    /// Inverts the given mapping of entries to the ones they depend on, i.e. maps them to their dependents.
+    fn invert_dependencies(dependencies: &HashMap<Vec<u8>, Vec<Vec<u8>>>) -> HashMap<Vec<u8>, Vec<Vec<u8>>> {
+        let mut dependents: HashMap<Vec<u8>, Vec<Vec<u8>>> = HashMap::new();
+        for (transaction_id, parents) in dependencies {
+            for parent in parents {
+                dependents
+                    .entry(parent.clone())
+                    .or_default()
+                    .push(transaction_id.clone());
+            }
+        }
+
+        dependents
+    }
+
+    /// Returns the ids reachable from the given one in the given dependency mapping, in breadth-first order.
+    fn walk_dependencies(transaction_id: &[u8], edges: &HashMap<Vec<u8>, Vec<Vec<u8>>>) -> Vec<Vec<u8>> {
+        let mut visited = HashSet::new();
+        visited.insert(transaction_id.to_vec());
+
+        let mut related = vec![];
+        let mut queue = VecDeque::new();
+        queue.push_back(transaction_id.to_vec());
+
+        while let Some(current) = queue.pop_front() {
+            for next in edges.get(&current).into_iter().flatten() {
+                if visited.insert(next.clone()) {
+                    related.push(next.clone());
+                    queue.push_back(next.clone());
+                }
+            }
+        }
+
+        related
+    }
+
+    /// Get candidate transactions for a new block, using the default selection policy.
     pub fn get_candidates<P: LoadableMerkleParameters>(
         &self,
         storage: &Ledger<T, P>,
         max_size: usize,
     ) -> Result<DPCTransactions<T>, ConsensusError> {
-        let max_size = max_size - (BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE);
+        self.get_candidates_with_policy(storage, max_size, SelectionPolicy::default())
+    }
+
+    /// Get candidate transactions for a new block, in the order determined by the given selection policy.
+    pub fn get_candidates_with_policy<P: LoadableMerkleParameters>(
+        &self,
+        storage: &Ledger<T, P>,
+        max_size: usize,
+        policy: SelectionPolicy,
+    ) -> Result<DPCTransactions<T>, ConsensusError> {
+        let max_size = max_size.saturating_sub(BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE);
+
+        // Ties are always resolved in favor of the older entries.
+        let mut entries: Vec<_> = self.transactions.iter().collect();
+        entries.sort_by_key(|(transaction_id, _)| self.insertion_order.get(*transaction_id));
+
+        let dependencies = match policy {
+            SelectionPolicy::HighestFee => {
+                entries.sort_by(|(_, entry_a), (_, entry_b)| entry_b.fee().cmp(&entry_a.fee()));
+                None
+            }
+            SelectionPolicy::Fifo => None,
+            SelectionPolicy::DependencyOrdered => {
+                let dependencies = self.dependencies();
+                entries = Self::order_by_dependencies(entries, &dependencies);
+                Some(dependencies)
+            }
+        };
 
         let mut block_size = 0;
         let mut transactions = DPCTransactions::new();
+        let mut selected = HashSet::new();
 
-        // TODO Change naive transaction selection
-        for (_transaction_id, entry) in self.transactions.iter() {
-            if block_size + entry.size_in_bytes <= max_size {
-                if storage.transaction_conflicts(&entry.transaction) || transactions.conflicts(&entry.transaction) {
+        for (transaction_id, entry) in entries {
+            if block_size + entry.size_in_bytes > max_size {
+                continue;
+            }
+
+            if storage.transaction_conflicts(&entry.transaction) || transactions.conflicts(&entry.transaction) {
+                continue;
+            }
+
+            // A transaction can't be included without the ones it depends on.
+            if let Some(parents) = dependencies
+                .as_ref()
+                .and_then(|dependencies| dependencies.get(transaction_id))
+            {
+                if !parents.iter().all(|parent| selected.contains(parent)) {
                     continue;
                 }
-
-                block_size += entry.size_in_bytes;
-                transactions.push(entry.transaction.clone());
             }
+
+            block_size += entry.size_in_bytes;
+            transactions.push(entry.transaction.clone());
+            selected.insert(transaction_id);
         }
 
         Ok(transactions)
     }
+
+    /// Orders the given entries so that each one is placed as soon as all the entries it depends on were
+    /// placed, retaining their relative order otherwise; the ones in a dependency cycle can never be valid,
+    /// so they are left out.
+    fn order_by_dependencies<'a>(
+        mut entries: Vec<(&'a Vec<u8>, &'a Entry<T>)>,
+        dependencies: &HashMap<Vec<u8>, Vec<Vec<u8>>>,
+    ) -> Vec<(&'a Vec<u8>, &'a Entry<T>)> {
+        let mut placed = HashSet::new();
+        let mut ordered = Vec::with_capacity(entries.len());
+
+        while !entries.is_empty() {
+            let remaining = entries.len();
+            entries.retain(|(transaction_id, entry)| {
+                let parents = dependencies
+                    .get(*transaction_id)
+                    .map(|parents| &parents[..])
+                    .unwrap_or(&[]);
+
+                if parents.iter().all(|parent| placed.contains(parent)) {
+                    placed.insert(*transaction_id);
+                    ordered.push((*transaction_id, *entry));
+                    false
+                } else {
+                    true
+                }
+            });
+
+            if entries.len() == remaining {
+                break;
+            }
+        }
+
+        ordered
+    }
 }
 
 impl<T: Transaction> Default for MemoryPool<T> {
     fn default() -> Self {
         Self {
             total_size_in_bytes: 0,
+            max_size_in_bytes: DEFAULT_MAX_MEMORY_POOL_SIZE,
             transactions: HashMap::<Vec<u8>, Entry<T>>::new(),
+            insertion_order: HashMap::new(),
+            next_insertion: 0,
+            insertion_times: HashMap::new(),
+            fee_index: BTreeSet::new(),
+            record_spends: HashMap::new(),
         }
     }
 }
@@ -239,10 +630,40 @@ mod tests {
     use super::*;
     use snarkos_testing::{consensus::*, storage::*};
     use snarkvm_dpc::base_dpc::instantiated::Tx;
-    use snarkvm_objects::Block;
+    use snarkvm_objects::{AleoAmount, Block};
 
     // MemoryPool tests use TRANSACTION_2 because memory pools shouldn't store coinbase transactions
 
+    /// Returns a variant of TRANSACTION_2 paying the given fee that doesn't conflict with
+    /// the variants made from other donors, as it borrows the donor's serial numbers,
+    /// commitments and memorandum.
+    fn transaction_with_fee(donor: &Tx, fee: i64) -> Tx {
+        let mut transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        transaction.old_serial_numbers = donor.old_serial_numbers.clone();
+        transaction.new_commitments = donor.new_commitments.clone();
+        transaction.memorandum = donor.memorandum;
+        transaction.value_balance = AleoAmount(fee);
+        transaction
+    }
+
+    /// Returns entries made from the fixture transactions, paying the given fees.
+    fn entries_with_fees(fees: [i64; 3]) -> Vec<Entry<Tx>> {
+        let donors = [
+            &DATA.block_1.transactions.0[0],
+            &DATA.block_2.transactions.0[0],
+            &DATA.block_2.transactions.0[1],
+        ];
+
+        donors
+            .iter()
+            .zip(fees.iter())
+            .map(|(donor, fee)| Entry {
+                size_in_bytes: 100,
+                transaction: transaction_with_fee(donor, *fee),
+            })
+            .collect()
+    }
+
     #[test]
     fn push() {
         let blockchain = FIXTURE_VK.ledger();
@@ -373,13 +794,49 @@ mod tests {
 
         mem_pool.store(&blockchain).unwrap();
 
-        let new_mem_pool = MemoryPool::from_storage(&blockchain).unwrap();
+        let new_mem_pool = MemoryPool::load(&blockchain).unwrap();
 
         assert_eq!(mem_pool.total_size_in_bytes, new_mem_pool.total_size_in_bytes);
 
         kill_storage_sync(blockchain);
     }
 
+    #[test]
+    fn load_memory_pool_drops_spent_transactions() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        // The first variant borrows the block 1 coinbase commitments, so it conflicts once block 1 is committed.
+        let spent = transaction_with_fee(&DATA.block_1.transactions.0[0], 1);
+        let unspent = Tx::read(&TRANSACTION_2[..]).unwrap();
+
+        let mut mem_pool = MemoryPool::new();
+        for transaction in vec![spent, unspent.clone()] {
+            mem_pool
+                .insert(&blockchain, Entry {
+                    size_in_bytes: TRANSACTION_2.len(),
+                    transaction,
+                })
+                .unwrap()
+                .unwrap();
+        }
+
+        mem_pool.store(&blockchain).unwrap();
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        blockchain.insert_and_commit(&block_1).unwrap();
+
+        let new_mem_pool = MemoryPool::load(&blockchain).unwrap();
+
+        assert_eq!(1, new_mem_pool.transactions.len());
+        assert_eq!(TRANSACTION_2.len(), new_mem_pool.total_size_in_bytes);
+        assert!(new_mem_pool.contains(&Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: unspent,
+        }));
+
+        kill_storage_sync(blockchain);
+    }
+
     #[test]
     fn cleanse_memory_pool() {
         let blockchain = FIXTURE_VK.ledger();
@@ -410,4 +867,314 @@ mod tests {
 
         kill_storage_sync(blockchain);
     }
+
+    #[test]
+    fn evict_lowest_fee_entry() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new().with_max_size(200);
+        let entries = entries_with_fees([1, 3, 2]);
+
+        mem_pool.insert(&blockchain, entries[0].clone()).unwrap();
+        mem_pool.insert(&blockchain, entries[1].clone()).unwrap();
+
+        assert_eq!(200, mem_pool.total_size_in_bytes);
+
+        // The entry paying 2 replaces the one paying 1.
+
+        assert!(mem_pool.insert(&blockchain, entries[2].clone()).unwrap().is_some());
+
+        assert!(!mem_pool.contains(&entries[0]));
+        assert!(mem_pool.contains(&entries[1]));
+        assert!(mem_pool.contains(&entries[2]));
+        assert_eq!(200, mem_pool.total_size_in_bytes);
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn reject_lowest_fee_entry() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new().with_max_size(200);
+        let entries = entries_with_fees([2, 3, 1]);
+
+        mem_pool.insert(&blockchain, entries[0].clone()).unwrap();
+        mem_pool.insert(&blockchain, entries[1].clone()).unwrap();
+
+        // The entry paying 1 is the lowest one, so it's rejected.
+
+        assert!(mem_pool.insert(&blockchain, entries[2].clone()).unwrap().is_none());
+
+        assert!(mem_pool.contains(&entries[0]));
+        assert!(mem_pool.contains(&entries[1]));
+        assert!(!mem_pool.contains(&entries[2]));
+        assert_eq!(200, mem_pool.total_size_in_bytes);
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn reject_entry_outbid_by_one_of_the_evictions() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new().with_max_size(200);
+        let mut entries = entries_with_fees([1, 3, 2]);
+
+        mem_pool.insert(&blockchain, entries[0].clone()).unwrap();
+        mem_pool.insert(&blockchain, entries[1].clone()).unwrap();
+
+        // Making room for the entry paying 2 would evict both entries, including the one paying 3,
+        // so it's rejected and the entry paying 1 isn't evicted either.
+        entries[2].size_in_bytes = 200;
+        assert!(mem_pool.insert(&blockchain, entries[2].clone()).unwrap().is_none());
+
+        assert!(mem_pool.contains(&entries[0]));
+        assert!(mem_pool.contains(&entries[1]));
+        assert_eq!(200, mem_pool.total_size_in_bytes);
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn reject_oversized_entry() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new().with_max_size(200);
+        let mut entries = entries_with_fees([1, 1, 100]);
+
+        mem_pool.insert(&blockchain, entries[0].clone()).unwrap();
+        mem_pool.insert(&blockchain, entries[1].clone()).unwrap();
+
+        // An entry exceeding the maximum size is rejected regardless of its fee, without any evictions.
+        entries[2].size_in_bytes = 201;
+        assert!(mem_pool.insert(&blockchain, entries[2].clone()).unwrap().is_none());
+
+        assert!(mem_pool.contains(&entries[0]));
+        assert!(mem_pool.contains(&entries[1]));
+        assert_eq!(200, mem_pool.total_size_in_bytes);
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn evict_oldest_entry_with_equal_fees() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new().with_max_size(200);
+        let entries = entries_with_fees([1, 1, 1]);
+
+        mem_pool.insert(&blockchain, entries[0].clone()).unwrap();
+        mem_pool.insert(&blockchain, entries[1].clone()).unwrap();
+
+        // All the fees are equal, so the oldest entry is evicted.
+
+        assert!(mem_pool.insert(&blockchain, entries[2].clone()).unwrap().is_some());
+
+        assert!(!mem_pool.contains(&entries[0]));
+        assert!(mem_pool.contains(&entries[1]));
+        assert!(mem_pool.contains(&entries[2]));
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn expire_old_entries() {
+        let blockchain = FIXTURE_VK.ledger();
+        let max_age = Duration::from_secs(60);
+
+        let mut mem_pool = MemoryPool::new();
+        let entries = entries_with_fees([1, 1, 1]);
+
+        let old_transaction_id = mem_pool.insert(&blockchain, entries[0].clone()).unwrap().unwrap();
+        mem_pool.insert(&blockchain, entries[1].clone()).unwrap();
+
+        assert!(mem_pool.expire(max_age).is_empty());
+
+        // Pretend the first entry was inserted well before the second one.
+        let inserted_at = mem_pool.insertion_times.get_mut(&old_transaction_id).unwrap();
+        *inserted_at = Instant::now().checked_sub(max_age * 2).unwrap();
+
+        // Re-validating the entries doesn't reset their age.
+        mem_pool.cleanse(&blockchain).unwrap();
+
+        assert_eq!(vec![old_transaction_id], mem_pool.expire(max_age));
+        assert!(!mem_pool.contains(&entries[0]));
+        assert!(mem_pool.contains(&entries[1]));
+        assert_eq!(100, mem_pool.total_size_in_bytes);
+
+        // Once enough time passes, the remaining entry expires as well.
+        let later = Instant::now() + max_age * 2;
+        assert_eq!(1, mem_pool.expire_at(max_age, later).len());
+        assert!(mem_pool.transactions.is_empty());
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn selection_policies() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        for entry in entries_with_fees([5, 30, 10]) {
+            mem_pool.insert(&blockchain, entry).unwrap();
+        }
+
+        let selected_fees = |policy| {
+            mem_pool
+                .get_candidates_with_policy(&blockchain, 1_000_000, policy)
+                .unwrap()
+                .iter()
+                .map(transaction_fee)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(selected_fees(SelectionPolicy::HighestFee), vec![30, 10, 5]);
+        assert_eq!(selected_fees(SelectionPolicy::Fifo), vec![5, 30, 10]);
+        // Without any dependencies, the entries are selected in their insertion order.
+        assert_eq!(selected_fees(SelectionPolicy::DependencyOrdered), vec![5, 30, 10]);
+
+        // When the block can only fit a single transaction, the policies pick different ones.
+        let max_size = 100 + BLOCK_HEADER_SIZE + COINBASE_TRANSACTION_SIZE;
+        let highest_fee = mem_pool
+            .get_candidates_with_policy(&blockchain, max_size, SelectionPolicy::HighestFee)
+            .unwrap();
+        let fifo = mem_pool
+            .get_candidates_with_policy(&blockchain, max_size, SelectionPolicy::Fifo)
+            .unwrap();
+        assert_eq!(highest_fee.iter().map(transaction_fee).collect::<Vec<_>>(), vec![30]);
+        assert_eq!(fifo.iter().map(transaction_fee).collect::<Vec<_>>(), vec![5]);
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn dependency_ordering_never_places_a_child_before_its_parent() {
+        let entries = entries_with_fees([1, 2, 3]);
+        let ids: Vec<_> = entries
+            .iter()
+            .map(|entry| entry.transaction.transaction_id().unwrap().to_vec())
+            .collect();
+
+        // The first entry depends on the last one.
+        let mut dependencies = HashMap::new();
+        dependencies.insert(ids[0].clone(), vec![ids[2].clone()]);
+
+        let ordered = MemoryPool::<Tx>::order_by_dependencies(ids.iter().zip(entries.iter()).collect(), &dependencies);
+        let ordered_ids: Vec<_> = ordered.into_iter().map(|(id, _)| id.clone()).collect();
+        assert_eq!(ordered_ids, vec![ids[1].clone(), ids[2].clone(), ids[0].clone()]);
+
+        // The entries in a dependency cycle are left out.
+        dependencies.insert(ids[2].clone(), vec![ids[0].clone()]);
+
+        let ordered = MemoryPool::<Tx>::order_by_dependencies(ids.iter().zip(entries.iter()).collect(), &dependencies);
+        let ordered_ids: Vec<_> = ordered.into_iter().map(|(id, _)| id.clone()).collect();
+        assert_eq!(ordered_ids, vec![ids[1].clone()]);
+    }
+
+    #[test]
+    fn selection_policy_names() {
+        for policy in &[
+            SelectionPolicy::HighestFee,
+            SelectionPolicy::Fifo,
+            SelectionPolicy::DependencyOrdered,
+        ] {
+            assert_eq!(policy.name().parse::<SelectionPolicy>().unwrap(), *policy);
+        }
+
+        assert!("lowest_fee".parse::<SelectionPolicy>().is_err());
+    }
+
+    #[test]
+    fn ancestors_and_descendants() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        let entries = entries_with_fees([1, 2, 3]);
+        let ids: Vec<_> = entries
+            .iter()
+            .map(|entry| mem_pool.insert(&blockchain, entry.clone()).unwrap().unwrap())
+            .collect();
+
+        // The fixture transactions don't depend on each other.
+        assert_eq!(mem_pool.ancestors(&ids[0]), Some(vec![]));
+        assert_eq!(mem_pool.descendants(&ids[0]), Some(vec![]));
+        assert_eq!(mem_pool.descendants(&[0u8; 32]), None);
+
+        // A parent with a child, which has a child of its own.
+        let mut dependencies = HashMap::new();
+        dependencies.insert(ids[1].clone(), vec![ids[0].clone()]);
+        dependencies.insert(ids[2].clone(), vec![ids[1].clone()]);
+        let dependents = MemoryPool::<Tx>::invert_dependencies(&dependencies);
+
+        assert_eq!(MemoryPool::<Tx>::walk_dependencies(&ids[0], &dependents), vec![
+            ids[1].clone(),
+            ids[2].clone()
+        ]);
+        assert_eq!(MemoryPool::<Tx>::walk_dependencies(&ids[2], &dependencies), vec![
+            ids[1].clone(),
+            ids[0].clone()
+        ]);
+        assert!(MemoryPool::<Tx>::walk_dependencies(&ids[2], &dependents).is_empty());
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn dependencies_follow_registered_record_spends() {
+        use snarkvm_dpc::Record;
+
+        let blockchain = FIXTURE_VK.ledger();
+
+        // The parent creates the coinbase records of block 1, which the child (TRANSACTION_2) spends.
+        let parent = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: transaction_with_fee(&DATA.block_1.transactions.0[0], 1),
+        };
+        let child = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+        };
+
+        let mut mem_pool = MemoryPool::new();
+        let parent_id = mem_pool.insert(&blockchain, parent).unwrap().unwrap();
+        let child_id = mem_pool.insert(&blockchain, child.clone()).unwrap().unwrap();
+
+        // The serial numbers alone don't reveal the spent records.
+        assert_eq!(mem_pool.descendants(&parent_id), Some(vec![]));
+        assert_eq!(mem_pool.ancestors(&child_id), Some(vec![]));
+
+        for (record, serial_number) in DATA.records_1.iter().zip(child.transaction.old_serial_numbers()) {
+            mem_pool.register_record_spend(
+                to_bytes![serial_number].unwrap(),
+                to_bytes![record.commitment()].unwrap(),
+            );
+        }
+
+        assert_eq!(mem_pool.dependencies()[&child_id], vec![
+            parent_id.clone();
+            DATA.records_1.len()
+        ]);
+        assert_eq!(mem_pool.descendants(&parent_id), Some(vec![child_id.clone()]));
+        assert_eq!(mem_pool.ancestors(&child_id), Some(vec![parent_id]));
+
+        kill_storage_sync(blockchain);
+    }
+
+    #[test]
+    fn fee_histogram() {
+        let blockchain = FIXTURE_VK.ledger();
+
+        let mut mem_pool = MemoryPool::new();
+        assert!(mem_pool.fee_histogram(&[0, 10, 100]).is_empty());
+
+        for entry in entries_with_fees([5, 50, 60]) {
+            mem_pool.insert(&blockchain, entry).unwrap();
+        }
+
+        assert_eq!(mem_pool.fee_histogram(&[0, 10, 100]), vec![(0, 1), (10, 2)]);
+        assert_eq!(mem_pool.fee_histogram(&[10, 55]), vec![(10, 2), (55, 1)]);
+
+        kill_storage_sync(blockchain);
+    }
 }
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{error::ConsensusError, ConsensusParameters, MemoryPool, MerkleTreeLedger};
+use crate::{error::ConsensusError, ConsensusParameters, MemoryPool, MerkleTreeLedger, SelectionPolicy};
 use snarkos_storage::Ledger;
 use snarkvm_algorithms::{traits::LoadableMerkleParameters, CRH};
 use snarkvm_dpc::{
@@ -55,14 +55,25 @@ impl Miner {
         }
     }
 
+    /// Returns the coinbase address that mining rewards are assigned to.
+    pub fn address(&self) -> &AccountAddress<Components> {
+        &self.address
+    }
+
+    /// Changes the coinbase address; only the blocks established afterwards pay out to it.
+    pub fn set_address(&mut self, address: AccountAddress<Components>) {
+        self.address = address;
+    }
+
     /// Fetches new transactions from the memory pool.
     pub async fn fetch_memory_pool_transactions<T: Transaction, P: LoadableMerkleParameters>(
         storage: &Ledger<T, P>,
         memory_pool: &Mutex<MemoryPool<T>>,
         max_size: usize,
+        policy: SelectionPolicy,
     ) -> Result<DPCTransactions<T>, ConsensusError> {
         let memory_pool = memory_pool.lock();
-        Ok(memory_pool.get_candidates(&storage, max_size)?)
+        Ok(memory_pool.get_candidates_with_policy(&storage, max_size, policy)?)
     }
 
     /// Add a coinbase transaction to a list of candidate block transactions
@@ -170,9 +181,13 @@ impl Miner {
         storage: &Arc<MerkleTreeLedger>,
         memory_pool: &Arc<Mutex<MemoryPool<Tx>>>,
     ) -> Result<(Block<Tx>, Vec<DPCRecord<Components>>), ConsensusError> {
-        let candidate_transactions =
-            Self::fetch_memory_pool_transactions(&storage, memory_pool, self.consensus_parameters.max_block_size)
-                .await?;
+        let candidate_transactions = Self::fetch_memory_pool_transactions(
+            &storage,
+            memory_pool,
+            self.consensus_parameters.block_size_target(),
+            self.consensus_parameters.selection_policy,
+        )
+        .await?;
 
         debug!("The miner is creating a block");
 
@@ -156,4 +156,38 @@ mod consensus_dpc {
 
         kill_storage(ledger);
     }
+
+    #[test]
+    fn coinbase_goes_to_the_changed_address() {
+        let parameters = &FIXTURE.parameters;
+        let ledger = FIXTURE.ledger();
+        let [_genesis_address, miner_acc, recipient] = FIXTURE.test_accounts.clone();
+
+        let consensus = Arc::new(TEST_CONSENSUS.clone());
+        let mut miner = Miner::new(miner_acc.address, consensus.clone());
+
+        // The coinbase of the blocks established after the change goes to the new address.
+        miner.set_address(recipient.address.clone());
+
+        let transactions = DPCTransactions::<Tx>::new();
+        let (previous_block_header, transactions, coinbase_records) =
+            miner.establish_block(&parameters, &ledger, &transactions).unwrap();
+        let header = miner.find_block(&transactions, &previous_block_header).unwrap();
+        let block = Block { header, transactions };
+
+        assert!(!coinbase_records[0].is_dummy());
+        assert_eq!(coinbase_records[0].owner(), &recipient.address);
+        assert_eq!(
+            coinbase_records[0].value(),
+            get_block_reward(ledger.len() as u32).0 as u64
+        );
+
+        let mut memory_pool = MemoryPool::new();
+        consensus
+            .receive_block(&parameters, &ledger, &mut memory_pool, &block)
+            .unwrap();
+        assert_eq!(ledger.len(), 2);
+
+        kill_storage(ledger);
+    }
 }
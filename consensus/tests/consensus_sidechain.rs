@@ -15,7 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 mod consensus_sidechain {
-    use snarkos_consensus::MemoryPool;
+    use snarkos_consensus::{ConsensusParameters, MemoryPool};
     use snarkos_testing::{consensus::*, dpc::load_verifying_parameters, storage::*};
     use snarkvm_dpc::base_dpc::instantiated::Tx;
     use snarkvm_objects::Block;
@@ -60,6 +60,60 @@ mod consensus_sidechain {
         kill_storage_sync(blockchain);
     }
 
+    // Receive block 2 as an orphan, and then its parent without connecting the orphan right away.
+    // The orphan should only be connected to the canon chain by the bounded resolution passes.
+    #[test]
+    fn resolve_orphans_in_passes() {
+        let blockchain = FIXTURE_VK.ledger();
+        let parameters = load_verifying_parameters();
+
+        let mut memory_pool = MemoryPool::new();
+
+        let consensus = TEST_CONSENSUS.clone();
+
+        let old_block_height = blockchain.get_current_block_height();
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_2)
+            .unwrap();
+        consensus
+            .process_block(&parameters, &blockchain, &mut memory_pool, &block_1)
+            .unwrap();
+
+        assert_eq!(old_block_height + 1, blockchain.get_current_block_height());
+
+        // An empty pass defers the orphan.
+        assert_eq!(
+            1,
+            consensus
+                .resolve_orphans(&parameters, &blockchain, &mut memory_pool, 0)
+                .unwrap()
+        );
+        assert_eq!(old_block_height + 1, blockchain.get_current_block_height());
+
+        // The next pass connects it.
+        assert_eq!(
+            0,
+            consensus
+                .resolve_orphans(&parameters, &blockchain, &mut memory_pool, 1)
+                .unwrap()
+        );
+        assert_eq!(old_block_height + 2, blockchain.get_current_block_height());
+
+        // Nothing is left for further passes.
+        assert_eq!(
+            0,
+            consensus
+                .resolve_orphans(&parameters, &blockchain, &mut memory_pool, 1)
+                .unwrap()
+        );
+
+        kill_storage_sync(blockchain);
+    }
+
     // Receive two blocks that reference the same parent.
     // Treat the first block received as the canonical chain but store and keep the rejected sidechain block in storage.
     #[test]
@@ -211,4 +265,56 @@ mod consensus_sidechain {
 
         kill_storage_sync(blockchain);
     }
+
+    // Receive two competing chains and switch to the one with more work.
+    #[test]
+    fn switch_to_heavier_chain() {
+        let blockchain = FIXTURE_VK.ledger();
+        let parameters = load_verifying_parameters();
+
+        let mut memory_pool = MemoryPool::new();
+
+        let consensus = TEST_CONSENSUS.clone();
+
+        let block_1_canon = Block::<Tx>::read(&ALTERNATIVE_BLOCK_1[..]).unwrap();
+        let block_1_side = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2_side = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+
+        // 1. Receive the canon chain.
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_1_canon)
+            .unwrap();
+
+        // 2. Receive a side chain block with the same amount of work; the canon chain is kept.
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_1_side)
+            .unwrap();
+
+        assert_eq!(blockchain.get_latest_block().unwrap(), block_1_canon);
+
+        // 3. Extend the side chain so that it carries more work than the canon chain.
+
+        let canon_work = ConsensusParameters::block_work(&block_1_canon.header);
+        let side_work = ConsensusParameters::block_work(&block_1_side.header)
+            + ConsensusParameters::block_work(&block_2_side.header);
+        assert!(side_work > canon_work);
+
+        consensus
+            .receive_block(&parameters, &blockchain, &mut memory_pool, &block_2_side)
+            .unwrap();
+
+        // 4. Ensure the node switched to the heavier chain.
+
+        let height = blockchain.get_current_block_height();
+
+        assert_eq!(blockchain.get_latest_block().unwrap(), block_2_side);
+        assert_eq!(
+            blockchain.get_block_hash(height - 1).unwrap(),
+            block_1_side.header.get_hash()
+        );
+
+        kill_storage_sync(blockchain);
+    }
 }
@@ -16,10 +16,20 @@
 
 use crate::prometheus::{
     metrics_handler,
+    BLOCK_PROPAGATION_DELAY,
+    BLOCK_PROPAGATION_DURATION,
+    BLOCK_PROPAGATION_PEERS,
     CONNECTED_PEERS,
+    INBOUND_MESSAGES,
+    OUTBOUND_MESSAGES,
+    OUTBOUND_MESSAGES_DROPPED,
+    PEER_DISCONNECTS,
+    PEER_RTT_MS,
+    RECEIVED_BLOCKS,
     {self},
 };
 
+use std::time::Duration;
 use warp::Filter;
 
 #[derive(Default)]
@@ -50,6 +60,76 @@ impl Metrics {
     pub fn connected_peers_dec() {
         CONNECTED_PEERS.dec();
     }
+
+    pub fn get_inbound_messages(message_type: &str) -> u64 {
+        INBOUND_MESSAGES.with_label_values(&[message_type]).get()
+    }
+
+    pub fn inbound_message_inc(message_type: &str) {
+        INBOUND_MESSAGES.with_label_values(&[message_type]).inc();
+    }
+
+    pub fn get_outbound_messages(message_type: &str) -> u64 {
+        OUTBOUND_MESSAGES.with_label_values(&[message_type]).get()
+    }
+
+    pub fn outbound_message_inc(message_type: &str) {
+        OUTBOUND_MESSAGES.with_label_values(&[message_type]).inc();
+    }
+
+    pub fn get_outbound_messages_dropped(message_type: &str) -> u64 {
+        OUTBOUND_MESSAGES_DROPPED.with_label_values(&[message_type]).get()
+    }
+
+    pub fn outbound_message_dropped_inc(message_type: &str) {
+        OUTBOUND_MESSAGES_DROPPED.with_label_values(&[message_type]).inc();
+    }
+
+    /// Returns the number of recorded block propagations and the total number of peers they reached.
+    pub fn get_block_propagation_peers() -> (u64, u64) {
+        (
+            BLOCK_PROPAGATION_PEERS.get_sample_count(),
+            BLOCK_PROPAGATION_PEERS.get_sample_sum() as u64,
+        )
+    }
+
+    pub fn block_propagation_observe(duration: Duration, peers: usize) {
+        BLOCK_PROPAGATION_DURATION.observe(duration.as_secs_f64());
+        BLOCK_PROPAGATION_PEERS.observe(peers as f64);
+    }
+
+    pub fn block_propagation_delay_observe(delay: Duration) {
+        BLOCK_PROPAGATION_DELAY.observe(delay.as_secs_f64());
+    }
+
+    /// Returns the given percentile (e.g. "p50") of the RTT across the connected peers in milliseconds.
+    pub fn get_peer_rtt(percentile: &str) -> i64 {
+        PEER_RTT_MS.with_label_values(&[percentile]).get()
+    }
+
+    pub fn peer_rtt_set(p50: u64, p90: u64, p99: u64) {
+        PEER_RTT_MS.with_label_values(&["p50"]).set(p50 as i64);
+        PEER_RTT_MS.with_label_values(&["p90"]).set(p90 as i64);
+        PEER_RTT_MS.with_label_values(&["p99"]).set(p99 as i64);
+    }
+
+    /// Returns the number of disconnects from peers for the given reason (e.g. "timeout").
+    pub fn get_peer_disconnects(reason: &str) -> u64 {
+        PEER_DISCONNECTS.with_label_values(&[reason]).get()
+    }
+
+    pub fn peer_disconnect_inc(reason: &str) {
+        PEER_DISCONNECTS.with_label_values(&[reason]).inc();
+    }
+
+    /// Returns the number of blocks received from peers with the given outcome (e.g. "orphan").
+    pub fn get_received_blocks(outcome: &str) -> u64 {
+        RECEIVED_BLOCKS.with_label_values(&[outcome]).get()
+    }
+
+    pub fn received_block_inc(outcome: &str) {
+        RECEIVED_BLOCKS.with_label_values(&[outcome]).inc();
+    }
 }
 
 #[cfg(test)]
@@ -90,4 +170,69 @@ mod tests {
         Metrics::connected_peers_inc();
         assert_eq!(0, Metrics::get_connected_peers());
     }
+
+    #[test_with_metrics]
+    fn test_message_counters() {
+        // Each message type is counted separately.
+        Metrics::inbound_message_inc("ping");
+        assert_eq!(1, Metrics::get_inbound_messages("ping"));
+        assert_eq!(0, Metrics::get_inbound_messages("pong"));
+
+        Metrics::inbound_message_inc("ping");
+        Metrics::inbound_message_inc("pong");
+        assert_eq!(2, Metrics::get_inbound_messages("ping"));
+        assert_eq!(1, Metrics::get_inbound_messages("pong"));
+
+        // Inbound and outbound messages are counted separately.
+        assert_eq!(0, Metrics::get_outbound_messages("ping"));
+        Metrics::outbound_message_inc("ping");
+        assert_eq!(1, Metrics::get_outbound_messages("ping"));
+        assert_eq!(2, Metrics::get_inbound_messages("ping"));
+
+        // Dropped outbound messages are counted separately from the sent ones.
+        Metrics::outbound_message_dropped_inc("transaction");
+        assert_eq!(1, Metrics::get_outbound_messages_dropped("transaction"));
+        assert_eq!(0, Metrics::get_outbound_messages("transaction"));
+    }
+
+    #[test_with_metrics]
+    fn test_block_propagation() {
+        let (propagations, peers) = Metrics::get_block_propagation_peers();
+
+        Metrics::block_propagation_observe(Duration::from_millis(10), 3);
+        Metrics::block_propagation_observe(Duration::from_millis(20), 5);
+
+        assert_eq!((propagations + 2, peers + 8), Metrics::get_block_propagation_peers());
+    }
+
+    #[test_with_metrics]
+    fn test_peer_rtt() {
+        Metrics::peer_rtt_set(10, 50, 200);
+
+        assert_eq!(10, Metrics::get_peer_rtt("p50"));
+        assert_eq!(50, Metrics::get_peer_rtt("p90"));
+        assert_eq!(200, Metrics::get_peer_rtt("p99"));
+    }
+
+    #[test_with_metrics]
+    fn test_peer_disconnects() {
+        Metrics::peer_disconnect_inc("timeout");
+        Metrics::peer_disconnect_inc("timeout");
+        Metrics::peer_disconnect_inc("banned");
+
+        assert_eq!(2, Metrics::get_peer_disconnects("timeout"));
+        assert_eq!(1, Metrics::get_peer_disconnects("banned"));
+        assert_eq!(0, Metrics::get_peer_disconnects("shutdown"));
+    }
+
+    #[test_with_metrics]
+    fn test_received_blocks() {
+        Metrics::received_block_inc("connected");
+        Metrics::received_block_inc("orphan");
+        Metrics::received_block_inc("orphan");
+
+        assert_eq!(1, Metrics::get_received_blocks("connected"));
+        assert_eq!(2, Metrics::get_received_blocks("orphan"));
+        assert_eq!(0, Metrics::get_received_blocks("invalid"));
+    }
 }
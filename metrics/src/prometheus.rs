@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use prometheus::{Encoder, IntCounter, IntGauge, Registry};
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry};
 use warp::{Rejection, Reply};
 
 lazy_static! {
@@ -29,6 +29,33 @@ lazy_static! {
 
     /// Counts the number of requests sent to the RPC server.
     pub static ref RPC_REQUESTS: IntCounter = IntCounter::new("rpc_requests", "RPC Requests").expect("rpc_requests to be created");
+
+    /// Counts the number of messages received by the node server, per message type.
+    pub static ref INBOUND_MESSAGES: IntCounterVec = IntCounterVec::new(Opts::new("inbound_messages", "Inbound Messages"), &["type"]).expect("inbound_messages to be created");
+
+    /// Counts the number of messages sent by the node server, per message type.
+    pub static ref OUTBOUND_MESSAGES: IntCounterVec = IntCounterVec::new(Opts::new("outbound_messages", "Outbound Messages"), &["type"]).expect("outbound_messages to be created");
+
+    /// Counts the number of messages dropped instead of being sent to slow peers, per message type.
+    pub static ref OUTBOUND_MESSAGES_DROPPED: IntCounterVec = IntCounterVec::new(Opts::new("outbound_messages_dropped", "Outbound Messages Dropped"), &["type"]).expect("outbound_messages_dropped to be created");
+
+    /// Measures the time (in seconds) it takes to broadcast a block to the connected peers.
+    pub static ref BLOCK_PROPAGATION_DURATION: Histogram = Histogram::with_opts(HistogramOpts::new("block_propagation_duration", "Block Propagation Duration")).expect("block_propagation_duration to be created");
+
+    /// Measures the number of peers a block is broadcast to.
+    pub static ref BLOCK_PROPAGATION_PEERS: Histogram = Histogram::with_opts(HistogramOpts::new("block_propagation_peers", "Block Propagation Peers").buckets(vec![1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0])).expect("block_propagation_peers to be created");
+
+    /// Measures the time (in seconds) between receiving a block and starting to propagate it.
+    pub static ref BLOCK_PROPAGATION_DELAY: Histogram = Histogram::with_opts(HistogramOpts::new("block_propagation_delay", "Block Propagation Delay")).expect("block_propagation_delay to be created");
+
+    /// Tracks the percentiles (p50, p90 and p99) of the RTT (in milliseconds) across the connected peers.
+    pub static ref PEER_RTT_MS: IntGaugeVec = IntGaugeVec::new(Opts::new("peer_rtt_ms", "Peer RTT (ms)"), &["percentile"]).expect("peer_rtt_ms to be created");
+
+    /// Counts the number of disconnects from peers, per reason.
+    pub static ref PEER_DISCONNECTS: IntCounterVec = IntCounterVec::new(Opts::new("peer_disconnects", "Peer Disconnects"), &["reason"]).expect("peer_disconnects to be created");
+
+    /// Counts the number of blocks received from peers, per outcome (connected, orphan or invalid).
+    pub static ref RECEIVED_BLOCKS: IntCounterVec = IntCounterVec::new(Opts::new("received_blocks", "Received Blocks"), &["outcome"]).expect("received_blocks to be created");
 }
 
 /// Initialize the metrics by registering them with the `Registry`.
@@ -45,6 +72,42 @@ pub fn initialize() {
     REGISTRY
         .register(Box::new(RPC_REQUESTS.clone()))
         .expect("RPC_REQUESTS to be registered");
+
+    REGISTRY
+        .register(Box::new(INBOUND_MESSAGES.clone()))
+        .expect("INBOUND_MESSAGES to be registered");
+
+    REGISTRY
+        .register(Box::new(OUTBOUND_MESSAGES.clone()))
+        .expect("OUTBOUND_MESSAGES to be registered");
+
+    REGISTRY
+        .register(Box::new(OUTBOUND_MESSAGES_DROPPED.clone()))
+        .expect("OUTBOUND_MESSAGES_DROPPED to be registered");
+
+    REGISTRY
+        .register(Box::new(BLOCK_PROPAGATION_DURATION.clone()))
+        .expect("BLOCK_PROPAGATION_DURATION to be registered");
+
+    REGISTRY
+        .register(Box::new(BLOCK_PROPAGATION_PEERS.clone()))
+        .expect("BLOCK_PROPAGATION_PEERS to be registered");
+
+    REGISTRY
+        .register(Box::new(BLOCK_PROPAGATION_DELAY.clone()))
+        .expect("BLOCK_PROPAGATION_DELAY to be registered");
+
+    REGISTRY
+        .register(Box::new(PEER_RTT_MS.clone()))
+        .expect("PEER_RTT_MS to be registered");
+
+    REGISTRY
+        .register(Box::new(PEER_DISCONNECTS.clone()))
+        .expect("PEER_DISCONNECTS to be registered");
+
+    REGISTRY
+        .register(Box::new(RECEIVED_BLOCKS.clone()))
+        .expect("RECEIVED_BLOCKS to be registered");
 }
 
 pub async fn metrics_handler() -> Result<impl Reply, Rejection> {
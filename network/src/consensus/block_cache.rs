@@ -0,0 +1,139 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm_objects::BlockHeaderHash;
+
+use std::collections::{HashMap, VecDeque};
+
+/// A bounded cache of recently served serialized blocks, evicting the least recently used ones first.
+/// Blocks are immutable, so the cached entries never need to be invalidated.
+#[derive(Debug)]
+pub struct BlockCache {
+    /// The maximum number of cached blocks.
+    capacity: usize,
+    /// The cached serialized blocks.
+    blocks: HashMap<BlockHeaderHash, Vec<u8>>,
+    /// The hashes of the cached blocks, from the least to the most recently used one.
+    recency: VecDeque<BlockHeaderHash>,
+    /// The number of lookups served from the cache.
+    hits: u64,
+}
+
+impl BlockCache {
+    /// Creates an empty cache holding up to `capacity` blocks.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            blocks: HashMap::with_capacity(capacity),
+            recency: VecDeque::with_capacity(capacity),
+            hits: 0,
+        }
+    }
+
+    /// Returns the serialized block with the given hash if it's cached, marking it as recently used.
+    pub fn get(&mut self, hash: &BlockHeaderHash) -> Option<Vec<u8>> {
+        let block = self.blocks.get(hash)?.clone();
+        self.touch(hash);
+        self.hits += 1;
+
+        Some(block)
+    }
+
+    /// Caches the given serialized block, evicting the least recently used one if the cache is full.
+    pub fn insert(&mut self, hash: BlockHeaderHash, block: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.blocks.insert(hash.clone(), block).is_some() {
+            self.touch(&hash);
+            return;
+        }
+
+        if self.recency.len() == self.capacity {
+            if let Some(evicted) = self.recency.pop_front() {
+                self.blocks.remove(&evicted);
+            }
+        }
+        self.recency.push_back(hash);
+    }
+
+    /// Returns the number of cached blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Checks whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns the number of lookups served from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    fn touch(&mut self, hash: &BlockHeaderHash) {
+        if let Some(index) = self.recency.iter().position(|cached| cached == hash) {
+            if let Some(hash) = self.recency.remove(index) {
+                self.recency.push_back(hash);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(i: u8) -> BlockHeaderHash {
+        BlockHeaderHash::new(vec![i; 32])
+    }
+
+    #[test]
+    fn hits_are_counted() {
+        let mut cache = BlockCache::new(2);
+
+        assert_eq!(cache.get(&hash(0)), None);
+        cache.insert(hash(0), vec![0]);
+        assert_eq!(cache.get(&hash(0)), Some(vec![0]));
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn least_recently_used_block_is_evicted() {
+        let mut cache = BlockCache::new(2);
+
+        cache.insert(hash(0), vec![0]);
+        cache.insert(hash(1), vec![1]);
+        // using the first block makes the second one the least recently used
+        cache.get(&hash(0));
+        cache.insert(hash(2), vec![2]);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&hash(0)), Some(vec![0]));
+        assert_eq!(cache.get(&hash(1)), None);
+        assert_eq!(cache.get(&hash(2)), Some(vec![2]));
+    }
+
+    #[test]
+    fn zero_capacity_caches_nothing() {
+        let mut cache = BlockCache::new(0);
+
+        cache.insert(hash(0), vec![0]);
+        assert!(cache.is_empty());
+    }
+}
@@ -14,18 +14,35 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{message::*, peers::PeerInfo, Consensus, NetworkError};
+use crate::{
+    consensus::{BlockOutcome, SeenBlock},
+    message::*,
+    peers::PeerInfo,
+    Consensus,
+    NetworkError,
+};
 use snarkos_consensus::error::ConsensusError;
+use snarkos_metrics::Metrics;
+use snarkvm_dpc::base_dpc::instantiated::Tx;
 use snarkvm_objects::{Block, BlockHeaderHash};
+use snarkvm_utilities::{
+    bytes::{FromBytes, ToBytes},
+    to_bytes,
+};
 
-use std::{collections::HashMap, net::SocketAddr};
+use chrono::Utc;
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 impl Consensus {
     ///
     /// Broadcasts updates with connected peers and maintains a permitted number of connected peers.
     ///
     pub async fn update_blocks(&self, sync_node: SocketAddr) {
-        let block_locator_hashes = self.storage().get_block_locator_hashes();
+        let block_locator_hashes = self.block_locator_hashes();
 
         if let Ok(block_locator_hashes) = block_locator_hashes {
             // Send a GetSync to the selected sync node.
@@ -42,89 +59,381 @@ impl Consensus {
         }
     }
 
-    /// Broadcast block to connected peers
+    /// Broadcast block to connected peers; returns the number of peers it was sent to.
     pub async fn propagate_block(
         &self,
         block_bytes: Vec<u8>,
         block_miner: SocketAddr,
         connected_peers: &HashMap<SocketAddr, PeerInfo>,
-    ) {
+    ) -> usize {
+        if connected_peers.keys().all(|addr| *addr == block_miner) {
+            return 0;
+        }
+
         debug!("Propagating a block to peers");
+        let propagation_start = Instant::now();
 
-        for remote_address in connected_peers.keys() {
+        let block = Block::<Tx>::deserialize(&block_bytes).ok();
+
+        // Peers are likely to already have most of the block's transactions, so relay it in compact form.
+        let payload = match block
+            .as_ref()
+            .and_then(|block| CompactBlock::new(block).ok())
+            .and_then(|compact_block| compact_block.serialize().ok())
+        {
+            Some(compact_block) => Payload::CompactBlock(compact_block),
+            None => Payload::Block(block_bytes),
+        };
+
+        let mut peers_reached = 0;
+        for (remote_address, peer_info) in connected_peers {
             if *remote_address != block_miner {
-                // Send a `Block` message to the connected peer.
+                // Send a `CompactBlock` message to the connected peer.
+                self.node()
+                    .outbound
+                    .send_request(Message::new(Direction::Outbound(*remote_address), payload.clone()))
+                    .await;
+                peers_reached += 1;
+
+                // Peers that loaded a bloom filter are also sent the block's transactions matching it.
+                if peer_info.quality.bloom_filter.read().is_some() {
+                    self.send_filtered_block_transactions(*remote_address, peer_info, block.as_ref())
+                        .await;
+                }
+            }
+        }
+
+        Metrics::block_propagation_observe(propagation_start.elapsed(), peers_reached);
+
+        peers_reached
+    }
+
+    /// Sends the transactions of the given block that match the peer's bloom filter as `Transaction` messages.
+    async fn send_filtered_block_transactions(
+        &self,
+        remote_address: SocketAddr,
+        peer_info: &PeerInfo,
+        block: Option<&Block<Tx>>,
+    ) {
+        let transactions = match block {
+            Some(block) => block
+                .transactions
+                .iter()
+                .filter(|tx| peer_info.wants_transaction(*tx))
+                .filter_map(|tx| to_bytes![tx].ok())
+                .collect::<Vec<_>>(),
+            None => return,
+        };
+
+        for transaction in transactions {
+            self.node()
+                .outbound
+                .send_request(Message::new(
+                    Direction::Outbound(remote_address),
+                    Payload::Transaction(transaction),
+                ))
+                .await;
+        }
+    }
+
+    /// A peer has sent us a new block in compact form; attempt to reconstruct it from the memory pool
+    /// and request any missing transactions.
+    pub(crate) async fn received_compact_block(
+        &self,
+        remote_address: SocketAddr,
+        compact_block: Vec<u8>,
+        connected_peers: HashMap<SocketAddr, PeerInfo>,
+    ) -> Result<(), NetworkError> {
+        let compact_block = CompactBlock::deserialize(&compact_block)?;
+
+        if self.storage().block_hash_exists(&compact_block.header.get_hash()) {
+            return Ok(());
+        }
+
+        let reconstruction = {
+            let memory_pool = self.memory_pool().lock();
+            compact_block.reconstruct(|id| {
+                memory_pool
+                    .transactions
+                    .get(&id.to_vec())
+                    .map(|entry| entry.transaction.clone())
+            })
+        };
+
+        match reconstruction {
+            Ok(block) => {
+                self.received_block(remote_address, block.serialize()?, Some(connected_peers))
+                    .await?;
+
+                Ok(())
+            }
+            Err(missing_ids) => {
+                debug!(
+                    "Requesting {} missing transactions of a compact block",
+                    missing_ids.len()
+                );
+                self.register_pending_compact_block(compact_block);
+
+                // Send a `GetBlockTxn` message to the connected peer.
                 self.node()
                     .outbound
                     .send_request(Message::new(
-                        Direction::Outbound(*remote_address),
-                        Payload::Block(block_bytes.clone()),
+                        Direction::Outbound(remote_address),
+                        Payload::GetBlockTxn(missing_ids),
                     ))
                     .await;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// A peer has requested the transactions of a compact block that it was missing.
+    pub(crate) async fn received_get_block_txn(
+        &self,
+        remote_address: SocketAddr,
+        transaction_ids: Vec<[u8; 32]>,
+    ) -> Result<(), NetworkError> {
+        let transactions = {
+            let memory_pool = self.memory_pool().lock();
+            let storage = self.storage();
+
+            transaction_ids
+                .iter()
+                .filter_map(|id| match memory_pool.transactions.get(&id.to_vec()) {
+                    Some(entry) => to_bytes![entry.transaction].ok(),
+                    None => storage.get_transaction_bytes(id).ok(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if !transactions.is_empty() {
+            // Send a `BlockTxn` message to the connected peer.
+            self.node()
+                .outbound
+                .send_request(Message::new(
+                    Direction::Outbound(remote_address),
+                    Payload::BlockTxn(transactions),
+                ))
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// A peer has sent us the transactions missing from our pending compact blocks.
+    pub(crate) async fn received_block_txn(
+        &self,
+        remote_address: SocketAddr,
+        transactions: Vec<Vec<u8>>,
+        connected_peers: HashMap<SocketAddr, PeerInfo>,
+    ) -> Result<(), NetworkError> {
+        let transactions = transactions
+            .iter()
+            .map(|bytes| Tx::read(&bytes[..]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for compact_block in self.fill_pending_compact_blocks(&transactions) {
+            let reconstruction = {
+                let memory_pool = self.memory_pool().lock();
+                compact_block.reconstruct(|id| {
+                    memory_pool
+                        .transactions
+                        .get(&id.to_vec())
+                        .map(|entry| entry.transaction.clone())
+                })
+            };
+
+            match reconstruction {
+                Ok(block) => {
+                    self.received_block(remote_address, block.serialize()?, Some(connected_peers.clone()))
+                        .await?;
+                }
+                Err(missing_ids) => debug!(
+                    "Discarding a compact block still missing {} transactions",
+                    missing_ids.len()
+                ),
             }
         }
+
+        Ok(())
     }
 
-    /// A peer has sent us a new block to process.
+    /// A peer has sent us a new block to process; returns `true` if the block was previously
+    /// unknown and valid. The `connected_peers` are only provided for blocks that were gossiped,
+    /// as opposed to ones received as part of a block sync, which are never propagated.
     pub(crate) async fn received_block(
         &self,
         remote_address: SocketAddr,
         block: Vec<u8>,
         connected_peers: Option<HashMap<SocketAddr, PeerInfo>>,
-    ) -> Result<(), NetworkError> {
+    ) -> Result<bool, NetworkError> {
+        let received_at = Instant::now();
         let block_size = block.len();
         let max_block_size = self.max_block_size();
 
         if block_size > max_block_size {
+            self.reject_invalid_block(remote_address);
             return Err(NetworkError::ConsensusError(ConsensusError::BlockTooLarge(
                 block_size,
                 max_block_size,
             )));
         }
 
-        let block_struct = Block::deserialize(&block)?;
+        let block_struct = match Block::deserialize(&block) {
+            Ok(block) => block,
+            Err(e) => {
+                self.reject_invalid_block(remote_address);
+                return Err(e.into());
+            }
+        };
+        self.received_requested_block(&block_struct.header.get_hash());
         info!(
             "Received block from epoch {} with hash {:?}",
             block_struct.header.time,
             hex::encode(block_struct.header.get_hash().0)
         );
 
-        // Verify the block and insert it into the storage.
-        let is_valid_block = self
-            .consensus_parameters()
-            .receive_block(
-                &self.dpc_parameters(),
-                &self.storage(),
-                &mut self.memory_pool().lock(),
-                &block_struct,
-            )
-            .is_ok();
-
-        // This is a new block, send it to our peers.
+        // Orphans aren't verified against their parent yet, so reject implausible timestamps early.
+        if let Err(e) = self.consensus_parameters().verify_timestamp(&block_struct.header) {
+            debug!("Received an invalid block from {}: {}", remote_address, e);
+            self.reject_invalid_block(remote_address);
+            return Err(NetworkError::ConsensusError(e));
+        }
+
+        // The same block is usually received from several peers in a row; it's only processed once.
+        let block_hash = block_struct.header.get_hash();
+        match self.register_seen_block(block_hash.clone()) {
+            None => {}
+            Some(SeenBlock::Invalid) => {
+                debug!("Received a known invalid block from {}", remote_address);
+                self.reject_invalid_block(remote_address);
+                return Ok(false);
+            }
+            Some(_) => {
+                debug!("Skipping the recently received block {}", hex::encode(&block_hash.0));
+                return Ok(false);
+            }
+        }
+
+        let is_known_block = self.storage().block_hash_exists(&block_hash);
+        let is_orphan = !is_known_block && !self.storage().previous_block_hash_exists(&block_struct);
+
+        // Verify the block and insert it into the storage; blocks with unknown parents are valid
+        // at this point, as they are stored as orphans.
+        let result = self.consensus_parameters().receive_block(
+            &self.dpc_parameters(),
+            &self.storage(),
+            &mut self.memory_pool().lock(),
+            &block_struct,
+        );
+        let is_valid_block = match result {
+            Ok(()) => {
+                self.set_seen_block_validity(&block_hash, Some(true));
+                true
+            }
+            Err(e) => {
+                if e.is_invalid_block() {
+                    debug!("Received an invalid block from {}: {}", remote_address, e);
+                    self.reject_invalid_block(remote_address);
+                    self.set_seen_block_validity(&block_hash, Some(false));
+                } else if matches!(e, ConsensusError::PreExistingBlock) {
+                    self.set_seen_block_validity(&block_hash, Some(true));
+                } else {
+                    self.set_seen_block_validity(&block_hash, None);
+                }
+                false
+            }
+        };
+
+        if is_valid_block && !is_known_block {
+            self.register_block_outcome(if is_orphan {
+                BlockOutcome::Orphan
+            } else {
+                BlockOutcome::Connected
+            });
+        }
+
+        // The new block might have connected a chain of orphans that couldn't all be processed at once.
+        if is_valid_block {
+            self.register_pending_orphans();
+        }
+
+        // The sender of an orphan is likely to have its parent too.
+        if is_valid_block && is_orphan {
+            self.request_orphan_parent(remote_address, block_struct.header.previous_block_hash.clone())
+                .await;
+        }
+
+        // This is a new gossiped block, send it to our peers; a sync with some other peer doesn't
+        // prevent that, as the block is likely to be new to the network.
         if let Some(connected_peers) = connected_peers {
-            if is_valid_block && !self.is_syncing_blocks() {
+            if is_valid_block {
+                // Measure the time elapsed since the block was mined.
+                let latency = Utc::now().timestamp_millis() - block_struct.header.time * 1000;
+                self.register_block_propagation(Duration::from_millis(latency.max(0) as u64));
+            }
+
+            // Propagate the block at most once per window, regardless of how many peers send it.
+            if is_valid_block && self.register_propagated_block(block_hash) {
+                Metrics::block_propagation_delay_observe(received_at.elapsed());
                 self.propagate_block(block, remote_address, &connected_peers).await;
             }
         }
 
-        Ok(())
+        Ok(is_valid_block && !is_known_block)
+    }
+
+    /// Penalizes the sender of an invalid block and counts the block.
+    fn reject_invalid_block(&self, remote_address: SocketAddr) {
+        self.node().register_invalid_block(remote_address);
+        self.register_block_outcome(BlockOutcome::Invalid);
+    }
+
+    /// Requests the missing parent of an orphan block from the peer that sent it, unless it's already requested.
+    async fn request_orphan_parent(&self, remote_address: SocketAddr, parent_hash: BlockHeaderHash) {
+        let block_hashes = self.register_block_requests(&[parent_hash]);
+
+        if !block_hashes.is_empty() {
+            debug!("Requesting the parent of an orphan block from {}", remote_address);
+
+            self.node()
+                .outbound
+                .send_request(Message::new(
+                    Direction::Outbound(remote_address),
+                    Payload::GetBlocks(block_hashes),
+                ))
+                .await;
+        }
     }
 
-    /// A peer has requested a block.
+    /// A peer has requested a block; at most `MAX_BLOCK_SYNC_COUNT` blocks are served per request, and
+    /// peers requesting more are penalized.
     pub(crate) async fn received_get_blocks(
         &self,
         remote_address: SocketAddr,
         header_hashes: Vec<BlockHeaderHash>,
     ) -> Result<(), NetworkError> {
-        for hash in header_hashes {
-            let block = self.storage().get_block(&hash)?;
+        let max_blocks = crate::MAX_BLOCK_SYNC_COUNT as usize;
+        if header_hashes.len() > max_blocks {
+            warn!(
+                "Peer {} requested {} blocks at once; only the first {} will be served",
+                remote_address,
+                header_hashes.len(),
+                max_blocks
+            );
+            self.node().peer_book.read().register_failure(remote_address);
+        }
+
+        for hash in header_hashes.into_iter().take(max_blocks) {
+            let block = self.get_serialized_block(&hash)?;
 
             // Send a `SyncBlock` message to the connected peer.
             self.node()
                 .outbound
                 .send_request(Message::new(
                     Direction::Outbound(remote_address),
-                    Payload::SyncBlock(block.serialize()?),
+                    Payload::SyncBlock(block),
                 ))
                 .await;
         }
@@ -138,6 +447,17 @@ impl Consensus {
         remote_address: SocketAddr,
         block_locator_hashes: Vec<BlockHeaderHash>,
     ) -> Result<(), NetworkError> {
+        // Reject malformed locators before they can cause any storage work.
+        if !is_valid_locator(&block_locator_hashes) {
+            warn!(
+                "Peer {} sent a malformed block locator ({} hashes)",
+                remote_address,
+                block_locator_hashes.len()
+            );
+            self.node().peer_book.read().register_failure(remote_address);
+            return Ok(());
+        }
+
         let sync = {
             let storage = self.storage();
 
@@ -150,7 +470,7 @@ impl Consensus {
 
                     // if the requester is behind more than MAX_BLOCK_SYNC_COUNT blocks
                     if current_height > height + crate::MAX_BLOCK_SYNC_COUNT {
-                        // send no more than MAX_BLOCK_SYNC_COUNT
+                        // send no more than MAX_BLOCK_SYNC_COUNT; the requester picks its own batch size
                         max_height = height + crate::MAX_BLOCK_SYNC_COUNT;
                     }
 
@@ -179,21 +499,39 @@ impl Consensus {
         Ok(())
     }
 
-    /// A peer has sent us their chain state.
-    pub(crate) async fn received_sync(&self, remote_address: SocketAddr, block_hashes: Vec<BlockHeaderHash>) {
+    /// A peer has sent us their chain state; returns the number of blocks requested from them.
+    pub(crate) async fn received_sync(&self, remote_address: SocketAddr, block_hashes: Vec<BlockHeaderHash>) -> usize {
+        // Only request as many blocks as the peer's current sync batch size permits; the rest
+        // will be picked up by subsequent syncs.
+        let batch_size = self.node().peer_book.read().sync_batch_size(remote_address) as usize;
+        let block_hashes = &block_hashes[..block_hashes.len().min(batch_size)];
+
+        // Skip the blocks that have already been requested as part of an overlapping sync.
+        let block_hashes = self.register_block_requests(block_hashes);
+
         // If empty sync is no-op as chain states match
         if !block_hashes.is_empty() {
-            for batch in block_hashes.chunks(crate::MAX_BLOCK_SYNC_COUNT as usize) {
-                // GetBlocks for each block hash: fire and forget, relying on block locator hashes to
-                // detect missing blocks and divergence in chain for now.
-                self.node()
-                    .outbound
-                    .send_request(Message::new(
-                        Direction::Outbound(remote_address),
-                        Payload::GetBlocks(batch.to_vec()),
-                    ))
-                    .await;
-            }
+            // GetBlocks for each block hash: fire and forget, relying on block locator hashes to
+            // detect missing blocks and divergence in chain for now.
+            self.node()
+                .outbound
+                .send_request(Message::new(
+                    Direction::Outbound(remote_address),
+                    Payload::GetBlocks(block_hashes.clone()),
+                ))
+                .await;
         }
+
+        block_hashes.len()
     }
 }
+
+/// Checks whether a block locator received from a peer is within the size limit and contains no duplicates.
+fn is_valid_locator(block_locator_hashes: &[BlockHeaderHash]) -> bool {
+    if block_locator_hashes.len() > crate::MAX_LOCATOR_HASHES {
+        return false;
+    }
+
+    let mut unique_hashes = HashSet::with_capacity(block_locator_hashes.len());
+    block_locator_hashes.iter().all(|hash| unique_hashes.insert(hash))
+}
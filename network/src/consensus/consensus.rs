@@ -14,21 +14,67 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::Node;
-use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
-use snarkvm_dpc::base_dpc::{
-    instantiated::{Components, Tx},
-    parameters::PublicParameters,
+use crate::{
+    consensus::{BlockCache, SeenBlock, SeenBlocks},
+    message::CompactBlock,
+    NetworkError,
+    Node,
 };
+use snarkos_consensus::{
+    memory_pool::transaction_fee,
+    ConsensusParameters,
+    MemoryPool,
+    MerkleTreeLedger,
+    MAX_ORPHANS_PER_PASS,
+};
+use snarkos_metrics::Metrics;
+use snarkos_storage::error::StorageError;
+use snarkvm_dpc::{
+    base_dpc::{
+        instantiated::{Components, Tx},
+        parameters::PublicParameters,
+        record::DPCRecord,
+    },
+    AccountAddress,
+    Record,
+};
+use snarkvm_objects::{BlockHeaderHash, Transaction};
+use snarkvm_utilities::{bytes::ToBytes, to_bytes};
 
 use parking_lot::{Mutex, RwLock};
 use std::{
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
+    mem,
+    net::SocketAddr,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
         Arc,
     },
     time::{Duration, Instant},
 };
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The outcome of processing a block received from a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockOutcome {
+    /// The block was valid and connected to a known chain.
+    Connected,
+    /// The block was valid, but its parent is missing.
+    Orphan,
+    /// The block failed validation.
+    Invalid,
+}
+
+impl BlockOutcome {
+    /// Returns the name of the outcome, as used in logs and metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Connected => "connected",
+            Self::Orphan => "orphan",
+            Self::Invalid => "invalid",
+        }
+    }
+}
 
 // TODO: Remove the inner Arcs, currently these objects are being cloned individually in the miner.
 pub struct Consensus {
@@ -44,14 +90,63 @@ pub struct Consensus {
     dpc_parameters: Arc<PublicParameters<Components>>,
     /// If `true`, initializes a mining task on this node.
     is_miner: bool,
-    /// The interval between each block sync.
-    block_sync_interval: Duration,
+    /// Was mining stopped? If `true`, the mining tasks exit before mining another block.
+    is_mining_stopped: AtomicBool,
+    /// The number of mining tasks that are currently running.
+    active_miners: AtomicU32,
+    /// The number of blocks mined by this node since it was started.
+    blocks_mined: AtomicU64,
+    /// The address receiving the coinbase rewards of the blocks mined from now on, if set.
+    coinbase_address: RwLock<Option<AccountAddress<Components>>>,
+    /// The interval between each block sync in milliseconds; it can be changed while the node is running.
+    block_sync_interval: AtomicU64,
     /// The last time a block sync was initiated.
     last_block_sync: RwLock<Instant>,
     /// The interval between each transaction (memory pool) sync.
     transaction_sync_interval: Duration,
     /// Is the node currently syncing blocks?
     is_syncing_blocks: AtomicBool,
+    /// The peer currently selected to sync with, along with its failure count at the time of the selection.
+    sync_node: Mutex<Option<(SocketAddr, u8)>>,
+    /// Could there be stored orphans that are yet to be connected to the canon chain?
+    has_pending_orphans: AtomicBool,
+    /// The number of previously unknown, valid blocks received during the current block sync.
+    new_sync_blocks: AtomicU64,
+    /// The number of already known or invalid blocks received during the current block sync.
+    wasted_sync_blocks: AtomicU64,
+    /// The propagation latencies (in milliseconds) of the most recently received blocks.
+    block_propagation_latencies: Mutex<VecDeque<u64>>,
+    /// The number of received transactions that were already in the memory pool or being verified, and skipped.
+    duplicate_transactions: AtomicU64,
+    /// The slots for the concurrent verification of received transactions.
+    transaction_verifications: Arc<Semaphore>,
+    /// The IDs of the received transactions that are currently being verified.
+    verifying_transactions: Mutex<HashSet<Vec<u8>>>,
+    /// The hashes of the blocks currently requested from peers, along with the time of the request.
+    requested_blocks: Mutex<HashMap<BlockHeaderHash, Instant>>,
+    /// The program commitments permitted in the memory pool; if empty, all programs are permitted.
+    authorized_program_commitments: Vec<Vec<u8>>,
+    /// The minimum fee a transaction must pay to be accepted into the memory pool, if any.
+    min_relay_fee: Option<i64>,
+    /// The number of most recent blocks whose coinbase outputs may not be spent yet, if any.
+    fee_sniping_depth: Option<u32>,
+    /// The serial numbers of the known spends of coinbase outputs, along with the height of the block
+    /// that created the spent records.
+    coinbase_spends: Mutex<HashMap<Vec<u8>, u32>>,
+    /// The hashes of the recently propagated blocks, along with the time of their propagation.
+    propagated_blocks: Mutex<HashMap<BlockHeaderHash, Instant>>,
+    /// The compact blocks awaiting their missing transactions, along with the time of the request.
+    pending_compact_blocks: Mutex<HashMap<BlockHeaderHash, (CompactBlock, Instant)>>,
+    /// The recently served serialized blocks.
+    block_cache: Mutex<BlockCache>,
+    /// The recently received blocks, which aren't processed again.
+    seen_blocks: Mutex<SeenBlocks>,
+    /// The number of received blocks per outcome, indexed by `BlockOutcome`.
+    received_blocks: [AtomicU64; 3],
+    /// The block locator hashes of the canon chain, along with the hash of its tip at the time they were computed.
+    block_locator: Mutex<Option<(BlockHeaderHash, Vec<BlockHeaderHash>)>>,
+    /// The number of block locators that were served from the cache.
+    block_locator_hits: AtomicU64,
 }
 
 impl Consensus {
@@ -65,6 +160,10 @@ impl Consensus {
         is_miner: bool,
         block_sync_interval: Duration,
         transaction_sync_interval: Duration,
+        authorized_program_commitments: Vec<Vec<u8>>,
+        min_relay_fee: Option<i64>,
+        fee_sniping_depth: Option<u32>,
+        max_concurrent_transaction_verifications: usize,
     ) -> Self {
         Self {
             node,
@@ -73,10 +172,37 @@ impl Consensus {
             consensus_parameters,
             dpc_parameters,
             is_miner,
-            block_sync_interval,
+            is_mining_stopped: Default::default(),
+            active_miners: Default::default(),
+            blocks_mined: Default::default(),
+            coinbase_address: Default::default(),
+            block_sync_interval: AtomicU64::new(block_sync_interval.as_millis() as u64),
             last_block_sync: RwLock::new(Instant::now()),
             transaction_sync_interval,
             is_syncing_blocks: Default::default(),
+            sync_node: Default::default(),
+            has_pending_orphans: Default::default(),
+            new_sync_blocks: Default::default(),
+            wasted_sync_blocks: Default::default(),
+            block_propagation_latencies: Default::default(),
+            duplicate_transactions: Default::default(),
+            transaction_verifications: Arc::new(Semaphore::new(max_concurrent_transaction_verifications.max(1))),
+            verifying_transactions: Default::default(),
+            requested_blocks: Default::default(),
+            authorized_program_commitments,
+            min_relay_fee,
+            fee_sniping_depth,
+            coinbase_spends: Default::default(),
+            propagated_blocks: Default::default(),
+            pending_compact_blocks: Default::default(),
+            block_cache: Mutex::new(BlockCache::new(crate::BLOCK_CACHE_SIZE)),
+            seen_blocks: Mutex::new(SeenBlocks::new(
+                crate::SEEN_BLOCKS_CAPACITY,
+                Duration::from_secs(crate::SEEN_BLOCK_EXPIRY_SECS),
+            )),
+            received_blocks: Default::default(),
+            block_locator: Default::default(),
+            block_locator_hits: Default::default(),
         }
     }
 
@@ -97,6 +223,13 @@ impl Consensus {
         &self.memory_pool
     }
 
+    /// Stores the current memory pool in the ledger, so that it survives a restart.
+    pub fn store_memory_pool(&self) -> Result<(), NetworkError> {
+        self.memory_pool.lock().store(&self.storage)?;
+
+        Ok(())
+    }
+
     /// Returns a reference to the consensus parameters of this node.
     #[inline]
     pub fn consensus_parameters(&self) -> &Arc<ConsensusParameters> {
@@ -115,6 +248,51 @@ impl Consensus {
         self.is_miner
     }
 
+    /// Stops the mining tasks of this node once they're done with the block they're currently mining.
+    pub fn stop_mining(&self) {
+        self.is_mining_stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Checks whether mining was stopped.
+    pub fn is_mining_stopped(&self) -> bool {
+        self.is_mining_stopped.load(Ordering::SeqCst)
+    }
+
+    /// Registers a mining task that started running.
+    pub fn register_miner_started(&self) {
+        self.active_miners.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Registers a mining task that stopped running, either on request or after repeated failures.
+    pub fn register_miner_stopped(&self) {
+        self.active_miners.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Checks whether any of the mining tasks of this node is running.
+    pub fn is_mining(&self) -> bool {
+        self.active_miners.load(Ordering::SeqCst) > 0
+    }
+
+    /// Registers a block mined by this node.
+    pub fn register_mined_block(&self) {
+        self.blocks_mined.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the number of blocks mined by this node since it was started.
+    pub fn blocks_mined(&self) -> u64 {
+        self.blocks_mined.load(Ordering::SeqCst)
+    }
+
+    /// Returns the address receiving the coinbase rewards of the blocks mined from now on, if set.
+    pub fn coinbase_address(&self) -> Option<AccountAddress<Components>> {
+        self.coinbase_address.read().clone()
+    }
+
+    /// Sets the address receiving the coinbase rewards; blocks that are already being mined are unaffected.
+    pub fn set_coinbase_address(&self, address: AccountAddress<Components>) {
+        *self.coinbase_address.write() = Some(address);
+    }
+
     /// Checks whether the node is currently syncing blocks.
     pub fn is_syncing_blocks(&self) -> bool {
         self.is_syncing_blocks.load(Ordering::SeqCst)
@@ -125,6 +303,48 @@ impl Consensus {
         self.is_syncing_blocks.store(false, Ordering::SeqCst);
     }
 
+    /// Returns the peer to sync with, out of the ones ahead of this node. The previously selected one is kept
+    /// for as long as it stays connected and ahead, and doesn't register new failures; otherwise, a new one is
+    /// selected, favoring the best peers.
+    pub fn sync_node(&self) -> Option<SocketAddr> {
+        let current_height = self.current_block_height();
+        let peer_book = self.node.peer_book.read();
+        let mut sync_node = self.sync_node.lock();
+
+        let failures = |addr: SocketAddr| {
+            peer_book
+                .connected_peers()
+                .get(&addr)
+                .filter(|peer_info| peer_info.quality.advertised_height.load(Ordering::SeqCst) > current_height)
+                .map(|peer_info| peer_info.quality.failures.load(Ordering::SeqCst))
+        };
+
+        let mut degraded = None;
+        if let Some((addr, failures_when_selected)) = *sync_node {
+            match failures(addr) {
+                Some(failures) if failures <= failures_when_selected => {
+                    // The failures decay over time, so the sync node may recover from earlier ones.
+                    *sync_node = Some((addr, failures));
+                    return Some(addr);
+                }
+                Some(_) => {
+                    debug!("Sync node {} degraded; selecting a new one", addr);
+                    degraded = Some(addr);
+                }
+                None => debug!(
+                    "Sync node {} is no longer connected or ahead; selecting a new one",
+                    addr
+                ),
+            }
+        }
+
+        *sync_node = peer_book
+            .weighted_sync_node(&mut *self.node.environment.rng(), current_height, degraded)
+            .and_then(|addr| failures(addr).map(|failures| (addr, failures)));
+
+        sync_node.map(|(addr, _)| addr)
+    }
+
     /// Returns the current block height of the ledger from storage.
     #[inline]
     pub fn current_block_height(&self) -> u32 {
@@ -133,13 +353,121 @@ impl Consensus {
 
     /// Checks whether enough time has elapsed for the node to attempt another block sync.
     pub fn should_sync_blocks(&self) -> bool {
-        !self.is_syncing_blocks() && self.last_block_sync.read().elapsed() > self.block_sync_interval
+        !self.is_syncing_blocks() && self.last_block_sync.read().elapsed() > self.block_sync_interval()
+    }
+
+    /// Returns the interval between each block sync.
+    pub fn block_sync_interval(&self) -> Duration {
+        Duration::from_millis(self.block_sync_interval.load(Ordering::SeqCst))
     }
 
-    /// Register that the node attempted to sync blocks.
+    /// Sets the interval between each block sync; it applies to the next sync attempt.
+    pub fn set_block_sync_interval(&self, interval: Duration) {
+        self.block_sync_interval
+            .store(interval.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    /// Register that the node attempted to sync blocks; this starts a new sync session.
     pub fn register_block_sync_attempt(&self) {
         *self.last_block_sync.write() = Instant::now();
         self.is_syncing_blocks.store(true, Ordering::SeqCst);
+        self.new_sync_blocks.store(0, Ordering::SeqCst);
+        self.wasted_sync_blocks.store(0, Ordering::SeqCst);
+    }
+
+    /// Registers a block received as part of a block sync, noting whether it was genuinely new.
+    pub fn register_sync_block(&self, is_new: bool) {
+        if is_new {
+            self.new_sync_blocks.fetch_add(1, Ordering::SeqCst);
+        } else {
+            self.wasted_sync_blocks.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns the number of genuinely new and of already known or invalid blocks received
+    /// during the current block sync.
+    pub fn sync_block_counts(&self) -> (u64, u64) {
+        (
+            self.new_sync_blocks.load(Ordering::SeqCst),
+            self.wasted_sync_blocks.load(Ordering::SeqCst),
+        )
+    }
+
+    /// Returns the ratio of genuinely new blocks to all the blocks received during the current
+    /// block sync, or `None` if no blocks were received yet.
+    pub fn sync_efficiency(&self) -> Option<f64> {
+        let (new, wasted) = self.sync_block_counts();
+
+        if new + wasted == 0 {
+            None
+        } else {
+            Some(new as f64 / (new + wasted) as f64)
+        }
+    }
+
+    /// Returns the fraction (between 0 and 1) of the chain advertised by the connected peers that
+    /// the node has already synced, or `None` if no peer advertised its block height yet.
+    pub fn sync_progress(&self) -> Option<f64> {
+        let max_height = self.node.peer_book.read().max_advertised_height();
+        if max_height == 0 {
+            return None;
+        }
+
+        let current_height = self.current_block_height();
+        if current_height >= max_height {
+            Some(1.0)
+        } else {
+            Some(current_height as f64 / max_height as f64)
+        }
+    }
+
+    /// Clears all the in-flight block requests and pending compact blocks, and allows
+    /// a new block sync to be initiated right away.
+    pub fn reset_sync(&self) {
+        self.requested_blocks.lock().clear();
+        self.pending_compact_blocks.lock().clear();
+
+        let elapsed = self.block_sync_interval() + Duration::from_secs(1);
+        *self.last_block_sync.write() = Instant::now().checked_sub(elapsed).unwrap_or_else(Instant::now);
+        self.finished_syncing_blocks();
+    }
+
+    /// Registers that stored orphans might have become connectable to the canon chain.
+    pub(crate) fn register_pending_orphans(&self) {
+        self.has_pending_orphans.store(true, Ordering::SeqCst);
+    }
+
+    /// Checks whether there might be stored orphans that are yet to be connected to the canon chain.
+    pub fn has_pending_orphans(&self) -> bool {
+        self.has_pending_orphans.load(Ordering::SeqCst)
+    }
+
+    /// Connects the next batch of pending orphans to the canon chain; this is done in bounded
+    /// passes, so that a long chain of orphans doesn't hold up the processing of other messages.
+    pub fn resolve_pending_orphans(&self) {
+        if !self.has_pending_orphans() {
+            return;
+        }
+
+        let remaining = self.consensus_parameters.resolve_orphans(
+            &self.dpc_parameters,
+            &self.storage,
+            &mut self.memory_pool.lock(),
+            MAX_ORPHANS_PER_PASS,
+        );
+
+        match remaining {
+            Ok(remaining) => {
+                if remaining != 0 {
+                    debug!("{} orphans remain to be connected to the canon chain", remaining);
+                }
+                self.has_pending_orphans.store(remaining != 0, Ordering::SeqCst);
+            }
+            Err(e) => {
+                warn!("Failed to connect the orphans to the canon chain: {}", e);
+                self.has_pending_orphans.store(false, Ordering::SeqCst);
+            }
+        }
     }
 
     /// Returns the interval between each transaction (memory pool) sync.
@@ -147,6 +475,364 @@ impl Consensus {
         self.transaction_sync_interval
     }
 
+    /// Registers the propagation latency of a newly received block, retaining
+    /// only the most recent `PROPAGATION_SAMPLE_COUNT` samples.
+    pub fn register_block_propagation(&self, latency: Duration) {
+        let mut latencies = self.block_propagation_latencies.lock();
+
+        if latencies.len() >= crate::PROPAGATION_SAMPLE_COUNT {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency.as_millis() as u64);
+    }
+
+    /// Returns the propagation latencies (in milliseconds) of the most recently received blocks.
+    pub fn block_propagation_latencies(&self) -> Vec<u64> {
+        self.block_propagation_latencies.lock().iter().copied().collect()
+    }
+
+    /// Registers a received transaction that was already in the memory pool or being verified.
+    pub fn register_duplicate_transaction(&self) {
+        self.duplicate_transactions.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the number of received transactions that were skipped, as they were already in the memory pool
+    /// or being verified.
+    pub fn duplicate_transactions(&self) -> u64 {
+        self.duplicate_transactions.load(Ordering::SeqCst)
+    }
+
+    /// Waits for a free transaction verification slot, which is released once the returned permit is dropped.
+    pub(crate) async fn transaction_verification_permit(&self) -> OwnedSemaphorePermit {
+        Arc::clone(&self.transaction_verifications)
+            .acquire_owned()
+            .await
+            .expect("the transaction verification semaphore is never closed")
+    }
+
+    /// Registers the transaction with the given ID as being verified; returns `false` if it already was.
+    pub(crate) fn start_verifying_transaction(&self, transaction_id: Vec<u8>) -> bool {
+        self.verifying_transactions.lock().insert(transaction_id)
+    }
+
+    /// Registers the verification of the transaction with the given ID as finished.
+    pub(crate) fn finish_verifying_transaction(&self, transaction_id: &[u8]) {
+        self.verifying_transactions.lock().remove(transaction_id);
+    }
+
+    /// Registers the given block hashes as requested, returning only the ones that weren't
+    /// already in flight; requests older than `BLOCK_REQUEST_TIMEOUT_SECS` are considered expired.
+    pub fn register_block_requests(&self, hashes: &[BlockHeaderHash]) -> Vec<BlockHeaderHash> {
+        let mut requested_blocks = self.requested_blocks.lock();
+        let timeout = Duration::from_secs(crate::BLOCK_REQUEST_TIMEOUT_SECS);
+
+        // Forget the requests that have timed out.
+        requested_blocks.retain(|_, requested_at| requested_at.elapsed() < timeout);
+
+        let now = Instant::now();
+        let mut new_requests = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            if !requested_blocks.contains_key(hash) {
+                requested_blocks.insert(hash.clone(), now);
+                new_requests.push(hash.clone());
+            }
+        }
+
+        new_requests
+    }
+
+    /// Registers the receipt of a block, clearing its in-flight request, if there was one.
+    pub fn received_requested_block(&self, hash: &BlockHeaderHash) {
+        self.requested_blocks.lock().remove(hash);
+    }
+
+    /// Checks whether a block with the given hash is currently being requested.
+    pub fn is_block_requested(&self, hash: &BlockHeaderHash) -> bool {
+        self.requested_blocks.lock().contains_key(hash)
+    }
+
+    /// Checks whether any requested blocks are still in flight, i.e. were requested less than
+    /// `BLOCK_REQUEST_TIMEOUT_SECS` ago and haven't arrived yet.
+    pub fn has_block_requests_in_flight(&self) -> bool {
+        let timeout = Duration::from_secs(crate::BLOCK_REQUEST_TIMEOUT_SECS);
+
+        self.requested_blocks
+            .lock()
+            .values()
+            .any(|requested_at| requested_at.elapsed() < timeout)
+    }
+
+    /// Checks whether the node is catching up with the network, i.e. whether a connected peer advertised
+    /// a higher block height than the node's or there are block requests in flight.
+    pub fn is_syncing(&self) -> bool {
+        self.node.peer_book.read().max_advertised_height() > self.current_block_height()
+            || self.has_block_requests_in_flight()
+    }
+
+    /// Returns the number of blocks currently requested from peers and the approximate size of their records in bytes.
+    pub fn requested_blocks_stats(&self) -> (usize, usize) {
+        let count = self.requested_blocks.lock().len();
+
+        (count, count * mem::size_of::<(BlockHeaderHash, Instant)>())
+    }
+
+    /// Returns the number of recently propagated blocks and the approximate size of their records in bytes.
+    pub fn propagated_blocks_stats(&self) -> (usize, usize) {
+        let count = self.propagated_blocks.lock().len();
+
+        (count, count * mem::size_of::<(BlockHeaderHash, Instant)>())
+    }
+
+    /// Returns the number of compact blocks awaiting their missing transactions and their approximate size in bytes.
+    pub fn pending_compact_blocks_stats(&self) -> (usize, usize) {
+        let pending_compact_blocks = self.pending_compact_blocks.lock();
+
+        let size = pending_compact_blocks
+            .values()
+            .map(|(compact_block, _)| {
+                mem::size_of::<(BlockHeaderHash, (CompactBlock, Instant))>()
+                    + compact_block.transaction_ids.len() * mem::size_of::<[u8; 32]>()
+                    + compact_block
+                        .prefilled_transactions
+                        .iter()
+                        .map(|transaction| transaction.size())
+                        .sum::<usize>()
+            })
+            .sum();
+
+        (pending_compact_blocks.len(), size)
+    }
+
+    /// Registers the propagation of a block, returning `false` if it was already propagated
+    /// within the last `BLOCK_PROPAGATION_WINDOW_SECS`.
+    pub fn register_propagated_block(&self, hash: BlockHeaderHash) -> bool {
+        let mut propagated_blocks = self.propagated_blocks.lock();
+        let window = Duration::from_secs(crate::BLOCK_PROPAGATION_WINDOW_SECS);
+
+        // Forget the propagations that are outside of the window.
+        propagated_blocks.retain(|_, propagated_at| propagated_at.elapsed() < window);
+
+        match propagated_blocks.entry(hash) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(Instant::now());
+                true
+            }
+        }
+    }
+
+    /// Checks whether a transaction with the given program commitment may be admitted to the memory pool.
+    pub fn is_program_commitment_authorized(&self, program_commitment: &[u8]) -> bool {
+        self.authorized_program_commitments.is_empty()
+            || self
+                .authorized_program_commitments
+                .iter()
+                .any(|commitment| commitment == program_commitment)
+    }
+
+    /// Returns the minimum fee a transaction must pay to be accepted into the memory pool, if any.
+    pub fn min_relay_fee(&self) -> Option<i64> {
+        self.min_relay_fee
+    }
+
+    /// Checks whether the given transaction pays at least the minimum relay fee.
+    pub fn meets_min_relay_fee(&self, transaction: &Tx) -> bool {
+        match self.min_relay_fee {
+            Some(min_relay_fee) => transaction_fee(transaction) >= min_relay_fee,
+            None => true,
+        }
+    }
+
+    /// Returns the number of most recent blocks whose coinbase outputs may not be spent yet, if any.
+    pub fn fee_sniping_depth(&self) -> Option<u32> {
+        self.fee_sniping_depth
+    }
+
+    /// Checks whether the given transaction looks like a fee-sniping construction, i.e. whether it spends
+    /// the coinbase outputs of one of the last `fee_sniping_depth` blocks; always `false` if the policy
+    /// is disabled.
+    ///
+    /// This is a heuristic: the serial numbers revealed by a transaction can't be linked to the records
+    /// it spends without the owners' keys, so only the spends registered with `register_spent_records`
+    /// (e.g. the transactions created by this node) are recognized.
+    pub fn is_fee_sniping(&self, transaction: &Tx, storage: &MerkleTreeLedger) -> Result<bool, StorageError> {
+        let depth = match self.fee_sniping_depth {
+            Some(depth) => depth,
+            None => return Ok(false),
+        };
+
+        let current_height = storage.get_current_block_height();
+        let coinbase_spends = self.coinbase_spends.lock();
+        for serial_number in transaction.old_serial_numbers() {
+            if let Some(height) = coinbase_spends.get(&to_bytes![serial_number]?) {
+                if current_height.saturating_sub(*height) < depth {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Registers the spends of the given records, which are the ones spent by the given transaction, in order,
+    /// so that the memory pool can link it to the transactions creating them; the spends of coinbase outputs
+    /// are also registered for the fee-sniping policy, if it's enabled.
+    pub fn register_spent_records(
+        &self,
+        records: &[DPCRecord<Components>],
+        transaction: &Tx,
+        storage: &MerkleTreeLedger,
+    ) -> Result<(), StorageError> {
+        {
+            let mut memory_pool = self.memory_pool().lock();
+            for (record, serial_number) in records.iter().zip(transaction.old_serial_numbers()) {
+                memory_pool.register_record_spend(to_bytes![serial_number]?, to_bytes![record.commitment()]?);
+            }
+        }
+
+        let depth = match self.fee_sniping_depth {
+            Some(depth) => depth,
+            None => return Ok(()),
+        };
+
+        // Only the outputs of the coinbase transactions of the last `depth` blocks are of interest.
+        let current_height = storage.get_current_block_height();
+        let mut coinbase_outputs = HashMap::new();
+        for height in (current_height + 1).saturating_sub(depth)..=current_height {
+            let block = storage.get_block(&storage.get_block_hash(height)?)?;
+            for coinbase in block.transactions.iter().filter(|tx| tx.value_balance.is_negative()) {
+                for commitment in coinbase.new_commitments() {
+                    coinbase_outputs.insert(to_bytes![commitment]?, height);
+                }
+            }
+        }
+
+        let mut coinbase_spends = self.coinbase_spends.lock();
+        coinbase_spends.retain(|_, height| current_height.saturating_sub(*height) < depth);
+
+        for (record, serial_number) in records.iter().zip(transaction.old_serial_numbers()) {
+            if record.is_dummy() {
+                continue;
+            }
+            if let Some(height) = coinbase_outputs.get(&to_bytes![record.commitment()]?) {
+                coinbase_spends.insert(to_bytes![serial_number]?, *height);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Registers a compact block that couldn't be reconstructed until its missing transactions arrive;
+    /// pending blocks older than `BLOCK_REQUEST_TIMEOUT_SECS` are discarded.
+    pub fn register_pending_compact_block(&self, compact_block: CompactBlock) {
+        let mut pending_compact_blocks = self.pending_compact_blocks.lock();
+        let timeout = Duration::from_secs(crate::BLOCK_REQUEST_TIMEOUT_SECS);
+
+        pending_compact_blocks.retain(|_, (_, requested_at)| requested_at.elapsed() < timeout);
+        pending_compact_blocks.insert(compact_block.header.get_hash(), (compact_block, Instant::now()));
+    }
+
+    /// Fills the pending compact blocks with the given transactions, returning the ones that may now
+    /// be complete; these are no longer considered pending.
+    pub fn fill_pending_compact_blocks(&self, transactions: &[Tx]) -> Vec<CompactBlock> {
+        let mut pending_compact_blocks = self.pending_compact_blocks.lock();
+
+        let filled_hashes = pending_compact_blocks
+            .iter_mut()
+            .filter_map(|(hash, (compact_block, _))| {
+                if compact_block.fill(transactions) {
+                    Some(hash.clone())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        filled_hashes
+            .iter()
+            .filter_map(|hash| pending_compact_blocks.remove(hash))
+            .map(|(compact_block, _)| compact_block)
+            .collect()
+    }
+
+    /// Checks whether a compact block with the given hash is awaiting its missing transactions.
+    pub fn is_compact_block_pending(&self, hash: &BlockHeaderHash) -> bool {
+        self.pending_compact_blocks.lock().contains_key(hash)
+    }
+
+    /// Returns the serialized block with the given hash, consulting the block cache before the storage.
+    pub fn get_serialized_block(&self, hash: &BlockHeaderHash) -> Result<Vec<u8>, NetworkError> {
+        let cached = self.block_cache.lock().get(hash);
+        if let Some(block) = cached {
+            return Ok(block);
+        }
+
+        let block = self.storage.get_block(hash)?.serialize()?;
+        self.block_cache.lock().insert(hash.clone(), block.clone());
+
+        Ok(block)
+    }
+
+    /// Returns the number of requested blocks that were served from the block cache.
+    pub fn block_cache_hits(&self) -> u64 {
+        self.block_cache.lock().hits()
+    }
+
+    /// Registers the arrival of a block, returning its state if it was recently received already.
+    pub(crate) fn register_seen_block(&self, hash: BlockHeaderHash) -> Option<SeenBlock> {
+        self.seen_blocks.lock().register(hash)
+    }
+
+    /// Records the outcome of processing a received block; if `None`, the block couldn't be processed,
+    /// and is processed again the next time it's received.
+    pub(crate) fn set_seen_block_validity(&self, hash: &BlockHeaderHash, is_valid: Option<bool>) {
+        let mut seen_blocks = self.seen_blocks.lock();
+        match is_valid {
+            Some(is_valid) => seen_blocks.set_validity(hash, is_valid),
+            None => seen_blocks.forget(hash),
+        }
+    }
+
+    /// Counts a block received from a peer, both for this node and in the metrics.
+    pub(crate) fn register_block_outcome(&self, outcome: BlockOutcome) {
+        self.received_blocks[outcome as usize].fetch_add(1, Ordering::SeqCst);
+        Metrics::received_block_inc(outcome.name());
+    }
+
+    /// Returns the number of blocks received from peers with the given outcome.
+    pub fn received_blocks(&self, outcome: BlockOutcome) -> u64 {
+        self.received_blocks[outcome as usize].load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of received blocks that were skipped, as they were recently received already.
+    pub fn duplicate_blocks(&self) -> u64 {
+        self.seen_blocks.lock().duplicates()
+    }
+
+    /// Returns the block locator hashes of the canon chain; they're only recomputed from the storage once its
+    /// tip changes, which includes reorganizations, as the new tip has a different hash even at the same height.
+    pub fn block_locator_hashes(&self) -> Result<Vec<BlockHeaderHash>, NetworkError> {
+        let tip = self.storage.get_block_hash(self.storage.get_current_block_height())?;
+
+        let mut block_locator = self.block_locator.lock();
+        if let Some((cached_tip, hashes)) = &*block_locator {
+            if *cached_tip == tip {
+                self.block_locator_hits.fetch_add(1, Ordering::SeqCst);
+                return Ok(hashes.clone());
+            }
+        }
+
+        let hashes = self.storage.get_block_locator_hashes()?;
+        *block_locator = Some((tip, hashes.clone()));
+
+        Ok(hashes)
+    }
+
+    /// Returns the number of block locators that were served from the cache.
+    pub fn block_locator_hits(&self) -> u64 {
+        self.block_locator_hits.load(Ordering::SeqCst)
+    }
+
     pub fn max_block_size(&self) -> usize {
         self.consensus_parameters.max_block_size
     }
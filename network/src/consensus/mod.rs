@@ -17,8 +17,14 @@
 pub mod consensus;
 pub use consensus::*;
 
+pub mod block_cache;
+pub use block_cache::*;
+
 pub mod blocks;
 pub use blocks::*;
 
+pub mod seen_blocks;
+pub use seen_blocks::*;
+
 pub mod transactions;
 pub use transactions::*;
@@ -0,0 +1,172 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm_objects::BlockHeaderHash;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// The state of a recently received block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeenBlock {
+    /// The block is still being processed.
+    Processing,
+    /// The block was found to be valid.
+    Valid,
+    /// The block was found to be invalid.
+    Invalid,
+}
+
+/// A bounded record of recently received blocks, so that a block arriving from several peers is only processed
+/// once. Entries expire after a while, and the oldest ones are evicted first if the record is full.
+#[derive(Debug)]
+pub struct SeenBlocks {
+    /// The maximum number of recorded blocks.
+    capacity: usize,
+    /// The time after which a recorded block is forgotten.
+    expiry: Duration,
+    /// The recorded blocks, along with the time they were first received.
+    blocks: HashMap<BlockHeaderHash, (SeenBlock, Instant)>,
+    /// The hashes of the recorded blocks, from the oldest to the most recently received one.
+    order: VecDeque<BlockHeaderHash>,
+    /// The number of received blocks that were already recorded.
+    duplicates: u64,
+}
+
+impl SeenBlocks {
+    /// Creates an empty record holding up to `capacity` blocks for the given time.
+    pub fn new(capacity: usize, expiry: Duration) -> Self {
+        Self {
+            capacity,
+            expiry,
+            blocks: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            duplicates: 0,
+        }
+    }
+
+    /// Registers the arrival of the block with the given hash, returning its state if it was already seen;
+    /// otherwise, it's recorded as being processed.
+    pub fn register(&mut self, hash: BlockHeaderHash) -> Option<SeenBlock> {
+        // The blocks are received in order, so the expired ones are the oldest ones.
+        while let Some(oldest) = self.order.front() {
+            let is_expired = match self.blocks.get(oldest) {
+                Some((_, seen_at)) => seen_at.elapsed() >= self.expiry,
+                None => true,
+            };
+            if !is_expired {
+                break;
+            }
+            if let Some(oldest) = self.order.pop_front() {
+                self.blocks.remove(&oldest);
+            }
+        }
+
+        if let Some((state, _)) = self.blocks.get(&hash) {
+            self.duplicates += 1;
+            return Some(*state);
+        }
+
+        if self.capacity == 0 {
+            return None;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.blocks.remove(&oldest);
+            }
+        }
+        self.blocks
+            .insert(hash.clone(), (SeenBlock::Processing, Instant::now()));
+        self.order.push_back(hash);
+
+        None
+    }
+
+    /// Records whether the block with the given hash turned out to be valid.
+    pub fn set_validity(&mut self, hash: &BlockHeaderHash, is_valid: bool) {
+        if let Some((state, _)) = self.blocks.get_mut(hash) {
+            *state = if is_valid { SeenBlock::Valid } else { SeenBlock::Invalid };
+        }
+    }
+
+    /// Forgets the block with the given hash, so that it's processed again the next time it's received.
+    pub fn forget(&mut self, hash: &BlockHeaderHash) {
+        if self.blocks.remove(hash).is_some() {
+            self.order.retain(|seen| seen != hash);
+        }
+    }
+
+    /// Returns the number of recorded blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Checks whether the record is empty.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns the number of received blocks that were already recorded.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(i: u8) -> BlockHeaderHash {
+        BlockHeaderHash::new(vec![i; 32])
+    }
+
+    #[test]
+    fn repeated_blocks_are_recognized() {
+        let mut seen_blocks = SeenBlocks::new(2, Duration::from_secs(60));
+
+        assert_eq!(seen_blocks.register(hash(0)), None);
+        assert_eq!(seen_blocks.register(hash(0)), Some(SeenBlock::Processing));
+        seen_blocks.set_validity(&hash(0), true);
+        assert_eq!(seen_blocks.register(hash(0)), Some(SeenBlock::Valid));
+        assert_eq!(seen_blocks.duplicates(), 2);
+
+        seen_blocks.forget(&hash(0));
+        assert_eq!(seen_blocks.register(hash(0)), None);
+    }
+
+    #[test]
+    fn oldest_block_is_evicted() {
+        let mut seen_blocks = SeenBlocks::new(2, Duration::from_secs(60));
+
+        seen_blocks.register(hash(0));
+        seen_blocks.register(hash(1));
+        seen_blocks.register(hash(2));
+
+        assert_eq!(seen_blocks.len(), 2);
+        assert_eq!(seen_blocks.register(hash(0)), None);
+    }
+
+    #[test]
+    fn blocks_expire() {
+        let mut seen_blocks = SeenBlocks::new(2, Duration::from_millis(0));
+
+        seen_blocks.register(hash(0));
+        assert_eq!(seen_blocks.register(hash(0)), None);
+    }
+}
@@ -15,14 +15,20 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{message::*, peers::PeerInfo, Consensus, NetworkError};
-use snarkos_consensus::memory_pool::Entry;
+use snarkos_consensus::{error::ConsensusError, memory_pool::Entry};
 use snarkvm_dpc::base_dpc::instantiated::Tx;
+use snarkvm_objects::Transaction;
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
 };
 
-use std::{collections::HashMap, net::SocketAddr};
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::Arc,
+};
+use tokio::task;
 
 impl Consensus {
     ///
@@ -40,7 +46,7 @@ impl Consensus {
     }
 
     /// Broadcast transaction to connected peers
-    pub(crate) async fn propagate_transaction(
+    pub async fn propagate_transaction(
         &self,
         transaction_bytes: Vec<u8>,
         transaction_sender: SocketAddr,
@@ -49,8 +55,14 @@ impl Consensus {
         debug!("Propagating a transaction to peers");
 
         let local_address = self.node().local_address().unwrap();
+        let transaction = Tx::read(&*transaction_bytes).ok();
+
+        for (remote_address, peer_info) in connected_peers {
+            // Peers that loaded a bloom filter are only sent the transactions matching it.
+            if matches!(&transaction, Some(tx) if !peer_info.wants_transaction(tx)) {
+                continue;
+            }
 
-        for remote_address in connected_peers.keys() {
             if *remote_address != transaction_sender && *remote_address != local_address {
                 // Send a `Transaction` message to the connected peer.
                 self.node()
@@ -66,6 +78,25 @@ impl Consensus {
         Ok(())
     }
 
+    /// Processes a received transaction in a separate task, so that a burst of them doesn't hold up other
+    /// messages; the number of transactions verified at once is bounded, and the ones past the limit wait
+    /// for a free slot before the next message is processed.
+    pub(crate) async fn spawn_received_transaction(
+        self: Arc<Self>,
+        source: SocketAddr,
+        transaction: Vec<u8>,
+        connected_peers: HashMap<SocketAddr, PeerInfo>,
+    ) {
+        let permit = self.transaction_verification_permit().await;
+
+        task::spawn(async move {
+            if let Err(e) = self.received_transaction(source, transaction, connected_peers).await {
+                error!("Failed to process a transaction from {}: {}", source, e);
+            }
+            drop(permit);
+        });
+    }
+
     /// Verify a transaction, add it to the memory pool, propagate it to peers.
     pub(crate) async fn received_transaction(
         &self,
@@ -74,50 +105,169 @@ impl Consensus {
         connected_peers: HashMap<SocketAddr, PeerInfo>,
     ) -> Result<(), NetworkError> {
         if let Ok(tx) = Tx::read(&*transaction) {
-            let insertion = {
-                let parameters = self.dpc_parameters();
-                let storage = self.storage();
-                let consensus = self.consensus_parameters();
-
-                if !consensus.verify_transaction(parameters, &tx, storage)? {
-                    error!("Received a transaction that was invalid");
+            // The same transaction is likely to arrive from many peers at once; skip the costly verification
+            // if it's already in the memory pool or being verified.
+            let transaction_id = tx.transaction_id().ok().map(|transaction_id| transaction_id.to_vec());
+            if let Some(ref transaction_id) = transaction_id {
+                if self.memory_pool().lock().transactions.contains_key(transaction_id)
+                    || !self.start_verifying_transaction(transaction_id.clone())
+                {
+                    trace!("Received a transaction that is already in the memory pool");
+                    self.register_duplicate_transaction();
                     return Ok(());
                 }
+            }
+
+            let result = self
+                .verify_and_insert_transaction(source, tx, transaction, connected_peers)
+                .await;
+
+            if let Some(transaction_id) = transaction_id {
+                self.finish_verifying_transaction(&transaction_id);
+            }
+
+            return result;
+        }
+
+        Ok(())
+    }
+
+    /// Verifies a transaction submitted to this node directly; it waits for a free verification slot, so
+    /// that it's bounded together with the transactions received from peers.
+    pub async fn verify_submitted_transaction(
+        &self,
+        tx: Tx,
+    ) -> Result<(Tx, Result<bool, ConsensusError>), task::JoinError> {
+        let _permit = self.transaction_verification_permit().await;
+
+        self.spawn_transaction_verification(tx).await
+    }
+
+    /// The verification is the costly part, so it's performed on a blocking thread, without holding any locks.
+    fn spawn_transaction_verification(&self, tx: Tx) -> task::JoinHandle<(Tx, Result<bool, ConsensusError>)> {
+        let consensus = Arc::clone(self.consensus_parameters());
+        let parameters = Arc::clone(self.dpc_parameters());
+        let storage = Arc::clone(self.storage());
+
+        task::spawn_blocking(move || {
+            let is_valid = consensus.verify_transaction(&parameters, &tx, &storage);
+            (tx, is_valid)
+        })
+    }
 
-                if tx.value_balance.is_negative() {
-                    error!("Received a transaction that was a coinbase transaction");
+    /// Verifies a received transaction and, if it's valid, adds it to the memory pool and propagates it to peers.
+    async fn verify_and_insert_transaction(
+        &self,
+        source: SocketAddr,
+        tx: Tx,
+        transaction: Vec<u8>,
+        connected_peers: HashMap<SocketAddr, PeerInfo>,
+    ) -> Result<(), NetworkError> {
+        let tx = match self.spawn_transaction_verification(tx).await {
+            Ok((tx, is_valid)) => {
+                if !is_valid? {
+                    error!("Received a transaction that was invalid");
                     return Ok(());
                 }
+                tx
+            }
+            Err(e) => {
+                error!("Failed to verify a received transaction: {}", e);
+                return Ok(());
+            }
+        };
+
+        let insertion = {
+            let storage = self.storage();
+
+            if tx.value_balance.is_negative() {
+                error!("Received a transaction that was a coinbase transaction");
+                return Ok(());
+            }
 
-                let entry = Entry::<Tx> {
-                    size_in_bytes: transaction.len(),
-                    transaction: tx,
-                };
+            if !self.is_program_commitment_authorized(&to_bytes![tx.program_commitment]?) {
+                debug!("Received a transaction with an unauthorized program commitment");
+                return Ok(());
+            }
+
+            if !self.meets_min_relay_fee(&tx) {
+                debug!("Received a transaction paying less than the minimum relay fee");
+                return Ok(());
+            }
 
-                self.memory_pool().lock().insert(storage, entry)
+            if self.is_fee_sniping(&tx, storage)? {
+                debug!("Received a transaction that looks like a fee-sniping construction");
+                return Ok(());
+            }
+
+            let entry = Entry::<Tx> {
+                size_in_bytes: transaction.len(),
+                transaction: tx,
             };
 
-            if let Ok(inserted) = insertion {
-                if inserted.is_some() {
-                    info!("Transaction added to memory pool.");
-                    self.propagate_transaction(transaction, source, &connected_peers)
-                        .await?;
-                }
+            // The ledger or the memory pool could have changed during the verification; the insertion checks
+            // the transaction for conflicts with both while holding the memory pool lock, so a transaction
+            // spending the same records can't slip in between the check and the insertion.
+            self.memory_pool().lock().insert(storage, entry)
+        };
+
+        if let Ok(inserted) = insertion {
+            if inserted.is_some() {
+                info!("Transaction added to memory pool.");
+                self.propagate_transaction(transaction, source, &connected_peers)
+                    .await?;
             }
         }
 
         Ok(())
     }
 
-    /// A peer has requested our memory pool transactions.
+    /// A peer has requested our memory pool transactions; they are sent in bounded batches, the highest-fee
+    /// ones first, so the ones that didn't fit are sent in response to the following requests.
     pub(crate) async fn received_get_memory_pool(&self, remote_address: SocketAddr) -> Result<(), NetworkError> {
-        // TODO (howardwu): This should have been written with Rayon - it is easily parallelizable.
+        let quality = self
+            .node()
+            .peer_book
+            .read()
+            .connected_peers()
+            .get(&remote_address)
+            .map(|peer_info| peer_info.quality.clone());
+
+        // Peers that loaded a bloom filter are only sent the transactions matching it.
+        let bloom_filter = quality.as_ref().and_then(|quality| quality.bloom_filter.read().clone());
+
         let transactions = {
+            let memory_pool = self.memory_pool().lock();
+            let mut sent_memory_pool = quality.as_ref().map(|quality| quality.sent_memory_pool.lock());
+            let mut untracked = HashSet::new();
+            let sent = sent_memory_pool.as_deref_mut().unwrap_or(&mut untracked);
+
+            // Forget the transactions that are no longer in the memory pool.
+            sent.retain(|txid| memory_pool.transactions.contains_key(txid));
+
+            let mut entries = memory_pool
+                .transactions
+                .iter()
+                .filter(|(txid, _)| !sent.contains(*txid))
+                .filter(|(_, entry)| {
+                    !matches!(&bloom_filter, Some(filter) if !filter.matches_transaction(&entry.transaction))
+                })
+                .collect::<Vec<_>>();
+            entries.sort_unstable_by_key(|(_, entry)| std::cmp::Reverse(entry.fee()));
+
             let mut txs = vec![];
+            let mut total_size = 0;
+            for (txid, entry) in entries {
+                if txs.len() == crate::MAX_MEMORY_POOL_RESPONSE_TRANSACTIONS {
+                    break;
+                }
 
-            let memory_pool = self.memory_pool().lock();
-            for entry in memory_pool.transactions.values() {
                 if let Ok(transaction_bytes) = to_bytes![entry.transaction] {
+                    if total_size + transaction_bytes.len() > crate::MAX_MEMORY_POOL_RESPONSE_SIZE {
+                        break;
+                    }
+                    total_size += transaction_bytes.len();
+                    sent.insert(txid.clone());
                     txs.push(transaction_bytes);
                 }
             }
@@ -14,11 +14,14 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::NetworkError;
+use crate::{peers::normalize_address, NetworkError};
 
-use rand::{thread_rng, Rng};
+use parking_lot::{Mutex, MutexGuard};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
 use std::{
     net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
     time::Duration,
     {self},
 };
@@ -40,6 +43,26 @@ pub struct Environment {
     is_bootnode: bool,
     /// The interval between each peer sync.
     peer_sync_interval: Duration,
+    /// The geographic regions or autonomous systems (ASNs) whose peers are preferred.
+    preferred_regions: Vec<String>,
+    /// The path to the MaxMind database used to resolve the locations of peers.
+    geoip_database: Option<PathBuf>,
+    /// The maximum size (in bytes) of a message accepted from a peer.
+    max_message_size: usize,
+    /// The maximum number of peer addresses shared in response to a `GetPeers` message.
+    max_peers_in_response: usize,
+    /// The maximum number of known addresses of a single peer dialed before giving up on it.
+    max_dial_addresses: usize,
+    /// The maximum moving average RTT of a peer; chronically slower peers are disconnected from.
+    max_peer_rtt: Option<Duration>,
+    /// The time within which a connection with a peer must be established, including the handshake.
+    handshake_timeout: Duration,
+    /// The maximum number of inbound connections waiting to be accepted by the listener.
+    listen_backlog: u32,
+    /// The maximum number of inbound connections accepted per second; the excess ones wait in the backlog.
+    max_inbound_connections_per_sec: Option<u32>,
+    /// The source of randomness for the node's handshake keys and nonces.
+    rng: Arc<Mutex<StdRng>>,
 }
 
 impl Environment {
@@ -57,12 +80,12 @@ impl Environment {
         let mut bootnodes = Vec::with_capacity(bootnodes_addresses.len());
         for bootnode_address in bootnodes_addresses.iter() {
             if let Ok(bootnode) = bootnode_address.parse::<SocketAddr>() {
-                bootnodes.push(bootnode);
+                bootnodes.push(normalize_address(bootnode));
             }
         }
 
         // Generate the node name.
-        let mut rng = thread_rng();
+        let mut rng = StdRng::from_entropy();
         let name = rng.gen();
 
         Ok(Self {
@@ -73,6 +96,16 @@ impl Environment {
             bootnodes,
             is_bootnode,
             peer_sync_interval,
+            preferred_regions: vec![],
+            geoip_database: None,
+            max_message_size: crate::MAX_MESSAGE_SIZE,
+            max_peers_in_response: crate::SHARED_PEER_COUNT,
+            max_dial_addresses: crate::MAX_DIAL_ADDRESSES,
+            max_peer_rtt: None,
+            handshake_timeout: Duration::from_secs(crate::HANDSHAKE_TIMEOUT_SECS),
+            listen_backlog: crate::LISTEN_BACKLOG,
+            max_inbound_connections_per_sec: None,
+            rng: Arc::new(Mutex::new(rng)),
         })
     }
 
@@ -116,4 +149,137 @@ impl Environment {
     pub fn peer_sync_interval(&self) -> Duration {
         self.peer_sync_interval
     }
+
+    /// Sets the geographic regions or autonomous systems (ASNs) whose peers are preferred.
+    #[inline]
+    pub fn set_preferred_regions(&mut self, preferred_regions: Vec<String>) {
+        self.preferred_regions = preferred_regions;
+    }
+
+    /// Returns the geographic regions or autonomous systems (ASNs) whose peers are preferred.
+    #[inline]
+    pub fn preferred_regions(&self) -> &[String] {
+        &self.preferred_regions
+    }
+
+    /// Sets the path to the MaxMind database used to resolve the locations of peers.
+    #[inline]
+    pub fn set_geoip_database(&mut self, geoip_database: Option<PathBuf>) {
+        self.geoip_database = geoip_database;
+    }
+
+    /// Returns the path to the MaxMind database used to resolve the locations of peers.
+    #[inline]
+    pub fn geoip_database(&self) -> Option<&Path> {
+        self.geoip_database.as_deref()
+    }
+
+    /// Sets the maximum size (in bytes) of a message accepted from a peer; it can't exceed `MAX_MESSAGE_SIZE`.
+    #[inline]
+    pub fn set_max_message_size(&mut self, max_message_size: usize) {
+        self.max_message_size = std::cmp::min(max_message_size, crate::MAX_MESSAGE_SIZE);
+    }
+
+    /// Returns the maximum size (in bytes) of a message accepted from a peer.
+    #[inline]
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Sets the maximum number of peer addresses shared in response to a `GetPeers` message.
+    #[inline]
+    pub fn set_max_peers_in_response(&mut self, max_peers_in_response: usize) {
+        self.max_peers_in_response = max_peers_in_response;
+    }
+
+    /// Returns the maximum number of peer addresses shared in response to a `GetPeers` message.
+    #[inline]
+    pub fn max_peers_in_response(&self) -> usize {
+        self.max_peers_in_response
+    }
+
+    /// Sets the maximum number of known addresses of a single peer dialed before giving up on it.
+    #[inline]
+    pub fn set_max_dial_addresses(&mut self, max_dial_addresses: usize) {
+        self.max_dial_addresses = max_dial_addresses.max(1);
+    }
+
+    /// Returns the maximum number of known addresses of a single peer dialed before giving up on it.
+    #[inline]
+    pub fn max_dial_addresses(&self) -> usize {
+        self.max_dial_addresses
+    }
+
+    /// Sets the maximum moving average RTT of a peer; chronically slower peers are disconnected from.
+    #[inline]
+    pub fn set_max_peer_rtt(&mut self, max_peer_rtt: Option<Duration>) {
+        self.max_peer_rtt = max_peer_rtt;
+    }
+
+    /// Returns the maximum moving average RTT of a peer; chronically slower peers are disconnected from.
+    #[inline]
+    pub fn max_peer_rtt(&self) -> Option<Duration> {
+        self.max_peer_rtt
+    }
+
+    /// Sets the time within which a connection with a peer must be established, including the handshake.
+    #[inline]
+    pub fn set_handshake_timeout(&mut self, handshake_timeout: Duration) {
+        self.handshake_timeout = handshake_timeout;
+    }
+
+    /// Returns the time within which a connection with a peer must be established, including the handshake.
+    #[inline]
+    pub fn handshake_timeout(&self) -> Duration {
+        self.handshake_timeout
+    }
+
+    /// Sets the maximum number of inbound connections waiting to be accepted by the listener.
+    #[inline]
+    pub fn set_listen_backlog(&mut self, listen_backlog: u32) {
+        self.listen_backlog = listen_backlog;
+    }
+
+    /// Returns the maximum number of inbound connections waiting to be accepted by the listener.
+    #[inline]
+    pub fn listen_backlog(&self) -> u32 {
+        self.listen_backlog
+    }
+
+    /// Sets the maximum number of inbound connections accepted per second; the excess ones wait in the backlog.
+    #[inline]
+    pub fn set_max_inbound_connections_per_sec(&mut self, max_inbound_connections_per_sec: Option<u32>) {
+        self.max_inbound_connections_per_sec = max_inbound_connections_per_sec;
+    }
+
+    /// Returns the maximum number of inbound connections accepted per second; the excess ones wait in the backlog.
+    #[inline]
+    pub fn max_inbound_connections_per_sec(&self) -> Option<u32> {
+        self.max_inbound_connections_per_sec
+    }
+
+    /// Seeds the source of randomness of the node and regenerates its name, making its handshake keys
+    /// and nonces reproducible; meant for tests.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.name = rng.gen();
+        self.rng = Arc::new(Mutex::new(rng));
+    }
+
+    /// Returns a new private key to be used in a handshake.
+    pub fn generate_static_key(&self) -> [u8; 32] {
+        let mut static_key = [0u8; 32];
+        self.rng.lock().fill_bytes(&mut static_key);
+        static_key
+    }
+
+    /// Returns a new random nonce.
+    pub fn generate_nonce(&self) -> u64 {
+        self.rng.lock().gen()
+    }
+
+    /// Returns the source of randomness of the node.
+    pub fn rng(&self) -> MutexGuard<'_, StdRng> {
+        self.rng.lock()
+    }
 }
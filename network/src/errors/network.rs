@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::Message;
+use crate::{DisconnectReason, Message};
 use snarkos_consensus::error::ConsensusError;
 use snarkos_storage::error::StorageError;
 use snarkvm_objects::BlockError;
@@ -27,7 +27,10 @@ pub enum NetworkError {
     BlockError(BlockError),
     CapnProto(capnp::Error),
     ConsensusError(ConsensusError),
+    HandshakeTimeout,
     Io(std::io::Error),
+    IncompatibleProtocolVersion(u32),
+    InvalidBloomFilter,
     InvalidHandshake,
     MessageTooBig(usize),
     Noise(snow::error::Error),
@@ -42,6 +45,7 @@ pub enum NetworkError {
     SelfConnectAttempt,
     SenderError(tokio::sync::mpsc::error::SendError<Message>),
     TooManyConnections,
+    TooManyPeersInASN(u32),
     OutboundChannelMissing,
     ReceiverFailedToParse,
     StorageError(StorageError),
@@ -63,6 +67,17 @@ impl NetworkError {
             _ => false,
         }
     }
+
+    /// Returns the reason for disconnecting from a peer due to this error.
+    pub fn disconnect_reason(&self) -> DisconnectReason {
+        match self {
+            Self::HandshakeTimeout => DisconnectReason::Timeout,
+            Self::Io(err) if err.kind() == ErrorKind::TimedOut => DisconnectReason::Timeout,
+            Self::Io(_) => DisconnectReason::PeerRequested,
+            Self::TooManyConnections | Self::TooManyPeersInASN(_) => DisconnectReason::TooManyPeers,
+            _ => DisconnectReason::ProtocolViolation,
+        }
+    }
 }
 
 impl From<capnp::Error> for NetworkError {
@@ -27,6 +27,8 @@ pub struct ConnReader {
     buffer: Box<[u8]>,
     noise_buffer: Box<[u8]>,
     noise: Arc<Mutex<snow::TransportState>>,
+    /// The maximum accepted size of a message (in bytes).
+    max_message_size: usize,
 }
 
 impl ConnReader {
@@ -35,6 +37,7 @@ impl ConnReader {
         reader: OwnedReadHalf,
         buffer: Box<[u8]>,
         noise: Arc<Mutex<snow::TransportState>>,
+        max_message_size: usize,
     ) -> Self {
         Self {
             addr,
@@ -42,6 +45,7 @@ impl ConnReader {
             noise_buffer: vec![0u8; crate::NOISE_BUF_LEN].into(),
             buffer,
             noise,
+            max_message_size,
         }
     }
 
@@ -51,7 +55,7 @@ impl ConnReader {
         self.reader.read_exact(&mut header_arr).await?;
         let header = MessageHeader::from(header_arr);
 
-        if header.len as usize > crate::MAX_MESSAGE_SIZE {
+        if header.len as usize > self.max_message_size {
             Err(NetworkError::MessageTooBig(header.len as usize))
         } else if header.len == 0 {
             Err(NetworkError::ZeroLengthMessage)
@@ -60,8 +64,8 @@ impl ConnReader {
         }
     }
 
-    /// Reads a message header + payload.
-    pub async fn read_message(&mut self) -> Result<Message, NetworkError> {
+    /// Reads a message header + payload; returns the message along with the number of bytes read.
+    pub async fn read_message(&mut self) -> Result<(Message, usize), NetworkError> {
         let header = self.read_header().await?;
         let len = header.len();
         let mut decrypted_len = 0;
@@ -83,6 +87,8 @@ impl ConnReader {
 
         debug!("Received a '{}' message from {}", payload, self.addr);
 
-        Ok(Message::new(Direction::Inbound(self.addr), payload))
+        let read_len = header.as_bytes().len() + len;
+
+        Ok((Message::new(Direction::Inbound(self.addr), payload), read_len))
     }
 }
@@ -14,22 +14,35 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{errors::NetworkError, message::*, ConnReader, ConnWriter, Environment, Receiver, Sender};
+use crate::{
+    errors::NetworkError,
+    message::*,
+    peers::normalize_address,
+    ConnReader,
+    ConnWriter,
+    Environment,
+    PeerBook,
+    Receiver,
+    Sender,
+};
+use snarkos_metrics::Metrics;
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     net::SocketAddr,
     sync::{atomic::AtomicU64, Arc},
+    time::{Duration, Instant},
 };
 
 use parking_lot::{Mutex, RwLock};
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    net::{TcpListener, TcpStream},
+    net::{TcpSocket, TcpStream},
     task::{
         JoinHandle,
         {self},
     },
+    time::{sleep, timeout},
 };
 
 /// The map of remote addresses to their active writers.
@@ -44,18 +57,22 @@ pub struct Inbound {
     receiver: Arc<Mutex<Option<Receiver>>>,
     /// The map of remote addresses to their active read channels.
     channels: Arc<RwLock<Channels>>,
+    /// The list of connected and disconnected peers of this node.
+    peer_book: Arc<RwLock<PeerBook>>,
     /// A counter for the number of received responses the handler processes.
     receive_response_count: Arc<AtomicU64>,
     /// A counter for the number of received responses that succeeded.
     receive_success_count: Arc<AtomicU64>,
     /// A counter for the number of received responses that failed.
     receive_failure_count: Arc<AtomicU64>,
+    /// The maximum accepted size of an inbound message (in bytes).
+    max_message_size: usize,
     /// The tasks dedicated to handling inbound messages.
     pub(crate) tasks: Arc<Mutex<HashMap<SocketAddr, JoinHandle<()>>>>,
 }
 
 impl Inbound {
-    pub fn new(channels: Arc<RwLock<Channels>>) -> Self {
+    pub fn new(channels: Arc<RwLock<Channels>>, peer_book: Arc<RwLock<PeerBook>>, max_message_size: usize) -> Self {
         // Initialize the sender and receiver.
         let (sender, receiver) = tokio::sync::mpsc::channel(1024);
 
@@ -63,41 +80,72 @@ impl Inbound {
             sender,
             receiver: Arc::new(Mutex::new(Some(receiver))),
             channels,
+            peer_book,
             receive_response_count: Default::default(),
             receive_success_count: Default::default(),
             receive_failure_count: Default::default(),
+            max_message_size,
             tasks: Default::default(),
         }
     }
 
     pub async fn listen(&self, environment: &mut Environment) -> Result<(), NetworkError> {
-        let (listener_address, listener) = if let Some(addr) = environment.local_address() {
-            let listener = TcpListener::bind(&addr).await?;
-            (listener.local_addr()?, listener)
+        let addr = environment
+            .local_address()
+            .unwrap_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)));
+        let socket = if addr.is_ipv4() {
+            TcpSocket::new_v4()?
         } else {
-            let listener = TcpListener::bind("0.0.0.0:0").await?;
-            let listener_address = listener.local_addr()?;
-            (listener_address, listener)
+            TcpSocket::new_v6()?
         };
+        #[cfg(not(windows))]
+        socket.set_reuseaddr(true)?;
+        socket.bind(addr)?;
+        let listener = socket.listen(environment.listen_backlog())?;
+        let listener_address = listener.local_addr()?;
         environment.set_local_address(listener_address);
         info!("Node {:x} listening at {}", environment.name, listener_address);
 
         let inbound = self.clone();
+        let environment = environment.clone();
         task::spawn(async move {
+            let mut recent_accepts = VecDeque::new();
+
             loop {
+                // During a burst of connections, the ones over the permitted rate wait in the backlog.
+                if let Some(max_per_sec) = environment.max_inbound_connections_per_sec() {
+                    if let Some(delay) = Self::accept_delay(&mut recent_accepts, max_per_sec) {
+                        debug!("Throttling inbound connections for {:?}", delay);
+                        sleep(delay).await;
+                    }
+                }
+
                 match listener.accept().await {
                     Ok((stream, remote_address)) => {
+                        recent_accepts.push_back(Instant::now());
+                        let remote_address = normalize_address(remote_address);
                         info!("Got a connection request from {}", remote_address);
 
-                        match inbound
-                            .connection_request(listener_address, remote_address, stream)
+                        let static_key = environment.generate_static_key();
+                        let handshake =
+                            inbound.connection_request(listener_address, remote_address, stream, &static_key);
+                        match timeout(environment.handshake_timeout(), handshake)
                             .await
+                            .unwrap_or(Err(NetworkError::HandshakeTimeout))
                         {
                             Ok((channel, mut reader)) => {
                                 // update the remote address to be the peer's listening address
                                 let remote_address = channel.addr;
-                                // Save the channel under the provided remote address
-                                inbound.channels.write().insert(remote_address, Arc::new(channel));
+                                // Save the channel under the provided remote address, unless a simultaneous
+                                // outbound connection with the peer got there first; the older one is kept
+                                {
+                                    let mut channels = inbound.channels.write();
+                                    if channels.contains_key(&remote_address) {
+                                        debug!("Dropping a redundant connection with {}", remote_address);
+                                        continue;
+                                    }
+                                    channels.insert(remote_address, Arc::new(channel));
+                                }
 
                                 let inbound_clone = inbound.clone();
                                 let task = tokio::spawn(async move {
@@ -111,7 +159,10 @@ impl Inbound {
                                 // FIXME(ljedrz/nkls): this should be done immediately, bypassing the message channel
                                 let _ = inbound
                                     .sender
-                                    .send(Message::new(Direction::Internal, Payload::Disconnect(remote_address)))
+                                    .send(Message::new(
+                                        Direction::Internal,
+                                        Payload::Disconnect(remote_address, e.disconnect_reason()),
+                                    ))
                                     .await;
                             }
                         }
@@ -124,6 +175,27 @@ impl Inbound {
         Ok(())
     }
 
+    /// Returns the time to wait before accepting another connection so that no more than `max_per_sec`
+    /// are accepted within a second, given the times of the recently accepted ones; the ones older than
+    /// a second are forgotten.
+    fn accept_delay(recent_accepts: &mut VecDeque<Instant>, max_per_sec: u32) -> Option<Duration> {
+        let window = Duration::from_secs(1);
+        while let Some(accepted_at) = recent_accepts.front() {
+            if accepted_at.elapsed() < window {
+                break;
+            }
+            recent_accepts.pop_front();
+        }
+
+        if recent_accepts.len() < max_per_sec.max(1) as usize {
+            return None;
+        }
+
+        recent_accepts
+            .front()
+            .map(|oldest| window.checked_sub(oldest.elapsed()).unwrap_or_default())
+    }
+
     pub async fn listen_for_messages(&self, reader: &mut ConnReader) {
         let mut failure_count = 0u8;
         let mut disconnect_from_peer = false;
@@ -135,16 +207,33 @@ impl Inbound {
 
             // Read the next message from the channel. This is a blocking operation.
             let message = match reader.read_message().await {
-                Ok(message) => message,
+                Ok((message, len)) => {
+                    self.peer_book.read().register_bytes_received(reader.addr, len);
+                    Metrics::inbound_message_inc(message.payload.name());
+                    message
+                }
                 Err(error) => {
+                    // Oversized messages are treated as misbehavior.
+                    if let NetworkError::MessageTooBig(len) = error {
+                        warn!(
+                            "Peer {} sent a message exceeding the size limit ({}B)",
+                            reader.addr, len
+                        );
+                        self.peer_book.read().register_failure(reader.addr);
+                    }
+
+                    let reason = error.disconnect_reason();
                     Self::handle_failure(&mut failure, &mut failure_count, &mut disconnect_from_peer, error);
 
                     // Determine if we should send a disconnect message.
                     match disconnect_from_peer {
                         true => {
                             // FIXME(ljedrz/nkls): this should be done immediately, bypassing the message channel
-                            self.route(Message::new(Direction::Internal, Payload::Disconnect(reader.addr)))
-                                .await;
+                            self.route(Message::new(
+                                Direction::Internal,
+                                Payload::Disconnect(reader.addr, reason),
+                            ))
+                            .await;
 
                             // TODO (howardwu): Remove this and rearchitect how disconnects are handled using the peer manager.
                             // TODO (howardwu): Implement a handler so the node does not lose state of undetected disconnects.
@@ -210,13 +299,15 @@ impl Inbound {
     }
 
     ///
-    /// Handles an incoming connection request, performing a secure handshake and establishing packet encryption.
+    /// Handles an incoming connection request, performing a secure handshake with the given static
+    /// private key and establishing packet encryption.
     ///
     pub async fn connection_request(
         &self,
         listener_address: SocketAddr,
         remote_address: SocketAddr,
         stream: TcpStream,
+        static_key: &[u8],
     ) -> Result<(ConnWriter, ConnReader), NetworkError> {
         self.sender
             .send(Message::new(Direction::Internal, Payload::ConnectingTo(remote_address)))
@@ -230,8 +321,7 @@ impl Inbound {
                 .expect("Invalid noise handshake pattern!"),
             Box::new(snow::resolvers::SodiumResolver),
         );
-        let static_key = builder.generate_keypair()?.private;
-        let noise_builder = builder.local_private_key(&static_key).psk(3, crate::HANDSHAKE_PSK);
+        let noise_builder = builder.local_private_key(static_key).psk(3, crate::HANDSHAKE_PSK);
         let mut noise = noise_builder.build_responder()?;
         let mut buffer: Box<[u8]> = vec![0u8; crate::MAX_MESSAGE_SIZE].into();
         let mut buf = [0u8; crate::NOISE_BUF_LEN]; // a temporary intermediate buffer to decrypt from
@@ -264,18 +354,32 @@ impl Inbound {
         let peer_version = Version::deserialize(&buffer[..len])?;
         trace!("received s, se, psk (XX handshake part 3/3)");
 
+        // Reject peers that don't support the minimum protocol version.
+        let protocol_version = peer_version.negotiate()?;
+
         // the remote listening address
         let remote_listener = SocketAddr::from((remote_address.ip(), peer_version.listening_port));
 
         self.sender
             .send(Message::new(
                 Direction::Internal,
-                Payload::ConnectedTo(remote_address, Some(remote_listener)),
+                Payload::ConnectedTo(
+                    remote_address,
+                    Some(remote_listener),
+                    protocol_version,
+                    peer_version.user_agent(),
+                ),
             ))
             .await?;
 
         let noise = Arc::new(Mutex::new(noise.into_transport_mode()?));
-        let reader = ConnReader::new(remote_listener, reader, buffer.clone(), Arc::clone(&noise));
+        let reader = ConnReader::new(
+            remote_listener,
+            reader,
+            buffer.clone(),
+            Arc::clone(&noise),
+            self.max_message_size,
+        );
         let writer = ConnWriter::new(remote_listener, writer, buffer, noise);
 
         Ok((writer, reader))
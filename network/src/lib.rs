@@ -51,18 +51,90 @@ pub use peers::*;
 use crate::ConnWriter;
 
 use parking_lot::RwLock;
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::{task, time::sleep};
 
 pub const HANDSHAKE_PATTERN: &str = "Noise_XXpsk3_25519_ChaChaPoly_SHA256";
 pub const HANDSHAKE_PSK: &[u8] = b"b765e427e836e0029a1e2a22ba60c52a"; // the PSK must be 32B
+/// The version of the network protocol implemented by this node.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// The lowest version of the network protocol that peers must support in order to connect.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// The name and version of the client software advertised to peers during the handshake.
+pub const USER_AGENT: &str = concat!("snarkOS/", env!("CARGO_PKG_VERSION"));
+/// The maximum size of a message (in bytes); also the default limit for inbound messages.
 pub const MAX_MESSAGE_SIZE: usize = 8 * 1024 * 1024; // 8MiB
 pub const NOISE_BUF_LEN: usize = 65535;
 pub const NOISE_TAG_LEN: usize = 16;
 /// The maximum number of block hashes that can be requested or provided in a single batch.
 pub const MAX_BLOCK_SYNC_COUNT: u32 = 250;
-/// The maximum number of peers shared at once in response to a `GetPeers` message.
+/// The maximum number of hashes accepted in a block locator; the ones built by the node contain
+/// about 20 + log2(block height) of them.
+pub const MAX_LOCATOR_HASHES: usize = 64;
+/// The initial (and minimum) number of block hashes that can be requested or provided in a single batch.
+pub const MIN_BLOCK_SYNC_COUNT: u32 = 10;
+/// The maximum RTT (in milliseconds) for a peer to be trusted with larger block sync batches.
+pub const FAST_PEER_RTT_MS: u64 = 500;
+/// The default maximum number of peers shared at once in response to a `GetPeers` message.
 pub const SHARED_PEER_COUNT: usize = 25;
+/// The default maximum number of known addresses of a single peer dialed before giving up on it.
+pub const MAX_DIAL_ADDRESSES: usize = 3;
+/// The number of seconds after which an unanswered block request is considered to have timed out.
+pub const BLOCK_REQUEST_TIMEOUT_SECS: u64 = 30;
+/// The number of seconds during which an already propagated block is not propagated again.
+pub const BLOCK_PROPAGATION_WINDOW_SECS: u64 = 60;
+/// The number of most recent block propagation latencies retained for statistics.
+pub const PROPAGATION_SAMPLE_COUNT: usize = 100;
+/// The number of seconds over which the current throughput with a peer is computed.
+pub const THROUGHPUT_WINDOW_SECS: u64 = 10;
+/// The number of seconds without new failures after which a peer's failure count is decremented.
+pub const FAILURE_DECAY_SECS: u64 = 5 * 60;
+/// The number of seconds during which a peer that couldn't be connected to isn't dialed again.
+pub const DIAL_BACKOFF_SECS: u64 = 60;
+/// The number of seconds within which a connection with a peer must be established, including the handshake.
+pub const HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+/// The default maximum number of inbound connections waiting to be accepted by the listener.
+pub const LISTEN_BACKLOG: u32 = 1024;
+/// The maximum number of messages queued for sending to a single peer.
+pub const OUTBOUND_QUEUE_CAPACITY: usize = 1024;
+/// The number of invalid blocks a peer can send before it is disconnected from as misbehaving.
+pub const MAX_INVALID_BLOCKS: u8 = 3;
+/// The number of consecutive RTT measurements over the permitted maximum after which a peer is considered slow.
+pub const SLOW_PEER_RTT_SAMPLES: u8 = 5;
+/// The minimum number of seconds between two `GetPeers` requests from a peer; more frequent ones are ignored.
+pub const MIN_GET_PEERS_INTERVAL_SECS: u64 = 2;
+/// The number of seconds during which the same set of addresses isn't advertised to a peer again.
+pub const PEER_ADVERTISEMENT_WINDOW_SECS: u64 = 5 * 60;
+/// The maximum size (in bytes) of a bloom filter loaded by a peer with a `FilterLoad` message.
+pub const MAX_BLOOM_FILTER_SIZE: usize = 36_000;
+/// The maximum number of hash functions of a bloom filter loaded by a peer.
+pub const MAX_BLOOM_HASH_FUNCS: u32 = 50;
+/// The maximum size (in bytes) of an element added to a peer's bloom filter with a `FilterAdd` message.
+pub const MAX_FILTER_ELEMENT_SIZE: usize = 520;
+/// The maximum number of recently served serialized blocks kept in memory.
+pub const BLOCK_CACHE_SIZE: usize = 256;
+/// The maximum number of recently received blocks remembered, so that they're not processed again.
+pub const SEEN_BLOCKS_CAPACITY: usize = 1024;
+/// The number of seconds during which a received block is not processed again.
+pub const SEEN_BLOCK_EXPIRY_SECS: u64 = 5 * 60;
+/// The number of seconds between each flush of the memory pool to storage.
+pub const MEMORY_POOL_FLUSH_INTERVAL_SECS: u64 = 60;
+/// The number of seconds after which an unconfirmed transaction is removed from the memory pool.
+pub const MEMORY_POOL_EXPIRY_SECS: u64 = 24 * 60 * 60;
+/// The maximum number of transactions sent in a single `MemoryPool` message.
+pub const MAX_MEMORY_POOL_RESPONSE_TRANSACTIONS: usize = 500;
+/// The maximum total size (in bytes) of the transactions sent in a single `MemoryPool` message.
+pub const MAX_MEMORY_POOL_RESPONSE_SIZE: usize = MAX_MESSAGE_SIZE / 2;
+/// The default maximum number of received transactions verified concurrently.
+pub const MAX_CONCURRENT_TRANSACTION_VERIFICATIONS: usize = 4;
 
 pub(crate) type Sender = tokio::sync::mpsc::Sender<Message>;
 
@@ -82,25 +154,70 @@ pub struct Node {
     pub peer_book: Arc<RwLock<PeerBook>>,
     /// The objects related to consensus.
     pub consensus: Option<Arc<Consensus>>,
+    /// The number of bootnodes found to be reachable on startup.
+    bootnodes_reachable: Arc<AtomicUsize>,
+    /// The resolver of the locations of peers, if a GeoIP database is configured.
+    #[cfg(feature = "geoip")]
+    geoip: Option<Arc<GeoIpLookup>>,
 }
 
 impl Node {
     /// Creates a new instance of `Node`.
     pub async fn new(environment: Environment) -> Result<Self, NetworkError> {
         let channels: Arc<RwLock<HashMap<SocketAddr, Arc<ConnWriter>>>> = Default::default();
+        let peer_book: Arc<RwLock<PeerBook>> = Default::default();
         // Create the inbound and outbound handlers.
-        let inbound = Arc::new(Inbound::new(channels.clone()));
-        let outbound = Arc::new(Outbound::new(channels));
+        let inbound = Arc::new(Inbound::new(
+            channels.clone(),
+            peer_book.clone(),
+            environment.max_message_size(),
+        ));
+        let outbound = Arc::new(Outbound::new(channels, peer_book.clone()));
+
+        #[cfg(feature = "geoip")]
+        let geoip = match environment.geoip_database() {
+            Some(path) => match GeoIpLookup::open(path) {
+                Ok(geoip) => Some(Arc::new(geoip)),
+                Err(e) => {
+                    warn!("Couldn't open the GeoIP database at {}: {}", path.display(), e);
+                    None
+                }
+            },
+            None => None,
+        };
+        #[cfg(not(feature = "geoip"))]
+        if environment.geoip_database().is_some() {
+            warn!("A GeoIP database is configured, but snarkOS was built without the `geoip` feature");
+        }
 
         Ok(Self {
             environment,
             inbound,
             outbound,
-            peer_book: Default::default(),
+            peer_book,
             consensus: None,
+            bootnodes_reachable: Default::default(),
+            #[cfg(feature = "geoip")]
+            geoip,
         })
     }
 
+    /// Resolves the country and ASN of the given peer, if a GeoIP database is available.
+    #[cfg_attr(not(feature = "geoip"), allow(unused_variables))]
+    fn locate_peer(&self, address: SocketAddr) -> Option<PeerLocation> {
+        #[cfg(feature = "geoip")]
+        return self.geoip.as_ref().and_then(|geoip| geoip.locate(address.ip()));
+        #[cfg(not(feature = "geoip"))]
+        None
+    }
+
+    /// Records the ASN of the given peer in the peer book, if it can be resolved.
+    fn register_peer_asn(&self, address: SocketAddr) {
+        if let Some(asn) = self.locate_peer(address).and_then(|location| location.asn) {
+            self.peer_book.write().register_asn(address.ip(), asn);
+        }
+    }
+
     pub fn set_consensus(&mut self, consensus: Consensus) {
         self.consensus = Some(Arc::new(consensus));
     }
@@ -140,6 +257,13 @@ impl Node {
             }
         });
 
+        if !self.environment.is_bootnode() {
+            let self_clone = self.clone();
+            task::spawn(async move {
+                self_clone.probe_bootnodes().await;
+            });
+        }
+
         let self_clone = self.clone();
         let peer_sync_interval = self.environment.peer_sync_interval();
         task::spawn(async move {
@@ -155,7 +279,6 @@ impl Node {
 
         if !self.environment.is_bootnode() {
             if let Some(ref consensus) = self.consensus() {
-                let self_clone = self.clone();
                 let consensus = Arc::clone(consensus);
                 let transaction_sync_interval = consensus.transaction_sync_interval();
                 task::spawn(async move {
@@ -165,12 +288,30 @@ impl Node {
                         if !consensus.is_syncing_blocks() {
                             info!("Updating transactions");
 
-                            // select last seen node as block sync node
-                            let sync_node = self_clone.peer_book.read().last_seen();
+                            let sync_node = consensus.sync_node();
                             consensus.update_transactions(sync_node).await;
                         }
                     }
                 });
+
+                let consensus = Arc::clone(consensus);
+                task::spawn(async move {
+                    loop {
+                        sleep(Duration::from_secs(MEMORY_POOL_FLUSH_INTERVAL_SECS)).await;
+
+                        let expired = consensus
+                            .memory_pool()
+                            .lock()
+                            .expire(Duration::from_secs(MEMORY_POOL_EXPIRY_SECS));
+                        if !expired.is_empty() {
+                            debug!("Removed {} expired transactions from the memory pool", expired.len());
+                        }
+
+                        if let Err(e) = consensus.store_memory_pool() {
+                            error!("Memory pool flush error: {}", e);
+                        }
+                    }
+                });
             }
         }
     }
@@ -189,6 +330,20 @@ impl Node {
         self.environment.local_address()
     }
 
+    /// Returns the number of bootnodes found to be reachable on startup.
+    #[inline]
+    pub fn bootnodes_reachable(&self) -> usize {
+        self.bootnodes_reachable.load(Ordering::SeqCst)
+    }
+
+    /// Abandons any ongoing block sync, so that a new one can be started from peer selection.
+    pub fn reset_sync(&self) {
+        if let Some(ref consensus) = self.consensus() {
+            consensus.reset_sync();
+        }
+        self.peer_book.read().reset_sync_batches();
+    }
+
     async fn process_incoming_messages(&self, receiver: &mut Receiver) -> Result<(), NetworkError> {
         let Message { direction, payload } = receiver.recv().await.ok_or(NetworkError::ReceiverFailedToParse)?;
 
@@ -207,20 +362,45 @@ impl Node {
         match payload {
             Payload::ConnectingTo(remote_address) => {
                 if direction == Direction::Internal {
+                    self.register_peer_asn(remote_address);
                     self.peer_book.write().set_connecting(remote_address)?;
                 }
             }
-            Payload::ConnectedTo(remote_address, remote_listener) => {
+            Payload::ConnectedTo(remote_address, remote_listener, protocol_version, user_agent) => {
                 if direction == Direction::Internal {
-                    self.peer_book.write().set_connected(remote_address, remote_listener)?;
+                    let peer_address = remote_listener.unwrap_or(remote_address);
+                    let location = self.locate_peer(peer_address);
+                    {
+                        let mut peer_book = self.peer_book.write();
+                        // The socket of a redundant connection is dropped once its channel turns out to be taken by
+                        // the connection that is kept, so there's nothing else to clean up.
+                        let result =
+                            peer_book.set_connected(remote_address, remote_listener, ConnectionDirection::Inbound);
+                        match result {
+                            Err(NetworkError::PeerAlreadyConnected) => {
+                                debug!("Already connected to {}; ignoring a redundant connection", peer_address);
+                                return Ok(());
+                            }
+                            // The peer's autonomous system is saturated, so the new connection is dropped.
+                            Err(e @ NetworkError::TooManyPeersInASN(_)) => {
+                                drop(peer_book);
+                                let _ = self.disconnect_from_peer(peer_address, e.disconnect_reason());
+                                return Err(e);
+                            }
+                            result => result?,
+                        }
+                        peer_book.register_handshake(peer_address, protocol_version, user_agent);
+                        peer_book.set_location(peer_address, location);
+                    }
+                    self.enforce_max_connected_peers(peer_address);
                 }
             }
             Payload::Transaction(transaction) => {
                 if let Some(ref consensus) = self.consensus() {
                     let connected_peers = self.peer_book.read().connected_peers().clone();
-                    consensus
-                        .received_transaction(source.unwrap(), transaction, connected_peers)
-                        .await?;
+                    Arc::clone(consensus)
+                        .spawn_received_transaction(source.unwrap(), transaction, connected_peers)
+                        .await;
                 }
             }
             Payload::Block(block) => {
@@ -231,10 +411,53 @@ impl Node {
                         .await?;
                 }
             }
+            Payload::CompactBlock(compact_block) => {
+                if let Some(ref consensus) = self.consensus() {
+                    let connected_peers = self.peer_book.read().connected_peers().clone();
+                    consensus
+                        .received_compact_block(source.unwrap(), compact_block, connected_peers)
+                        .await?;
+                }
+            }
+            Payload::GetBlockTxn(transaction_ids) => {
+                if let Some(ref consensus) = self.consensus() {
+                    consensus
+                        .received_get_block_txn(source.unwrap(), transaction_ids)
+                        .await?;
+                }
+            }
+            Payload::BlockTxn(transactions) => {
+                if let Some(ref consensus) = self.consensus() {
+                    let connected_peers = self.peer_book.read().connected_peers().clone();
+                    consensus
+                        .received_block_txn(source.unwrap(), transactions, connected_peers)
+                        .await?;
+                }
+            }
+            Payload::FilterLoad(data, hash_funcs, tweak) => {
+                self.peer_book
+                    .read()
+                    .load_bloom_filter(source.unwrap(), data, hash_funcs, tweak);
+            }
+            Payload::FilterAdd(element) => {
+                self.peer_book.read().add_to_bloom_filter(source.unwrap(), &element);
+            }
+            Payload::FilterClear => {
+                self.peer_book.read().clear_bloom_filter(source.unwrap());
+            }
             Payload::SyncBlock(block) => {
                 if let Some(ref consensus) = self.consensus() {
-                    consensus.received_block(source.unwrap(), block, None).await?;
-                    if self.peer_book.read().got_sync_block(source.unwrap()) {
+                    // Blocks can also be requested outside of a sync batch, e.g. the parents of orphans;
+                    // those don't count towards the batch accounting.
+                    let is_batch_block = self.peer_book.read().is_syncing_blocks(source.unwrap());
+
+                    let is_new_block = consensus.received_block(source.unwrap(), block, None).await;
+                    if is_batch_block {
+                        consensus.register_sync_block(matches!(is_new_block, Ok(true)));
+                    }
+                    is_new_block?;
+
+                    if is_batch_block && self.peer_book.read().got_sync_block(source.unwrap()) {
                         consensus.finished_syncing_blocks();
                     }
                 }
@@ -267,13 +490,15 @@ impl Node {
             }
             Payload::Sync(sync) => {
                 if let Some(ref consensus) = self.consensus() {
-                    self.peer_book.read().expecting_sync_blocks(source.unwrap(), sync.len());
-                    consensus.received_sync(source.unwrap(), sync).await;
+                    let requested_count = consensus.received_sync(source.unwrap(), sync).await;
+                    self.peer_book
+                        .read()
+                        .expecting_sync_blocks(source.unwrap(), requested_count);
                 }
             }
-            Payload::Disconnect(addr) => {
+            Payload::Disconnect(addr, reason) => {
                 if direction == Direction::Internal {
-                    self.disconnect_from_peer(addr)?;
+                    self.disconnect_from_peer(addr, reason)?;
                 }
             }
             Payload::GetPeers => {
@@ -282,12 +507,21 @@ impl Node {
             Payload::Peers(peers) => {
                 self.process_inbound_peers(peers);
             }
-            Payload::Ping(block_height) => {
+            Payload::Ping(block_height, nonce) => {
+                self.peer_book
+                    .read()
+                    .register_advertised_height(source.unwrap(), block_height);
+
                 self.outbound
-                    .send_request(Message::new(Direction::Outbound(source.unwrap()), Payload::Pong))
+                    .send_request(Message::new(Direction::Outbound(source.unwrap()), Payload::Pong(nonce)))
                     .await;
 
                 if let Some(ref consensus) = self.consensus() {
+                    // Give up on a sync batch that the peer failed to deliver in time.
+                    if self.peer_book.read().expire_sync_batch(source.unwrap()) {
+                        debug!("Sync batch from {} timed out", source.unwrap());
+                    }
+
                     if block_height > consensus.current_block_height() + 1
                         && consensus.should_sync_blocks()
                         && !self.peer_book.read().is_syncing_blocks(source.unwrap())
@@ -300,14 +534,19 @@ impl Node {
                     }
                 }
             }
-            Payload::Pong => {
-                self.peer_book.read().received_pong(source.unwrap());
+            Payload::Pong(nonce) => {
+                self.peer_book.read().received_pong(source.unwrap(), nonce);
             }
             Payload::Unknown => {
                 warn!("Unknown payload received; this could indicate that the client you're using is out-of-date");
             }
         }
 
+        // Keep connecting the orphans left over by a previous block, one bounded pass per message.
+        if let Some(ref consensus) = self.consensus() {
+            consensus.resolve_pending_orphans();
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,154 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use snarkvm_dpc::base_dpc::instantiated::Tx;
+use snarkvm_objects::{Block, BlockHeader, DPCTransactions, Transaction};
+use snarkvm_utilities::bytes::{FromBytes, ToBytes};
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Result as IoResult, Write},
+};
+
+/// A block relayed without the transactions the receiver is expected to already have.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactBlock {
+    /// The header of the block.
+    pub header: BlockHeader,
+    /// The ids of all the transactions in the block, in their original order.
+    pub transaction_ids: Vec<[u8; 32]>,
+    /// The transactions sent in full, as the receiver can't have them in its memory pool.
+    pub prefilled_transactions: Vec<Tx>,
+}
+
+impl CompactBlock {
+    /// Creates a compact representation of the given block; only its coinbase transactions are sent in full.
+    pub fn new(block: &Block<Tx>) -> io::Result<Self> {
+        let mut transaction_ids = Vec::with_capacity(block.transactions.len());
+        let mut prefilled_transactions = vec![];
+
+        for transaction in block.transactions.iter() {
+            transaction_ids.push(transaction.transaction_id().map_err(to_io_error)?);
+
+            if transaction.value_balance.is_negative() {
+                prefilled_transactions.push(transaction.clone());
+            }
+        }
+
+        Ok(Self {
+            header: block.header.clone(),
+            transaction_ids,
+            prefilled_transactions,
+        })
+    }
+
+    /// Attempts to rebuild the full block using the prefilled transactions and the given lookup
+    /// (usually the memory pool); returns the ids of the missing transactions upon failure.
+    pub fn reconstruct<F: Fn(&[u8; 32]) -> Option<Tx>>(&self, lookup: F) -> Result<Block<Tx>, Vec<[u8; 32]>> {
+        let mut prefilled = HashMap::with_capacity(self.prefilled_transactions.len());
+        for transaction in &self.prefilled_transactions {
+            if let Ok(id) = transaction.transaction_id() {
+                prefilled.insert(id, transaction);
+            }
+        }
+
+        let mut transactions = DPCTransactions::new();
+        let mut missing = vec![];
+
+        for id in &self.transaction_ids {
+            match prefilled.get(id).map(|&tx| tx.clone()).or_else(|| lookup(id)) {
+                Some(transaction) => transactions.push(transaction),
+                None => missing.push(*id),
+            }
+        }
+
+        if missing.is_empty() {
+            Ok(Block {
+                header: self.header.clone(),
+                transactions,
+            })
+        } else {
+            Err(missing)
+        }
+    }
+
+    /// Registers transactions that were fetched separately, so that they can be used in a reconstruction;
+    /// returns `true` if any of them belong to this block.
+    pub fn fill(&mut self, transactions: &[Tx]) -> bool {
+        let mut filled = false;
+
+        for transaction in transactions {
+            if let Ok(id) = transaction.transaction_id() {
+                if self.transaction_ids.contains(&id) && !self.prefilled_transactions.contains(transaction) {
+                    self.prefilled_transactions.push(transaction.clone());
+                    filled = true;
+                }
+            }
+        }
+
+        filled
+    }
+
+    pub fn serialize(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        self.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn deserialize(bytes: &[u8]) -> io::Result<Self> {
+        Self::read(bytes)
+    }
+}
+
+impl ToBytes for CompactBlock {
+    #[inline]
+    fn write<W: Write>(&self, mut writer: W) -> IoResult<()> {
+        self.header.write(&mut writer)?;
+
+        (self.transaction_ids.len() as u32).write(&mut writer)?;
+        for id in &self.transaction_ids {
+            id.write(&mut writer)?;
+        }
+
+        DPCTransactions(self.prefilled_transactions.clone()).write(&mut writer)
+    }
+}
+
+impl FromBytes for CompactBlock {
+    #[inline]
+    fn read<R: Read>(mut reader: R) -> IoResult<Self> {
+        let header: BlockHeader = FromBytes::read(&mut reader)?;
+
+        let num_ids: u32 = FromBytes::read(&mut reader)?;
+        let mut transaction_ids = vec![];
+        for _ in 0..num_ids {
+            let id: [u8; 32] = FromBytes::read(&mut reader)?;
+            transaction_ids.push(id);
+        }
+
+        let prefilled_transactions: DPCTransactions<Tx> = FromBytes::read(&mut reader)?;
+
+        Ok(Self {
+            header,
+            transaction_ids,
+            prefilled_transactions: prefilled_transactions.0,
+        })
+    }
+}
+
+fn to_io_error<E: std::fmt::Debug>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", error))
+}
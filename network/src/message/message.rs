@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::DisconnectReason;
 use snarkos_storage::BlockHeight;
 use snarkvm_objects::BlockHeaderHash;
 
@@ -66,6 +67,18 @@ impl fmt::Display for Message {
 pub enum Payload {
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/block.md"))]
     Block(Vec<u8>),
+    #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/block_txn.md"))]
+    BlockTxn(Vec<Vec<u8>>),
+    #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/compact_block.md"))]
+    CompactBlock(Vec<u8>),
+    #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/filter_add.md"))]
+    FilterAdd(Vec<u8>),
+    #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/filter_clear.md"))]
+    FilterClear,
+    #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/filter_load.md"))]
+    FilterLoad(Vec<u8>, u32, u32),
+    #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/get_block_txn.md"))]
+    GetBlockTxn(Vec<[u8; 32]>),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/get_blocks.md"))]
     GetBlocks(Vec<BlockHeaderHash>),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/get_memory_pool.md"))]
@@ -79,9 +92,9 @@ pub enum Payload {
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/peers.md"))]
     Peers(Vec<SocketAddr>),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/ping.md"))]
-    Ping(BlockHeight),
+    Ping(BlockHeight, u64),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/pong.md"))]
-    Pong,
+    Pong(u64),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/sync.md"))]
     Sync(Vec<BlockHeaderHash>),
     #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/sync_block.md"))]
@@ -95,19 +108,43 @@ pub enum Payload {
 
     /* internal messages */
     #[doc(hidden)]
-    ConnectedTo(SocketAddr, Option<SocketAddr>),
+    ConnectedTo(SocketAddr, Option<SocketAddr>, u32, Option<String>),
     #[doc(hidden)]
     ConnectingTo(SocketAddr),
     // TODO: used internally, but can also be used to allow a clean disconnect for connected peers on shutdown
     // add a doc if this is introduced
     #[doc(hidden)]
-    Disconnect(SocketAddr),
+    Disconnect(SocketAddr, DisconnectReason),
 }
 
-impl fmt::Display for Payload {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let str = match self {
+/// The priority of an outbound message; lower-priority ones are shed first when a peer is slow to read them.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum MessagePriority {
+    /// Messages relayed unprompted, whose loss is recoverable.
+    Low,
+    /// Messages the connection or sync with the peer depends on.
+    High,
+}
+
+impl Payload {
+    /// Returns the priority of the message when it's sent to a peer.
+    pub fn priority(&self) -> MessagePriority {
+        match self {
+            Self::Block(..) | Self::CompactBlock(..) | Self::Transaction(..) => MessagePriority::Low,
+            _ => MessagePriority::High,
+        }
+    }
+
+    /// Returns the name of the message type.
+    pub fn name(&self) -> &'static str {
+        match self {
             Self::Block(..) => "block",
+            Self::BlockTxn(..) => "blocktxn",
+            Self::CompactBlock(..) => "compactblock",
+            Self::FilterAdd(..) => "filteradd",
+            Self::FilterClear => "filterclear",
+            Self::FilterLoad(..) => "filterload",
+            Self::GetBlockTxn(..) => "getblocktxn",
             Self::GetBlocks(..) => "getblocks",
             Self::GetMemoryPool => "getmempool",
             Self::GetPeers => "getpeers",
@@ -115,7 +152,7 @@ impl fmt::Display for Payload {
             Self::MemoryPool(..) => "memorypool",
             Self::Peers(..) => "peers",
             Self::Ping(..) => "ping",
-            Self::Pong => "pong",
+            Self::Pong(..) => "pong",
             Self::Sync(..) => "sync",
             Self::SyncBlock(..) => "syncblock",
             Self::Transaction(..) => "transaction",
@@ -123,8 +160,12 @@ impl fmt::Display for Payload {
             Self::ConnectingTo(..) => "connectingto",
             Self::Disconnect(..) => "disconnect",
             Self::Unknown => "unknown",
-        };
+        }
+    }
+}
 
-        f.write_str(str)
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
     }
 }
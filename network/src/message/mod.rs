@@ -14,6 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod compact_block;
+pub use compact_block::*;
+
 pub mod message;
 pub use message::*;
 
@@ -1347,6 +1347,395 @@ pub mod ping {
     pub fn get_block_height(self) -> u32 {
       self.reader.get_data_field::<u32>(0)
     }
+    #[inline]
+    pub fn get_nonce(self) -> u64 {
+      self.reader.get_data_field::<u64>(1)
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_block_height(self) -> u32 {
+      self.builder.get_data_field::<u32>(0)
+    }
+    #[inline]
+    pub fn set_block_height(&mut self, value: u32)  {
+      self.builder.set_data_field::<u32>(0, value);
+    }
+    #[inline]
+    pub fn get_nonce(self) -> u64 {
+      self.builder.get_data_field::<u64>(1)
+    }
+    #[inline]
+    pub fn set_nonce(&mut self, value: u64)  {
+      self.builder.set_data_field::<u64>(1, value);
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 2, pointers: 0 };
+    pub const TYPE_ID: u64 = 0x87ca_2c1e_0607_67fe;
+  }
+}
+
+pub mod get_memory_pool {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+    #[inline]
+    pub fn set_placeholder(&mut self, _value: ())  {
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
+    pub const TYPE_ID: u64 = 0xfba1_7045_02d0_44f3;
+  }
+}
+
+pub mod get_peers {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+  }
+
+  pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
+  impl <'a,> ::capnp::traits::HasStructSize for Builder<'a,>  {
+    #[inline]
+    fn struct_size() -> ::capnp::private::layout::StructSize { _private::STRUCT_SIZE }
+  }
+  impl <'a,> ::capnp::traits::HasTypeId for Builder<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructBuilder<'a> for Builder<'a,>  {
+    fn new(builder: ::capnp::private::layout::StructBuilder<'a>) -> Builder<'a, > {
+      Builder { builder,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::ImbueMut<'a> for Builder<'a,>  {
+    fn imbue_mut(&mut self, cap_table: &'a mut ::capnp::private::layout::CapTable) {
+      self.builder.imbue(::capnp::private::layout::CapTableBuilder::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerBuilder<'a> for Builder<'a,>  {
+    fn init_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, _size: u32) -> Builder<'a,> {
+      ::capnp::traits::FromStructBuilder::new(builder.init_struct(_private::STRUCT_SIZE))
+    }
+    fn get_from_pointer(builder: ::capnp::private::layout::PointerBuilder<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Builder<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructBuilder::new(builder.get_struct(_private::STRUCT_SIZE, default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::SetPointerBuilder for Reader<'a,>  {
+    fn set_pointer_builder<'b>(pointer: ::capnp::private::layout::PointerBuilder<'b>, value: Reader<'a,>, canonicalize: bool) -> ::capnp::Result<()> { pointer.set_struct(&value.reader, canonicalize) }
+  }
+
+  impl <'a,> Builder<'a,>  {
+    pub fn into_reader(self) -> Reader<'a,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+    pub fn reborrow(&mut self) -> Builder<'_,> {
+      Builder { .. *self }
+    }
+    pub fn reborrow_as_reader(&self) -> Reader<'_,> {
+      ::capnp::traits::FromStructReader::new(self.builder.into_reader())
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.builder.into_reader().total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+    #[inline]
+    pub fn set_placeholder(&mut self, _value: ())  {
+    }
+  }
+
+  pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
+  impl ::capnp::capability::FromTypelessPipeline for Pipeline {
+    fn new(typeless: ::capnp::any_pointer::Pipeline) -> Pipeline {
+      Pipeline { _typeless: typeless,  }
+    }
+  }
+  impl Pipeline  {
+  }
+  mod _private {
+    use capnp::private::layout;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
+    pub const TYPE_ID: u64 = 0xc075_4205_a0c7_dafa;
+  }
+}
+
+pub mod pong {
+  #[derive(Copy, Clone)]
+  pub struct Owned(());
+  impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl <'a> ::capnp::traits::OwnedStruct<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
+  impl ::capnp::traits::Pipelined for Owned { type Pipeline = Pipeline; }
+
+  #[derive(Clone, Copy)]
+  pub struct Reader<'a> { reader: ::capnp::private::layout::StructReader<'a> }
+
+  impl <'a,> ::capnp::traits::HasTypeId for Reader<'a,>  {
+    #[inline]
+    fn type_id() -> u64 { _private::TYPE_ID }
+  }
+  impl <'a,> ::capnp::traits::FromStructReader<'a> for Reader<'a,>  {
+    fn new(reader: ::capnp::private::layout::StructReader<'a>) -> Reader<'a,> {
+      Reader { reader,  }
+    }
+  }
+
+  impl <'a,> ::capnp::traits::FromPointerReader<'a> for Reader<'a,>  {
+    fn get_from_pointer(reader: &::capnp::private::layout::PointerReader<'a>, default: ::core::option::Option<&'a [capnp::Word]>) -> ::capnp::Result<Reader<'a,>> {
+      ::core::result::Result::Ok(::capnp::traits::FromStructReader::new(reader.get_struct(default)?))
+    }
+  }
+
+  impl <'a,> ::capnp::traits::IntoInternalStructReader<'a> for Reader<'a,>  {
+    fn into_internal_struct_reader(self) -> ::capnp::private::layout::StructReader<'a> {
+      self.reader
+    }
+  }
+
+  impl <'a,> ::capnp::traits::Imbue<'a> for Reader<'a,>  {
+    fn imbue(&mut self, cap_table: &'a ::capnp::private::layout::CapTable) {
+      self.reader.imbue(::capnp::private::layout::CapTableReader::Plain(cap_table))
+    }
+  }
+
+  impl <'a,> Reader<'a,>  {
+    pub fn reborrow(&self) -> Reader<'_,> {
+      Reader { .. *self }
+    }
+
+    pub fn total_size(&self) -> ::capnp::Result<::capnp::MessageSize> {
+      self.reader.total_size()
+    }
+    #[inline]
+    pub fn get_placeholder(self)  {
+      
+    }
+    #[inline]
+    pub fn get_nonce(self) -> u64 {
+      self.reader.get_data_field::<u64>(0)
+    }
   }
 
   pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
@@ -1398,12 +1787,19 @@ pub mod ping {
       self.builder.into_reader().total_size()
     }
     #[inline]
-    pub fn get_block_height(self) -> u32 {
-      self.builder.get_data_field::<u32>(0)
+    pub fn get_placeholder(self)  {
+      
     }
     #[inline]
-    pub fn set_block_height(&mut self, value: u32)  {
-      self.builder.set_data_field::<u32>(0, value);
+    pub fn set_placeholder(&mut self, _value: ())  {
+    }
+    #[inline]
+    pub fn get_nonce(self) -> u64 {
+      self.builder.get_data_field::<u64>(0)
+    }
+    #[inline]
+    pub fn set_nonce(&mut self, value: u64)  {
+      self.builder.set_data_field::<u64>(0, value);
     }
   }
 
@@ -1418,11 +1814,11 @@ pub mod ping {
   mod _private {
     use capnp::private::layout;
     pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 1, pointers: 0 };
-    pub const TYPE_ID: u64 = 0x87ca_2c1e_0607_67fe;
+    pub const TYPE_ID: u64 = 0xfedd_3465_2295_4326;
   }
 }
 
-pub mod get_memory_pool {
+pub mod transaction_id {
   #[derive(Copy, Clone)]
   pub struct Owned(());
   impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
@@ -1469,8 +1865,11 @@ pub mod get_memory_pool {
       self.reader.total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_id(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_id(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
     }
   }
 
@@ -1523,11 +1922,19 @@ pub mod get_memory_pool {
       self.builder.into_reader().total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_id(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
     }
     #[inline]
-    pub fn set_placeholder(&mut self, _value: ())  {
+    pub fn set_id(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(0).set_data(value);
+    }
+    #[inline]
+    pub fn init_id(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(0).init_data(size)
+    }
+    pub fn has_id(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
     }
   }
 
@@ -1541,12 +1948,12 @@ pub mod get_memory_pool {
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
-    pub const TYPE_ID: u64 = 0xfba1_7045_02d0_44f3;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 1 };
+    pub const TYPE_ID: u64 = 0xd5e6_1a3c_7b29_e4f8;
   }
 }
 
-pub mod get_peers {
+pub mod filter_load {
   #[derive(Copy, Clone)]
   pub struct Owned(());
   impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
@@ -1593,8 +2000,19 @@ pub mod get_peers {
       self.reader.total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_data(self) -> ::capnp::Result<::capnp::data::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_data(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_hash_funcs(self) -> u32 {
+      self.reader.get_data_field::<u32>(0)
+    }
+    #[inline]
+    pub fn get_tweak(self) -> u32 {
+      self.reader.get_data_field::<u32>(1)
     }
   }
 
@@ -1647,11 +2065,35 @@ pub mod get_peers {
       self.builder.into_reader().total_size()
     }
     #[inline]
-    pub fn get_placeholder(self)  {
-      
+    pub fn get_data(self) -> ::capnp::Result<::capnp::data::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
     }
     #[inline]
-    pub fn set_placeholder(&mut self, _value: ())  {
+    pub fn set_data(&mut self, value: ::capnp::data::Reader<'_>)  {
+      self.builder.get_pointer_field(0).set_data(value);
+    }
+    #[inline]
+    pub fn init_data(self, size: u32) -> ::capnp::data::Builder<'a> {
+      self.builder.get_pointer_field(0).init_data(size)
+    }
+    pub fn has_data(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
+    }
+    #[inline]
+    pub fn get_hash_funcs(self) -> u32 {
+      self.builder.get_data_field::<u32>(0)
+    }
+    #[inline]
+    pub fn set_hash_funcs(&mut self, value: u32)  {
+      self.builder.set_data_field::<u32>(0, value);
+    }
+    #[inline]
+    pub fn get_tweak(self) -> u32 {
+      self.builder.get_data_field::<u32>(1)
+    }
+    #[inline]
+    pub fn set_tweak(&mut self, value: u32)  {
+      self.builder.set_data_field::<u32>(1, value);
     }
   }
 
@@ -1665,12 +2107,13 @@ pub mod get_peers {
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
-    pub const TYPE_ID: u64 = 0xc075_4205_a0c7_dafa;
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 1, pointers: 1 };
+    pub const TYPE_ID: u64 = 0xe2a7_5c19_03bd_6f41;
   }
 }
 
-pub mod pong {
+
+pub mod filter_clear {
   #[derive(Copy, Clone)]
   pub struct Owned(());
   impl <'a> ::capnp::traits::Owned<'a> for Owned { type Reader = Reader<'a>; type Builder = Builder<'a>; }
@@ -1790,10 +2233,11 @@ pub mod pong {
   mod _private {
     use capnp::private::layout;
     pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 0, pointers: 0 };
-    pub const TYPE_ID: u64 = 0xfedd_3465_2295_4326;
+    pub const TYPE_ID: u64 = 0x9d4b_e8f2_61c3_a057;
   }
 }
 
+
 pub mod payload {
   #[derive(Copy, Clone)]
   pub struct Owned(());
@@ -1924,7 +2368,7 @@ pub mod payload {
   }
 
   pub mod payload_type {
-    pub use self::Which::{Block,GetBlocks,GetMemoryPool,GetPeers,GetSync,MemoryPool,Peers,Ping,Pong,Sync,SyncBlock,Transaction};
+    pub use self::Which::{Block,GetBlocks,GetMemoryPool,GetPeers,GetSync,MemoryPool,Peers,Ping,Pong,Sync,SyncBlock,Transaction,CompactBlock,GetBlockTxn,BlockTxn,FilterLoad,FilterAdd,FilterClear};
 
     #[derive(Copy, Clone)]
     pub struct Owned(());
@@ -2019,6 +2463,30 @@ pub mod payload {
         if self.reader.get_data_field::<u16>(0) != 11 { return false; }
         !self.reader.get_pointer_field(0).is_null()
       }
+      pub fn has_compact_block(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 12 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_get_block_txn(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 13 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_block_txn(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 14 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_filter_load(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 15 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_filter_add(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 16 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
+      pub fn has_filter_clear(&self) -> bool {
+        if self.reader.get_data_field::<u16>(0) != 17 { return false; }
+        !self.reader.get_pointer_field(0).is_null()
+      }
       #[inline]
       pub fn which(self) -> ::core::result::Result<WhichReader<'a,>, ::capnp::NotInSchema> {
         match self.reader.get_data_field::<u16>(0) {
@@ -2082,6 +2550,36 @@ pub mod payload {
               ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
             ))
           }
+          12 => {
+            ::core::result::Result::Ok(CompactBlock(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          13 => {
+            ::core::result::Result::Ok(GetBlockTxn(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          14 => {
+            ::core::result::Result::Ok(BlockTxn(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          15 => {
+            ::core::result::Result::Ok(FilterLoad(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          16 => {
+            ::core::result::Result::Ok(FilterAdd(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          17 => {
+            ::core::result::Result::Ok(FilterClear(
+              ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
           x => ::core::result::Result::Err(::capnp::NotInSchema(x))
         }
       }
@@ -2304,6 +2802,90 @@ pub mod payload {
         !self.builder.get_pointer_field(0).is_null()
       }
       #[inline]
+      pub fn set_compact_block(&mut self, value: crate::payload_capnp::block::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 12);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_compact_block(self, ) -> crate::payload_capnp::block::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 12);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_compact_block(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 12 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_get_block_txn(&mut self, value: ::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction_id::Owned>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 13);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_get_block_txn(self, size: u32) -> ::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction_id::Owned> {
+        self.builder.set_data_field::<u16>(0, 13);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), size)
+      }
+      pub fn has_get_block_txn(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 13 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_block_txn(&mut self, value: ::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 14);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_block_txn(self, size: u32) -> ::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned> {
+        self.builder.set_data_field::<u16>(0, 14);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), size)
+      }
+      pub fn has_block_txn(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 14 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_filter_load(&mut self, value: crate::payload_capnp::filter_load::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 15);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_filter_load(self, ) -> crate::payload_capnp::filter_load::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 15);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_filter_load(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 15 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_filter_add(&mut self, value: ::capnp::data::Reader<'_>)  {
+        self.builder.set_data_field::<u16>(0, 16);
+        self.builder.get_pointer_field(0).set_data(value);
+      }
+      #[inline]
+      pub fn init_filter_add(self, size: u32) -> ::capnp::data::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 16);
+        self.builder.get_pointer_field(0).init_data(size)
+      }
+      pub fn has_filter_add(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 16 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
+      pub fn set_filter_clear(&mut self, value: crate::payload_capnp::filter_clear::Reader<'_>) -> ::capnp::Result<()> {
+        self.builder.set_data_field::<u16>(0, 17);
+        ::capnp::traits::SetPointerBuilder::set_pointer_builder(self.builder.get_pointer_field(0), value, false)
+      }
+      #[inline]
+      pub fn init_filter_clear(self, ) -> crate::payload_capnp::filter_clear::Builder<'a> {
+        self.builder.set_data_field::<u16>(0, 17);
+        ::capnp::traits::FromPointerBuilder::init_pointer(self.builder.get_pointer_field(0), 0)
+      }
+      pub fn has_filter_clear(&self) -> bool {
+        if self.builder.get_data_field::<u16>(0) != 17 { return false; }
+        !self.builder.get_pointer_field(0).is_null()
+      }
+      #[inline]
       pub fn which(self) -> ::core::result::Result<WhichBuilder<'a,>, ::capnp::NotInSchema> {
         match self.builder.get_data_field::<u16>(0) {
           0 => {
@@ -2366,6 +2948,36 @@ pub mod payload {
               ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
             ))
           }
+          12 => {
+            ::core::result::Result::Ok(CompactBlock(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          13 => {
+            ::core::result::Result::Ok(GetBlockTxn(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          14 => {
+            ::core::result::Result::Ok(BlockTxn(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          15 => {
+            ::core::result::Result::Ok(FilterLoad(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          16 => {
+            ::core::result::Result::Ok(FilterAdd(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
+          17 => {
+            ::core::result::Result::Ok(FilterClear(
+              ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+            ))
+          }
           x => ::core::result::Result::Err(::capnp::NotInSchema(x))
         }
       }
@@ -2384,7 +2996,7 @@ pub mod payload {
       pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 1, pointers: 1 };
       pub const TYPE_ID: u64 = 0xb8b4_27fe_5891_d61c;
     }
-    pub enum Which<A0,A1,A2,A3,A4,A5,A6,A7,A8,A9,A10,A11> {
+    pub enum Which<A0,A1,A2,A3,A4,A5,A6,A7,A8,A9,A10,A11,A12,A13,A14,A15,A16,A17> {
       Block(A0),
       GetBlocks(A1),
       GetMemoryPool(A2),
@@ -2397,9 +3009,15 @@ pub mod payload {
       Sync(A9),
       SyncBlock(A10),
       Transaction(A11),
+      CompactBlock(A12),
+      GetBlockTxn(A13),
+      BlockTxn(A14),
+      FilterLoad(A15),
+      FilterAdd(A16),
+      FilterClear(A17),
     }
-    pub type WhichReader<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Reader<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Reader<'a>>,::capnp::Result<crate::payload_capnp::pong::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<crate::payload_capnp::transaction::Reader<'a>>>;
-    pub type WhichBuilder<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Builder<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Builder<'a>>,::capnp::Result<crate::payload_capnp::pong::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<crate::payload_capnp::transaction::Builder<'a>>>;
+    pub type WhichReader<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Reader<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Reader<'a>>,::capnp::Result<crate::payload_capnp::pong::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<crate::payload_capnp::transaction::Reader<'a>>,::capnp::Result<crate::payload_capnp::block::Reader<'a>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction_id::Owned>>,::capnp::Result<::capnp::struct_list::Reader<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<crate::payload_capnp::filter_load::Reader<'a>>,::capnp::Result<::capnp::data::Reader<'a>>,::capnp::Result<crate::payload_capnp::filter_clear::Reader<'a>>>;
+    pub type WhichBuilder<'a,> = Which<::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::get_memory_pool::Builder<'a>>,::capnp::Result<crate::payload_capnp::get_peers::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::socket_addr::Owned>>,::capnp::Result<crate::payload_capnp::ping::Builder<'a>>,::capnp::Result<crate::payload_capnp::pong::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::block_hash::Owned>>,::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<crate::payload_capnp::transaction::Builder<'a>>,::capnp::Result<crate::payload_capnp::block::Builder<'a>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction_id::Owned>>,::capnp::Result<::capnp::struct_list::Builder<'a,crate::payload_capnp::transaction::Owned>>,::capnp::Result<crate::payload_capnp::filter_load::Builder<'a>>,::capnp::Result<::capnp::data::Builder<'a>>,::capnp::Result<crate::payload_capnp::filter_clear::Builder<'a>>>;
   }
 }
 
@@ -2457,6 +3075,17 @@ pub mod version {
     pub fn get_listening_port(self) -> u16 {
       self.reader.get_data_field::<u16>(4)
     }
+    #[inline]
+    pub fn get_protocol_version(self) -> u32 {
+      self.reader.get_data_field::<u32>(3)
+    }
+    #[inline]
+    pub fn get_user_agent(self) -> ::capnp::Result<::capnp::text::Reader<'a>> {
+      ::capnp::traits::FromPointerReader::get_from_pointer(&self.reader.get_pointer_field(0), ::core::option::Option::None)
+    }
+    pub fn has_user_agent(&self) -> bool {
+      !self.reader.get_pointer_field(0).is_null()
+    }
   }
 
   pub struct Builder<'a> { builder: ::capnp::private::layout::StructBuilder<'a> }
@@ -2523,6 +3152,29 @@ pub mod version {
     pub fn set_listening_port(&mut self, value: u16)  {
       self.builder.set_data_field::<u16>(4, value);
     }
+    #[inline]
+    pub fn get_protocol_version(self) -> u32 {
+      self.builder.get_data_field::<u32>(3)
+    }
+    #[inline]
+    pub fn set_protocol_version(&mut self, value: u32)  {
+      self.builder.set_data_field::<u32>(3, value);
+    }
+    #[inline]
+    pub fn get_user_agent(self) -> ::capnp::Result<::capnp::text::Builder<'a>> {
+      ::capnp::traits::FromPointerBuilder::get_from_pointer(self.builder.get_pointer_field(0), ::core::option::Option::None)
+    }
+    #[inline]
+    pub fn set_user_agent(&mut self, value: ::capnp::text::Reader<'_>)  {
+      self.builder.get_pointer_field(0).set_text(value);
+    }
+    #[inline]
+    pub fn init_user_agent(self, size: u32) -> ::capnp::text::Builder<'a> {
+      self.builder.get_pointer_field(0).init_text(size)
+    }
+    pub fn has_user_agent(&self) -> bool {
+      !self.builder.get_pointer_field(0).is_null()
+    }
   }
 
   pub struct Pipeline { _typeless: ::capnp::any_pointer::Pipeline }
@@ -2535,7 +3187,7 @@ pub mod version {
   }
   mod _private {
     use capnp::private::layout;
-    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 2, pointers: 0 };
+    pub const STRUCT_SIZE: layout::StructSize = layout::StructSize { data: 2, pointers: 1 };
     pub const TYPE_ID: u64 = 0xf6b9_300e_617a_79e5;
   }
 }
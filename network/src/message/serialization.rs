@@ -26,6 +26,7 @@ use payload_capnp::{
     },
     socket_addr,
     transaction,
+    transaction_id,
     version,
 };
 
@@ -41,6 +42,7 @@ pub mod payload_capnp {
 type BlockHashes<'a> = capnp::struct_list::Reader<'a, block_hash::Owned>;
 type SocketAddrs<'a> = capnp::struct_list::Reader<'a, socket_addr::Owned>;
 type Transactions<'a> = capnp::struct_list::Reader<'a, transaction::Owned>;
+type TransactionIds<'a> = capnp::struct_list::Reader<'a, transaction_id::Owned>;
 
 impl Version {
     pub fn deserialize(bytes: &[u8]) -> capnp::Result<Version> {
@@ -52,6 +54,12 @@ impl Version {
         Ok(Version {
             version: version.get_version(),
             listening_port: version.get_listening_port(),
+            protocol_version: version.get_protocol_version(),
+            user_agent: if version.has_user_agent() {
+                version.get_user_agent()?.to_owned()
+            } else {
+                String::new()
+            },
         })
     }
 
@@ -60,6 +68,8 @@ impl Version {
         let mut builder = message.init_root::<version::Builder>();
         builder.set_version(self.version);
         builder.set_listening_port(self.listening_port);
+        builder.set_protocol_version(self.protocol_version);
+        builder.set_user_agent(&self.user_agent);
 
         let mut writer = Vec::new();
         capnp::serialize_packed::write_message(&mut writer, &message)?;
@@ -87,11 +97,27 @@ impl Payload {
             payload_type::Which::GetSync(hashes) => Ok(Payload::GetSync(deserialize_block_hashes(hashes?)?)),
             payload_type::Which::MemoryPool(txs) => deserialize_transactions(txs?),
             payload_type::Which::Peers(peers) => Ok(Payload::Peers(deserialize_addresses(peers?)?)),
-            payload_type::Which::Ping(ping) => Ok(Payload::Ping(ping?.get_block_height())),
-            payload_type::Which::Pong(_) => Ok(Payload::Pong),
+            payload_type::Which::Ping(ping) => {
+                let ping = ping?;
+                Ok(Payload::Ping(ping.get_block_height(), ping.get_nonce()))
+            }
+            payload_type::Which::Pong(pong) => Ok(Payload::Pong(pong?.get_nonce())),
             payload_type::Which::Sync(hashes) => Ok(Payload::Sync(deserialize_block_hashes(hashes?)?)),
             payload_type::Which::SyncBlock(block) => deserialize_block(block?, true),
             payload_type::Which::Transaction(tx) => Ok(Payload::Transaction(tx?.get_data()?.to_vec())),
+            payload_type::Which::CompactBlock(block) => Ok(Payload::CompactBlock(block?.get_data()?.to_vec())),
+            payload_type::Which::GetBlockTxn(ids) => Ok(Payload::GetBlockTxn(deserialize_transaction_ids(ids?)?)),
+            payload_type::Which::BlockTxn(txs) => Ok(Payload::BlockTxn(deserialize_transaction_blobs(txs?)?)),
+            payload_type::Which::FilterLoad(filter) => {
+                let filter = filter?;
+                Ok(Payload::FilterLoad(
+                    filter.get_data()?.to_vec(),
+                    filter.get_hash_funcs(),
+                    filter.get_tweak(),
+                ))
+            }
+            payload_type::Which::FilterAdd(element) => Ok(Payload::FilterAdd(element?.to_vec())),
+            payload_type::Which::FilterClear(_) => Ok(Payload::FilterClear),
         }
     }
 
@@ -162,13 +188,14 @@ impl Payload {
                         }
                     }
                 }
-                Payload::Ping(block_height) => {
+                Payload::Ping(block_height, nonce) => {
                     let mut builder = builder.init_ping();
                     builder.set_block_height(*block_height);
+                    builder.set_nonce(*nonce);
                 }
-                Payload::Pong => {
+                Payload::Pong(nonce) => {
                     let mut builder = builder.init_pong();
-                    builder.set_placeholder(());
+                    builder.set_nonce(*nonce);
                 }
                 Payload::Sync(hashes) => {
                     let mut builder = builder.init_sync(hashes.len() as u32);
@@ -185,6 +212,37 @@ impl Payload {
                     let mut builder = builder.init_transaction();
                     builder.set_data(&bytes);
                 }
+                Payload::CompactBlock(bytes) => {
+                    let mut builder = builder.init_compact_block();
+                    builder.set_data(&bytes);
+                }
+                Payload::GetBlockTxn(ids) => {
+                    let mut builder = builder.init_get_block_txn(ids.len() as u32);
+                    for (i, id) in ids.iter().enumerate() {
+                        let mut elem_builder = builder.reborrow().get(i as u32);
+                        elem_builder.set_id(id);
+                    }
+                }
+                Payload::BlockTxn(txs) => {
+                    let mut builder = builder.init_block_txn(txs.len() as u32);
+                    for (i, tx) in txs.iter().enumerate() {
+                        let mut elem_builder = builder.reborrow().get(i as u32);
+                        elem_builder.set_data(tx);
+                    }
+                }
+                Payload::FilterLoad(data, hash_funcs, tweak) => {
+                    let mut builder = builder.init_filter_load();
+                    builder.set_data(data);
+                    builder.set_hash_funcs(*hash_funcs);
+                    builder.set_tweak(*tweak);
+                }
+                Payload::FilterAdd(element) => {
+                    builder.init_filter_add(element.len() as u32).copy_from_slice(element);
+                }
+                Payload::FilterClear => {
+                    let mut builder = builder.init_filter_clear();
+                    builder.set_placeholder(());
+                }
                 _ => unreachable!(),
             }
         }
@@ -212,6 +270,12 @@ fn deserialize_block_hashes(hashes: BlockHashes<'_>) -> capnp::Result<Vec<BlockH
 
     for hash in hashes.iter() {
         let bytes = hash.get_hash()?;
+        if bytes.len() != 32 {
+            return Err(capnp::Error {
+                kind: capnp::ErrorKind::Failed,
+                description: "invalid block hash: expected 32 bytes".to_owned(),
+            });
+        }
         let mut block_hash = [0u8; 32];
         block_hash.copy_from_slice(&bytes);
         vec.push(BlockHeaderHash(block_hash));
@@ -271,6 +335,10 @@ fn deserialize_addresses(addrs: SocketAddrs<'_>) -> capnp::Result<Vec<SocketAddr
 }
 
 fn deserialize_transactions(txs: Transactions<'_>) -> capnp::Result<Payload> {
+    Ok(Payload::MemoryPool(deserialize_transaction_blobs(txs)?))
+}
+
+fn deserialize_transaction_blobs(txs: Transactions<'_>) -> capnp::Result<Vec<Vec<u8>>> {
     let mut vec = Vec::with_capacity(txs.len() as usize);
 
     for tx in txs.iter() {
@@ -278,7 +346,26 @@ fn deserialize_transactions(txs: Transactions<'_>) -> capnp::Result<Payload> {
         vec.push(bytes.to_vec());
     }
 
-    Ok(Payload::MemoryPool(vec))
+    Ok(vec)
+}
+
+fn deserialize_transaction_ids(ids: TransactionIds<'_>) -> capnp::Result<Vec<[u8; 32]>> {
+    let mut vec = Vec::with_capacity(ids.len() as usize);
+
+    for id in ids.iter() {
+        let bytes = id.get_id()?;
+        if bytes.len() != 32 {
+            return Err(capnp::Error {
+                kind: capnp::ErrorKind::Failed,
+                description: "invalid transaction id: expected 32 bytes".to_owned(),
+            });
+        }
+        let mut transaction_id = [0u8; 32];
+        transaction_id.copy_from_slice(&bytes);
+        vec.push(transaction_id);
+    }
+
+    Ok(vec)
 }
 
 #[cfg(test)]
@@ -287,7 +374,7 @@ mod tests {
 
     #[test]
     fn serialize_deserialize_empty_payloads() {
-        for payload in &[Payload::GetMemoryPool, Payload::GetPeers, Payload::Pong] {
+        for payload in &[Payload::FilterClear, Payload::GetMemoryPool, Payload::GetPeers] {
             assert_eq!(
                 Payload::deserialize(&Payload::serialize(payload).unwrap()).unwrap(),
                 *payload
@@ -301,6 +388,9 @@ mod tests {
 
         for payload in &[
             Payload::Block(blob.clone()),
+            Payload::BlockTxn(vec![blob.clone(); 10]),
+            Payload::CompactBlock(blob.clone()),
+            Payload::FilterAdd(blob.clone()),
             Payload::MemoryPool(vec![blob.clone(); 10]),
             Payload::SyncBlock(blob.clone()),
             Payload::Transaction(blob),
@@ -328,6 +418,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn deserialize_malformed_block_hashes() {
+        let mut message = capnp::message::Builder::new_default();
+        {
+            let builder = message.init_root::<payload::Builder>().init_payload_type();
+            let mut builder = builder.init_get_sync(1);
+            builder.reborrow().get(0).set_hash(&[0u8; 31]);
+        }
+        let mut bytes = Vec::new();
+        capnp::serialize_packed::write_message(&mut bytes, &message).unwrap();
+
+        assert!(Payload::deserialize(&bytes).is_err());
+    }
+
+    #[test]
+    fn serialize_deserialize_transaction_ids() {
+        let ids = (0u8..10).map(|i| [i; 32]).collect::<Vec<_>>();
+        let payload = Payload::GetBlockTxn(ids);
+
+        assert_eq!(
+            Payload::deserialize(&Payload::serialize(&payload).unwrap()).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn serialize_deserialize_filter_load() {
+        let payload = Payload::FilterLoad((0u8..255).collect(), 11, u32::MAX);
+
+        assert_eq!(
+            Payload::deserialize(&Payload::serialize(&payload).unwrap()).unwrap(),
+            payload
+        );
+    }
+
     #[test]
     fn serialize_deserialize_peers() {
         let addrs: Vec<SocketAddr> = [
@@ -352,7 +477,19 @@ mod tests {
     #[test]
     fn serialize_deserialize_ping() {
         for i in 0u8..255 {
-            let payload = Payload::Ping(i as u32);
+            let payload = Payload::Ping(i as u32, u64::MAX - i as u64);
+
+            assert_eq!(
+                Payload::deserialize(&Payload::serialize(&payload).unwrap()).unwrap(),
+                payload
+            );
+        }
+    }
+
+    #[test]
+    fn serialize_deserialize_pong() {
+        for nonce in &[0, 1, u64::MAX] {
+            let payload = Payload::Pong(*nonce);
 
             assert_eq!(
                 Payload::deserialize(&Payload::serialize(&payload).unwrap()).unwrap(),
@@ -369,5 +506,10 @@ mod tests {
             Version::deserialize(&Version::serialize(&version).unwrap()).unwrap(),
             version
         );
+
+        let mut version = Version::new(1, 4141);
+        version.user_agent = String::new();
+        let deserialized = Version::deserialize(&Version::serialize(&version).unwrap()).unwrap();
+        assert_eq!(deserialized.user_agent(), None);
     }
 }
@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::NetworkError;
+
 #[cfg_attr(nightly, doc(include = "../../documentation/network_messages/version.md"))]
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Version {
@@ -21,6 +23,10 @@ pub struct Version {
     pub version: u64,
     /// The listening port of the sender.
     pub listening_port: u16,
+    /// The version of the network protocol supported by the sender; 0 if the sender predates version negotiation.
+    pub protocol_version: u32,
+    /// The name and version of the sender's client software; empty if not advertised.
+    pub user_agent: String,
 }
 
 impl Version {
@@ -28,6 +34,27 @@ impl Version {
         Self {
             version,
             listening_port,
+            protocol_version: crate::PROTOCOL_VERSION,
+            user_agent: crate::USER_AGENT.to_owned(),
+        }
+    }
+
+    /// Returns the user agent advertised by the sender, if any.
+    pub fn user_agent(&self) -> Option<String> {
+        if self.user_agent.is_empty() {
+            None
+        } else {
+            Some(self.user_agent.clone())
+        }
+    }
+
+    /// Returns the protocol version to be used with the sender, or an error if its protocol
+    /// version is below `MIN_PROTOCOL_VERSION`.
+    pub fn negotiate(&self) -> Result<u32, NetworkError> {
+        if self.protocol_version < crate::MIN_PROTOCOL_VERSION {
+            Err(NetworkError::IncompatibleProtocolVersion(self.protocol_version))
+        } else {
+            Ok(self.protocol_version.min(crate::PROTOCOL_VERSION))
         }
     }
 }
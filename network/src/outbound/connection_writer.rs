@@ -46,11 +46,11 @@ impl ConnWriter {
         }
     }
 
-    /// Writes a message consisting of a header and payload.
-    pub async fn write_message(&self, payload: &Payload) -> Result<(), NetworkError> {
+    /// Writes a message consisting of a header and payload; returns the number of bytes written.
+    pub async fn write_message(&self, payload: &Payload) -> Result<usize, NetworkError> {
         let serialized_payload = Payload::serialize(payload)?;
 
-        {
+        let written_len = {
             let mut buffer = self.buffer.lock().await;
             let mut encrypted_len = 0;
             let mut processed_len = 0;
@@ -70,10 +70,12 @@ impl ConnWriter {
             let mut writer = self.writer.lock().await;
             writer.write_all(&header.as_bytes()[..]).await?;
             writer.write_all(&buffer[..encrypted_len]).await?;
-        }
+
+            header.as_bytes().len() + encrypted_len
+        };
 
         debug!("Sent a {} to {}", payload, self.addr);
 
-        Ok(())
+        Ok(written_len)
     }
 }
@@ -19,3 +19,6 @@ pub use connection_writer::ConnWriter;
 
 pub mod outbound;
 pub use outbound::*;
+
+pub mod outbound_queue;
+pub use outbound_queue::OutboundQueue;
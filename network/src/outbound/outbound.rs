@@ -14,7 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{ConnWriter, Message, NetworkError};
+use crate::{ConnWriter, Message, NetworkError, OutboundQueue, PeerBook};
+use snarkos_metrics::Metrics;
 
 use std::{
     collections::HashMap,
@@ -30,11 +31,18 @@ use parking_lot::RwLock;
 /// The map of remote addresses to their active write channels.
 type Channels = HashMap<SocketAddr, Arc<ConnWriter>>;
 
+/// The map of remote addresses to the queues of messages to be sent to them.
+type Queues = HashMap<SocketAddr, Arc<OutboundQueue>>;
+
 /// A core data structure for handling outbound network traffic.
 #[derive(Debug, Clone)]
 pub struct Outbound {
     /// The map of remote addresses to their active write channels.
     pub(crate) channels: Arc<RwLock<Channels>>,
+    /// The map of remote addresses to the queues of messages to be sent to them.
+    queues: Arc<RwLock<Queues>>,
+    /// The list of connected and disconnected peers of this node.
+    peer_book: Arc<RwLock<PeerBook>>,
     /// The monotonic counter for the number of send requests that succeeded.
     send_success_count: Arc<AtomicU64>,
     /// The monotonic counter for the number of send requests that failed.
@@ -42,9 +50,11 @@ pub struct Outbound {
 }
 
 impl Outbound {
-    pub fn new(channels: Arc<RwLock<Channels>>) -> Self {
+    pub fn new(channels: Arc<RwLock<Channels>>, peer_book: Arc<RwLock<PeerBook>>) -> Self {
         Self {
             channels,
+            queues: Default::default(),
+            peer_book,
             send_success_count: Default::default(),
             send_failure_count: Default::default(),
         }
@@ -53,12 +63,65 @@ impl Outbound {
     ///
     /// Sends the given request to the address associated with it.
     ///
-    /// Creates or fetches an existing channel with the remote address,
-    /// and attempts to send the given request to them.
+    /// Queues the request for sending over the channel with the remote address; if the peer is slow
+    /// to read its messages and the queue is full, lower-priority messages are dropped first.
     ///
     #[inline]
     pub async fn send_request(&self, request: Message) {
-        self.send(&request).await
+        let queue = match self.outbound_queue(request.receiver()) {
+            Ok(queue) => queue,
+            Err(error) => {
+                warn!("Failed to send a {}: {}", request, error);
+                return;
+            }
+        };
+
+        if let Some(dropped) = queue.push(request) {
+            debug!("The outbound queue is full; dropping a {}", dropped);
+            Metrics::outbound_message_dropped_inc(dropped.payload.name());
+        }
+    }
+
+    ///
+    /// Removes the queue of messages to be sent to the given remote address; the messages that are
+    /// already queued are still sent, as long as the channel remains open.
+    ///
+    pub(crate) fn remove_queue(&self, remote_address: SocketAddr) {
+        if let Some(queue) = self.queues.write().remove(&remote_address) {
+            queue.close();
+        }
+    }
+
+    ///
+    /// Fetches the queue of messages to be sent to the given remote address, creating it along with
+    /// the task sending them if it doesn't exist.
+    ///
+    fn outbound_queue(&self, remote_address: SocketAddr) -> Result<Arc<OutboundQueue>, NetworkError> {
+        if let Some(queue) = self.queues.read().get(&remote_address) {
+            return Ok(queue.clone());
+        }
+
+        let channel = self.outbound_channel(remote_address)?;
+        let queue = self
+            .queues
+            .write()
+            .entry(remote_address)
+            .or_insert_with(|| {
+                let queue = Arc::new(OutboundQueue::new(crate::OUTBOUND_QUEUE_CAPACITY));
+
+                let outbound = self.clone();
+                let task_queue = queue.clone();
+                tokio::spawn(async move {
+                    while let Some(request) = task_queue.next().await {
+                        outbound.send(&channel, &request).await;
+                    }
+                });
+
+                queue
+            })
+            .clone();
+
+        Ok(queue)
     }
 
     ///
@@ -74,19 +137,12 @@ impl Outbound {
             .clone())
     }
 
-    async fn send(&self, request: &Message) {
-        // Fetch the outbound channel.
-        let channel = match self.outbound_channel(request.receiver()) {
-            Ok(channel) => channel,
-            Err(error) => {
-                warn!("Failed to send a {}: {}", request, error);
-                return;
-            }
-        };
-
+    async fn send(&self, channel: &ConnWriter, request: &Message) {
         // Write the request to the outbound channel.
         match channel.write_message(&request.payload).await {
-            Ok(_) => {
+            Ok(len) => {
+                self.peer_book.read().register_bytes_sent(request.receiver(), len);
+                Metrics::outbound_message_inc(request.payload.name());
                 self.send_success_count.fetch_add(1, Ordering::SeqCst);
             }
             Err(error) => {
@@ -0,0 +1,148 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::message::{Message, MessagePriority};
+
+use parking_lot::Mutex;
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use tokio::sync::Notify;
+
+/// A bounded queue of the messages to be sent to a single peer, which keeps a peer that is slow to read
+/// them from making the node's memory use grow without bound.
+#[derive(Debug)]
+pub struct OutboundQueue {
+    /// The queued messages, oldest first.
+    messages: Mutex<VecDeque<Message>>,
+    /// The maximum number of queued messages.
+    capacity: usize,
+    /// Wakes up the task sending the queued messages.
+    notify: Notify,
+    /// Set once no more messages are to be queued.
+    closed: AtomicBool,
+}
+
+impl OutboundQueue {
+    /// Creates a new queue holding up to `capacity` messages.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: Default::default(),
+            capacity: capacity.max(1),
+            notify: Default::default(),
+            closed: Default::default(),
+        }
+    }
+
+    /// Queues the given message. If the queue is full, the oldest low-priority message is shed to make room
+    /// for it; if there are none, a low-priority message is shed itself, while a high-priority one displaces
+    /// the oldest queued message. Returns the shed message, if any.
+    pub fn push(&self, message: Message) -> Option<Message> {
+        let mut messages = self.messages.lock();
+
+        let shed = if messages.len() >= self.capacity {
+            let oldest_low_priority = messages
+                .iter()
+                .position(|queued| queued.payload.priority() == MessagePriority::Low);
+
+            match oldest_low_priority {
+                Some(index) => messages.remove(index),
+                None if message.payload.priority() == MessagePriority::Low => return Some(message),
+                None => messages.pop_front(),
+            }
+        } else {
+            None
+        };
+
+        messages.push_back(message);
+        drop(messages);
+        self.notify.notify_one();
+
+        shed
+    }
+
+    /// Returns the next message to be sent, waiting for one to be queued if necessary; returns `None` once
+    /// the queue is closed and empty.
+    pub async fn next(&self) -> Option<Message> {
+        loop {
+            if let Some(message) = self.messages.lock().pop_front() {
+                return Some(message);
+            }
+            if self.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    /// Closes the queue; the messages queued so far can still be obtained.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+
+    /// Returns the number of queued messages.
+    pub fn len(&self) -> usize {
+        self.messages.lock().len()
+    }
+
+    /// Returns `true` if there are no queued messages.
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{Direction, Payload};
+
+    fn message(payload: Payload) -> Message {
+        Message::new(Direction::Outbound("127.0.0.1:4130".parse().unwrap()), payload)
+    }
+
+    #[tokio::test]
+    async fn low_priority_messages_are_shed_first() {
+        let queue = OutboundQueue::new(3);
+
+        assert!(queue.push(message(Payload::Transaction(vec![1]))).is_none());
+        assert!(queue.push(message(Payload::Pong(1))).is_none());
+        assert!(queue.push(message(Payload::Transaction(vec![2]))).is_none());
+
+        // The queue is full, so the oldest low-priority message makes room for a new one.
+        let shed = queue.push(message(Payload::Pong(2))).unwrap();
+        assert_eq!(shed.payload, Payload::Transaction(vec![1]));
+        let shed = queue.push(message(Payload::Pong(3))).unwrap();
+        assert_eq!(shed.payload, Payload::Transaction(vec![2]));
+
+        // With only high-priority messages queued, a low-priority one is shed itself...
+        let shed = queue.push(message(Payload::Transaction(vec![3]))).unwrap();
+        assert_eq!(shed.payload, Payload::Transaction(vec![3]));
+
+        // ...while a high-priority one displaces the oldest message.
+        let shed = queue.push(message(Payload::Pong(4))).unwrap();
+        assert_eq!(shed.payload, Payload::Pong(1));
+        assert_eq!(queue.len(), 3);
+
+        // The remaining messages are sent in order, until the queue is closed.
+        queue.close();
+        for nonce in 2..=4 {
+            assert_eq!(queue.next().await.unwrap().payload, Payload::Pong(nonce));
+        }
+        assert!(queue.next().await.is_none());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// The length of the prefix grouping IPv4 addresses into subnets.
+pub const IPV4_SUBNET_PREFIX_LEN: u32 = 24;
+/// The length of the prefix grouping IPv6 addresses into subnets.
+pub const IPV6_SUBNET_PREFIX_LEN: u32 = 64;
+
+///
+/// Returns the canonical form of the given address, under which a peer is known to the node.
+///
+/// An IPv4 peer connecting to a dual-stack listener is seen at an IPv4-mapped IPv6 address
+/// (`::ffff:a.b.c.d`), so such addresses are converted back to plain IPv4 ones.
+///
+pub fn normalize_address(address: SocketAddr) -> SocketAddr {
+    match address {
+        SocketAddr::V6(v6) => match ipv4_mapped(v6.ip()) {
+            Some(ip) => SocketAddr::from((ip, v6.port())),
+            None => address,
+        },
+        SocketAddr::V4(_) => address,
+    }
+}
+
+///
+/// Returns the subnet the given IP belongs to, i.e. its /24 for IPv4 and its /64 for IPv6.
+///
+/// IPv4-mapped IPv6 addresses are grouped along with the IPv4 address they map.
+///
+pub fn subnet(ip: IpAddr) -> IpAddr {
+    let ip = match ip {
+        IpAddr::V6(v6) => ipv4_mapped(&v6).map(IpAddr::V4).unwrap_or(ip),
+        IpAddr::V4(_) => ip,
+    };
+
+    match ip {
+        IpAddr::V4(v4) => {
+            let mask = u32::MAX << (32 - IPV4_SUBNET_PREFIX_LEN);
+            IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+        }
+        IpAddr::V6(v6) => {
+            let mask = u128::MAX << (128 - IPV6_SUBNET_PREFIX_LEN);
+            IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+        }
+    }
+}
+
+/// Returns the IPv4 address mapped by the given IPv6 one, if it is IPv4-mapped.
+///
+/// Unlike `Ipv6Addr::to_ipv4`, this doesn't treat IPv4-compatible addresses like `::1` as IPv4 ones.
+fn ipv4_mapped(ip: &Ipv6Addr) -> Option<Ipv4Addr> {
+    match ip.octets() {
+        [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => Some(Ipv4Addr::new(a, b, c, d)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv4_mapped_addresses_are_normalized() {
+        let mapped: SocketAddr = "[::ffff:10.0.0.1]:4130".parse().unwrap();
+        let expected: SocketAddr = "10.0.0.1:4130".parse().unwrap();
+
+        assert_eq!(normalize_address(mapped), expected);
+        assert_eq!(normalize_address(expected), expected);
+    }
+
+    #[test]
+    fn native_ipv6_addresses_are_kept() {
+        for address in &["[::1]:4130", "[2001:db8::1]:4130", "[::]:4130"] {
+            let address: SocketAddr = address.parse().unwrap();
+            assert_eq!(normalize_address(address), address);
+        }
+    }
+
+    #[test]
+    fn addresses_are_grouped_into_subnets() {
+        let subnet_of = |ip: &str| subnet(ip.parse().unwrap());
+
+        assert_eq!(subnet_of("10.0.0.1"), subnet_of("10.0.0.254"));
+        assert_ne!(subnet_of("10.0.0.1"), subnet_of("10.0.1.1"));
+        assert_eq!(subnet_of("::ffff:10.0.0.1"), subnet_of("10.0.0.2"));
+
+        assert_eq!(
+            subnet_of("2001:db8:0:1::1"),
+            subnet_of("2001:db8:0:1:ffff:ffff:ffff:ffff")
+        );
+        assert_ne!(subnet_of("2001:db8:0:1::1"), subnet_of("2001:db8:0:2::1"));
+        assert_eq!(
+            subnet_of("2001:db8:0:1::1"),
+            "2001:db8:0:1::".parse::<IpAddr>().unwrap()
+        );
+    }
+}
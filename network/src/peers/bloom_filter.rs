@@ -0,0 +1,170 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{NetworkError, MAX_BLOOM_FILTER_SIZE, MAX_BLOOM_HASH_FUNCS, MAX_FILTER_ELEMENT_SIZE};
+use snarkvm_objects::Transaction;
+use snarkvm_utilities::{bytes::ToBytes, to_bytes};
+
+/// The multiplier used to derive the seed of each hash function from its index, as in BIP37.
+const HASH_SEED_MULTIPLIER: u32 = 0xFBA4_C795;
+
+/// A BIP37-style bloom filter loaded by a peer in order to only be relayed the transactions it's interested in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    /// The bit field of the filter.
+    data: Vec<u8>,
+    /// The number of hash functions applied to each element.
+    hash_funcs: u32,
+    /// The value mixed into the seed of every hash function.
+    tweak: u32,
+}
+
+impl BloomFilter {
+    /// Creates a bloom filter from the parameters of a `FilterLoad` message, rejecting ones
+    /// exceeding `MAX_BLOOM_FILTER_SIZE` or `MAX_BLOOM_HASH_FUNCS`.
+    pub fn new(data: Vec<u8>, hash_funcs: u32, tweak: u32) -> Result<Self, NetworkError> {
+        if data.is_empty() || data.len() > MAX_BLOOM_FILTER_SIZE || hash_funcs == 0 || hash_funcs > MAX_BLOOM_HASH_FUNCS
+        {
+            return Err(NetworkError::InvalidBloomFilter);
+        }
+
+        Ok(Self {
+            data,
+            hash_funcs,
+            tweak,
+        })
+    }
+
+    /// Adds an element to the filter; elements larger than `MAX_FILTER_ELEMENT_SIZE` are rejected.
+    pub fn insert(&mut self, element: &[u8]) -> Result<(), NetworkError> {
+        if element.len() > MAX_FILTER_ELEMENT_SIZE {
+            return Err(NetworkError::InvalidBloomFilter);
+        }
+
+        for i in 0..self.hash_funcs {
+            let index = self.bit_index(i, element);
+            self.data[index >> 3] |= 1 << (index & 7);
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the element may have been added to the filter.
+    pub fn contains(&self, element: &[u8]) -> bool {
+        (0..self.hash_funcs).all(|i| {
+            let index = self.bit_index(i, element);
+            self.data[index >> 3] & (1 << (index & 7)) != 0
+        })
+    }
+
+    /// Checks whether the transaction is relevant to the filter, i.e. whether its id or any of its
+    /// serial numbers, commitments or memorandum match it.
+    pub fn matches_transaction<T: Transaction>(&self, transaction: &T) -> bool {
+        if let Ok(id) = transaction.transaction_id() {
+            if self.contains(&id) {
+                return true;
+            }
+        }
+
+        let serial_numbers = transaction
+            .old_serial_numbers()
+            .iter()
+            .filter_map(|sn| to_bytes![sn].ok());
+        let commitments = transaction.new_commitments().iter().filter_map(|cm| to_bytes![cm].ok());
+        let memo = to_bytes![transaction.memorandum()].ok();
+
+        serial_numbers
+            .chain(commitments)
+            .chain(memo)
+            .any(|element| self.contains(&element))
+    }
+
+    fn bit_index(&self, hash_num: u32, element: &[u8]) -> usize {
+        let seed = hash_num.wrapping_mul(HASH_SEED_MULTIPLIER).wrapping_add(self.tweak);
+        murmur3_32(seed, element) as usize % (self.data.len() * 8)
+    }
+}
+
+/// The 32-bit variant of the MurmurHash3 function.
+fn murmur3_32(seed: u32, data: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mut h1 = seed;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k1 = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k1 = k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+
+        h1 ^= k1;
+        h1 = h1.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k1 = 0u32;
+        for (i, byte) in tail.iter().enumerate() {
+            k1 ^= (*byte as u32) << (8 * i);
+        }
+        h1 ^= k1.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    h1 ^= data.len() as u32;
+    h1 ^= h1 >> 16;
+    h1 = h1.wrapping_mul(0x85eb_ca6b);
+    h1 ^= h1 >> 13;
+    h1 = h1.wrapping_mul(0xc2b2_ae35);
+    h1 ^= h1 >> 16;
+
+    h1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmur3_test_vectors() {
+        // vectors from the BIP37 reference implementation's tests
+        assert_eq!(murmur3_32(0x0000_0000, &[]), 0x0000_0000);
+        assert_eq!(murmur3_32(0xFBA4_C795, &[]), 0x6a39_6f08);
+        assert_eq!(murmur3_32(0x0000_0000, &[0x00]), 0x514e_28b7);
+        assert_eq!(murmur3_32(0x0000_0000, &[0x21, 0x43, 0x65, 0x87]), 0xf55b_516b);
+        assert_eq!(murmur3_32(0x5082_EDEE, &[0x21, 0x43, 0x65, 0x87]), 0x2362_f9de);
+    }
+
+    #[test]
+    fn insert_and_contains() {
+        let mut filter = BloomFilter::new(vec![0u8; 32], 5, 42).unwrap();
+        let (present, absent) = ([1u8; 32], [2u8; 32]);
+
+        assert!(!filter.contains(&present));
+        filter.insert(&present).unwrap();
+        assert!(filter.contains(&present));
+        assert!(!filter.contains(&absent));
+    }
+
+    #[test]
+    fn oversized_filters_are_rejected() {
+        assert!(BloomFilter::new(vec![0u8; MAX_BLOOM_FILTER_SIZE + 1], 1, 0).is_err());
+        assert!(BloomFilter::new(vec![0u8; 32], MAX_BLOOM_HASH_FUNCS + 1, 0).is_err());
+        assert!(BloomFilter::new(vec![], 1, 0).is_err());
+
+        let mut filter = BloomFilter::new(vec![0u8; 32], 1, 0).unwrap();
+        assert!(filter.insert(&[0u8; MAX_FILTER_ELEMENT_SIZE + 1]).is_err());
+    }
+}
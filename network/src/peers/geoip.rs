@@ -0,0 +1,119 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+
+/// The location of a peer derived from its IP address.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerLocation {
+    /// The ISO 3166-1 code of the country the peer is located in, if known.
+    pub country: Option<String>,
+    /// The number of the autonomous system the peer belongs to, if known.
+    pub asn: Option<u32>,
+}
+
+#[cfg(feature = "geoip")]
+pub use lookup::GeoIpLookup;
+
+#[cfg(feature = "geoip")]
+mod lookup {
+    use super::PeerLocation;
+
+    use maxminddb::{geoip2, MaxMindDBError, Reader};
+    use parking_lot::Mutex;
+    use std::{collections::HashMap, net::IpAddr, path::Path};
+
+    /// Resolves the locations of peers using a MaxMind (e.g. GeoLite2) database, caching them per IP.
+    pub struct GeoIpLookup {
+        /// The reader of the database.
+        reader: Reader<Vec<u8>>,
+        /// The locations resolved so far; `None` if the IP isn't in the database.
+        cache: Mutex<HashMap<IpAddr, Option<PeerLocation>>>,
+    }
+
+    impl GeoIpLookup {
+        /// Opens the MaxMind database at the given path.
+        pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, MaxMindDBError> {
+            Ok(Self {
+                reader: Reader::open_readfile(path)?,
+                cache: Default::default(),
+            })
+        }
+
+        /// Returns the location of the given IP; both country and ASN records are looked up, so the
+        /// database can be a country, ASN or combined one.
+        pub fn locate(&self, ip: IpAddr) -> Option<PeerLocation> {
+            if let Some(location) = self.cache.lock().get(&ip) {
+                return location.clone();
+            }
+
+            let country = self
+                .reader
+                .lookup::<geoip2::Country>(ip)
+                .ok()
+                .and_then(|record| record.country)
+                .and_then(|country| country.iso_code)
+                .map(|iso_code| iso_code.to_owned());
+            let asn = self
+                .reader
+                .lookup::<geoip2::Asn>(ip)
+                .ok()
+                .and_then(|record| record.autonomous_system_number);
+
+            let location = if country.is_none() && asn.is_none() {
+                None
+            } else {
+                Some(PeerLocation { country, asn })
+            };
+            self.cache.lock().insert(ip, location.clone());
+
+            location
+        }
+
+        /// Returns the number of IPs whose location was already looked up.
+        pub fn cached_count(&self) -> usize {
+            self.cache.lock().len()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // A tiny database mapping 127.0.0.0/8 to the country "AQ" and the ASN 64512.
+        const FIXTURE_DB: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/geoip-test.mmdb");
+
+        #[test]
+        fn location_is_looked_up_and_cached() {
+            let geoip = GeoIpLookup::open(FIXTURE_DB).unwrap();
+
+            let expected = Some(PeerLocation {
+                country: Some("AQ".into()),
+                asn: Some(64512),
+            });
+            assert_eq!(geoip.locate("127.0.0.1".parse().unwrap()), expected);
+            assert_eq!(geoip.locate("127.0.0.1".parse().unwrap()), expected);
+            assert_eq!(geoip.cached_count(), 1);
+        }
+
+        #[test]
+        fn unknown_ips_have_no_location() {
+            let geoip = GeoIpLookup::open(FIXTURE_DB).unwrap();
+
+            assert_eq!(geoip.locate("10.0.0.1".parse().unwrap()), None);
+        }
+    }
+}
@@ -14,6 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
+pub mod address;
+pub use address::*;
+
+pub mod bloom_filter;
+pub use bloom_filter::*;
+
+pub mod geoip;
+pub use geoip::*;
+
 pub mod peers;
 pub use peers::*;
 
@@ -15,7 +15,7 @@
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    peers::{PeerInfo, PeerQuality},
+    peers::{BloomFilter, ConnectionDirection, DisconnectReason, PeerInfo, PeerLocation, PeerQuality},
     NetworkError,
 };
 use snarkos_metrics::Metrics;
@@ -23,14 +23,26 @@ use snarkos_storage::Ledger;
 use snarkvm_algorithms::traits::LoadableMerkleParameters;
 use snarkvm_objects::Transaction;
 
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     sync::{atomic::Ordering, Arc},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
+/// The way in which a peer was first discovered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiscoverySource {
+    /// The peer is one of the default bootnodes of the network.
+    Bootnode,
+    /// The peer was shared by another peer in a `Peers` message.
+    Gossip,
+    /// The peer was added explicitly.
+    Manual,
+}
+
 ///
 /// A data structure for storing the history of all peers with this node server.
 ///
@@ -42,6 +54,27 @@ pub struct PeerBook {
     connected_peers: HashMap<SocketAddr, PeerInfo>,
     /// The map of disconnected peers to their metadata.
     disconnected_peers: HashMap<SocketAddr, PeerInfo>,
+    /// The map of the addresses connected peers connected from to their listener addresses, if they differ.
+    #[serde(skip)]
+    listener_addresses: HashMap<SocketAddr, SocketAddr>,
+    /// The map of the peers discovered during the node's lifetime to the way they were first discovered.
+    #[serde(skip)]
+    discovery_sources: HashMap<SocketAddr, DiscoverySource>,
+    /// The number of connections attempted by this node during its lifetime.
+    #[serde(skip)]
+    dial_attempts: u64,
+    /// The number of connections attempted by this node that resulted in a completed handshake.
+    #[serde(skip)]
+    successful_dials: u64,
+    /// The addresses of the peers that are never evicted or banned, and are dialed first.
+    #[serde(skip)]
+    allowlisted: HashSet<SocketAddr>,
+    /// The maximum number of peers from a single autonomous system that can be connected or connecting, if any.
+    #[serde(skip)]
+    max_peers_per_asn: Option<usize>,
+    /// The autonomous systems (ASNs) the IPs of the peers belong to, as far as they're known.
+    #[serde(skip)]
+    peer_asns: HashMap<IpAddr, u32>,
 }
 
 impl PeerBook {
@@ -145,18 +178,36 @@ impl PeerBook {
         if self.is_connected(address) {
             return Err(NetworkError::PeerAlreadyConnected);
         }
+        self.check_asn_capacity(address, true)?;
         self.connecting_peers.insert(address);
 
         Ok(())
     }
 
     ///
-    /// Adds the given address to the connected peers in the `PeerBook`.
+    /// Adds the given address to the connected peers in the `PeerBook`, recording the side that initiated
+    /// the connection.
     ///
-    pub fn set_connected(&mut self, address: SocketAddr, listener: Option<SocketAddr>) -> Result<(), NetworkError> {
+    pub fn set_connected(
+        &mut self,
+        address: SocketAddr,
+        listener: Option<SocketAddr>,
+        direction: ConnectionDirection,
+    ) -> Result<(), NetworkError> {
         // If listener.is_some(), then it's different than the address; otherwise it's just the address param.
         let listener = if let Some(addr) = listener { addr } else { address };
 
+        // Simultaneous handshakes (e.g. an inbound and an outbound one) can complete for the same listener;
+        // the older connection is kept, and the caller is expected to drop the redundant socket.
+        if self.connected_peers.contains_key(&listener) {
+            self.connecting_peers.remove(&address);
+            return Err(NetworkError::PeerAlreadyConnected);
+        }
+        if let Err(e) = self.check_asn_capacity(listener, false) {
+            self.connecting_peers.remove(&address);
+            return Err(e);
+        }
+
         // Remove the address from the connecting peers, if it exists.
         let mut peer_info = match self.disconnected_peers.remove(&listener) {
             // Case 1 - A previously known peer.
@@ -168,8 +219,13 @@ impl PeerBook {
         // Remove the peer's address from the list of connecting peers.
         self.connecting_peers.remove(&address);
 
+        // Remember the listener address, so that the peer can be disconnected using the address it connected from.
+        if listener != address {
+            self.listener_addresses.insert(address, listener);
+        }
+
         // Update the peer info to connected.
-        peer_info.set_connected()?;
+        peer_info.set_connected(direction)?;
 
         // Add the address into the connected peers.
         let success = self.connected_peers.insert(listener, peer_info).is_none();
@@ -181,23 +237,28 @@ impl PeerBook {
 
     ///
     /// Removes the given address from the connecting and connected peers in this `PeerBook`,
-    /// and adds the given address to the disconnected peers in this `PeerBook`.
+    /// and adds the given address to the disconnected peers in this `PeerBook`, recording the
+    /// reason for the disconnect.
     ///
-    pub fn set_disconnected(&mut self, address: SocketAddr) -> Result<(), NetworkError> {
+    pub fn set_disconnected(&mut self, address: SocketAddr, reason: DisconnectReason) -> Result<(), NetworkError> {
         // Case 1 - The given address is a connecting peer, attempt to disconnect.
         if self.connecting_peers.remove(&address) {
             return Ok(());
         }
 
+        // A peer is connected under its listener address, which may differ from the one it connected from.
+        let address = self.listener_addresses.remove(&address).unwrap_or(address);
+
         // Case 2 - The given address is a connected peer, attempt to disconnect.
         if let Some(mut peer_info) = self.connected_peers.remove(&address) {
             // Update the peer info to disconnected.
-            peer_info.set_disconnected()?;
+            peer_info.set_disconnected(reason)?;
 
             // Add the address into the disconnected peers.
             let success = self.disconnected_peers.insert(address, peer_info).is_none();
             // On success, decrement the connected peer count.
             connected_peers_dec!(success);
+            Metrics::peer_disconnect_inc(reason.name());
 
             return Ok(());
         }
@@ -219,6 +280,15 @@ impl PeerBook {
     /// Adds the given address to the disconnected peers in this `PeerBook`.
     ///
     pub fn add_peer(&mut self, address: SocketAddr) {
+        self.add_peer_from(address, DiscoverySource::Manual);
+    }
+
+    ///
+    /// Adds the given address, discovered in the given way, to the disconnected peers in the `PeerBook`.
+    ///
+    pub fn add_peer_from(&mut self, address: SocketAddr, source: DiscoverySource) {
+        self.register_discovery(address, source);
+
         if self.is_connected(address) || self.is_disconnected(address) || self.is_connecting(address) {
             return;
         }
@@ -231,6 +301,31 @@ impl PeerBook {
         debug!("Added {} to the peer book", address);
     }
 
+    ///
+    /// Registers an alternate address of the given known peer, to be dialed if its main address is unreachable.
+    ///
+    pub fn add_alternate_address(&mut self, address: SocketAddr, alternate: SocketAddr) {
+        let peer_info = match self.connected_peers.get_mut(&address) {
+            Some(peer_info) => Some(peer_info),
+            None => self.disconnected_peers.get_mut(&address),
+        };
+
+        if let Some(peer_info) = peer_info {
+            peer_info.add_alternate_address(alternate);
+        }
+    }
+
+    ///
+    /// Returns the alternate addresses of the given peer, or an empty list if it's not known.
+    ///
+    pub fn alternate_addresses(&self, address: SocketAddr) -> Vec<SocketAddr> {
+        self.connected_peers
+            .get(&address)
+            .or_else(|| self.disconnected_peers.get(&address))
+            .map(|peer_info| peer_info.alternate_addresses().to_vec())
+            .unwrap_or_default()
+    }
+
     ///
     /// Returns a reference to the peer info of the given address, if it exists.
     ///
@@ -275,12 +370,336 @@ impl PeerBook {
 
         // Remove the address from the disconnected peers, if it exists.
         self.disconnected_peers.remove(address);
+
+        // Remove the listener address mappings involving the address, if they exist.
+        self.listener_addresses
+            .retain(|connected_from, listener| connected_from != address && listener != address);
     }
 
     fn peer_quality(&self, addr: SocketAddr) -> Option<Arc<PeerQuality>> {
         self.connected_peers().get(&addr).map(|peer| Arc::clone(&peer.quality))
     }
 
+    ///
+    /// Registers the way in which the given peer was discovered, unless it's already known.
+    ///
+    pub fn register_discovery(&mut self, address: SocketAddr, source: DiscoverySource) {
+        self.discovery_sources.entry(address).or_insert(source);
+    }
+
+    ///
+    /// Returns the number of peers first discovered in the given way during the node's lifetime.
+    ///
+    pub fn number_of_discovered_peers(&self, source: DiscoverySource) -> usize {
+        self.discovery_sources.values().filter(|&&s| s == source).count()
+    }
+
+    ///
+    /// Registers an attempt to connect to a peer.
+    ///
+    pub fn register_dial_attempt(&mut self) {
+        self.dial_attempts += 1;
+    }
+
+    ///
+    /// Registers an attempt to connect to a peer that resulted in a completed handshake.
+    ///
+    pub fn register_successful_dial(&mut self) {
+        self.successful_dials += 1;
+    }
+
+    ///
+    /// Returns the number of connections attempted by this node during its lifetime.
+    ///
+    pub fn dial_attempts(&self) -> u64 {
+        self.dial_attempts
+    }
+
+    ///
+    /// Returns the number of connections attempted by this node that resulted in a completed handshake.
+    ///
+    pub fn successful_dials(&self) -> u64 {
+        self.successful_dials
+    }
+
+    ///
+    /// Adds the given address to the allowlisted peers, which are never evicted or banned.
+    ///
+    pub fn allowlist_peer(&mut self, address: SocketAddr) {
+        self.allowlisted.insert(address);
+        self.add_peer(address);
+    }
+
+    ///
+    /// Returns `true` if the given address is an allowlisted peer.
+    ///
+    #[inline]
+    pub fn is_allowlisted(&self, address: SocketAddr) -> bool {
+        self.allowlisted.contains(&address)
+    }
+
+    ///
+    /// Sets the maximum number of peers from a single autonomous system that can be connected or connecting;
+    /// if `None`, or if the ASNs of the peers aren't known, the number isn't limited.
+    ///
+    pub fn set_max_peers_per_asn(&mut self, max_peers_per_asn: Option<usize>) {
+        self.max_peers_per_asn = max_peers_per_asn;
+    }
+
+    ///
+    /// Records the autonomous system (ASN) the given IP belongs to.
+    ///
+    pub fn register_asn(&mut self, ip: IpAddr, asn: u32) {
+        self.peer_asns.insert(ip, asn);
+    }
+
+    ///
+    /// Checks whether one more peer at the given address can be admitted without exceeding the maximum
+    /// number of peers from its autonomous system; connecting peers are only counted if `count_connecting`.
+    ///
+    fn check_asn_capacity(&self, address: SocketAddr, count_connecting: bool) -> Result<(), NetworkError> {
+        let (max_peers_per_asn, asn) = match (self.max_peers_per_asn, self.peer_asns.get(&address.ip())) {
+            (Some(max_peers_per_asn), Some(asn)) => (max_peers_per_asn, *asn),
+            _ => return Ok(()),
+        };
+        if self.is_allowlisted(address) {
+            return Ok(());
+        }
+
+        let connecting_peers = self.connecting_peers.iter().filter(|_| count_connecting);
+        let peers_in_asn = self
+            .connected_peers
+            .keys()
+            .chain(connecting_peers)
+            .filter(|&&peer| peer != address && self.peer_asns.get(&peer.ip()) == Some(&asn))
+            .count();
+
+        if peers_in_asn >= max_peers_per_asn {
+            debug!(
+                "Not admitting {}, as there are {} peers from AS{} already",
+                address, peers_in_asn, asn
+            );
+            Err(NetworkError::TooManyPeersInASN(asn))
+        } else {
+            Ok(())
+        }
+    }
+
+    ///
+    /// Sets the geographic region or autonomous system (ASN) of a known peer.
+    ///
+    pub fn set_region(&mut self, address: SocketAddr, region: Option<String>) {
+        if let Some(peer_info) = self.connected_peers.get_mut(&address) {
+            peer_info.set_region(region);
+        } else if let Some(peer_info) = self.disconnected_peers.get_mut(&address) {
+            peer_info.set_region(region);
+        }
+    }
+
+    ///
+    /// Sets the country and ASN of a known peer.
+    ///
+    pub fn set_location(&mut self, address: SocketAddr, location: Option<PeerLocation>) {
+        if let Some(peer_info) = self.connected_peers.get_mut(&address) {
+            peer_info.set_location(location);
+        } else if let Some(peer_info) = self.disconnected_peers.get_mut(&address) {
+            peer_info.set_location(location);
+        }
+    }
+
+    ///
+    /// Returns up to `needed` disconnected peers to dial, the best ones first: the allowlisted ones,
+    /// then those with the highest score, then the rest ranked as in `ranked_bootstrap_candidates`,
+    /// and then the most recently seen ones. Banned and backed-off peers are skipped.
+    ///
+    pub fn candidates_to_dial(&self, needed: usize) -> Vec<SocketAddr> {
+        let mut candidates = self
+            .disconnected_peers
+            .values()
+            .filter(|peer_info| !peer_info.quality.is_banned() && !peer_info.quality.is_backed_off())
+            .collect::<Vec<_>>();
+        candidates.sort_unstable_by_key(|peer_info| {
+            (
+                !self.is_allowlisted(peer_info.address()),
+                std::cmp::Reverse(peer_info.quality.score()),
+                peer_info.connected_count() == 0,
+                std::cmp::Reverse(peer_info.persisted_score()),
+                std::cmp::Reverse(peer_info.last_seen().max(peer_info.last_connected())),
+            )
+        });
+
+        candidates
+            .into_iter()
+            .take(needed)
+            .map(|peer_info| peer_info.address())
+            .collect()
+    }
+
+    ///
+    /// Returns the disconnected peers that aren't banned, ordered by the score persisted along with them
+    /// and then by how recently they were connected to, so that the historically best peers are dialed
+    /// first after a restart, when the live scores aren't known yet. Peers that were never connected to
+    /// have no score on record, so they come last.
+    ///
+    pub fn ranked_bootstrap_candidates(&self) -> Vec<SocketAddr> {
+        let mut candidates = self
+            .disconnected_peers
+            .values()
+            .filter(|peer_info| !peer_info.quality.is_banned())
+            .collect::<Vec<_>>();
+        candidates.sort_unstable_by_key(|peer_info| {
+            (
+                peer_info.connected_count() == 0,
+                std::cmp::Reverse(peer_info.persisted_score()),
+                std::cmp::Reverse(peer_info.last_connected()),
+            )
+        });
+
+        candidates.into_iter().map(|peer_info| peer_info.address()).collect()
+    }
+
+    ///
+    /// Returns up to `count` disconnected peers to dial, chosen as in `candidates_to_dial`; the allowlisted
+    /// ones and the ones in the preferred regions are chosen first, and the rest only fill the remaining slots.
+    ///
+    pub fn candidate_peers(&self, count: usize, preferred_regions: &[String]) -> Vec<SocketAddr> {
+        let (preferred, others): (Vec<_>, Vec<_>) = self
+            .candidates_to_dial(self.disconnected_peers.len())
+            .into_iter()
+            .partition(|addr| {
+                self.is_allowlisted(*addr) || self.disconnected_peers[addr].is_in_regions(preferred_regions)
+            });
+
+        preferred.into_iter().chain(others).take(count).collect()
+    }
+
+    ///
+    /// Registers a failed attempt to connect to the given known peer, which backs off dialing it again.
+    ///
+    pub fn register_failed_dial(&self, address: SocketAddr) {
+        if let Some(peer_info) = self.disconnected_peers.get(&address) {
+            *peer_info.quality.last_failed_dial.lock() = Some(Instant::now());
+        }
+    }
+
+    ///
+    /// Returns up to `count` addresses of known peers to share with the given peer, the most recently
+    /// seen ones first. Bootnodes share their disconnected peers, as they don't maintain connections.
+    ///
+    pub fn peers_to_share(&self, requester: SocketAddr, count: usize, is_bootnode: bool) -> Vec<SocketAddr> {
+        let known_peers = if is_bootnode {
+            &self.disconnected_peers
+        } else {
+            &self.connected_peers
+        };
+
+        let mut peers = known_peers
+            .values()
+            .filter(|peer_info| peer_info.address() != requester)
+            .collect::<Vec<_>>();
+        peers.sort_unstable_by_key(|peer_info| {
+            std::cmp::Reverse(
+                peer_info
+                    .last_seen()
+                    .max(peer_info.last_connected())
+                    .max(peer_info.last_disconnected()),
+            )
+        });
+
+        peers
+            .into_iter()
+            .take(count)
+            .map(|peer_info| peer_info.address())
+            .collect()
+    }
+
+    ///
+    /// Registers a `GetPeers` request from the given peer. Returns `false` if the peer has already sent
+    /// one less than `MIN_GET_PEERS_INTERVAL_SECS` ago, in which case the request should be ignored.
+    ///
+    pub fn register_get_peers(&self, addr: SocketAddr) -> bool {
+        let quality = match self.peer_quality(addr) {
+            Some(quality) => quality,
+            None => return true,
+        };
+
+        let mut last_get_peers = quality.last_get_peers.lock();
+        let min_interval = Duration::from_secs(crate::MIN_GET_PEERS_INTERVAL_SECS);
+        if matches!(*last_get_peers, Some(last) if last.elapsed() < min_interval) {
+            return false;
+        }
+        *last_get_peers = Some(Instant::now());
+
+        true
+    }
+
+    ///
+    /// Registers the given addresses as advertised to the given peer. Returns `false` if the same set
+    /// of addresses was already advertised to it less than `PEER_ADVERTISEMENT_WINDOW_SECS` ago, in
+    /// which case it shouldn't be sent again.
+    ///
+    pub fn register_advertised_peers(&self, addr: SocketAddr, peers: &[SocketAddr]) -> bool {
+        let quality = match self.peer_quality(addr) {
+            Some(quality) => quality,
+            None => return true,
+        };
+
+        let mut peers = peers.to_vec();
+        peers.sort_unstable();
+
+        let mut last_advertised = quality.last_advertised.lock();
+        let window = Duration::from_secs(crate::PEER_ADVERTISEMENT_WINDOW_SECS);
+        if matches!(&*last_advertised, Some((time, advertised)) if time.elapsed() < window && *advertised == peers) {
+            return false;
+        }
+        *last_advertised = Some((Instant::now(), peers));
+
+        true
+    }
+
+    ///
+    /// Loads the bloom filter sent by the given peer in a `FilterLoad` message, replacing the previous
+    /// one; invalid (e.g. oversized) filters are rejected and count as a failure.
+    ///
+    pub fn load_bloom_filter(&self, addr: SocketAddr, data: Vec<u8>, hash_funcs: u32, tweak: u32) {
+        if let Some(quality) = self.peer_quality(addr) {
+            match BloomFilter::new(data, hash_funcs, tweak) {
+                Ok(filter) => *quality.bloom_filter.write() = Some(filter),
+                Err(_) => {
+                    warn!("Peer {} sent an invalid bloom filter", addr);
+                    quality.register_failure();
+                }
+            }
+        }
+    }
+
+    ///
+    /// Adds the element sent by the given peer in a `FilterAdd` message to its bloom filter; oversized
+    /// elements and ones sent without a loaded filter are rejected and count as a failure.
+    ///
+    pub fn add_to_bloom_filter(&self, addr: SocketAddr, element: &[u8]) {
+        if let Some(quality) = self.peer_quality(addr) {
+            let added = match &mut *quality.bloom_filter.write() {
+                Some(filter) => filter.insert(element).is_ok(),
+                None => false,
+            };
+
+            if !added {
+                warn!("Peer {} sent an invalid bloom filter element", addr);
+                quality.register_failure();
+            }
+        }
+    }
+
+    ///
+    /// Removes the bloom filter of the given peer, so that all transactions are relayed to it again.
+    ///
+    pub fn clear_bloom_filter(&self, addr: SocketAddr) {
+        if let Some(quality) = self.peer_quality(addr) {
+            *quality.bloom_filter.write() = None;
+        }
+    }
+
     ///
     /// Returns the `SocketAddr` of the last seen peer to be used as a sync node, or `None`.
     ///
@@ -296,6 +715,41 @@ impl PeerBook {
         }
     }
 
+    ///
+    /// Returns a connected peer to sync with, chosen at random with a probability inversely proportional
+    /// to its RTT and failure count, so that the sync load is spread while good peers are still favored.
+    /// Only the peers that advertised a height above `current_height` are considered; the ones that never
+    /// advertised one aren't known to be ahead. The `avoided` peer is only chosen if there is no other one.
+    ///
+    pub fn weighted_sync_node<R: Rng>(
+        &self,
+        rng: &mut R,
+        current_height: u32,
+        avoided: Option<SocketAddr>,
+    ) -> Option<SocketAddr> {
+        let candidates = self
+            .connected_peers
+            .iter()
+            .filter(|(_, peer_info)| peer_info.quality.advertised_height.load(Ordering::SeqCst) > current_height)
+            .collect::<Vec<_>>();
+        let num_candidates = candidates.len();
+
+        let (addresses, weights): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .filter(|(addr, _)| Some(**addr) != avoided || num_candidates == 1)
+            .map(|(addr, peer_info)| {
+                let rtt_ms = peer_info.quality.rtt_ms.load(Ordering::SeqCst) as f64;
+                let failures = peer_info.quality.failures.load(Ordering::SeqCst) as f64;
+
+                (*addr, 1.0 / ((rtt_ms + 1.0) * (failures + 1.0)))
+            })
+            .unzip();
+
+        let distribution = WeightedIndex::new(&weights).ok()?;
+
+        Some(addresses[distribution.sample(rng)])
+    }
+
     ///
     /// Updates the last seen timestamp of this peer to the current time.
     ///
@@ -308,27 +762,30 @@ impl PeerBook {
         }
     }
 
-    pub fn sending_ping(&self, target: SocketAddr) {
+    /// Registers a `Ping` with the given nonce being sent to a peer.
+    pub fn sending_ping(&self, target: SocketAddr, nonce: u64) {
         if let Some(quality) = self.peer_quality(target) {
             let timestamp = Instant::now();
             *quality.last_ping_sent.lock() = Some(timestamp);
-            quality.expecting_pong.store(true, Ordering::SeqCst);
+            *quality.expected_pong_nonce.lock() = Some(nonce);
         } else {
             // shouldn't occur, but just in case
             warn!("Tried to send a Ping to an unknown peer: {}!", target);
         }
     }
 
-    /// Handles an incoming `Pong` message.
-    pub fn received_pong(&self, source: SocketAddr) {
+    /// Handles an incoming `Pong` message; only the one answering the outstanding `Ping` is used
+    /// to measure the RTT, while delayed, duplicate or unsolicited ones count as failures.
+    pub fn received_pong(&self, source: SocketAddr, nonce: u64) {
         if let Some(quality) = self.peer_quality(source) {
-            if quality.expecting_pong.load(Ordering::SeqCst) {
+            let mut expected_nonce = quality.expected_pong_nonce.lock();
+
+            if *expected_nonce == Some(nonce) {
                 let ping_sent = quality.last_ping_sent.lock().unwrap();
-                let rtt = ping_sent.elapsed().as_millis() as u64;
-                quality.rtt_ms.store(rtt, Ordering::SeqCst);
-                quality.expecting_pong.store(false, Ordering::SeqCst);
+                quality.register_rtt(ping_sent.elapsed().as_millis() as u64);
+                *expected_nonce = None;
             } else {
-                quality.failures.fetch_add(1, Ordering::Relaxed);
+                quality.register_failure();
             }
         } else {
             // shouldn't occur, but just in case
@@ -336,25 +793,169 @@ impl PeerBook {
         }
     }
 
+    /// Returns the connected peers whose moving average RTT exceeded the given maximum over `SLOW_PEER_RTT_SAMPLES`
+    /// consecutive measurements; allowlisted peers are exempt.
+    pub fn slow_peers(&self, max_rtt_ms: u64) -> Vec<SocketAddr> {
+        self.connected_peers
+            .iter()
+            .filter(|(addr, _)| !self.is_allowlisted(**addr))
+            .filter(|(_, peer_info)| peer_info.quality.is_chronically_slow(max_rtt_ms))
+            .map(|(addr, _)| *addr)
+            .collect()
+    }
+
+    /// Returns the p50, p90 and p99 RTT across the connected peers in milliseconds, using the nearest-rank
+    /// method; peers whose RTT wasn't measured yet are skipped, and `None` is returned if there are none.
+    pub fn rtt_percentiles(&self) -> Option<(u64, u64, u64)> {
+        let mut rtts = self
+            .connected_peers
+            .values()
+            .filter(|peer_info| peer_info.quality.rtt_samples.load(Ordering::SeqCst) != 0)
+            .map(|peer_info| peer_info.quality.rtt_ms.load(Ordering::SeqCst))
+            .collect::<Vec<_>>();
+        if rtts.is_empty() {
+            return None;
+        }
+        rtts.sort_unstable();
+
+        let percentile = |p: usize| rtts[(p * rtts.len() + 99) / 100 - 1];
+
+        Some((percentile(50), percentile(90), percentile(99)))
+    }
+
+    /// Registers the block height advertised by the given peer in a `Ping` message.
+    pub fn register_advertised_height(&self, addr: SocketAddr, block_height: u32) {
+        if let Some(quality) = self.peer_quality(addr) {
+            quality.advertised_height.store(block_height, Ordering::SeqCst);
+        }
+    }
+
+    /// Returns the highest block height advertised by the connected peers; 0 if none of them advertised one.
+    pub fn max_advertised_height(&self) -> u32 {
+        self.connected_peers()
+            .values()
+            .map(|peer_info| peer_info.quality.advertised_height.load(Ordering::SeqCst))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Registers the protocol version negotiated with the given connected peer and the user agent it advertised.
+    pub fn register_handshake(&mut self, addr: SocketAddr, protocol_version: u32, user_agent: Option<String>) {
+        if let Some(peer_info) = self.connected_peers.get_mut(&addr) {
+            peer_info.set_protocol_version(protocol_version);
+            *peer_info.quality.version.write() = user_agent;
+        }
+    }
+
+    /// Registers a failure (e.g. a protocol violation) associated with the given peer.
+    pub fn register_failure(&self, addr: SocketAddr) {
+        if let Some(quality) = self.peer_quality(addr) {
+            quality.register_failure();
+        } else {
+            warn!("Tried to register a failure of an unknown peer: {}!", addr);
+        }
+    }
+
+    /// Registers an invalid block received from the given peer, which also counts as a failure;
+    /// returns the number of invalid blocks received from it so far. Allowlisted peers only have the
+    /// failure registered, so that they can't be banned.
+    pub fn register_invalid_block(&self, addr: SocketAddr) -> u8 {
+        if let Some(quality) = self.peer_quality(addr) {
+            quality.register_failure();
+            if self.is_allowlisted(addr) {
+                return 0;
+            }
+            quality.invalid_blocks.fetch_add(1, Ordering::SeqCst) + 1
+        } else {
+            warn!("Tried to register an invalid block from an unknown peer: {}!", addr);
+            0
+        }
+    }
+
+    /// Lets the failure counts of the connected peers decay over periods of good behavior.
+    pub fn decay_failures(&self) {
+        for peer_info in self.connected_peers().values() {
+            peer_info.quality.decay_failures();
+        }
+    }
+
     /// Registers that the given number of blocks is expected as part of syncing with a peer.
     pub fn expecting_sync_blocks(&self, addr: SocketAddr, count: usize) {
         if let Some(ref pq) = self.peer_quality(addr) {
             pq.remaining_sync_blocks.store(count as u16, Ordering::SeqCst);
+            *pq.sync_batch_started.lock() = Some(Instant::now());
         } else {
             error!("Peer for expecting_sync_blocks purposes not found!");
         }
     }
 
     /// Registers the receipt of a sync block from a peer; returns `true` when finished syncing.
+    /// Blocks that weren't expected as part of a sync batch are ignored.
     pub fn got_sync_block(&self, addr: SocketAddr) -> bool {
         if let Some(ref pq) = self.peer_quality(addr) {
-            pq.remaining_sync_blocks.fetch_sub(1, Ordering::SeqCst) == 1
+            let finished = pq
+                .remaining_sync_blocks
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                == Ok(1);
+            if finished {
+                pq.register_sync_batch_success();
+            }
+            finished
         } else {
             error!("Peer for got_sync_block purposes not found!");
             true
         }
     }
 
+    /// Returns the number of blocks that can currently be requested from or provided to a peer in a single batch.
+    pub fn sync_batch_size(&self, addr: SocketAddr) -> u32 {
+        if let Some(ref pq) = self.peer_quality(addr) {
+            pq.sync_batch_size()
+        } else {
+            crate::MIN_BLOCK_SYNC_COUNT
+        }
+    }
+
+    /// Abandons the sync batch expected from a peer if it hasn't been delivered within
+    /// `BLOCK_REQUEST_TIMEOUT_SECS`, shrinking the peer's batch size; returns `true` if it timed out.
+    pub fn expire_sync_batch(&self, addr: SocketAddr) -> bool {
+        if let Some(ref pq) = self.peer_quality(addr) {
+            let timeout = Duration::from_secs(crate::BLOCK_REQUEST_TIMEOUT_SECS);
+            let timed_out = pq.remaining_sync_blocks.load(Ordering::SeqCst) != 0
+                && matches!(*pq.sync_batch_started.lock(), Some(started) if started.elapsed() > timeout);
+
+            if timed_out {
+                pq.remaining_sync_blocks.store(0, Ordering::SeqCst);
+                pq.register_sync_batch_timeout();
+            }
+            timed_out
+        } else {
+            false
+        }
+    }
+
+    /// Abandons the sync batches expected from all the connected peers.
+    pub fn reset_sync_batches(&self) {
+        for peer_info in self.connected_peers.values() {
+            peer_info.quality.remaining_sync_blocks.store(0, Ordering::SeqCst);
+            *peer_info.quality.sync_batch_started.lock() = None;
+        }
+    }
+
+    /// Registers the given number of bytes sent to a peer.
+    pub fn register_bytes_sent(&self, addr: SocketAddr, bytes: usize) {
+        if let Some(ref pq) = self.peer_quality(addr) {
+            pq.register_bytes_sent(bytes as u64);
+        }
+    }
+
+    /// Registers the given number of bytes received from a peer.
+    pub fn register_bytes_received(&self, addr: SocketAddr, bytes: usize) {
+        if let Some(ref pq) = self.peer_quality(addr) {
+            pq.register_bytes_received(bytes as u64);
+        }
+    }
+
     /// Checks whether the current peer is involved in a block syncing process.
     pub fn is_syncing_blocks(&self, addr: SocketAddr) -> bool {
         if let Some(ref pq) = self.peer_quality(addr) {
@@ -369,7 +970,9 @@ impl PeerBook {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::net::{IpAddr, Ipv4Addr};
+    use crate::peers::normalize_address;
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
     #[test]
     fn test_set_connecting_from_never_connected() {
@@ -397,7 +1000,9 @@ mod tests {
         assert_eq!(false, peer_book.is_connected(remote_address));
         assert_eq!(false, peer_book.is_disconnected(remote_address));
 
-        peer_book.set_connected(remote_address, None).unwrap();
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
         assert_eq!(false, peer_book.is_connecting(remote_address));
         assert_eq!(true, peer_book.is_connected(remote_address));
         assert_eq!(false, peer_book.is_disconnected(remote_address));
@@ -415,7 +1020,9 @@ mod tests {
         assert_eq!(false, peer_book.is_connected(remote_address));
         assert_eq!(true, peer_book.is_disconnected(remote_address));
 
-        peer_book.set_disconnected(remote_address).unwrap();
+        peer_book
+            .set_disconnected(remote_address, DisconnectReason::Shutdown)
+            .unwrap();
         assert_eq!(false, peer_book.is_connecting(remote_address));
         assert_eq!(false, peer_book.is_connected(remote_address));
         assert_eq!(true, peer_book.is_disconnected(remote_address));
@@ -431,15 +1038,81 @@ mod tests {
         assert_eq!(false, peer_book.is_connected(remote_address));
         assert_eq!(false, peer_book.is_disconnected(remote_address));
 
-        peer_book.set_connected(remote_address, None).unwrap();
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
         assert_eq!(false, peer_book.is_connecting(remote_address));
         assert_eq!(true, peer_book.is_connected(remote_address));
         assert_eq!(false, peer_book.is_disconnected(remote_address));
 
-        peer_book.set_disconnected(remote_address).unwrap();
+        peer_book
+            .set_disconnected(remote_address, DisconnectReason::ProtocolViolation)
+            .unwrap();
         assert_eq!(false, peer_book.is_connecting(remote_address));
         assert_eq!(false, peer_book.is_connected(remote_address));
         assert_eq!(true, peer_book.is_disconnected(remote_address));
+        assert_eq!(
+            Some(DisconnectReason::ProtocolViolation),
+            peer_book.disconnected_peers()[&remote_address].last_disconnect_reason()
+        );
+    }
+
+    #[test]
+    fn test_set_disconnected_from_listener_override() {
+        let mut peer_book = PeerBook::default();
+        let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 50123));
+        let remote_listener = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+
+        peer_book.set_connecting(remote_address).unwrap();
+        peer_book
+            .set_connected(remote_address, Some(remote_listener), ConnectionDirection::Inbound)
+            .unwrap();
+        assert!(peer_book.is_connected(remote_listener));
+        assert!(!peer_book.is_connected(remote_address));
+
+        // The peer is disconnected using the address it connected from.
+        peer_book
+            .set_disconnected(remote_address, DisconnectReason::Shutdown)
+            .unwrap();
+        assert_eq!(0, peer_book.number_of_connected_peers());
+        assert!(!peer_book.is_connected(remote_listener));
+        assert!(peer_book.is_disconnected(remote_listener));
+        assert!(!peer_book.is_disconnected(remote_address));
+    }
+
+    #[test]
+    fn test_simultaneous_connections_to_the_same_listener() {
+        let mut peer_book = PeerBook::default();
+        let remote_listener = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+        let inbound_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 50123));
+
+        // An outbound and an inbound handshake with the same peer are in progress at the same time.
+        peer_book.set_connecting(remote_listener).unwrap();
+        peer_book.set_connecting(inbound_address).unwrap();
+
+        // The outbound one completes first and is kept.
+        peer_book
+            .set_connected(remote_listener, None, ConnectionDirection::Outbound)
+            .unwrap();
+        peer_book.register_handshake(remote_listener, 1, None);
+
+        // The inbound one is rejected, so that its socket can be dropped.
+        assert!(matches!(
+            peer_book.set_connected(inbound_address, Some(remote_listener), ConnectionDirection::Inbound),
+            Err(NetworkError::PeerAlreadyConnected)
+        ));
+        assert_eq!(1, peer_book.number_of_connected_peers());
+        assert!(!peer_book.is_connecting(inbound_address));
+        assert_eq!(
+            Some(1),
+            peer_book.connected_peers()[&remote_listener].protocol_version()
+        );
+
+        // The redundant connection isn't mistaken for the kept one once it's gone.
+        peer_book
+            .set_disconnected(inbound_address, DisconnectReason::Shutdown)
+            .unwrap();
+        assert!(peer_book.is_connected(remote_listener));
     }
 
     #[test]
@@ -448,15 +1121,556 @@ mod tests {
         let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
 
         peer_book.set_connecting(remote_address).unwrap();
-        peer_book.set_connected(remote_address, None).unwrap();
-        peer_book.set_disconnected(remote_address).unwrap();
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
+        peer_book
+            .set_disconnected(remote_address, DisconnectReason::Shutdown)
+            .unwrap();
         assert_eq!(false, peer_book.is_connecting(remote_address));
         assert_eq!(false, peer_book.is_connected(remote_address));
         assert_eq!(true, peer_book.is_disconnected(remote_address));
 
-        assert!(peer_book.set_connected(remote_address, None).is_ok());
+        assert!(peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .is_ok());
+
+        assert_eq!(false, peer_book.is_connecting(remote_address));
+        assert_eq!(true, peer_book.is_connected(remote_address));
+    }
+
+    #[test]
+    fn test_sync_batch_size_adapts() {
+        let mut peer_book = PeerBook::default();
+        let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+
+        peer_book.set_connecting(remote_address).unwrap();
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
+        assert_eq!(crate::MIN_BLOCK_SYNC_COUNT, peer_book.sync_batch_size(remote_address));
+
+        // The batch size grows with every fully delivered batch, up to the cap.
+        let mut expected_size = crate::MIN_BLOCK_SYNC_COUNT;
+        for _ in 0..10 {
+            peer_book.expecting_sync_blocks(remote_address, 2);
+            assert!(!peer_book.got_sync_block(remote_address));
+            assert!(peer_book.got_sync_block(remote_address));
+
+            expected_size = (expected_size * 2).min(crate::MAX_BLOCK_SYNC_COUNT);
+            assert_eq!(expected_size, peer_book.sync_batch_size(remote_address));
+        }
+
+        // Blocks that aren't part of a batch don't wrap the count of the remaining ones.
+        assert!(!peer_book.got_sync_block(remote_address));
+        assert!(!peer_book.is_syncing_blocks(remote_address));
+
+        // A batch that isn't timed out yet is left alone.
+        peer_book.expecting_sync_blocks(remote_address, 2);
+        assert!(!peer_book.expire_sync_batch(remote_address));
+        assert!(peer_book.is_syncing_blocks(remote_address));
+
+        // Simulate a timeout; the batch is abandoned and the batch size shrinks.
+        let pq = peer_book.peer_quality(remote_address).unwrap();
+        *pq.sync_batch_started.lock() =
+            Instant::now().checked_sub(Duration::from_secs(crate::BLOCK_REQUEST_TIMEOUT_SECS + 1));
+        assert!(peer_book.expire_sync_batch(remote_address));
+        assert!(!peer_book.is_syncing_blocks(remote_address));
+        assert_eq!(
+            crate::MAX_BLOCK_SYNC_COUNT / 2,
+            peer_book.sync_batch_size(remote_address)
+        );
+    }
+
+    #[test]
+    fn test_candidate_peers_favor_preferred_regions() {
+        let mut peer_book = PeerBook::default();
+        let preferred_regions = vec!["eu-central".to_string(), "AS13335".to_string()];
+
+        let regions = [
+            Some("eu-central"),
+            Some("us-east"),
+            Some("AS13335"),
+            None,
+            Some("ap-south"),
+        ];
+        for (i, region) in regions.iter().enumerate() {
+            let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031 + i as u16));
+            peer_book.add_peer(remote_address);
+            peer_book.set_region(remote_address, region.map(|r| r.to_string()));
+        }
+
+        let preferred_peers = [
+            SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031)),
+            SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4033)),
+        ];
+
+        // Only the preferred peers are dialed when there are enough of them.
+        for _ in 0..10 {
+            let mut candidates = peer_book.candidate_peers(2, &preferred_regions);
+            candidates.sort();
+            assert_eq!(candidates, preferred_peers);
+        }
+
+        // The other peers are still used to fill the remaining slots.
+        let candidates = peer_book.candidate_peers(4, &preferred_regions);
+        assert_eq!(4, candidates.len());
+        assert!(preferred_peers.iter().all(|peer| candidates.contains(peer)));
+
+        // Without any preferences, all the peers are candidates.
+        assert_eq!(5, peer_book.candidate_peers(10, &[]).len());
+    }
+
+    #[test]
+    fn test_candidates_to_dial() {
+        let mut peer_book = PeerBook::default();
+        let addrs = (0..5u16)
+            .map(|i| SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031 + i)))
+            .collect::<Vec<_>>();
+        for addr in &addrs {
+            peer_book.add_peer(*addr);
+        }
+        let quality = |addr: &SocketAddr| peer_book.disconnected_peers()[addr].quality.clone();
+
+        // The first peer was seen more recently than the second one.
+        *quality(&addrs[0]).last_seen.write() = Some(chrono::Utc::now());
+        *quality(&addrs[1]).last_seen.write() = Some(chrono::Utc::now() - chrono::Duration::hours(1));
+        // The third one has a failure on its record, lowering its score.
+        quality(&addrs[2]).failures.store(1, Ordering::SeqCst);
+        // The fourth one is banned.
+        quality(&addrs[3])
+            .invalid_blocks
+            .store(crate::MAX_INVALID_BLOCKS, Ordering::SeqCst);
+        // The fifth one couldn't be connected to recently.
+        peer_book.register_failed_dial(addrs[4]);
+
+        assert_eq!(peer_book.candidates_to_dial(2), vec![addrs[0], addrs[1]]);
+        assert_eq!(peer_book.candidates_to_dial(10), vec![addrs[0], addrs[1], addrs[2]]);
+    }
+
+    #[test]
+    fn test_allowlisted_peer_cant_be_banned() {
+        let mut peer_book = PeerBook::default();
+        let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+
+        peer_book.allowlist_peer(remote_address);
+        assert!(peer_book.is_allowlisted(remote_address));
+
+        peer_book.set_connecting(remote_address).unwrap();
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
+        for _ in 0..crate::MAX_INVALID_BLOCKS {
+            assert_eq!(peer_book.register_invalid_block(remote_address), 0);
+        }
+        let quality = peer_book.peer_quality(remote_address).unwrap();
+        assert!(!quality.is_banned());
+        assert_eq!(quality.failures.load(Ordering::SeqCst), crate::MAX_INVALID_BLOCKS);
+
+        // it's still dialed, and before any other peer
+        peer_book
+            .set_disconnected(remote_address, DisconnectReason::Shutdown)
+            .unwrap();
+        peer_book.add_peer(SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4032)));
+        assert_eq!(peer_book.candidate_peers(1, &[]), vec![remote_address]);
+    }
+
+    #[test]
+    fn test_rtt_percentiles() {
+        let mut peer_book = PeerBook::default();
+        assert_eq!(peer_book.rtt_percentiles(), None);
+
+        for i in 0..=10u16 {
+            let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031 + i));
+            peer_book.set_connecting(remote_address).unwrap();
+            peer_book
+                .set_connected(remote_address, None, ConnectionDirection::Outbound)
+                .unwrap();
+
+            // The last peer's RTT wasn't measured yet.
+            if i < 10 {
+                let quality = peer_book.peer_quality(remote_address).unwrap();
+                quality.rtt_ms.store((i as u64 + 1) * 10, Ordering::SeqCst);
+                quality.rtt_samples.store(1, Ordering::SeqCst);
+            }
+        }
+
+        assert_eq!(peer_book.rtt_percentiles(), Some((50, 90, 100)));
+    }
+
+    #[test]
+    fn test_slow_peers() {
+        let mut peer_book = PeerBook::default();
+        let fast_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+        let slow_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4032));
+        let spiking_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4033));
+        let allowlisted_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4034));
+
+        for addr in &[fast_peer, slow_peer, spiking_peer, allowlisted_peer] {
+            peer_book.set_connecting(*addr).unwrap();
+            peer_book
+                .set_connected(*addr, None, ConnectionDirection::Outbound)
+                .unwrap();
+        }
+        peer_book.allowlist_peer(allowlisted_peer);
+
+        for round in 1..=crate::SLOW_PEER_RTT_SAMPLES {
+            peer_book.peer_quality(fast_peer).unwrap().register_rtt(10);
+            peer_book.peer_quality(slow_peer).unwrap().register_rtt(2000);
+            peer_book.peer_quality(allowlisted_peer).unwrap().register_rtt(2000);
+            // A single spike pushes the moving average over the limit only briefly.
+            let spiking_rtt = if round == 2 { 2000 } else { 10 };
+            peer_book.peer_quality(spiking_peer).unwrap().register_rtt(spiking_rtt);
+
+            let slow_peers = peer_book.slow_peers(500);
+            if round < crate::SLOW_PEER_RTT_SAMPLES {
+                assert!(slow_peers.is_empty());
+            } else {
+                assert_eq!(slow_peers, vec![slow_peer]);
+            }
+        }
+
+        // The peer remains slow until it's measured to be faster.
+        assert_eq!(peer_book.slow_peers(500), vec![slow_peer]);
+        for _ in 0..10 {
+            peer_book.peer_quality(slow_peer).unwrap().register_rtt(10);
+        }
+        assert!(peer_book.slow_peers(500).is_empty());
+    }
+
+    #[test]
+    fn test_weighted_sync_node_favors_fast_peers() {
+        let mut peer_book = PeerBook::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(peer_book.weighted_sync_node(&mut rng, 0, None), None);
+
+        let fast_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+        let slow_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4032));
+        let failing_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4033));
+        for (addr, rtt_ms, failures) in &[(fast_peer, 10, 0), (slow_peer, 100, 0), (failing_peer, 10, 9)] {
+            peer_book.set_connecting(*addr).unwrap();
+            peer_book
+                .set_connected(*addr, None, ConnectionDirection::Outbound)
+                .unwrap();
+            let quality = peer_book.peer_quality(*addr).unwrap();
+            quality.rtt_ms.store(*rtt_ms, Ordering::SeqCst);
+            quality.failures.store(*failures, Ordering::SeqCst);
+            peer_book.register_advertised_height(*addr, 1);
+        }
+
+        let mut counts = HashMap::new();
+        for _ in 0..1000 {
+            *counts
+                .entry(peer_book.weighted_sync_node(&mut rng, 0, None).unwrap())
+                .or_insert(0) += 1;
+        }
+
+        // The expected shares are ~83%, ~9% and ~8%; every peer is still chosen sometimes.
+        assert!(counts[&fast_peer] > 700);
+        assert!(counts[&slow_peer] > 0 && counts[&slow_peer] < 200);
+        assert!(counts[&failing_peer] > 0 && counts[&failing_peer] < 200);
+    }
+
+    #[test]
+    fn test_weighted_sync_node_skips_peers_not_ahead() {
+        let mut peer_book = PeerBook::default();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let behind_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+        let silent_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4032));
+        let ahead_peer = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4033));
+        for (addr, rtt_ms) in &[(behind_peer, 1), (silent_peer, 1), (ahead_peer, 500)] {
+            peer_book.set_connecting(*addr).unwrap();
+            peer_book
+                .set_connected(*addr, None, ConnectionDirection::Outbound)
+                .unwrap();
+            peer_book
+                .peer_quality(*addr)
+                .unwrap()
+                .rtt_ms
+                .store(*rtt_ms, Ordering::SeqCst);
+        }
+        peer_book.register_advertised_height(behind_peer, 10);
+        peer_book.register_advertised_height(ahead_peer, 11);
+
+        // The fast peers are either behind or never advertised a height, so only the slow one is chosen.
+        for _ in 0..100 {
+            assert_eq!(peer_book.weighted_sync_node(&mut rng, 10, None), Some(ahead_peer));
+        }
+
+        // No peer is chosen once none of them is ahead.
+        assert_eq!(peer_book.weighted_sync_node(&mut rng, 11, None), None);
+    }
+
+    #[test]
+    fn test_only_matching_pong_updates_rtt() {
+        let mut peer_book = PeerBook::default();
+        let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+
+        peer_book.set_connecting(remote_address).unwrap();
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
+
+        let quality = peer_book.peer_quality(remote_address).unwrap();
+        quality.rtt_ms.store(u64::MAX, Ordering::SeqCst);
+
+        // The second ping supersedes the first one.
+        peer_book.sending_ping(remote_address, 1);
+        peer_book.sending_ping(remote_address, 2);
+
+        // A delayed pong answering the first ping is a failure.
+        peer_book.received_pong(remote_address, 1);
+        assert_eq!(u64::MAX, quality.rtt_ms.load(Ordering::SeqCst));
+        assert_eq!(1, quality.failures.load(Ordering::SeqCst));
+
+        // The pong answering the outstanding ping is used to measure the RTT.
+        peer_book.received_pong(remote_address, 2);
+        assert_ne!(u64::MAX, quality.rtt_ms.load(Ordering::SeqCst));
+        assert_eq!(1, quality.failures.load(Ordering::SeqCst));
+
+        // A duplicate of it is a failure too.
+        quality.rtt_ms.store(u64::MAX, Ordering::SeqCst);
+        peer_book.received_pong(remote_address, 2);
+        assert_eq!(u64::MAX, quality.rtt_ms.load(Ordering::SeqCst));
+        assert_eq!(2, quality.failures.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_discovery_stats() {
+        let mut peer_book = PeerBook::default();
+        let address = |port| SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+
+        peer_book.register_discovery(address(4031), DiscoverySource::Bootnode);
+        peer_book.add_peer_from(address(4032), DiscoverySource::Gossip);
+        peer_book.add_peer_from(address(4033), DiscoverySource::Gossip);
+        peer_book.add_peer(address(4034));
+
+        // A peer keeps the source it was first discovered by.
+        peer_book.add_peer_from(address(4031), DiscoverySource::Gossip);
+        peer_book.add_peer(address(4032));
+
+        assert_eq!(1, peer_book.number_of_discovered_peers(DiscoverySource::Bootnode));
+        assert_eq!(2, peer_book.number_of_discovered_peers(DiscoverySource::Gossip));
+        assert_eq!(1, peer_book.number_of_discovered_peers(DiscoverySource::Manual));
+
+        peer_book.register_dial_attempt();
+        peer_book.register_dial_attempt();
+        peer_book.register_successful_dial();
+
+        assert_eq!(2, peer_book.dial_attempts());
+        assert_eq!(1, peer_book.successful_dials());
+    }
+
+    #[test]
+    fn test_peers_to_share_are_limited() {
+        let mut peer_book = PeerBook::default();
+        let addresses = (0..30)
+            .map(|port| SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4000 + port)))
+            .collect::<Vec<_>>();
+
+        for address in &addresses {
+            peer_book.set_connecting(*address).unwrap();
+            peer_book
+                .set_connected(*address, None, ConnectionDirection::Outbound)
+                .unwrap();
+        }
+
+        // The peer seen most recently is shared first.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        peer_book.update_last_seen(addresses[5]);
+
+        let shared = peer_book.peers_to_share(addresses[0], 10, false);
+        assert_eq!(10, shared.len());
+        assert_eq!(addresses[5], shared[0]);
+        // The requester's own address is never shared with it.
+        assert!(!shared.contains(&addresses[0]));
+
+        // Bootnodes share disconnected peers instead.
+        assert!(peer_book.peers_to_share(addresses[0], 10, true).is_empty());
+    }
+
+    #[test]
+    fn test_register_advertised_peers() {
+        let mut peer_book = PeerBook::default();
+        let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+        let peer_1 = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4032));
+        let peer_2 = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4033));
+
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
+
+        // The same set of addresses is only advertised once, regardless of the order...
+        assert!(peer_book.register_advertised_peers(remote_address, &[peer_1, peer_2]));
+        assert!(!peer_book.register_advertised_peers(remote_address, &[peer_2, peer_1]));
+
+        // ...but a different set is advertised right away.
+        assert!(peer_book.register_advertised_peers(remote_address, &[peer_1]));
+
+        // Once the window has passed, the same set can be advertised again.
+        let quality = &peer_book.connected_peers()[&remote_address].quality;
+        let window = Duration::from_secs(crate::PEER_ADVERTISEMENT_WINDOW_SECS + 1);
+        quality.last_advertised.lock().as_mut().unwrap().0 = Instant::now() - window;
+        assert!(peer_book.register_advertised_peers(remote_address, &[peer_1]));
+    }
+
+    #[test]
+    fn test_advertised_heights() {
+        let mut peer_book = PeerBook::default();
+        let peer_1 = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+        let peer_2 = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4032));
+
+        peer_book
+            .set_connected(peer_1, None, ConnectionDirection::Outbound)
+            .unwrap();
+        peer_book
+            .set_connected(peer_2, None, ConnectionDirection::Outbound)
+            .unwrap();
+        assert_eq!(0, peer_book.max_advertised_height());
+
+        // A peer that never sends a height stays at 0.
+        peer_book.register_advertised_height(peer_1, 10);
+        let height = |addr| {
+            peer_book.connected_peers()[&addr]
+                .quality
+                .advertised_height
+                .load(Ordering::SeqCst)
+        };
+        assert_eq!(10, height(peer_1));
+        assert_eq!(0, height(peer_2));
+        assert_eq!(10, peer_book.max_advertised_height());
+
+        // The latest height is kept, even if it's lower than the previous one.
+        peer_book.register_advertised_height(peer_2, 15);
+        peer_book.register_advertised_height(peer_1, 5);
+        assert_eq!(5, height(peer_1));
+        assert_eq!(15, peer_book.max_advertised_height());
+
+        // Only the connected peers are taken into account.
+        peer_book
+            .set_disconnected(peer_2, DisconnectReason::ProtocolViolation)
+            .unwrap();
+        assert_eq!(5, peer_book.max_advertised_height());
+    }
+
+    #[test]
+    fn test_ranked_bootstrap_candidates() {
+        let mut peer_book = PeerBook::default();
+        let addrs = (0..4u16)
+            .map(|i| SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031 + i)))
+            .collect::<Vec<_>>();
+
+        // The peers are disconnected with varied scores: a failure weighs more than the RTTs.
+        for (addr, (failures, rtt_ms)) in addrs.iter().zip(vec![(1, 10), (0, 300), (0, 20), (2, 0)]) {
+            peer_book.set_connecting(*addr).unwrap();
+            peer_book
+                .set_connected(*addr, None, ConnectionDirection::Outbound)
+                .unwrap();
+            let quality = peer_book.peer_quality(*addr).unwrap();
+            quality.failures.store(failures, Ordering::SeqCst);
+            quality.rtt_ms.store(rtt_ms, Ordering::SeqCst);
+            peer_book.set_disconnected(*addr, DisconnectReason::Shutdown).unwrap();
+        }
+        // A peer that was never connected to has no score on record, so it's ranked last.
+        let unknown = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4040));
+        peer_book.add_peer(unknown);
+
+        // The scores survive a restart, unlike the live ones.
+        let peer_book: PeerBook = bincode::deserialize(&bincode::serialize(&peer_book).unwrap()).unwrap();
+        for addr in &addrs {
+            assert_eq!(0, peer_book.disconnected_peers()[addr].quality.score());
+        }
+
+        let expected = vec![addrs[2], addrs[1], addrs[0], addrs[3], unknown];
+        assert_eq!(peer_book.ranked_bootstrap_candidates(), expected);
+        assert_eq!(peer_book.candidates_to_dial(10), expected);
+    }
+
+    #[test]
+    fn ipv6_peer_transitions_through_the_states() {
+        let mut peer_book = PeerBook::default();
+        let remote_address = SocketAddr::from((IpAddr::V6(Ipv6Addr::LOCALHOST), 4031));
+        // an IPv4 peer on the same port is a different one
+        let ipv4_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+
+        peer_book.add_peer(remote_address);
+        assert_eq!(true, peer_book.is_disconnected(remote_address));
+        assert_eq!(false, peer_book.is_disconnected(ipv4_address));
+
+        peer_book.set_connecting(remote_address).unwrap();
+        assert_eq!(true, peer_book.is_connecting(remote_address));
+        assert_eq!(false, peer_book.is_connecting(ipv4_address));
 
+        peer_book
+            .set_connected(remote_address, None, ConnectionDirection::Outbound)
+            .unwrap();
         assert_eq!(false, peer_book.is_connecting(remote_address));
         assert_eq!(true, peer_book.is_connected(remote_address));
+        assert_eq!(false, peer_book.is_disconnected(remote_address));
+        assert_eq!(false, peer_book.is_connected(ipv4_address));
+
+        peer_book
+            .set_disconnected(remote_address, DisconnectReason::Shutdown)
+            .unwrap();
+        assert_eq!(false, peer_book.is_connected(remote_address));
+        assert_eq!(true, peer_book.is_disconnected(remote_address));
+    }
+
+    #[test]
+    fn ipv4_mapped_peer_is_known_under_its_ipv4_address() {
+        let mut peer_book = PeerBook::default();
+        let mapped_address: SocketAddr = "[::ffff:127.0.0.1]:4031".parse().unwrap();
+        let remote_address = SocketAddr::from((IpAddr::V4(Ipv4Addr::LOCALHOST), 4031));
+
+        peer_book.add_peer(remote_address);
+        peer_book.add_peer(normalize_address(mapped_address));
+        assert_eq!(1, peer_book.disconnected_peers().len());
+
+        peer_book.set_connecting(normalize_address(mapped_address)).unwrap();
+        peer_book
+            .set_connected(normalize_address(mapped_address), None, ConnectionDirection::Inbound)
+            .unwrap();
+        assert_eq!(true, peer_book.is_connected(remote_address));
+        assert_eq!(false, peer_book.is_disconnected(remote_address));
+    }
+
+    #[test]
+    fn peers_per_asn_are_capped() {
+        let mut peer_book = PeerBook::default();
+        let address = |i: u8| SocketAddr::from((IpAddr::V4(Ipv4Addr::new(10, 0, 0, i)), 4130));
+        for i in 0..3 {
+            peer_book.register_asn(address(i).ip(), 64512);
+        }
+        peer_book.register_asn(address(3).ip(), 64513);
+
+        // without a cap, any number of peers from an AS is admitted
+        peer_book.set_connecting(address(0)).unwrap();
+        peer_book.set_connecting(address(1)).unwrap();
+        peer_book
+            .set_disconnected(address(1), DisconnectReason::Shutdown)
+            .unwrap();
+
+        peer_book.set_max_peers_per_asn(Some(1));
+        peer_book
+            .set_connected(address(0), None, ConnectionDirection::Outbound)
+            .unwrap();
+
+        // the AS is saturated, so its further peers are rejected...
+        assert!(matches!(
+            peer_book.set_connecting(address(1)),
+            Err(NetworkError::TooManyPeersInASN(64512))
+        ));
+        assert!(matches!(
+            peer_book.set_connected(address(2), None, ConnectionDirection::Inbound),
+            Err(NetworkError::TooManyPeersInASN(64512))
+        ));
+        assert!(!peer_book.is_connected(address(2)));
+
+        // ...unless they're allowlisted, while peers from other or unknown ASes are admitted
+        peer_book.allowlist_peer(address(1));
+        peer_book.set_connecting(address(1)).unwrap();
+        peer_book.set_connecting(address(3)).unwrap();
+        peer_book.set_connecting(address(4)).unwrap();
     }
 }
@@ -14,19 +14,22 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::NetworkError;
+use crate::{BloomFilter, NetworkError, PeerLocation};
+use snarkvm_objects::Transaction;
 
 use chrono::{DateTime, Utc};
 use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
 
 use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
     net::SocketAddr,
     sync::{
-        atomic::{AtomicBool, AtomicU16, AtomicU64, AtomicU8},
+        atomic::{AtomicU16, AtomicU32, AtomicU64, AtomicU8, Ordering},
         Arc,
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
@@ -36,20 +39,316 @@ pub enum PeerStatus {
     NeverConnected,
 }
 
-#[derive(Debug, Default)]
+/// The reason for disconnecting from a peer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DisconnectReason {
+    /// The peer didn't respond in time, or is too slow to keep.
+    Timeout,
+    /// The peer sent malformed, oversized or otherwise invalid data.
+    ProtocolViolation,
+    /// The permitted number of connected peers was exceeded.
+    TooManyPeers,
+    /// The peer sent too many invalid blocks.
+    Banned,
+    /// The connection was no longer needed and was closed in an orderly fashion.
+    Shutdown,
+    /// The peer closed the connection.
+    PeerRequested,
+}
+
+impl DisconnectReason {
+    /// Returns the name of the reason, as used in logs and metrics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::ProtocolViolation => "protocol_violation",
+            Self::TooManyPeers => "too_many_peers",
+            Self::Banned => "banned",
+            Self::Shutdown => "shutdown",
+            Self::PeerRequested => "peer_requested",
+        }
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The side that initiated a connection with a peer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ConnectionDirection {
+    /// The peer connected to us.
+    Inbound,
+    /// We connected to the peer.
+    Outbound,
+}
+
+impl ConnectionDirection {
+    /// Returns the name of the direction, as used in logs and RPC responses.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Inbound => "inbound",
+            Self::Outbound => "outbound",
+        }
+    }
+}
+
+impl fmt::Display for ConnectionDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
 pub struct PeerQuality {
     /// The timestamp of when the peer has been seen last.
     pub last_seen: RwLock<Option<DateTime<Utc>>>,
-    /// An indicator of whether a `Pong` message is currently expected from this peer.
-    pub expecting_pong: AtomicBool,
+    /// The client version (user agent) advertised by the peer during the handshake, if any.
+    pub version: RwLock<Option<String>>,
+    /// The nonce of the `Ping` sent to this peer that is yet to be answered with a `Pong`, if any.
+    pub expected_pong_nonce: Mutex<Option<u64>>,
     /// The timestamp of the last `Ping` sent to the peer.
     pub last_ping_sent: Mutex<Option<Instant>>,
     /// The time it took to send a `Ping` to the peer and for it to respond with a `Pong`.
     pub rtt_ms: AtomicU64,
+    /// The number of times the RTT to the peer was measured.
+    pub rtt_samples: AtomicU64,
+    /// The exponential moving average of the RTT to the peer.
+    pub avg_rtt_ms: AtomicU64,
+    /// The number of consecutive RTT measurements for which the moving average exceeded the permitted maximum.
+    pub slow_rtt_streak: AtomicU8,
+    /// The number of RTT measurements as of the last check against the permitted maximum.
+    rtt_samples_checked: AtomicU64,
     /// The number of failures associated with the peer; grounds for dismissal.
     pub failures: AtomicU8,
+    /// The number of failures registered during the current connection with the peer; unlike `failures`,
+    /// it doesn't decay.
+    pub session_failures: AtomicU64,
+    /// The failure count as of the last decay pass, along with the time since which it hasn't increased.
+    failures_unchanged_since: Mutex<(u8, Instant)>,
+    /// The number of blocks received from the peer that failed validation.
+    pub invalid_blocks: AtomicU8,
+    /// The timestamp of the last failed attempt to connect to the peer.
+    pub last_failed_dial: Mutex<Option<Instant>>,
+    /// The timestamp of the last `GetPeers` request received from the peer.
+    pub last_get_peers: Mutex<Option<Instant>>,
+    /// The timestamp of the last advertisement of addresses to the peer, along with the advertised addresses.
+    pub last_advertised: Mutex<Option<(Instant, Vec<SocketAddr>)>>,
+    /// The IDs of the memory pool transactions already sent to the peer in response to its `GetMemoryPool`s.
+    pub sent_memory_pool: Mutex<HashSet<Vec<u8>>>,
+    /// The bloom filter loaded by the peer; if set, only the transactions matching it are relayed to the peer.
+    pub bloom_filter: RwLock<Option<BloomFilter>>,
+    /// The block height advertised by the peer in its latest `Ping`; 0 if it hasn't sent one yet.
+    pub advertised_height: AtomicU32,
     /// The number of remaining blocs to sync with.
     pub remaining_sync_blocks: AtomicU16,
+    /// The number of blocks that can currently be requested from or provided to the peer in a single batch.
+    pub sync_batch_size: AtomicU32,
+    /// The timestamp of when the current block sync batch was requested from the peer.
+    pub sync_batch_started: Mutex<Option<Instant>>,
+    /// The number of bytes sent to the peer.
+    pub bytes_sent: AtomicU64,
+    /// The number of bytes received from the peer.
+    pub bytes_received: AtomicU64,
+    /// The recent traffic sent to the peer.
+    send_window: Mutex<TrafficWindow>,
+    /// The recent traffic received from the peer.
+    recv_window: Mutex<TrafficWindow>,
+}
+
+impl Default for PeerQuality {
+    fn default() -> Self {
+        Self {
+            last_seen: Default::default(),
+            version: Default::default(),
+            expected_pong_nonce: Default::default(),
+            last_ping_sent: Default::default(),
+            rtt_ms: Default::default(),
+            rtt_samples: Default::default(),
+            avg_rtt_ms: Default::default(),
+            slow_rtt_streak: Default::default(),
+            rtt_samples_checked: Default::default(),
+            failures: Default::default(),
+            session_failures: Default::default(),
+            failures_unchanged_since: Mutex::new((0, Instant::now())),
+            invalid_blocks: Default::default(),
+            last_failed_dial: Default::default(),
+            last_get_peers: Default::default(),
+            last_advertised: Default::default(),
+            sent_memory_pool: Default::default(),
+            bloom_filter: Default::default(),
+            advertised_height: Default::default(),
+            remaining_sync_blocks: Default::default(),
+            sync_batch_size: AtomicU32::new(crate::MIN_BLOCK_SYNC_COUNT),
+            sync_batch_started: Default::default(),
+            bytes_sent: Default::default(),
+            bytes_received: Default::default(),
+            send_window: Default::default(),
+            recv_window: Default::default(),
+        }
+    }
+}
+
+impl PeerQuality {
+    /// Returns the number of blocks that can currently be requested from or provided to the peer in a single batch.
+    pub fn sync_batch_size(&self) -> u32 {
+        self.sync_batch_size.load(Ordering::SeqCst)
+    }
+
+    /// Registers a fully delivered sync batch, doubling the batch size (up to `MAX_BLOCK_SYNC_COUNT`)
+    /// if the peer has a low RTT and no failures.
+    pub fn register_sync_batch_success(&self) {
+        if self.rtt_ms.load(Ordering::SeqCst) <= crate::FAST_PEER_RTT_MS && self.failures.load(Ordering::SeqCst) == 0 {
+            let size = self.sync_batch_size();
+            self.sync_batch_size
+                .store((size * 2).min(crate::MAX_BLOCK_SYNC_COUNT), Ordering::SeqCst);
+        }
+    }
+
+    /// Registers a sync batch that the peer failed to deliver in time, halving the batch size
+    /// (down to `MIN_BLOCK_SYNC_COUNT`).
+    pub fn register_sync_batch_timeout(&self) {
+        let size = self.sync_batch_size();
+        self.sync_batch_size
+            .store((size / 2).max(crate::MIN_BLOCK_SYNC_COUNT), Ordering::SeqCst);
+    }
+
+    /// Registers a failure associated with the peer.
+    pub fn register_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        self.session_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Decrements the failure count if no new failures were registered for `FAILURE_DECAY_SECS`,
+    /// so that peers that recovered from a bad patch can regain their standing.
+    pub fn decay_failures(&self) {
+        self.decay_failures_at(Instant::now());
+    }
+
+    /// Returns a score reflecting the quality of the connection with the peer, where higher is better;
+    /// a failure weighs as much as a second of RTT.
+    pub fn score(&self) -> i64 {
+        let failures = self.failures.load(Ordering::SeqCst) as i64;
+        let rtt_ms = self.rtt_ms.load(Ordering::SeqCst).min(u32::MAX as u64) as i64;
+
+        -(failures * 1000 + rtt_ms)
+    }
+
+    /// Registers a new RTT measurement, updating its moving average.
+    pub fn register_rtt(&self, rtt_ms: u64) {
+        self.rtt_ms.store(rtt_ms, Ordering::SeqCst);
+
+        let avg_rtt_ms = if self.rtt_samples.fetch_add(1, Ordering::SeqCst) == 0 {
+            rtt_ms
+        } else {
+            let avg_rtt_ms = self.avg_rtt_ms.load(Ordering::SeqCst);
+            avg_rtt_ms.saturating_mul(3).saturating_add(rtt_ms) / 4
+        };
+        self.avg_rtt_ms.store(avg_rtt_ms, Ordering::SeqCst);
+    }
+
+    /// Checks the moving average RTT against the given maximum if it was measured since the last check; returns
+    /// `true` if it exceeded it for `SLOW_PEER_RTT_SAMPLES` consecutive measurements, so transient spikes are ignored.
+    pub fn is_chronically_slow(&self, max_rtt_ms: u64) -> bool {
+        let samples = self.rtt_samples.load(Ordering::SeqCst);
+
+        if self.rtt_samples_checked.swap(samples, Ordering::SeqCst) != samples {
+            if self.avg_rtt_ms.load(Ordering::SeqCst) > max_rtt_ms {
+                let streak = self.slow_rtt_streak.load(Ordering::SeqCst);
+                self.slow_rtt_streak.store(streak.saturating_add(1), Ordering::SeqCst);
+            } else {
+                self.slow_rtt_streak.store(0, Ordering::SeqCst);
+            }
+        }
+
+        self.slow_rtt_streak.load(Ordering::SeqCst) >= crate::SLOW_PEER_RTT_SAMPLES
+    }
+
+    /// Checks whether the peer has sent enough invalid blocks to no longer be connected to.
+    pub fn is_banned(&self) -> bool {
+        self.invalid_blocks.load(Ordering::SeqCst) >= crate::MAX_INVALID_BLOCKS
+    }
+
+    /// Checks whether the last attempt to connect to the peer failed less than `DIAL_BACKOFF_SECS` ago.
+    pub fn is_backed_off(&self) -> bool {
+        matches!(*self.last_failed_dial.lock(), Some(time) if time.elapsed() < Duration::from_secs(crate::DIAL_BACKOFF_SECS))
+    }
+
+    fn decay_failures_at(&self, now: Instant) {
+        let mut unchanged_since = self.failures_unchanged_since.lock();
+        let failures = self.failures.load(Ordering::SeqCst);
+
+        if failures != unchanged_since.0 {
+            // New failures were registered since the last pass; restart the decay period.
+            *unchanged_since = (failures, now);
+        } else if failures > 0
+            && now.saturating_duration_since(unchanged_since.1) >= Duration::from_secs(crate::FAILURE_DECAY_SECS)
+        {
+            self.failures.fetch_sub(1, Ordering::SeqCst);
+            *unchanged_since = (failures - 1, now);
+        }
+    }
+
+    /// Registers the given number of bytes sent to the peer.
+    pub fn register_bytes_sent(&self, bytes: u64) {
+        self.register_bytes_sent_at(bytes, Instant::now());
+    }
+
+    /// Registers the given number of bytes received from the peer.
+    pub fn register_bytes_received(&self, bytes: u64) {
+        self.register_bytes_received_at(bytes, Instant::now());
+    }
+
+    /// Returns the current throughput to the peer in bytes per second.
+    pub fn send_rate(&self) -> u64 {
+        self.send_window.lock().rate(Instant::now())
+    }
+
+    /// Returns the current throughput from the peer in bytes per second.
+    pub fn recv_rate(&self) -> u64 {
+        self.recv_window.lock().rate(Instant::now())
+    }
+
+    fn register_bytes_sent_at(&self, bytes: u64, now: Instant) {
+        self.bytes_sent.fetch_add(bytes, Ordering::SeqCst);
+        self.send_window.lock().register(bytes, now);
+    }
+
+    fn register_bytes_received_at(&self, bytes: u64, now: Instant) {
+        self.bytes_received.fetch_add(bytes, Ordering::SeqCst);
+        self.recv_window.lock().register(bytes, now);
+    }
+}
+
+/// The traffic with a peer over the last `THROUGHPUT_WINDOW_SECS`.
+#[derive(Debug, Default)]
+struct TrafficWindow {
+    /// The number of bytes transferred at the given times, oldest first.
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl TrafficWindow {
+    /// Registers the given number of bytes transferred at the given time.
+    fn register(&mut self, bytes: u64, now: Instant) {
+        self.samples.push_back((now, bytes));
+        self.prune(now);
+    }
+
+    /// Returns the throughput in bytes per second as of the given time.
+    fn rate(&mut self, now: Instant) -> u64 {
+        self.prune(now);
+        self.samples.iter().map(|(_, bytes)| bytes).sum::<u64>() / crate::THROUGHPUT_WINDOW_SECS
+    }
+
+    /// Removes the samples that have fallen out of the window.
+    fn prune(&mut self, now: Instant) {
+        let window = Duration::from_secs(crate::THROUGHPUT_WINDOW_SECS);
+        while matches!(self.samples.front(), Some((time, _)) if now.saturating_duration_since(*time) >= window) {
+            self.samples.pop_front();
+        }
+    }
 }
 
 /// A data structure containing information about a peer.
@@ -69,6 +368,31 @@ pub struct PeerInfo {
     connected_count: u64,
     /// The number of times we have disconnected from this peer.
     disconnected_count: u64,
+    /// The reason for the last disconnect from this peer, if any.
+    #[serde(default)]
+    last_disconnect_reason: Option<DisconnectReason>,
+    /// The number of failures associated with this peer during its past connections.
+    #[serde(default)]
+    past_failures: u64,
+    /// The geographic region or autonomous system (ASN) of this peer, if known.
+    #[serde(default)]
+    region: Option<String>,
+    /// The country and ASN of this peer, if resolved from a GeoIP database.
+    #[serde(default)]
+    location: Option<PeerLocation>,
+    /// The other known addresses of this peer, dialed in order if its main address is unreachable.
+    #[serde(default)]
+    alternate_addresses: Vec<SocketAddr>,
+    /// The score of the connection with this peer as of the last disconnect from it; unlike the live one,
+    /// it's persisted, so that the historically best peers can be favored after a restart.
+    #[serde(default)]
+    persisted_score: i64,
+    /// The side that initiated the latest connection with this peer, if it was ever connected.
+    #[serde(default)]
+    direction: Option<ConnectionDirection>,
+    /// The protocol version negotiated with this peer during the latest handshake.
+    #[serde(skip)]
+    protocol_version: Option<u32>,
     /// The quality of the connection with the peer.
     #[serde(skip)]
     pub quality: Arc<PeerQuality>,
@@ -87,6 +411,14 @@ impl PeerInfo {
             last_disconnected: None,
             connected_count: 0,
             disconnected_count: 0,
+            last_disconnect_reason: None,
+            past_failures: 0,
+            region: None,
+            location: None,
+            alternate_addresses: vec![],
+            persisted_score: 0,
+            direction: None,
+            protocol_version: None,
             quality: Default::default(),
         }
     }
@@ -123,6 +455,14 @@ impl PeerInfo {
         *self.quality.last_seen.read()
     }
 
+    ///
+    /// Returns the client version (user agent) advertised by this peer during the handshake, if any.
+    ///
+    #[inline]
+    pub fn version(&self) -> Option<String> {
+        self.quality.version.read().clone()
+    }
+
     ///
     /// Returns the timestamp of the last connection to this peer.
     ///
@@ -131,6 +471,93 @@ impl PeerInfo {
         self.last_connected
     }
 
+    ///
+    /// Returns the geographic region or autonomous system (ASN) of this peer, if known.
+    ///
+    #[inline]
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    ///
+    /// Sets the geographic region or autonomous system (ASN) of this peer.
+    ///
+    #[inline]
+    pub fn set_region(&mut self, region: Option<String>) {
+        self.region = region;
+    }
+
+    ///
+    /// Returns the country and ASN of this peer, if known.
+    ///
+    #[inline]
+    pub fn location(&self) -> Option<&PeerLocation> {
+        self.location.as_ref()
+    }
+
+    ///
+    /// Sets the country and ASN of this peer.
+    ///
+    #[inline]
+    pub fn set_location(&mut self, location: Option<PeerLocation>) {
+        self.location = location;
+    }
+
+    ///
+    /// Returns the other known addresses of this peer, in the order they should be dialed.
+    ///
+    #[inline]
+    pub fn alternate_addresses(&self) -> &[SocketAddr] {
+        &self.alternate_addresses
+    }
+
+    ///
+    /// Adds an alternate address of this peer, unless it's already known.
+    ///
+    pub fn add_alternate_address(&mut self, address: SocketAddr) {
+        if address != self.address && !self.alternate_addresses.contains(&address) {
+            self.alternate_addresses.push(address);
+        }
+    }
+
+    ///
+    /// Returns the protocol version negotiated with this peer, if it is connected.
+    ///
+    #[inline]
+    pub fn protocol_version(&self) -> Option<u32> {
+        self.protocol_version
+    }
+
+    ///
+    /// Sets the protocol version negotiated with this peer.
+    ///
+    #[inline]
+    pub fn set_protocol_version(&mut self, protocol_version: u32) {
+        self.protocol_version = Some(protocol_version);
+    }
+
+    ///
+    /// Returns `true` if this peer is known to be located in one of the given regions.
+    ///
+    #[inline]
+    pub fn is_in_regions(&self, regions: &[String]) -> bool {
+        match self.region() {
+            Some(region) => regions.iter().any(|preferred| preferred == region),
+            None => false,
+        }
+    }
+
+    ///
+    /// Returns `true` if the transaction should be relayed to this peer, i.e. if the peer hasn't
+    /// loaded a bloom filter or the transaction matches it.
+    ///
+    pub fn wants_transaction<T: Transaction>(&self, transaction: &T) -> bool {
+        match &*self.quality.bloom_filter.read() {
+            Some(filter) => filter.matches_transaction(transaction),
+            None => true,
+        }
+    }
+
     ///
     /// Returns the timestamp of the last disconnect from this peer.
     ///
@@ -156,12 +583,45 @@ impl PeerInfo {
     }
 
     ///
-    /// Updates the peer to connected.
+    /// Returns the reason for the last disconnect from this peer, if any.
+    ///
+    #[inline]
+    pub fn last_disconnect_reason(&self) -> Option<DisconnectReason> {
+        self.last_disconnect_reason
+    }
+
+    ///
+    /// Returns the number of failures associated with this peer across all of its connections.
+    ///
+    #[inline]
+    pub fn cumulative_failures(&self) -> u64 {
+        self.past_failures + self.quality.session_failures.load(Ordering::SeqCst)
+    }
+
+    ///
+    /// Returns the score of the connection with this peer as of the last disconnect from it.
+    ///
+    #[inline]
+    pub fn persisted_score(&self) -> i64 {
+        self.persisted_score
+    }
+
+    ///
+    /// Returns the side that initiated the latest connection with this peer, if it was ever connected.
+    ///
+    #[inline]
+    pub fn direction(&self) -> Option<ConnectionDirection> {
+        self.direction
+    }
+
+    ///
+    /// Updates the peer to connected, recording the side that initiated the connection.
     ///
-    pub(crate) fn set_connected(&mut self) -> Result<(), NetworkError> {
+    pub(crate) fn set_connected(&mut self, direction: ConnectionDirection) -> Result<(), NetworkError> {
         if self.status() != PeerStatus::Connected {
             // Set the state of this peer to connected.
             self.status = PeerStatus::Connected;
+            self.direction = Some(direction);
 
             self.last_connected = Some(Utc::now());
             self.connected_count += 1;
@@ -173,12 +633,12 @@ impl PeerInfo {
     }
 
     ///
-    /// Updates the peer to disconnected.
+    /// Updates the peer to disconnected for the given reason.
     ///
     /// If the peer is not transitioning from `PeerStatus::Connecting` or `PeerStatus::Connected`,
     /// this function returns a `NetworkError`.
     ///
-    pub(crate) fn set_disconnected(&mut self) -> Result<(), NetworkError> {
+    pub(crate) fn set_disconnected(&mut self, reason: DisconnectReason) -> Result<(), NetworkError> {
         match self.status() {
             PeerStatus::Connected => {
                 // Set the state of this peer to disconnected.
@@ -186,6 +646,14 @@ impl PeerInfo {
 
                 self.last_disconnected = Some(Utc::now());
                 self.disconnected_count += 1;
+                self.last_disconnect_reason = Some(reason);
+                self.protocol_version = None;
+                self.persisted_score = self.quality.score();
+
+                // The failures are only held against the peer for the duration of a connection, but they're also
+                // accumulated, so that peers misbehaving across connections can be identified.
+                self.past_failures += self.quality.session_failures.swap(0, Ordering::SeqCst);
+                self.quality.failures.store(0, Ordering::SeqCst);
 
                 Ok(())
             }
@@ -201,6 +669,35 @@ impl PeerInfo {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_traffic_rates() {
+        let quality = PeerQuality::default();
+        let start = Instant::now();
+        let window = crate::THROUGHPUT_WINDOW_SECS;
+
+        quality.register_bytes_sent_at(1000 * window, start);
+        quality.register_bytes_received_at(500 * window, start);
+        quality.register_bytes_sent_at(2000 * window, start + Duration::from_secs(window / 2));
+
+        // All the samples are within the window.
+        let now = start + Duration::from_secs(window / 2);
+        assert_eq!(3000, quality.send_window.lock().rate(now));
+        assert_eq!(500, quality.recv_window.lock().rate(now));
+
+        // The first samples have fallen out of the window.
+        let now = start + Duration::from_secs(window);
+        assert_eq!(2000, quality.send_window.lock().rate(now));
+        assert_eq!(0, quality.recv_window.lock().rate(now));
+
+        // All the samples have fallen out of the window.
+        let now = start + Duration::from_secs(2 * window);
+        assert_eq!(0, quality.send_window.lock().rate(now));
+
+        // The cumulative counters are unaffected by the window.
+        assert_eq!(3000 * window, quality.bytes_sent.load(Ordering::SeqCst));
+        assert_eq!(500 * window, quality.bytes_received.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn test_new() {
         let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
@@ -217,17 +714,18 @@ mod tests {
         let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
         let mut peer_info = PeerInfo::new(address);
 
-        peer_info.set_connected().unwrap();
+        peer_info.set_connected(ConnectionDirection::Outbound).unwrap();
         assert_eq!(address, peer_info.address());
         assert_eq!(PeerStatus::Connected, peer_info.status());
         assert_eq!(1, peer_info.connected_count());
         assert_eq!(0, peer_info.disconnected_count());
 
-        peer_info.set_disconnected().unwrap();
+        peer_info.set_disconnected(DisconnectReason::Shutdown).unwrap();
         assert_eq!(address, peer_info.address());
         assert_eq!(PeerStatus::Disconnected, peer_info.status());
         assert_eq!(1, peer_info.connected_count());
         assert_eq!(1, peer_info.disconnected_count());
+        assert_eq!(Some(DisconnectReason::Shutdown), peer_info.last_disconnect_reason());
     }
 
     #[test]
@@ -235,7 +733,7 @@ mod tests {
         let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
         let mut peer_info = PeerInfo::new(address);
 
-        assert!(peer_info.set_disconnected().is_err());
+        assert!(peer_info.set_disconnected(DisconnectReason::Shutdown).is_err());
 
         assert_eq!(address, peer_info.address());
         assert_eq!(PeerStatus::NeverConnected, peer_info.status());
@@ -248,18 +746,90 @@ mod tests {
         let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
         let mut peer_info = PeerInfo::new(address);
 
-        peer_info.set_connected().unwrap();
-        peer_info.set_disconnected().unwrap();
+        peer_info.set_connected(ConnectionDirection::Outbound).unwrap();
+        peer_info.set_disconnected(DisconnectReason::Shutdown).unwrap();
         assert_eq!(address, peer_info.address());
         assert_eq!(PeerStatus::Disconnected, peer_info.status());
         assert_eq!(1, peer_info.connected_count());
         assert_eq!(1, peer_info.disconnected_count());
 
-        assert!(peer_info.set_connected().is_ok());
+        assert!(peer_info.set_connected(ConnectionDirection::Outbound).is_ok());
 
         assert_eq!(address, peer_info.address());
         assert_eq!(PeerStatus::Connected, peer_info.status());
         assert_eq!(2, peer_info.connected_count());
         assert_eq!(1, peer_info.disconnected_count());
     }
+
+    #[test]
+    fn test_cumulative_failures_survive_reconnects() {
+        let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
+        let mut peer_info = PeerInfo::new(address);
+
+        peer_info.set_connected(ConnectionDirection::Outbound).unwrap();
+        peer_info.quality.register_failure();
+        peer_info.quality.register_failure();
+        assert_eq!(2, peer_info.quality.failures.load(Ordering::SeqCst));
+        assert_eq!(2, peer_info.cumulative_failures());
+
+        // The transient failures are reset once the peer is disconnected from, but the cumulative ones aren't.
+        peer_info.set_disconnected(DisconnectReason::ProtocolViolation).unwrap();
+        peer_info.set_connected(ConnectionDirection::Outbound).unwrap();
+        assert_eq!(0, peer_info.quality.failures.load(Ordering::SeqCst));
+        assert_eq!(2, peer_info.cumulative_failures());
+
+        peer_info.quality.register_failure();
+        assert_eq!(1, peer_info.quality.failures.load(Ordering::SeqCst));
+        assert_eq!(3, peer_info.cumulative_failures());
+
+        // The cumulative failures are also persisted.
+        peer_info.set_disconnected(DisconnectReason::ProtocolViolation).unwrap();
+        let peer_info: PeerInfo = bincode::deserialize(&bincode::serialize(&peer_info).unwrap()).unwrap();
+        assert_eq!(0, peer_info.quality.failures.load(Ordering::SeqCst));
+        assert_eq!(3, peer_info.cumulative_failures());
+    }
+
+    #[test]
+    fn test_failure_decay() {
+        let quality = PeerQuality::default();
+        let start = Instant::now();
+        let period = Duration::from_secs(crate::FAILURE_DECAY_SECS);
+
+        quality.failures.store(3, Ordering::SeqCst);
+        quality.decay_failures_at(start);
+        assert_eq!(3, quality.failures.load(Ordering::SeqCst));
+
+        // A full period without new failures decrements the count.
+        quality.decay_failures_at(start + period / 2);
+        assert_eq!(3, quality.failures.load(Ordering::SeqCst));
+        quality.decay_failures_at(start + period);
+        assert_eq!(2, quality.failures.load(Ordering::SeqCst));
+
+        // A new failure restarts the decay period.
+        quality.failures.fetch_add(1, Ordering::SeqCst);
+        quality.decay_failures_at(start + 2 * period);
+        assert_eq!(3, quality.failures.load(Ordering::SeqCst));
+        quality.decay_failures_at(start + 3 * period);
+        assert_eq!(2, quality.failures.load(Ordering::SeqCst));
+
+        // Good behavior eventually clears the failures.
+        for i in 4..=6 {
+            quality.decay_failures_at(start + i * period);
+        }
+        assert_eq!(0, quality.failures.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_alternate_addresses() {
+        let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
+        let alternate: SocketAddr = "127.0.0.2:4130".parse().unwrap();
+        let mut peer_info = PeerInfo::new(address);
+
+        // The main address and duplicates aren't registered as alternates.
+        peer_info.add_alternate_address(address);
+        peer_info.add_alternate_address(alternate);
+        peer_info.add_alternate_address(alternate);
+
+        assert_eq!(peer_info.alternate_addresses(), &[alternate]);
+    }
 }
@@ -14,15 +14,29 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{message::*, ConnReader, ConnWriter, NetworkError, Node, Version};
+use crate::{
+    message::*,
+    peers::{normalize_address, ConnectionDirection, DisconnectReason, DiscoverySource},
+    ConnReader,
+    ConnWriter,
+    NetworkError,
+    Node,
+    Version,
+};
+use snarkos_metrics::Metrics;
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    cmp::Reverse,
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant},
+};
 
 use parking_lot::Mutex;
-use rand::seq::IteratorRandom;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
     net::TcpStream,
+    time::{sleep, timeout},
 };
 
 impl Node {
@@ -60,37 +74,48 @@ impl Node {
         if number_of_connected_peers > max_peers {
             let number_to_disconnect = number_of_connected_peers - max_peers;
             trace!(
-                "Disconnecting from the most recent {} peers to maintain their permitted number",
+                "Disconnecting from the {} worst peers to maintain their permitted number",
                 number_to_disconnect
             );
 
-            let connected_peers = self.peer_book.read().connected_peers().clone();
-
-            let mut connected = connected_peers
-                .iter()
-                .map(|(_, peer_info)| peer_info)
-                .collect::<Vec<_>>();
-            connected.sort_unstable_by_key(|info| info.last_connected());
-
-            for _ in 0..number_to_disconnect {
-                if let Some(peer_info) = connected.pop() {
-                    let addr = peer_info.address();
-                    let _ = self.disconnect_from_peer(addr);
-                }
+            for addr in self.peers_to_evict(number_to_disconnect, None) {
+                let _ = self.disconnect_from_peer(addr, DisconnectReason::TooManyPeers);
             }
         }
 
         // disconnect from peers after a while, even if they haven't sent a GetPeers
         let now = chrono::Utc::now();
         if self.environment.is_bootnode() {
-            for (peer_addr, peer_info) in self.peer_book.read().connected_peers().clone() {
-                if (now - peer_info.last_connected().unwrap()).num_seconds() > 10 {
-                    let _ = self.disconnect_from_peer(peer_addr);
+            let connected_peers = self.peer_book.read().connected_peers().clone();
+            for (peer_addr, peer_info) in connected_peers {
+                if (now - peer_info.last_connected().unwrap()).num_seconds() > 10
+                    && !self.peer_book.read().is_allowlisted(peer_addr)
+                {
+                    let _ = self.disconnect_from_peer(peer_addr, DisconnectReason::Shutdown);
                 }
             }
         }
 
         if number_of_connected_peers != 0 {
+            // Let the failures of well-behaved peers decay.
+            self.peer_book.read().decay_failures();
+
+            // Free the slots of chronically slow peers for better ones; bootnodes are exempt.
+            if let Some(max_peer_rtt) = self.environment.max_peer_rtt() {
+                let slow_peers = self.peer_book.read().slow_peers(max_peer_rtt.as_millis() as u64);
+                for addr in slow_peers {
+                    if !self.environment.bootnodes().contains(&addr) {
+                        debug!("Disconnecting from {} due to its high RTT", addr);
+                        let _ = self.disconnect_from_peer(addr, DisconnectReason::Timeout);
+                    }
+                }
+            }
+
+            // Export the distribution of the RTT across the connected peers.
+            if let Some((p50, p90, p99)) = self.peer_book.read().rtt_percentiles() {
+                Metrics::peer_rtt_set(p50, p90, p99);
+            }
+
             if !self.environment.is_bootnode() {
                 // Send a `Ping` to every connected peer.
                 self.broadcast_pings().await;
@@ -103,6 +128,54 @@ impl Node {
         Ok(())
     }
 
+    ///
+    /// Attempts to connect to the given peer, dialing its known alternate addresses in order
+    /// if the previous ones fail, up to the configured maximum number of dialed addresses.
+    ///
+    /// Returns the error of the last attempt if none of the addresses could be connected to.
+    ///
+    async fn connect_to_peer(&self, remote_address: SocketAddr) -> Result<(), NetworkError> {
+        let alternate_addresses = self.peer_book.read().alternate_addresses(remote_address);
+        let addresses = std::iter::once(remote_address)
+            .chain(alternate_addresses)
+            .take(self.environment.max_dial_addresses());
+
+        let mut result = Ok(());
+        for address in addresses {
+            result = timeout(self.environment.handshake_timeout(), self.initiate_connection(address))
+                .await
+                .unwrap_or(Err(NetworkError::HandshakeTimeout));
+
+            if let Err(ref e) = result {
+                debug!(
+                    "Couldn't connect to {} (an address of {}): {}",
+                    address, remote_address, e
+                );
+                // An existing connection with the peer mustn't be torn down.
+                if !matches!(e, NetworkError::PeerAlreadyConnected) {
+                    let _ = self.disconnect_from_peer(address, e.disconnect_reason());
+                }
+            }
+
+            match result {
+                Ok(()) => break,
+                // These errors don't depend on the dialed address, so there's no point in trying the others.
+                Err(NetworkError::TooManyConnections)
+                | Err(NetworkError::PeerAlreadyConnecting)
+                | Err(NetworkError::PeerAlreadyConnected) => break,
+                // Unresponsive peers are also remembered as disconnected, so that they're backed off from.
+                Err(NetworkError::HandshakeTimeout) => {
+                    let mut peer_book = self.peer_book.write();
+                    peer_book.add_peer(address);
+                    peer_book.register_failed_dial(address);
+                }
+                Err(_) => self.peer_book.read().register_failed_dial(address),
+            }
+        }
+
+        result
+    }
+
     async fn initiate_connection(&self, remote_address: SocketAddr) -> Result<(), NetworkError> {
         let own_address = self.local_address().unwrap(); // must be known by now
         if !self.can_connect() {
@@ -122,7 +195,9 @@ impl Node {
             return Err(NetworkError::PeerAlreadyConnected);
         }
 
+        self.register_peer_asn(remote_address);
         self.peer_book.write().set_connecting(remote_address)?;
+        self.peer_book.write().register_dial_attempt();
 
         // open the connection
         let stream = TcpStream::connect(remote_address).await?;
@@ -134,7 +209,7 @@ impl Node {
                 .expect("Invalid noise handshake pattern!"),
             Box::new(snow::resolvers::SodiumResolver),
         );
-        let static_key = builder.generate_keypair()?.private;
+        let static_key = self.environment.generate_static_key();
         let noise_builder = builder.local_private_key(&static_key).psk(3, crate::HANDSHAKE_PSK);
         let mut noise = noise_builder.build_initiator()?;
         let mut buffer: Box<[u8]> = vec![0u8; crate::MAX_MESSAGE_SIZE].into();
@@ -154,9 +229,12 @@ impl Node {
         }
         let len = reader.read_exact(&mut buf[..len]).await?;
         let len = noise.read_message(&buf[..len], &mut buffer)?;
-        let _peer_version = Version::deserialize(&buffer[..len])?;
+        let peer_version = Version::deserialize(&buffer[..len])?;
         trace!("received e, ee, s, es (XX handshake part 2/3)");
 
+        // Reject peers that don't support the minimum protocol version.
+        let protocol_version = peer_version.negotiate()?;
+
         // -> s, se, psk
         let own_version = Version::serialize(&Version::new(1u64, own_address.port())).unwrap();
         let len = noise.write_message(&own_version, &mut buffer)?;
@@ -166,7 +244,31 @@ impl Node {
 
         let noise = Arc::new(Mutex::new(noise.into_transport_mode()?));
         let writer = ConnWriter::new(remote_address, writer, buffer.clone(), Arc::clone(&noise));
-        let mut reader = ConnReader::new(remote_address, reader, buffer, noise);
+        let mut reader = ConnReader::new(
+            remote_address,
+            reader,
+            buffer,
+            noise,
+            self.environment.max_message_size(),
+        );
+
+        // save the outbound channel, unless a simultaneous inbound connection with the peer got there first;
+        // in that case the older connection is kept and this one is dropped
+        let is_redundant = {
+            let mut channels = self.outbound.channels.write();
+            if channels.contains_key(&remote_address) {
+                true
+            } else {
+                channels.insert(remote_address, Arc::new(writer));
+                false
+            }
+        };
+        if is_redundant {
+            self.peer_book
+                .write()
+                .set_disconnected(remote_address, DisconnectReason::Shutdown)?;
+            return Err(NetworkError::PeerAlreadyConnected);
+        }
 
         // spawn the inbound loop
         let inbound = self.inbound.clone();
@@ -174,10 +276,39 @@ impl Node {
             inbound.listen_for_messages(&mut reader).await;
         });
 
-        // save the outbound channel
-        self.outbound.channels.write().insert(remote_address, Arc::new(writer));
+        let mut peer_book = self.peer_book.write();
+        peer_book.register_successful_dial();
+        peer_book.set_connected(remote_address, None, ConnectionDirection::Outbound)?;
+        peer_book.register_handshake(remote_address, protocol_version, peer_version.user_agent());
 
-        self.peer_book.write().set_connected(remote_address, None)
+        Ok(())
+    }
+
+    ///
+    /// Attempts to reach each of the bootnodes of the network, logging whether it's reachable, and returns
+    /// the number of reachable ones; a bootnode that doesn't accept a connection within the handshake timeout
+    /// is considered unreachable.
+    ///
+    pub async fn probe_bootnodes(&self) -> usize {
+        let mut bootnodes_reachable = 0;
+
+        for bootnode in self.environment.bootnodes() {
+            match timeout(self.environment.handshake_timeout(), TcpStream::connect(bootnode)).await {
+                Ok(Ok(_)) => {
+                    info!("Bootnode {} is reachable", bootnode);
+                    bootnodes_reachable += 1;
+                }
+                Ok(Err(e)) => warn!("Bootnode {} is unreachable: {}", bootnode, e),
+                Err(_) => warn!("Bootnode {} is unreachable: the connection timed out", bootnode),
+            }
+        }
+
+        if bootnodes_reachable == 0 && !self.environment.bootnodes().is_empty() {
+            error!("None of the bootnodes are reachable; the node may find no peers unless they're reconfigured");
+        }
+
+        self.bootnodes_reachable.store(bootnodes_reachable, Ordering::SeqCst);
+        bootnodes_reachable
     }
 
     ///
@@ -202,9 +333,12 @@ impl Node {
             .filter(|addr| !connected_peers.contains_key(addr))
             .copied()
         {
-            if let Err(e) = self.initiate_connection(bootnode_address).await {
+            self.peer_book
+                .write()
+                .register_discovery(bootnode_address, DiscoverySource::Bootnode);
+
+            if let Err(e) = self.connect_to_peer(bootnode_address).await {
                 warn!("Couldn't connect to bootnode {}: {}", bootnode_address, e);
-                let _ = self.disconnect_from_peer(bootnode_address);
             }
         }
     }
@@ -213,20 +347,15 @@ impl Node {
     async fn connect_to_disconnected_peers(&self, count: usize) {
         trace!("Connecting to disconnected peers");
 
-        // Iterate through a selection of random peers and attempt to connect.
-        let random_peers = self
+        // Iterate through a selection of random peers, favoring the preferred regions, and attempt to connect.
+        let candidate_peers = self
             .peer_book
             .read()
-            .disconnected_peers()
-            .iter()
-            .map(|(k, _)| k)
-            .copied()
-            .choose_multiple(&mut rand::thread_rng(), count);
+            .candidate_peers(count, self.environment.preferred_regions());
 
-        for remote_address in random_peers {
-            if let Err(e) = self.initiate_connection(remote_address).await {
+        for remote_address in candidate_peers {
+            if let Err(e) = self.connect_to_peer(remote_address).await {
                 trace!("Couldn't connect to the disconnected peer {}: {}", remote_address, e);
-                let _ = self.disconnect_from_peer(remote_address);
             }
         }
     }
@@ -235,23 +364,82 @@ impl Node {
     async fn broadcast_pings(&self) {
         trace!("Broadcasting Ping messages");
 
+        let connected_peers = self.peer_book.read().connected_peers().clone();
+        for (remote_address, _) in connected_peers {
+            self.send_ping(remote_address).await;
+        }
+    }
+
+    /// Sends a `Ping` message with a fresh nonce to the given connected peer.
+    async fn send_ping(&self, remote_address: SocketAddr) {
         // consider peering tests that don't use the consensus layer
         let current_block_height = if let Some(ref consensus) = self.consensus() {
             consensus.current_block_height()
         } else {
             0
         };
-        let connected_peers = self.peer_book.read().connected_peers().clone();
-        for (remote_address, _) in connected_peers {
-            self.peer_book.read().sending_ping(remote_address);
 
-            self.outbound
-                .send_request(Message::new(
-                    Direction::Outbound(remote_address),
-                    Payload::Ping(current_block_height),
-                ))
-                .await;
+        let nonce = self.environment.generate_nonce();
+        self.peer_book.read().sending_ping(remote_address, nonce);
+
+        self.outbound
+            .send_request(Message::new(
+                Direction::Outbound(remote_address),
+                Payload::Ping(current_block_height, nonce),
+            ))
+            .await;
+    }
+
+    /// Immediately sends a `Ping` to every connected peer and waits up to the given duration for their
+    /// `Pong`s. Returns each peer with its fresh RTT in milliseconds, or `None` if it didn't answer in time.
+    pub async fn ping_peers(&self, max_wait: Duration) -> Vec<(SocketAddr, Option<u64>)> {
+        let connected_peers = self
+            .peer_book
+            .read()
+            .connected_peers()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+
+        // A peer's RTT is fresh once a `Pong` answering the new `Ping` was measured.
+        let rtt_samples = |addr: SocketAddr| {
+            self.peer_book
+                .read()
+                .connected_peers()
+                .get(&addr)
+                .map(|peer| peer.quality.rtt_samples.load(Ordering::SeqCst))
+        };
+
+        let mut pending = Vec::with_capacity(connected_peers.len());
+        for remote_address in connected_peers {
+            let samples = rtt_samples(remote_address);
+            self.send_ping(remote_address).await;
+            pending.push((remote_address, samples));
+        }
+
+        // Peers that disconnected in the meantime no longer need to be waited for.
+        let deadline = Instant::now() + max_wait;
+        let is_pending =
+            |(addr, samples): &(SocketAddr, Option<u64>)| samples.is_some() && rtt_samples(*addr) == *samples;
+        while Instant::now() < deadline && pending.iter().any(is_pending) {
+            sleep(Duration::from_millis(10)).await;
         }
+
+        pending
+            .into_iter()
+            .map(|(addr, samples)| {
+                let peer_book = self.peer_book.read();
+                let rtt = samples.and_then(|samples| {
+                    peer_book
+                        .connected_peers()
+                        .get(&addr)
+                        .filter(|peer| peer.quality.rtt_samples.load(Ordering::SeqCst) != samples)
+                        .map(|peer| peer.quality.rtt_ms.load(Ordering::SeqCst))
+                });
+
+                (addr, rtt)
+            })
+            .collect()
     }
 
     /// Broadcasts a `GetPeers` message to all connected peers to request for more peers.
@@ -302,11 +490,16 @@ impl Node {
 
     /// TODO (howardwu): Add logic to remove the active channels
     ///  and handshakes of the peer from this struct.
-    /// Sets the given remote address in the peer book as disconnected from this node server.
+    /// Sets the given remote address in the peer book as disconnected from this node server
+    /// for the given reason.
     ///
     #[inline]
-    pub(crate) fn disconnect_from_peer(&self, remote_address: SocketAddr) -> Result<(), NetworkError> {
-        debug!("Disconnecting from {}", remote_address);
+    pub(crate) fn disconnect_from_peer(
+        &self,
+        remote_address: SocketAddr,
+        reason: DisconnectReason,
+    ) -> Result<(), NetworkError> {
+        debug!("Disconnecting from {} ({})", remote_address, reason);
 
         if let Some(ref consensus) = self.consensus() {
             if self.peer_book.read().is_syncing_blocks(remote_address) {
@@ -318,44 +511,111 @@ impl Node {
             handle.abort();
         };
         self.outbound.channels.write().remove(&remote_address);
+        self.outbound.remove_queue(remote_address);
 
-        self.peer_book.write().set_disconnected(remote_address)
+        self.peer_book.write().set_disconnected(remote_address, reason)
         // TODO (howardwu): Attempt to blindly send disconnect message to peer.
     }
 
+    /// Makes room for a newly connected peer once the maximum number of connected peers is exceeded by
+    /// disconnecting from the worst one, which may be the new peer itself.
+    pub(crate) fn enforce_max_connected_peers(&self, new_peer: SocketAddr) {
+        let max_peers = self.environment.maximum_number_of_connected_peers() as usize;
+        if self.peer_book.read().number_of_connected_peers() as usize <= max_peers {
+            return;
+        }
+
+        for addr in self.peers_to_evict(1, Some(new_peer)) {
+            debug!("Evicting {} to stay within the maximum number of connected peers", addr);
+            let _ = self.disconnect_from_peer(addr, DisconnectReason::TooManyPeers);
+        }
+    }
+
+    /// Returns up to `count` connected peers to disconnect from in order to free their slots, the lowest-scoring
+    /// ones first. On a tie, the peers outside of the preferred regions go first, followed by the inbound ones, as
+    /// the outbound ones were chosen by this node and are harder to take over; an already connected peer goes
+    /// before the `new_peer`, and the most recently connected ones before the rest. Bootnodes and allowlisted
+    /// peers are never evicted.
+    fn peers_to_evict(&self, count: usize, new_peer: Option<SocketAddr>) -> Vec<SocketAddr> {
+        let bootnodes = self.environment.bootnodes();
+        let preferred_regions = self.environment.preferred_regions();
+        let peer_book = self.peer_book.read();
+
+        let mut candidates = peer_book
+            .connected_peers()
+            .values()
+            .filter(|peer_info| {
+                !bootnodes.contains(&peer_info.address()) && !peer_book.is_allowlisted(peer_info.address())
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_unstable_by_key(|peer_info| {
+            (
+                peer_info.quality.score(),
+                peer_info.is_in_regions(preferred_regions),
+                peer_info.direction() == Some(ConnectionDirection::Outbound),
+                Some(peer_info.address()) == new_peer,
+                Reverse(peer_info.last_connected()),
+            )
+        });
+
+        candidates
+            .into_iter()
+            .take(count)
+            .map(|peer_info| peer_info.address())
+            .collect()
+    }
+
+    /// Penalizes a peer for sending an invalid block, disconnecting from it once it has sent
+    /// `MAX_INVALID_BLOCKS` of them.
+    pub(crate) fn register_invalid_block(&self, remote_address: SocketAddr) {
+        let invalid_blocks = self.peer_book.read().register_invalid_block(remote_address);
+
+        if invalid_blocks >= crate::MAX_INVALID_BLOCKS {
+            warn!(
+                "Disconnecting from {} after receiving {} invalid blocks from it",
+                remote_address, invalid_blocks
+            );
+            if let Err(e) = self.disconnect_from_peer(remote_address, DisconnectReason::Banned) {
+                warn!("Couldn't disconnect from {}: {}", remote_address, e);
+            }
+        }
+    }
+
     pub(crate) async fn send_peers(&self, remote_address: SocketAddr) {
-        // TODO (howardwu): Simplify this and parallelize this with Rayon.
-        // Broadcast the sanitized list of connected peers back to requesting peer.
-        let peers = if !self.environment.is_bootnode() {
-            self.peer_book
-                .read()
-                .connected_peers()
-                .iter()
-                .map(|(k, _)| k)
-                .filter(|&addr| *addr != remote_address)
-                .copied()
-                .choose_multiple(&mut rand::thread_rng(), crate::SHARED_PEER_COUNT)
+        // Ignore peers requesting addresses too often.
+        if !self.peer_book.read().register_get_peers(remote_address) {
+            debug!(
+                "Ignoring a GetPeers request from {}, as it requested peers recently",
+                remote_address
+            );
         } else {
-            self.peer_book
-                .read()
-                .disconnected_peers()
-                .iter()
-                .map(|(k, _)| k)
-                .filter(|&addr| *addr != remote_address)
-                .copied()
-                .choose_multiple(&mut rand::thread_rng(), crate::SHARED_PEER_COUNT)
-        };
+            // TODO (howardwu): Simplify this and parallelize this with Rayon.
+            // Broadcast the sanitized list of connected peers back to requesting peer.
+            let peers = self.peer_book.read().peers_to_share(
+                remote_address,
+                self.environment.max_peers_in_response(),
+                self.environment.is_bootnode(),
+            );
 
-        self.outbound
-            .send_request(Message::new(Direction::Outbound(remote_address), Payload::Peers(peers)))
-            .await;
+            // Don't repeat the same addresses to the peer.
+            if self.peer_book.read().register_advertised_peers(remote_address, &peers) {
+                self.outbound
+                    .send_request(Message::new(Direction::Outbound(remote_address), Payload::Peers(peers)))
+                    .await;
+            } else {
+                debug!("Not sending the same peers to {} again", remote_address);
+            }
+        }
 
         // the bootstrapper's job is finished once it's sent its peer a list of peers
         if self.environment.is_bootnode() {
             let _ = self
                 .inbound
                 .sender
-                .send(Message::new(Direction::Internal, Payload::Disconnect(remote_address)))
+                .send(Message::new(
+                    Direction::Internal,
+                    Payload::Disconnect(remote_address, DisconnectReason::Shutdown),
+                ))
                 .await;
         }
     }
@@ -378,13 +638,15 @@ impl Node {
         for peer_address in peers
             .iter()
             .take(number_to_connect as usize)
-            .filter(|&peer_addr| *peer_addr != local_address)
-            .copied()
+            .map(|&peer_addr| normalize_address(peer_addr))
+            .filter(|&peer_addr| peer_addr != local_address)
         {
             // Inform the peer book that we found a peer.
             // The peer book will determine if we have seen the peer before,
             // and include the peer if it is new.
-            self.peer_book.write().add_peer(peer_address);
+            self.peer_book
+                .write()
+                .add_peer_from(peer_address, DiscoverySource::Gossip);
         }
     }
 
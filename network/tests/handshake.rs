@@ -84,6 +84,66 @@ async fn handshake_responder_side() {
     assert!(peer_book.is_connected(peer_address));
     assert_eq!(peer_book.number_of_connecting_peers(), 0);
     assert_eq!(peer_book.number_of_connected_peers(), 1);
+
+    // the negotiated protocol version should have been registered too
+    let peer_info = peer_book.connected_peers().get(&peer_address).unwrap();
+    assert_eq!(peer_info.protocol_version(), Some(snarkos_network::PROTOCOL_VERSION));
+    assert_eq!(peer_info.version().as_deref(), Some(snarkos_network::USER_AGENT));
+}
+
+#[tokio::test]
+async fn handshake_rejects_old_protocol_version() {
+    // start a test node and listen for incoming connections
+    let setup = TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let node_listener = node.local_address().unwrap();
+
+    // set up a fake node (peer), which is just a socket
+    let mut peer_stream = TcpStream::connect(&node_listener).await.unwrap();
+    let peer_address = peer_stream.local_addr().unwrap();
+
+    let builder = snow::Builder::with_resolver(
+        snarkos_network::HANDSHAKE_PATTERN.parse().unwrap(),
+        Box::new(snow::resolvers::SodiumResolver),
+    );
+    let static_key = builder.generate_keypair().unwrap().private;
+    let noise_builder = builder
+        .local_private_key(&static_key)
+        .psk(3, snarkos_network::HANDSHAKE_PSK);
+    let mut noise = noise_builder.build_initiator().unwrap();
+    let mut buffer: Box<[u8]> = vec![0u8; snarkos_network::NOISE_BUF_LEN].into();
+    let mut buf = [0u8; snarkos_network::NOISE_BUF_LEN]; // a temporary intermediate buffer to decrypt from
+
+    wait_until!(1, node.peer_book.read().is_connecting(peer_address));
+
+    // -> e
+    let len = noise.write_message(&[], &mut buffer).unwrap();
+    peer_stream.write_all(&[len as u8]).await.unwrap();
+    peer_stream.write_all(&buffer[..len]).await.unwrap();
+
+    // <- e, ee, s, es
+    peer_stream.read_exact(&mut buf[..1]).await.unwrap();
+    let len = buf[0] as usize;
+    let len = peer_stream.read_exact(&mut buf[..len]).await.unwrap();
+    noise.read_message(&buf[..len], &mut buffer).unwrap();
+
+    // -> s, se, psk; advertise a protocol version older than the minimum supported one
+    let mut peer_version = Version::new(1u64, peer_address.port());
+    peer_version.protocol_version = snarkos_network::MIN_PROTOCOL_VERSION - 1;
+    let peer_version = Version::serialize(&peer_version).unwrap();
+    let len = noise.write_message(&peer_version, &mut buffer).unwrap();
+    peer_stream.write_all(&[len as u8]).await.unwrap();
+    peer_stream.write_all(&buffer[..len]).await.unwrap();
+
+    // the node should have rejected the peer
+    wait_until!(1, !node.peer_book.read().is_connecting(peer_address));
+    sleep(Duration::from_millis(200)).await;
+    let peer_book = node.peer_book.read();
+    assert!(!peer_book.is_connected(peer_address));
+    assert_eq!(peer_book.number_of_connected_peers(), 0);
 }
 
 #[tokio::test]
@@ -146,6 +206,57 @@ async fn handshake_initiator_side() {
     assert_eq!(peer_book.number_of_connected_peers(), 1);
 }
 
+/// Performs the first half of a handshake with a node started with the given seed and returns its static public key.
+async fn seeded_node_static_key(seed: u64) -> (u64, Vec<u8>) {
+    let setup = TestSetup {
+        consensus_setup: None,
+        rng_seed: Some(seed),
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let mut peer_stream = TcpStream::connect(node.local_address().unwrap()).await.unwrap();
+
+    let builder = snow::Builder::with_resolver(
+        snarkos_network::HANDSHAKE_PATTERN.parse().unwrap(),
+        Box::new(snow::resolvers::SodiumResolver),
+    );
+    let static_key = builder.generate_keypair().unwrap().private;
+    let noise_builder = builder
+        .local_private_key(&static_key)
+        .psk(3, snarkos_network::HANDSHAKE_PSK);
+    let mut noise = noise_builder.build_initiator().unwrap();
+    let mut buffer: Box<[u8]> = vec![0u8; snarkos_network::NOISE_BUF_LEN].into();
+    let mut buf = [0u8; snarkos_network::NOISE_BUF_LEN]; // a temporary intermediate buffer to decrypt from
+
+    // -> e
+    let len = noise.write_message(&[], &mut buffer).unwrap();
+    peer_stream.write_all(&[len as u8]).await.unwrap();
+    peer_stream.write_all(&buffer[..len]).await.unwrap();
+
+    // <- e, ee, s, es
+    peer_stream.read_exact(&mut buf[..1]).await.unwrap();
+    let len = buf[0] as usize;
+    let len = peer_stream.read_exact(&mut buf[..len]).await.unwrap();
+    noise.read_message(&buf[..len], &mut buffer).unwrap();
+
+    (node.environment.name, noise.get_remote_static().unwrap().to_vec())
+}
+
+#[tokio::test]
+async fn seeded_nodes_are_reproducible() {
+    let (name_1, static_key_1) = seeded_node_static_key(42).await;
+    let (name_2, static_key_2) = seeded_node_static_key(42).await;
+    let (name_3, static_key_3) = seeded_node_static_key(43).await;
+
+    // the same seed results in the same node name and handshake key
+    assert_eq!(name_1, name_2);
+    assert_eq!(static_key_1, static_key_2);
+
+    // a different seed doesn't
+    assert_ne!(name_1, name_3);
+    assert_ne!(static_key_1, static_key_3);
+}
+
 async fn assert_node_rejected_message(node: &Node, peer_stream: &mut TcpStream) {
     // read the response from the stream
     let mut buffer = String::new();
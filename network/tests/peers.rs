@@ -14,12 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_network::message::*;
+use snarkos_network::{message::*, ConnectionDirection};
 use snarkos_testing::{
-    network::{handshaken_node_and_peer, random_bound_address, test_node, TestSetup},
+    network::{handshaken_node_and_peer, handshaken_peer, random_bound_address, test_node, TestSetup},
     wait_until,
 };
 
+use std::time::Duration;
+use tokio::time::timeout;
+
 #[tokio::test]
 async fn peer_initiator_side() {
     let setup = TestSetup {
@@ -58,6 +61,26 @@ async fn peer_responder_side() {
     assert!(matches!(payload, Payload::Peers(..)));
 }
 
+#[tokio::test]
+async fn repeated_get_peers_are_ignored() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let (_node, mut peer) = handshaken_node_and_peer(setup).await;
+
+    // the first GetPeers is answered
+    peer.write_message(&Payload::GetPeers).await;
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Peers(..)));
+
+    // while an immediate second one isn't; the node answers the Ping that follows first
+    peer.write_message(&Payload::GetPeers).await;
+    peer.write_message(&Payload::Ping(0, 42)).await;
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Pong(42)));
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn triangle() {
     let setup = |bootnodes| TestSetup {
@@ -88,3 +111,275 @@ async fn triangle() {
     // Make sure C connects to A => peer propagation works.
     wait_until!(5, triangle_is_formed());
 }
+
+#[tokio::test]
+async fn connection_direction_is_recorded() {
+    let setup = |bootnodes| TestSetup {
+        consensus_setup: None,
+        min_peers: 1,
+        peer_sync_interval: 1,
+        bootnodes,
+        ..Default::default()
+    };
+
+    // Spin up node A, and node B that connects to it.
+    let node_alice = test_node(setup(vec![])).await;
+    let addr_alice = node_alice.local_address().unwrap();
+
+    let node_bob = test_node(setup(vec![addr_alice.to_string()])).await;
+    let addr_bob = node_bob.local_address().unwrap();
+
+    wait_until!(
+        5,
+        node_alice.peer_book.read().is_connected(addr_bob) && node_bob.peer_book.read().is_connected(addr_alice)
+    );
+
+    // A was connected to by B, and B connected to A.
+    let alice_peers = node_alice.peer_book.read().connected_peers().clone();
+    assert_eq!(alice_peers[&addr_bob].direction(), Some(ConnectionDirection::Inbound));
+    let bob_peers = node_bob.peer_book.read().connected_peers().clone();
+    assert_eq!(bob_peers[&addr_alice].direction(), Some(ConnectionDirection::Outbound));
+}
+
+#[tokio::test]
+async fn ipv6_bootnode_is_connected_to() {
+    let setup = |bootnodes| TestSetup {
+        consensus_setup: None,
+        socket_address: "[::1]:0".parse().ok(),
+        min_peers: 1,
+        peer_sync_interval: 1,
+        bootnodes,
+        ..Default::default()
+    };
+
+    // Spin up node A, and node B that uses its IPv6 address as a bootnode.
+    let node_alice = test_node(setup(vec![])).await;
+    let addr_alice = node_alice.local_address().unwrap();
+    assert!(addr_alice.is_ipv6());
+
+    let node_bob = test_node(setup(vec![format!("[::1]:{}", addr_alice.port())])).await;
+    let addr_bob = node_bob.local_address().unwrap();
+
+    wait_until!(
+        5,
+        node_alice.peer_book.read().is_connected(addr_bob) && node_bob.peer_book.read().is_connected(addr_alice)
+    );
+}
+
+#[tokio::test]
+async fn alternate_address_is_dialed() {
+    // an address nothing is listening on anymore
+    let (unreachable_addr, listener) = random_bound_address().await;
+    drop(listener);
+
+    // the address the peer can actually be reached at
+    let (reachable_addr, peer_listener) = random_bound_address().await;
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+
+    // the peer is known under the unreachable address, with the reachable one as an alternate
+    {
+        let mut peer_book = node.peer_book.write();
+        peer_book.add_peer(unreachable_addr);
+        peer_book.add_alternate_address(unreachable_addr, reachable_addr);
+    }
+
+    // the node should fail to connect to the first address and dial the alternate one
+    let accepted = timeout(Duration::from_secs(5), peer_listener.accept()).await;
+    assert!(accepted.is_ok());
+    assert!(!node.peer_book.read().is_connecting(unreachable_addr));
+    assert!(node.peer_book.read().is_connecting(reachable_addr));
+}
+
+#[tokio::test]
+async fn worst_peer_is_evicted_at_capacity() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        max_peers: 2,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let node_addr = node.local_address().unwrap();
+
+    // fill the node up to capacity
+    let _peer1 = handshaken_peer(node_addr).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+    let _peer2 = handshaken_peer(node_addr).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+
+    // one of the peers misbehaves, making it the worst one
+    let connected: Vec<_> = node.peer_book.read().connected_peers().keys().copied().collect();
+    let (worst_addr, good_addr) = (connected[0], connected[1]);
+    node.peer_book.read().register_failure(worst_addr);
+
+    // a new peer connects, which evicts the worst one
+    let _peer3 = handshaken_peer(node_addr).await;
+    wait_until!(1, !node.peer_book.read().is_connected(worst_addr));
+    assert!(node.peer_book.read().is_connected(good_addr));
+    assert_eq!(node.peer_book.read().number_of_connected_peers(), 2);
+}
+
+#[tokio::test]
+async fn allowlisted_peer_survives_eviction() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        max_peers: 2,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let node_addr = node.local_address().unwrap();
+
+    // fill the node up to capacity
+    let _peer1 = handshaken_peer(node_addr).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+    let _peer2 = handshaken_peer(node_addr).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+
+    // the worst peer is allowlisted
+    let connected: Vec<_> = node.peer_book.read().connected_peers().keys().copied().collect();
+    let allowlisted_addr = connected[0];
+    node.peer_book.write().allowlist_peer(allowlisted_addr);
+    node.peer_book.read().register_failure(allowlisted_addr);
+
+    // a new peer connects, which evicts another peer instead
+    let _peer3 = handshaken_peer(node_addr).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+    assert!(node.peer_book.read().is_connected(allowlisted_addr));
+}
+
+#[tokio::test]
+async fn stalled_handshake_times_out() {
+    // a peer that accepts connections, but never completes the handshake
+    let (peer_addr, peer_listener) = random_bound_address().await;
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        peer_sync_interval: 1,
+        handshake_timeout_secs: 1,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    node.peer_book.write().add_peer(peer_addr);
+
+    // the node dials the peer, which leaves it hanging
+    let (_stream, _) = timeout(Duration::from_secs(5), peer_listener.accept())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(node.peer_book.read().is_connecting(peer_addr));
+
+    // the node gives up on the peer and backs off from it
+    wait_until!(3, !node.peer_book.read().is_connecting(peer_addr));
+    let peer_book = node.peer_book.read();
+    assert!(peer_book.is_disconnected(peer_addr));
+    assert!(peer_book.disconnected_peers()[&peer_addr].quality.is_backed_off());
+}
+
+#[tokio::test]
+async fn bootnode_reachability_is_probed() {
+    // one bootnode is listening, while the other one is down
+    let (live_bootnode, _listener) = random_bound_address().await;
+    let (dead_bootnode, listener) = random_bound_address().await;
+    drop(listener);
+
+    let setup = TestSetup {
+        consensus_setup: None,
+        bootnodes: vec![live_bootnode.to_string(), dead_bootnode.to_string()],
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+
+    assert_eq!(node.probe_bootnodes().await, 1);
+    assert_eq!(node.bootnodes_reachable(), 1);
+}
+
+#[tokio::test]
+async fn pinging_peers_reports_fresh_latencies() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let node_addr = node.local_address().unwrap();
+
+    // a peer that answers Pings
+    let mut responsive_peer = handshaken_peer(node_addr).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+    let responsive_addr = *node.peer_book.read().connected_peers().keys().next().unwrap();
+
+    tokio::spawn(async move {
+        while let Ok(payload) = responsive_peer.read_payload().await {
+            if let Payload::Ping(_, nonce) = payload {
+                responsive_peer.write_message(&Payload::Pong(nonce)).await;
+            }
+        }
+    });
+
+    // a peer that never answers
+    let _silent_peer = handshaken_peer(node_addr).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+
+    let mut latencies = node.ping_peers(Duration::from_millis(500)).await;
+    assert_eq!(latencies.len(), 2);
+
+    let responsive_idx = latencies.iter().position(|(addr, _)| *addr == responsive_addr).unwrap();
+    let (_, responsive_rtt) = latencies.remove(responsive_idx);
+    let (_, silent_rtt) = latencies.remove(0);
+    assert!(responsive_rtt.is_some());
+    assert!(silent_rtt.is_none());
+}
+
+#[cfg(feature = "geoip")]
+#[tokio::test]
+async fn peers_from_a_saturated_asn_are_rejected() {
+    use snarkos_network::Node;
+    use snarkos_testing::network::test_environment;
+
+    let mut environment = test_environment(TestSetup {
+        consensus_setup: None,
+        ..Default::default()
+    });
+    // The fixture database maps 127.0.0.0/8 to AS64512.
+    let geoip_database = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/geoip-test.mmdb");
+    environment.set_geoip_database(Some(geoip_database.into()));
+
+    let node = Node::new(environment).await.unwrap();
+    node.peer_book.write().set_max_peers_per_asn(Some(1));
+    node.start().await.unwrap();
+    let node_listener = node.local_address().unwrap();
+
+    let _first_peer = handshaken_peer(node_listener).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+
+    // another peer from the same autonomous system isn't admitted
+    let _second_peer = handshaken_peer(node_listener).await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(node.peer_book.read().number_of_connected_peers(), 1);
+}
+
+#[tokio::test]
+async fn inbound_connections_are_throttled() {
+    let setup = TestSetup {
+        consensus_setup: None,
+        max_inbound_connections_per_sec: Some(1),
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let node_addr = node.local_address().unwrap();
+
+    // a burst of peers dials the node at once
+    let _peers: Vec<_> = (0..3).map(|_| tokio::spawn(handshaken_peer(node_addr))).collect();
+
+    // only one of them is accepted right away, while the others wait in the backlog
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(node.peer_book.read().number_of_connected_peers(), 1);
+
+    // the rest are accepted at the permitted rate
+    wait_until!(5, node.peer_book.read().number_of_connected_peers() == 3);
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_consensus::{ConsensusParameters, MerkleTreeLedger};
+use snarkos_consensus::{ConsensusParameters, MerkleTreeLedger, SelectionPolicy, MAX_FUTURE_BLOCK_TIME};
 use snarkos_storage::{key_value::NUM_COLS, storage::Storage, Ledger};
 use snarkvm_algorithms::{merkle_tree::MerkleTree, traits::LoadableMerkleParameters, MerkleParameters, CRH};
 use snarkvm_dpc::{
@@ -76,8 +76,11 @@ pub fn generate(recipient: &str, value: u64, network_id: u8, file_name: &str) ->
 
     let consensus = ConsensusParameters {
         max_block_size: 1_000_000_000usize,
+        block_size_target: 1_000_000_000usize,
+        selection_policy: SelectionPolicy::default(),
         max_nonce: u32::max_value(),
         target_block_time: 10i64,
+        max_future_block_time: MAX_FUTURE_BLOCK_TIME,
         network_id: Network::from_network_id(network_id),
         verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
         authorized_inner_snark_ids: vec![],
@@ -57,8 +57,28 @@ pub enum RpcError {
     #[error("{}", _0)]
     StorageError(StorageError),
 
+    #[error("transaction {} conflicts with the ledger or the memory pool", _0)]
+    TransactionConflicts(String),
+
     #[error("{}", _0)]
     TransactionError(TransactionError),
+
+    #[error("transaction {} was rejected: {}", _0, _1)]
+    TransactionInvalid(String, String),
+}
+
+impl RpcError {
+    /// Returns the JSON-RPC error code the error is reported with; the errors that clients are likely to
+    /// handle have their own codes, while the rest share the generic server error code.
+    pub fn code(&self) -> i64 {
+        match self {
+            RpcError::NoConsensus => -32001,
+            RpcError::InvalidBlockHash(_) => -32002,
+            RpcError::TransactionInvalid(..) => -32003,
+            RpcError::TransactionConflicts(_) => -32004,
+            _ => -32000,
+        }
+    }
 }
 
 impl From<AccountError> for RpcError {
@@ -134,8 +154,12 @@ impl From<anyhow::Error> for RpcError {
 }
 
 impl From<RpcError> for jsonrpc_core::Error {
-    fn from(_error: RpcError) -> Self {
-        jsonrpc_core::Error::invalid_request()
+    fn from(error: RpcError) -> Self {
+        jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(error.code()),
+            message: error.to_string(),
+            data: None,
+        }
     }
 }
 
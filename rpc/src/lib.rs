@@ -47,5 +47,9 @@ pub mod rpc_types;
 #[doc(inline)]
 pub use rpc_types::*;
 
+pub mod storage_pool;
+#[doc(inline)]
+pub use storage_pool::*;
+
 #[macro_use]
 extern crate tracing;
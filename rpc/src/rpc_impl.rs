@@ -18,14 +18,22 @@
 //!
 //! See [RpcFunctions](../trait.RpcFunctions.html) for documentation of public endpoints.
 
-use crate::{error::RpcError, rpc_trait::RpcFunctions, rpc_types::*};
+use crate::{
+    error::RpcError,
+    rpc_trait::RpcFunctions,
+    rpc_types::*,
+    storage_pool::{PooledStorage, StoragePool},
+};
 use snarkos_consensus::{get_block_reward, memory_pool::Entry, ConsensusParameters, MemoryPool, MerkleTreeLedger};
-use snarkos_network::{Consensus, Environment, Node};
-use snarkvm_dpc::base_dpc::{
-    instantiated::{Components, Tx},
-    parameters::PublicParameters,
+use snarkos_network::{Consensus, DiscoverySource, Environment, Node};
+use snarkvm_dpc::{
+    base_dpc::{
+        instantiated::{Components, Tx},
+        parameters::PublicParameters,
+    },
+    AccountAddress,
 };
-use snarkvm_objects::{BlockHeaderHash, Transaction};
+use snarkvm_objects::{Block, BlockError, BlockHeaderHash, Transaction};
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
@@ -35,7 +43,59 @@ use snarkvm_utilities::{
 use chrono::Utc;
 use parking_lot::{Mutex, RwLock};
 
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// The maximum number of block headers returned by a single `getheaderchain` request.
+pub const MAX_HEADER_CHAIN_COUNT: u32 = 2000;
+
+/// The maximum number of samples taken by a single `benchmarkstorage` request.
+pub const MAX_STORAGE_BENCHMARK_SAMPLES: u32 = 10_000;
+
+/// The maximum time a single `pingpeers` request waits for the peers' `Pong`s.
+pub const PING_PEERS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The maximum number of idle secondary storage instances kept open for concurrent reads.
+pub const STORAGE_POOL_CAPACITY: usize = 4;
+
+/// The default maximum number of blocks returned by a single `getblocksinrange` request.
+pub const MAX_BLOCK_RANGE: u32 = 500;
+
+/// The lower bounds of the fee buckets of a `getmempoolfeehistogram` response; a bucket spans the fees
+/// from its bound up to the next one, and the last one holds any greater fee.
+pub const MEMPOOL_FEE_HISTOGRAM_BUCKETS: [u64; 10] = [
+    0,
+    1,
+    10,
+    100,
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+];
+
+/// Determines when the secondary storage instance used by the RPC catches up with the primary one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CatchUpMode {
+    /// Catch up on every read; the data is always current.
+    Eager,
+    /// Catch up in the background at the given interval; reads may return slightly stale data.
+    Lazy(Duration),
+    /// Catch up on every read, but if that fails, `getblock` serves the block from the last successful
+    /// catch-up and flags it as stale; the other endpoints can't flag their data, so they still error,
+    /// as does `getblock` if the secondary storage never caught up.
+    Tolerant,
+}
 
 /// Implements JSON-RPC HTTP endpoint functions for a node.
 /// The constructor is given Arc::clone() copies of all needed node components.
@@ -53,35 +113,108 @@ pub struct RpcImpl {
     /// RPC credentials for accessing guarded endpoints
     pub(crate) credentials: Option<RpcCredentials>,
 
+    /// A static bearer token for accessing guarded endpoints, accepted alongside the credentials
+    pub(crate) token: Option<String>,
+
     /// A clone of the network Node
     pub(crate) node: Node,
+
+    /// Determines when the secondary storage catches up with the primary one.
+    pub(crate) catch_up_mode: CatchUpMode,
+
+    /// Set once the secondary storage has successfully caught up with the primary one.
+    pub(crate) has_caught_up: Arc<AtomicBool>,
+
+    /// If set, the secondary storage catches up on reads at most once per this interval.
+    pub(crate) catch_up_throttle: Option<Duration>,
+
+    /// The time of the last successful catch-up of the secondary storage on a read.
+    pub(crate) last_catch_up: Arc<Mutex<Option<Instant>>>,
+
+    /// Additional secondary storage instances, so that concurrent reads don't contend on `storage`.
+    pub(crate) storage_pool: Arc<StoragePool>,
+
+    /// A handle to the runtime of the node, used to relay blocks submitted over RPC.
+    pub(crate) runtime: tokio::runtime::Handle,
+
+    /// The maximum number of blocks returned by a single `getblocksinrange` request.
+    pub(crate) max_block_range: u32,
 }
 
 impl RpcImpl {
     /// Creates a new struct for calling public and private RPC endpoints.
+    ///
+    /// The given `runtime` is the one of the node; in the lazy catch-up mode, the background
+    /// catch-up task is spawned on it.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         storage: Arc<RwLock<MerkleTreeLedger>>,
         storage_path: PathBuf,
         environment: Environment,
         credentials: Option<RpcCredentials>,
+        token: Option<String>,
         node: Node,
+        runtime: tokio::runtime::Handle,
+        catch_up_mode: CatchUpMode,
     ) -> Self {
+        if let CatchUpMode::Lazy(interval) = catch_up_mode {
+            // The task only holds a weak reference, so that it ends once the storage is dropped.
+            let weak_storage = Arc::downgrade(&storage);
+
+            runtime.spawn(async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+
+                    let storage = match weak_storage.upgrade() {
+                        Some(storage) => storage,
+                        None => break,
+                    };
+
+                    if let Err(e) = storage.read().catch_up_secondary(false) {
+                        error!("Couldn't catch up with the primary storage: {}", e);
+                    }
+                }
+            });
+        }
+
+        let storage_pool = Arc::new(StoragePool::new(storage_path.clone(), STORAGE_POOL_CAPACITY));
+
         Self {
             storage,
             storage_path,
             environment,
             credentials,
+            token,
             node,
+            catch_up_mode,
+            has_caught_up: Default::default(),
+            catch_up_throttle: None,
+            last_catch_up: Default::default(),
+            storage_pool,
+            runtime,
+            max_block_range: MAX_BLOCK_RANGE,
         }
     }
 
+    /// Sets the maximum number of blocks returned by a single `getblocksinrange` request.
+    pub fn with_max_block_range(mut self, max_block_range: u32) -> Self {
+        self.max_block_range = max_block_range;
+        self
+    }
+
+    /// Makes the secondary storage instances catch up on reads at most once per the given interval, with the
+    /// reads in between served from the recently synced state; it has no effect in the lazy catch-up mode.
+    pub fn with_catch_up_throttle(mut self, interval: Duration) -> Self {
+        self.catch_up_throttle = Some(interval);
+        self
+    }
+
     /// Open a new secondary storage instance.
     pub fn new_secondary_storage_instance(&self) -> Result<MerkleTreeLedger, RpcError> {
         Ok(MerkleTreeLedger::open_secondary_at_path(self.storage_path.clone())?)
     }
 
-    fn consensus_layer(&self) -> Result<&Arc<Consensus>, RpcError> {
+    pub(crate) fn consensus_layer(&self) -> Result<&Arc<Consensus>, RpcError> {
         self.node.consensus().ok_or(RpcError::NoConsensus)
     }
 
@@ -96,17 +229,92 @@ impl RpcImpl {
     pub fn memory_pool(&self) -> Result<&Arc<Mutex<MemoryPool<Tx>>>, RpcError> {
         Ok(self.consensus_layer()?.memory_pool())
     }
+
+    /// Catches the secondary storage up with the primary one, unless that is done in the background.
+    /// The reads using it can't be flagged as stale, so a failure is an error in every mode.
+    /// If throttled, it's skipped if the storage caught up recently, unless `force` is set.
+    pub(crate) fn catch_up_secondary(&self, storage: &MerkleTreeLedger, force: bool) -> Result<(), RpcError> {
+        if let CatchUpMode::Lazy(_) = self.catch_up_mode {
+            return Ok(());
+        }
+
+        if !force && is_recent(*self.last_catch_up.lock(), self.catch_up_throttle) {
+            return Ok(());
+        }
+
+        self.try_catch_up(storage, &self.has_caught_up, false)?;
+        *self.last_catch_up.lock() = Some(Instant::now());
+
+        Ok(())
+    }
+
+    /// Catches a pooled secondary storage instance up with the primary one; the background task doesn't
+    /// cover pooled instances, so in the lazy mode they catch up on use, at most once per interval.
+    /// Returns `true` if the catch-up failed and the reads are served from stale data instead.
+    pub(crate) fn catch_up_pooled_secondary(&self, storage: &mut PooledStorage) -> Result<bool, RpcError> {
+        let interval = match self.catch_up_mode {
+            CatchUpMode::Lazy(interval) => Some(interval),
+            _ => self.catch_up_throttle,
+        };
+        if is_recent(storage.last_catch_up, interval) {
+            return Ok(false);
+        }
+
+        let is_stale = self.try_catch_up(&storage.ledger, &storage.has_caught_up, true)?;
+        if !is_stale {
+            storage.last_catch_up = Some(Instant::now());
+        }
+
+        Ok(is_stale)
+    }
+
+    /// Attempts to catch the given secondary storage up with the primary one. In the tolerant mode, a
+    /// failure is only logged if the storage has caught up before and the read can be flagged as stale.
+    /// Returns `true` if the catch-up failed and the reads are served from stale data instead.
+    fn try_catch_up(
+        &self,
+        storage: &MerkleTreeLedger,
+        has_caught_up: &AtomicBool,
+        can_flag_stale: bool,
+    ) -> Result<bool, RpcError> {
+        match storage.catch_up_secondary(false) {
+            Ok(()) => {
+                has_caught_up.store(true, Ordering::SeqCst);
+                Ok(false)
+            }
+            Err(e)
+                if can_flag_stale
+                    && self.catch_up_mode == CatchUpMode::Tolerant
+                    && has_caught_up.load(Ordering::SeqCst) =>
+            {
+                warn!("Couldn't catch up with the primary storage; serving stale data: {}", e);
+                Ok(true)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Checks whether the given catch-up happened within the given interval, if any.
+fn is_recent(last_catch_up: Option<Instant>, interval: Option<Duration>) -> bool {
+    match (last_catch_up, interval) {
+        (Some(last_catch_up), Some(interval)) => last_catch_up.elapsed() < interval,
+        _ => false,
+    }
 }
 
 impl RpcFunctions for RpcImpl {
     /// Returns information about a block from a block hash.
     fn get_block(&self, block_hash_string: String) -> Result<BlockInfo, RpcError> {
         let block_hash = hex::decode(&block_hash_string)?;
-        assert_eq!(block_hash.len(), 32);
-
-        let storage = self.storage.read();
+        if block_hash.len() != 32 {
+            return Err(RpcError::InvalidBlockHash(block_hash_string));
+        }
 
-        storage.catch_up_secondary(false)?;
+        // Blocks are read from a pooled storage instance, so that concurrent requests don't contend.
+        let mut pooled_storage = self.storage_pool.get()?;
+        let stale = self.catch_up_pooled_secondary(&mut pooled_storage)?;
+        let storage = &pooled_storage.ledger;
 
         let block_header_hash = BlockHeaderHash::new(block_hash);
         let height = match storage.get_block_number(&block_header_hash) {
@@ -142,23 +350,77 @@ impl RpcFunctions for RpcImpl {
                 difficulty_target: block.header.difficulty_target,
                 nonce: block.header.nonce,
                 transactions,
+                stale,
             })
         } else {
             Err(RpcError::InvalidBlockHash(block_hash_string))
         }
     }
 
+    /// Returns information about the blocks in the given inclusive range of heights of the canonical chain.
+    fn get_blocks_in_range(&self, start_height: u32, end_height: u32) -> Result<Vec<BlockInfo>, RpcError> {
+        if end_height < start_height {
+            return Err(RpcError::Message(format!(
+                "the end height {} precedes the start height {}",
+                end_height, start_height
+            )));
+        }
+        if end_height - start_height >= self.max_block_range {
+            return Err(RpcError::Message(format!(
+                "at most {} blocks can be requested at once",
+                self.max_block_range
+            )));
+        }
+
+        let block_hashes = {
+            let storage = self.storage.read();
+            self.catch_up_secondary(&storage, false)?;
+
+            (start_height..=end_height)
+                .map(|height| storage.get_block_hash(height))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        block_hashes
+            .into_iter()
+            .map(|block_hash| self.get_block(hex::encode(&block_hash.0)))
+            .collect()
+    }
+
+    /// Returns the hex encoded bytes of a block from its block hash.
+    fn get_raw_block(&self, block_hash_string: String) -> Result<String, RpcError> {
+        let block_hash = hex::decode(&block_hash_string)?;
+        if block_hash.len() != 32 {
+            return Err(RpcError::InvalidBlockHash(block_hash_string));
+        }
+
+        let storage = self.storage.read();
+        self.catch_up_secondary(&storage, false)?;
+
+        match storage.get_block(&BlockHeaderHash::new(block_hash)) {
+            Ok(block) => Ok(hex::encode(&block.serialize()?)),
+            Err(_) => Err(RpcError::InvalidBlockHash(block_hash_string)),
+        }
+    }
+
     /// Returns the number of blocks in the canonical chain.
     fn get_block_count(&self) -> Result<u32, RpcError> {
         let storage = self.storage.read();
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
         Ok(storage.get_block_count())
     }
 
+    /// Returns the total work of the canonical chain as a hex-encoded integer.
+    fn get_chain_work(&self) -> Result<String, RpcError> {
+        let storage = self.storage.read();
+        self.catch_up_secondary(&storage, false)?;
+        Ok(format!("{:x}", storage.get_best_chain_work()?))
+    }
+
     /// Returns the block hash of the head of the canonical chain.
     fn get_best_block_hash(&self) -> Result<String, RpcError> {
         let storage = self.storage.read();
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
         let best_block_hash = storage.get_block_hash(storage.get_current_block_height())?;
 
         Ok(hex::encode(&best_block_hash.0))
@@ -167,7 +429,7 @@ impl RpcFunctions for RpcImpl {
     /// Returns the block hash of the index specified if it exists in the canonical chain.
     fn get_block_hash(&self, block_height: u32) -> Result<String, RpcError> {
         let storage = self.storage.read();
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
         let block_hash = storage.get_block_hash(block_height)?;
 
         Ok(hex::encode(&block_hash.0))
@@ -176,7 +438,7 @@ impl RpcFunctions for RpcImpl {
     /// Returns the hex encoded bytes of a transaction from its transaction id.
     fn get_raw_transaction(&self, transaction_id: String) -> Result<String, RpcError> {
         let storage = self.storage.read();
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
         Ok(hex::encode(
             &storage.get_transaction_bytes(&hex::decode(transaction_id)?)?,
         ))
@@ -188,9 +450,60 @@ impl RpcFunctions for RpcImpl {
         self.decode_raw_transaction(transaction_bytes)
     }
 
+    /// Returns the ids of the memory pool transactions the given one depends on, nearest first.
+    fn get_mempool_ancestors(&self, transaction_id: String) -> Result<Vec<String>, RpcError> {
+        let ancestors = self.memory_pool()?.lock().ancestors(&hex::decode(&transaction_id)?);
+
+        match ancestors {
+            Some(ancestors) => Ok(ancestors.iter().map(hex::encode).collect()),
+            None => Err(RpcError::Message(format!(
+                "transaction {} is not in the memory pool",
+                transaction_id
+            ))),
+        }
+    }
+
+    /// Returns the ids of the memory pool transactions that depend on the given one, nearest first.
+    fn get_mempool_descendants(&self, transaction_id: String) -> Result<Vec<String>, RpcError> {
+        let descendants = self.memory_pool()?.lock().descendants(&hex::decode(&transaction_id)?);
+
+        match descendants {
+            Some(descendants) => Ok(descendants.iter().map(hex::encode).collect()),
+            None => Err(RpcError::Message(format!(
+                "transaction {} is not in the memory pool",
+                transaction_id
+            ))),
+        }
+    }
+
+    /// Returns the number of memory pool transactions per fee bucket, as pairs of the lowest fee of a bucket and
+    /// its count; only the non-empty buckets are listed.
+    fn get_mempool_fee_histogram(&self) -> Result<Vec<(u64, usize)>, RpcError> {
+        Ok(self.memory_pool()?.lock().fee_histogram(&MEMPOOL_FEE_HISTOGRAM_BUCKETS))
+    }
+
+    /// Returns the location of a confirmed transaction from its transaction id, or `None` if it's unconfirmed.
+    fn get_transaction_location(&self, transaction_id: String) -> Result<Option<TxLocation>, RpcError> {
+        let storage = self.storage.read();
+        self.catch_up_secondary(&storage, false)?;
+
+        match storage.get_transaction_location(&hex::decode(transaction_id)?)? {
+            Some(location) => {
+                let block_hash = BlockHeaderHash(location.block_hash);
+
+                Ok(Some(TxLocation {
+                    block_height: storage.get_block_number(&block_hash)?,
+                    block_hash: hex::encode(&block_hash.0),
+                    index: location.index,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
     /// Returns information about a transaction from serialized transaction bytes.
     fn decode_raw_transaction(&self, transaction_bytes: String) -> Result<TransactionInfo, RpcError> {
-        self.storage.read().catch_up_secondary(false)?;
+        self.catch_up_secondary(&self.storage.read(), false)?;
         let transaction_bytes = hex::decode(transaction_bytes)?;
         let transaction = Tx::read(&transaction_bytes[..])?;
 
@@ -198,7 +511,8 @@ impl RpcFunctions for RpcImpl {
 
         for sn in transaction.old_serial_numbers() {
             let mut serial_number: Vec<u8> = vec![];
-            CanonicalSerialize::serialize(sn, &mut serial_number).unwrap();
+            CanonicalSerialize::serialize(sn, &mut serial_number)
+                .map_err(|e| RpcError::Message(format!("couldn't serialize a serial number: {}", e)))?;
             old_serial_numbers.push(hex::encode(serial_number));
         }
 
@@ -250,42 +564,97 @@ impl RpcFunctions for RpcImpl {
 
     /// Send raw transaction bytes to this node to be added into the mempool.
     /// If valid, the transaction will be stored and propagated to all peers.
-    /// Returns the transaction id if valid.
-    fn send_raw_transaction(&self, transaction_bytes: String) -> Result<String, RpcError> {
+    /// Returns whether the transaction was accepted and, if not, why.
+    fn send_raw_transaction(&self, transaction_bytes: String) -> Result<SubmitResult, RpcError> {
         let transaction_bytes = hex::decode(transaction_bytes)?;
         let transaction = Tx::read(&transaction_bytes[..])?;
-        let transaction_hex_id = hex::encode(transaction.transaction_id()?);
+        let transaction_id_bytes = transaction.transaction_id()?.to_vec();
+        let transaction_id = hex::encode(&transaction_id_bytes);
+
+        let rejected = |reason: String| Err(RpcError::TransactionInvalid(transaction_id.clone(), reason));
+
+        // A transaction already in the memory pool isn't verified again.
+        if self
+            .memory_pool()?
+            .lock()
+            .transactions
+            .contains_key(&transaction_id_bytes)
+        {
+            return Ok(SubmitResult::AlreadyInPool { transaction_id });
+        }
+
+        if !self.consensus_layer()?.meets_min_relay_fee(&transaction) {
+            return rejected(format!(
+                "Transaction fee is below the minimum relay fee of {}",
+                self.consensus_layer()?.min_relay_fee().unwrap_or_default()
+            ));
+        }
 
+        {
+            let storage = self.storage.read();
+
+            // The transaction is checked against the latest state of the ledger, regardless of any throttling.
+            self.catch_up_secondary(&storage, true)?;
+
+            // Check for conflicts first, as a transaction spending spent records doesn't verify either.
+            if storage.transaction_conflicts(&transaction) {
+                return Err(RpcError::TransactionConflicts(transaction_id));
+            }
+        }
+
+        // The verification shares the bounded pool of blocking threads with the transactions received from
+        // peers; the RPC server has its own threads, so it can block while it's handled by the node's runtime.
+        let (sender, receiver) = mpsc::channel();
+        let consensus = self.consensus_layer()?.clone();
+        self.runtime.spawn(async move {
+            let _ = sender.send(consensus.verify_submitted_transaction(transaction).await);
+        });
+
+        let (transaction, is_valid) = receiver
+            .recv()
+            .map_err(|_| RpcError::Message("the node stopped before the transaction was verified".into()))?
+            .map_err(|e| RpcError::Message(format!("the transaction verification failed: {}", e)))?;
+
+        if !is_valid? {
+            return rejected("Transaction did not verify".into());
+        }
+
+        // The ledger could have changed during the verification; the memory pool insertion checks for conflicts
+        // again while holding the pool lock.
         let storage = self.storage.read();
+        self.catch_up_secondary(&storage, true)?;
 
-        storage.catch_up_secondary(false)?;
+        if self.consensus_layer()?.is_fee_sniping(&transaction, &storage)? {
+            return rejected("Transaction spends too recent coinbase outputs (possible fee sniping)".into());
+        }
 
         if !self
-            .consensus()?
-            .verify_transaction(self.parameters()?, &transaction, &storage)?
+            .consensus_layer()?
+            .is_program_commitment_authorized(&to_bytes![transaction.program_commitment]?)
         {
-            // TODO (raychu86) Add more descriptive message. (e.g. tx already exists)
-            return Ok("Transaction did not verify".into());
-        }
-
-        match !storage.transaction_conflicts(&transaction) {
-            true => {
-                let entry = Entry::<Tx> {
-                    size_in_bytes: transaction_bytes.len(),
-                    transaction,
-                };
-
-                if let Ok(inserted) = self.memory_pool()?.lock().insert(&storage, entry) {
-                    if inserted.is_some() {
-                        info!("Transaction added to the memory pool.");
-                        // TODO(ljedrz): checks if needs to be propagated to the network; if need be, this could
-                        // be made automatic at the time when a tx from any source is added the memory pool
-                    }
-                }
+            return rejected("Transaction uses an unauthorized program".into());
+        }
+
+        let entry = Entry::<Tx> {
+            size_in_bytes: transaction_bytes.len(),
+            transaction,
+        };
 
-                Ok(transaction_hex_id)
+        // The transaction could have been added since the check above.
+        let mut memory_pool = self.memory_pool()?.lock();
+        if memory_pool.contains(&entry) {
+            return Ok(SubmitResult::AlreadyInPool { transaction_id });
+        }
+
+        match memory_pool.insert(&storage, entry)? {
+            Some(_) => {
+                info!("Transaction added to the memory pool.");
+                // TODO(ljedrz): checks if needs to be propagated to the network; if need be, this could
+                // be made automatic at the time when a tx from any source is added the memory pool
+                Ok(SubmitResult::Accepted { transaction_id })
             }
-            false => Ok("Transaction contains spent records".into()),
+            // The transaction conflicts with another one in the memory pool.
+            None => Err(RpcError::TransactionConflicts(transaction_id)),
         }
     }
 
@@ -296,13 +665,26 @@ impl RpcFunctions for RpcImpl {
 
         let storage = self.storage.read();
 
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
 
         Ok(self
             .consensus()?
             .verify_transaction(self.parameters()?, &transaction, &storage)?)
     }
 
+    /// Returns `true` if the transaction conflicts with the ledger, e.g. by spending already spent records.
+    /// The transaction is not added to the memory pool.
+    fn check_transaction_conflicts(&self, transaction_bytes: String) -> Result<bool, RpcError> {
+        let transaction_bytes = hex::decode(transaction_bytes)?;
+        let transaction = Tx::read(&transaction_bytes[..])?;
+
+        let storage = self.storage.read();
+
+        self.catch_up_secondary(&storage, false)?;
+
+        Ok(storage.transaction_conflicts(&transaction))
+    }
+
     /// Fetch the number of connected peers this node has.
     fn get_connection_count(&self) -> Result<usize, RpcError> {
         // Create a temporary tokio runtime to make an asynchronous function call
@@ -319,31 +701,110 @@ impl RpcFunctions for RpcImpl {
         Ok(PeerInfo { peers })
     }
 
+    /// Returns detailed information about the peers connected to this node.
+    fn get_detailed_peer_info(&self) -> Result<DetailedPeerInfo, RpcError> {
+        let peer_book = self.node.peer_book.read();
+        let peers = peer_book
+            .connected_peers()
+            .values()
+            .map(|peer| PeerDetails {
+                address: peer.address(),
+                version: peer.version(),
+                direction: peer
+                    .direction()
+                    .map(|direction| direction.to_string())
+                    .unwrap_or_default(),
+                rtt_ms: peer.quality.rtt_ms.load(Ordering::SeqCst),
+                failures: peer.quality.failures.load(Ordering::SeqCst),
+                cumulative_failures: peer.cumulative_failures(),
+                bytes_sent: peer.quality.bytes_sent.load(Ordering::SeqCst),
+                bytes_received: peer.quality.bytes_received.load(Ordering::SeqCst),
+                send_rate: peer.quality.send_rate(),
+                recv_rate: peer.quality.recv_rate(),
+                country: peer.location().and_then(|location| location.country.clone()),
+                asn: peer.location().and_then(|location| location.asn),
+                is_syncing_blocks: peer_book.is_syncing_blocks(peer.address()),
+                remaining_sync_blocks: peer.quality.remaining_sync_blocks.load(Ordering::SeqCst),
+            })
+            .collect();
+
+        Ok(DetailedPeerInfo { peers })
+    }
+
+    /// Returns statistics about the peers discovered and dialed by the node.
+    fn get_discovery_stats(&self) -> Result<DiscoveryStats, RpcError> {
+        let peer_book = self.node.peer_book.read();
+
+        let dial_attempts = peer_book.dial_attempts();
+        let successful_dials = peer_book.successful_dials();
+        let success_rate = if dial_attempts == 0 {
+            0.0
+        } else {
+            successful_dials as f64 / dial_attempts as f64
+        };
+
+        Ok(DiscoveryStats {
+            bootnode_peers: peer_book.number_of_discovered_peers(DiscoverySource::Bootnode),
+            gossip_peers: peer_book.number_of_discovered_peers(DiscoverySource::Gossip),
+            manual_peers: peer_book.number_of_discovered_peers(DiscoverySource::Manual),
+            dial_attempts,
+            successful_dials,
+            success_rate,
+        })
+    }
+
     /// Returns data about the node.
     fn get_node_info(&self) -> Result<NodeInfo, RpcError> {
-        // FIXME(ljedrz): actually check if syncing
-        let is_syncing = false;
+        let consensus = self.consensus_layer()?;
 
         Ok(NodeInfo {
-            is_miner: self.consensus_layer()?.is_miner(),
-            is_syncing,
+            is_miner: consensus.is_miner(),
+            is_syncing: consensus.is_syncing(),
+            sync_progress: consensus.sync_progress(),
+            bootnodes_reachable: self.node.bootnodes_reachable(),
+        })
+    }
+
+    /// Returns information about the node's mining activity.
+    fn get_mining_info(&self) -> Result<MiningInfo, RpcError> {
+        let consensus_layer = self.consensus_layer()?;
+
+        let storage = self.storage.read();
+        self.catch_up_secondary(&storage, false)?;
+
+        let latest_block = storage.get_latest_block()?;
+        let consensus = self.consensus()?;
+        let template_transactions = self
+            .memory_pool()?
+            .lock()
+            .get_candidates_with_policy(&storage, consensus.block_size_target(), consensus.selection_policy)?
+            .len();
+
+        Ok(MiningInfo {
+            is_miner: consensus_layer.is_miner(),
+            is_mining: consensus_layer.is_mining(),
+            difficulty_target: consensus.get_block_difficulty(&latest_block.header, Utc::now().timestamp()),
+            template_transactions,
+            blocks_mined: consensus_layer.blocks_mined(),
         })
     }
 
     /// Returns the current mempool and consensus information known by this node.
     fn get_block_template(&self) -> Result<BlockTemplate, RpcError> {
         let storage = self.storage.read();
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
 
         let block_height = storage.get_current_block_height();
         let block = storage.get_block_from_block_number(block_height)?;
 
         let time = Utc::now().timestamp();
 
-        let full_transactions = self
-            .memory_pool()?
-            .lock()
-            .get_candidates(&storage, self.consensus()?.max_block_size)?;
+        let consensus = self.consensus()?;
+        let full_transactions = self.memory_pool()?.lock().get_candidates_with_policy(
+            &storage,
+            consensus.block_size_target(),
+            consensus.selection_policy,
+        )?;
 
         let transaction_strings = full_transactions.serialize_as_str()?;
 
@@ -361,4 +822,189 @@ impl RpcFunctions for RpcImpl {
             coinbase_value: coinbase_value.0 as u64,
         })
     }
+
+    /// Returns the difficulty target of the next block, as used in `getblocktemplate`.
+    fn get_difficulty(&self) -> Result<u64, RpcError> {
+        let storage = self.storage.read();
+        self.catch_up_secondary(&storage, false)?;
+
+        // On a genesis-only chain this is the genesis block.
+        let latest_block = storage.get_latest_block()?;
+
+        Ok(self
+            .consensus()?
+            .get_block_difficulty(&latest_block.header, Utc::now().timestamp()))
+    }
+
+    /// Submit a solved block to this node.
+    /// If valid, the block will be stored and propagated to all peers.
+    fn submit_block(&self, block_bytes: String) -> Result<String, RpcError> {
+        let block_bytes = hex::decode(block_bytes)?;
+        let block = Block::<Tx>::read(&block_bytes[..])?;
+        let block_hash = block.header.get_hash();
+
+        let consensus = self.consensus_layer()?.clone();
+        consensus.consensus_parameters().receive_block(
+            consensus.dpc_parameters(),
+            consensus.storage(),
+            &mut consensus.memory_pool().lock(),
+            &block,
+        )?;
+        info!("Received a block over RPC: {}", hex::encode(&block_hash.0));
+
+        if let Some(local_address) = self.node.local_address() {
+            if consensus.register_propagated_block(block_hash.clone()) {
+                let connected_peers = self.node.peer_book.read().connected_peers().clone();
+
+                self.runtime.spawn(async move {
+                    consensus
+                        .propagate_block(block_bytes, local_address, &connected_peers)
+                        .await;
+                });
+            }
+        }
+
+        Ok(hex::encode(&block_hash.0))
+    }
+
+    /// Returns statistics about the propagation latency of the most recently received blocks.
+    fn get_propagation_stats(&self) -> Result<PropagationStats, RpcError> {
+        let mut latencies = self.consensus_layer()?.block_propagation_latencies();
+        latencies.sort_unstable();
+
+        let samples = latencies.len();
+        if samples == 0 {
+            return Ok(PropagationStats {
+                samples,
+                mean_ms: 0,
+                median_ms: 0,
+                p95_ms: 0,
+            });
+        }
+
+        let mean_ms = latencies.iter().sum::<u64>() / samples as u64;
+        let median_ms = if samples % 2 == 0 {
+            (latencies[samples / 2 - 1] + latencies[samples / 2]) / 2
+        } else {
+            latencies[samples / 2]
+        };
+        // Use the nearest-rank method for the percentile.
+        let p95_ms = latencies[(samples * 95 + 99) / 100 - 1];
+
+        Ok(PropagationStats {
+            samples,
+            mean_ms,
+            median_ms,
+            p95_ms,
+        })
+    }
+
+    /// Returns the progress and efficiency of the current block sync.
+    fn get_sync_status(&self) -> Result<SyncStatus, RpcError> {
+        let consensus = self.consensus_layer()?;
+        let (new_blocks, wasted_blocks) = consensus.sync_block_counts();
+
+        Ok(SyncStatus {
+            is_syncing: consensus.is_syncing_blocks(),
+            new_blocks,
+            wasted_blocks,
+            efficiency: consensus.sync_efficiency(),
+        })
+    }
+
+    /// Returns the hex encoded serialized block headers of the canon chain in the given (capped) range, concatenated.
+    fn get_header_chain(&self, start_height: u32, count: u32) -> Result<String, RpcError> {
+        let storage = self.storage.read();
+
+        self.catch_up_secondary(&storage, false)?;
+
+        let current_height = storage.get_current_block_height();
+        if start_height > current_height {
+            return Err(RpcError::BlockError(BlockError::InvalidBlockNumber(start_height)));
+        }
+
+        let end_height = start_height
+            .saturating_add(count.min(MAX_HEADER_CHAIN_COUNT))
+            .min(current_height + 1);
+
+        let mut header_chain = vec![];
+        for height in start_height..end_height {
+            let block_hash = storage.get_block_hash(height)?;
+            storage.get_block_header(&block_hash)?.write(&mut header_chain)?;
+        }
+
+        Ok(hex::encode(header_chain))
+    }
+
+    /// Returns the number of entries and the approximate memory footprint of the node's in-memory caches.
+    fn get_cache_stats(&self) -> Result<CacheStats, RpcError> {
+        let consensus = self.consensus_layer()?;
+
+        let memory_pool = {
+            let memory_pool = consensus.memory_pool().lock();
+            CacheStat {
+                entries: memory_pool.transactions.len(),
+                size_in_bytes: memory_pool.total_size_in_bytes,
+            }
+        };
+        let (entries, size_in_bytes) = consensus.requested_blocks_stats();
+        let requested_blocks = CacheStat { entries, size_in_bytes };
+        let (entries, size_in_bytes) = consensus.propagated_blocks_stats();
+        let propagated_blocks = CacheStat { entries, size_in_bytes };
+        let (entries, size_in_bytes) = consensus.pending_compact_blocks_stats();
+        let pending_compact_blocks = CacheStat { entries, size_in_bytes };
+
+        Ok(CacheStats {
+            memory_pool,
+            requested_blocks,
+            propagated_blocks,
+            pending_compact_blocks,
+        })
+    }
+
+    /// Returns a summary of the RTTs and failures of the peers connected to this node.
+    fn get_peer_health(&self) -> Result<PeerHealth, RpcError> {
+        let peer_book = self.node.peer_book.read();
+        let connected_peers = peer_book.connected_peers();
+
+        let mut rtts = connected_peers
+            .values()
+            .map(|peer| peer.quality.rtt_ms.load(Ordering::SeqCst))
+            .collect::<Vec<_>>();
+        rtts.sort_unstable();
+
+        let peers = rtts.len();
+        let median_rtt_ms = match peers {
+            0 => None,
+            n if n % 2 == 0 => Some((rtts[n / 2 - 1] + rtts[n / 2]) / 2),
+            n => Some(rtts[n / 2]),
+        };
+        let total_failures = connected_peers
+            .values()
+            .map(|peer| peer.quality.failures.load(Ordering::SeqCst) as u64)
+            .sum();
+
+        Ok(PeerHealth {
+            peers,
+            min_rtt_ms: rtts.first().copied(),
+            median_rtt_ms,
+            max_rtt_ms: rtts.last().copied(),
+            total_failures,
+        })
+    }
+
+    /// Checks whether the given string is a well-formed account address.
+    fn validate_address(&self, address: String) -> Result<AddressValidation, RpcError> {
+        // Malformed addresses are a valid query, so they aren't reported as errors.
+        let address_bytes = AccountAddress::<Components>::from_str(&address)
+            .ok()
+            .and_then(|account_address| to_bytes![account_address].ok())
+            .map(hex::encode);
+
+        Ok(AddressValidation {
+            address,
+            is_valid: address_bytes.is_some(),
+            address_bytes,
+        })
+    }
 }
@@ -18,7 +18,14 @@
 //!
 //! See [ProtectedRpcFunctions](../trait.ProtectedRpcFunctions.html) for documentation of private endpoints.
 
-use crate::{error::RpcError, rpc_trait::ProtectedRpcFunctions, rpc_types::*, RpcImpl};
+use crate::{
+    error::RpcError,
+    rpc_trait::{ProtectedRpcFunctions, RpcFunctions},
+    rpc_types::*,
+    RpcImpl,
+    MAX_STORAGE_BENCHMARK_SAMPLES,
+    PING_PEERS_TIMEOUT,
+};
 use snarkos_consensus::ConsensusParameters;
 use snarkos_toolkit::{
     account::{Address, PrivateKey},
@@ -43,6 +50,7 @@ use snarkvm_dpc::{
     DPCScheme,
     Record as RecordModel,
 };
+use snarkvm_objects::Transaction;
 use snarkvm_utilities::{
     bytes::{FromBytes, ToBytes},
     to_bytes,
@@ -51,25 +59,44 @@ use snarkvm_utilities::{
 use itertools::Itertools;
 use jsonrpc_http_server::jsonrpc_core::{IoDelegate, MetaIoHandler, Params, Value};
 use rand::{thread_rng, Rng};
-use std::{str::FromStr, sync::Arc};
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    str::FromStr,
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
 
 type JsonRPCError = jsonrpc_core::Error;
 
 /// The following `*_protected` functions wrap an authentication check around sensitive functions
 /// before being exposed as an RPC endpoint
 impl RpcImpl {
-    /// Validate the authentication header in the request metadata
+    /// Validate the authentication header in the request metadata; either the basic auth credentials
+    /// or the bearer token are accepted
     pub fn validate_auth(&self, meta: Meta) -> Result<(), JsonRPCError> {
-        if let Some(credentials) = &self.credentials {
-            let auth = meta.auth.unwrap_or_else(String::new);
+        if self.credentials.is_none() && self.token.is_none() {
+            return Ok(());
+        }
+
+        let auth = meta.auth.unwrap_or_else(String::new);
+
+        let is_valid_basic_auth = self.credentials.as_ref().map_or(false, |credentials| {
             let basic_auth_encoding = format!(
                 "Basic {}",
                 base64::encode(format!("{}:{}", credentials.username, credentials.password))
             );
 
-            if basic_auth_encoding != auth {
-                return Err(JsonRPCError::invalid_params("Authentication Error"));
-            }
+            basic_auth_encoding == auth
+        });
+
+        let is_valid_bearer_auth = self
+            .token
+            .as_ref()
+            .map_or(false, |token| format!("Bearer {}", token) == auth);
+
+        if !is_valid_basic_auth && !is_valid_bearer_auth {
+            return Err(JsonRPCError::invalid_params("Authentication Error"));
         }
 
         Ok(())
@@ -164,6 +191,33 @@ impl RpcImpl {
         }
     }
 
+    /// Wrap authentication around `get_record_set_at_height`
+    pub async fn get_record_set_at_height_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 2 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 2 elements",
+                value.len()
+            )));
+        }
+
+        let block_height: u32 = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+        let private_key: String = serde_json::from_value(value[1].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.get_record_set_at_height(block_height, private_key) {
+            Ok(record_set) => Ok(Value::from(record_set)),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
     /// Wrap authentication around `get_raw_record`
     pub async fn get_raw_record_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
         self.validate_auth(meta)?;
@@ -232,6 +286,142 @@ impl RpcImpl {
         }
     }
 
+    /// Wrap authentication around `reset_sync`
+    pub async fn reset_sync_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        match self.reset_sync() {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `set_sync_interval`
+    pub async fn set_sync_interval_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let seconds: u64 = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.set_sync_interval(seconds) {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `benchmark_storage`
+    pub async fn benchmark_storage_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let samples: u32 = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.benchmark_storage(samples) {
+            Ok(benchmark) => Ok(serde_json::to_value(benchmark).expect("benchmark serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `export_snapshot`
+    pub async fn export_snapshot_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let path: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.export_snapshot(path) {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `ping_peers`
+    pub async fn ping_peers_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        match self.ping_peers() {
+            Ok(latencies) => Ok(serde_json::to_value(latencies).expect("latencies serialization failed")),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `set_coinbase_address`
+    pub async fn set_coinbase_address_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        let value = match params {
+            Params::Array(arr) => arr,
+            _ => return Err(JsonRPCError::invalid_request()),
+        };
+
+        if value.len() != 1 {
+            return Err(JsonRPCError::invalid_params(format!(
+                "invalid length {}, expected 1 element",
+                value.len()
+            )));
+        }
+
+        let address: String = serde_json::from_value(value[0].clone())
+            .map_err(|e| JsonRPCError::invalid_params(format!("Invalid params: {}.", e)))?;
+
+        match self.set_coinbase_address(address) {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
+    /// Wrap authentication around `compact_storage`
+    pub async fn compact_storage_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
+        self.validate_auth(meta)?;
+
+        params.expect_no_params()?;
+
+        match self.compact_storage() {
+            Ok(()) => Ok(Value::Null),
+            Err(err) => Err(JsonRPCError::invalid_params(err.to_string())),
+        }
+    }
+
     /// Wrap authentication around `create_account`
     pub async fn create_account_protected(self, params: Params, meta: Meta) -> Result<Value, JsonRPCError> {
         self.validate_auth(meta)?;
@@ -276,10 +466,42 @@ impl RpcImpl {
             let rpc = rpc.clone();
             rpc.get_record_commitments_protected(params, meta)
         });
+        d.add_method_with_meta("getrecordsetatheight", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.get_record_set_at_height_protected(params, meta)
+        });
         d.add_method_with_meta("getrawrecord", |rpc, params, meta| {
             let rpc = rpc.clone();
             rpc.get_raw_record_protected(params, meta)
         });
+        d.add_method_with_meta("resetsync", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.reset_sync_protected(params, meta)
+        });
+        d.add_method_with_meta("setsyncinterval", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.set_sync_interval_protected(params, meta)
+        });
+        d.add_method_with_meta("benchmarkstorage", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.benchmark_storage_protected(params, meta)
+        });
+        d.add_method_with_meta("exportsnapshot", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.export_snapshot_protected(params, meta)
+        });
+        d.add_method_with_meta("pingpeers", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.ping_peers_protected(params, meta)
+        });
+        d.add_method_with_meta("setcoinbaseaddress", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.set_coinbase_address_protected(params, meta)
+        });
+        d.add_method_with_meta("compactstorage", |rpc, params, meta| {
+            let rpc = rpc.clone();
+            rpc.compact_storage_protected(params, meta)
+        });
         d.add_method_with_meta("createaccount", |rpc, params, meta| {
             let rpc = rpc.clone();
             rpc.create_account_protected(params, meta)
@@ -434,6 +656,9 @@ impl ProtectedRpcFunctions for RpcImpl {
         // new secondary storage instance to prevent storage bottle-necking.
         let storage = self.new_secondary_storage_instance()?;
 
+        // The spent records are remembered, so that the fee-sniping policy can recognize the transaction.
+        let spent_records = old_records.clone();
+
         // Generate transaction
         let (records, transaction) = self.consensus()?.create_transaction(
             self.parameters()?,
@@ -450,6 +675,9 @@ impl ProtectedRpcFunctions for RpcImpl {
             rng,
         )?;
 
+        self.consensus_layer()?
+            .register_spent_records(&spent_records, &transaction, &storage)?;
+
         let encoded_transaction = hex::encode(to_bytes![transaction]?);
         let mut encoded_records = Vec::with_capacity(records.len());
         for record in records {
@@ -560,7 +788,7 @@ impl ProtectedRpcFunctions for RpcImpl {
     /// Returns the number of record commitments that are stored on the full node.
     fn get_record_commitment_count(&self) -> Result<usize, RpcError> {
         let storage = self.storage.read();
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
         let record_commitments = storage.get_record_commitments(None)?;
 
         Ok(record_commitments.len())
@@ -569,13 +797,173 @@ impl ProtectedRpcFunctions for RpcImpl {
     /// Returns a list of record commitments that are stored on the full node.
     fn get_record_commitments(&self) -> Result<Vec<String>, RpcError> {
         let storage = self.storage.read();
-        storage.catch_up_secondary(false)?;
+        self.catch_up_secondary(&storage, false)?;
         let record_commitments = storage.get_record_commitments(Some(100))?;
         let record_commitment_strings: Vec<String> = record_commitments.iter().map(hex::encode).collect();
 
         Ok(record_commitment_strings)
     }
 
+    /// Returns the commitments of the records that are unspent as of the given block height.
+    fn get_record_set_at_height(&self, block_height: u32, private_key: String) -> Result<Vec<String>, RpcError> {
+        let storage = self.storage.read();
+        self.catch_up_secondary(&storage, false)?;
+
+        let private_key = AccountPrivateKey::<Components>::from_str(&private_key)?;
+        let record_set =
+            self.consensus()?
+                .record_set_at_height(self.parameters()?, &storage, &private_key, block_height)?;
+
+        Ok(record_set.iter().map(hex::encode).collect())
+    }
+
+    /// Abandons any ongoing block sync and clears the related state, so that a new sync can be started.
+    fn reset_sync(&self) -> Result<(), RpcError> {
+        self.node.reset_sync();
+
+        Ok(())
+    }
+
+    /// Changes the interval between each block sync without restarting the node.
+    fn set_sync_interval(&self, seconds: u64) -> Result<(), RpcError> {
+        if seconds == 0 {
+            return Err(RpcError::Message("the sync interval must be at least 1 second".into()));
+        }
+
+        self.consensus_layer()?
+            .set_block_sync_interval(Duration::from_secs(seconds));
+
+        Ok(())
+    }
+
+    /// Performs the given number of (capped) random block and transaction reads, and returns their latencies.
+    fn benchmark_storage(&self, samples: u32) -> Result<StorageBenchmark, RpcError> {
+        let storage = self.storage.read();
+        let current_height = storage.get_current_block_height();
+        let rng = &mut thread_rng();
+
+        let mut block_latencies = vec![];
+        let mut transaction_latencies = vec![];
+
+        for _ in 0..samples.min(MAX_STORAGE_BENCHMARK_SAMPLES) {
+            let height = rng.gen_range(0..=current_height);
+
+            let start = Instant::now();
+            let block = storage.get_block_from_block_number(height)?;
+            block_latencies.push(start.elapsed().as_micros() as u64);
+
+            if !block.transactions.is_empty() {
+                let index = rng.gen_range(0..block.transactions.len());
+                let transaction_id = block.transactions.0[index].transaction_id()?;
+
+                let start = Instant::now();
+                storage.get_transaction_bytes(&transaction_id)?;
+                transaction_latencies.push(start.elapsed().as_micros() as u64);
+            }
+        }
+
+        // Returns the mean and the 95th percentile of the given latencies.
+        let stats = |mut latencies: Vec<u64>| {
+            if latencies.is_empty() {
+                return (0, 0);
+            }
+            latencies.sort_unstable();
+
+            let mean = latencies.iter().sum::<u64>() / latencies.len() as u64;
+            let p95 = latencies[(latencies.len() * 95 / 100).min(latencies.len() - 1)];
+            (mean, p95)
+        };
+
+        let block_reads = block_latencies.len() as u32;
+        let transaction_reads = transaction_latencies.len() as u32;
+        let (block_mean_us, block_p95_us) = stats(block_latencies);
+        let (transaction_mean_us, transaction_p95_us) = stats(transaction_latencies);
+
+        Ok(StorageBenchmark {
+            block_reads,
+            block_mean_us,
+            block_p95_us,
+            transaction_reads,
+            transaction_mean_us,
+            transaction_p95_us,
+        })
+    }
+
+    /// Writes a snapshot of the canon chain to a file at the given path.
+    fn export_snapshot(&self, path: String) -> Result<(), RpcError> {
+        // The secondary instance is held exclusively, so that it can't catch up (including in the
+        // background) while the snapshot is written, and the snapshot reflects a single height.
+        let storage = self.storage.write();
+        storage.catch_up_secondary(false)?;
+
+        // The snapshot is written to a temporary file first, so that a failed export doesn't leave
+        // a truncated snapshot behind.
+        let temp_path = format!("{}.partial", path);
+        let export = || -> Result<u32, RpcError> {
+            let mut writer = BufWriter::new(File::create(&temp_path)?);
+            let height = storage.export_snapshot(&mut writer)?;
+            writer.get_ref().sync_all()?;
+            Ok(height)
+        };
+
+        match export() {
+            Ok(height) => {
+                fs::rename(&temp_path, &path)?;
+                info!("Exported a snapshot of the ledger at height {} to {}", height, path);
+                Ok(())
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&temp_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Immediately pings every connected peer and returns their fresh RTTs in milliseconds, or `None` for
+    /// the peers that didn't answer within `PING_PEERS_TIMEOUT`.
+    fn ping_peers(&self) -> Result<Vec<(String, Option<u64>)>, RpcError> {
+        // The RPC server has its own threads, so it can block while the pings are handled by the node's runtime.
+        let (sender, receiver) = mpsc::channel();
+        let node = self.node.clone();
+        self.runtime.spawn(async move {
+            let _ = sender.send(node.ping_peers(PING_PEERS_TIMEOUT).await);
+        });
+
+        let latencies = receiver
+            .recv()
+            .map_err(|_| RpcError::Message("the node stopped before the pings were answered".into()))?;
+
+        Ok(latencies
+            .into_iter()
+            .map(|(addr, rtt_ms)| (addr.to_string(), rtt_ms))
+            .collect())
+    }
+
+    /// Changes the address receiving the coinbase rewards of the blocks mined from now on.
+    fn set_coinbase_address(&self, address: String) -> Result<(), RpcError> {
+        if !self.validate_address(address.clone())?.is_valid {
+            return Err(RpcError::Message(format!("{} is not a valid address", address)));
+        }
+
+        self.consensus_layer()?
+            .set_coinbase_address(AccountAddress::<Components>::from_str(&address)?);
+
+        Ok(())
+    }
+
+    /// Starts a manual compaction of the node's primary storage in the background and returns right away.
+    fn compact_storage(&self) -> Result<(), RpcError> {
+        let storage = self.consensus_layer()?.storage().clone();
+
+        self.runtime.spawn_blocking(move || {
+            let started_at = Instant::now();
+            storage.storage.compact();
+            info!("Compacted the storage in {:?}", started_at.elapsed());
+        });
+
+        Ok(())
+    }
+
     /// Returns the hex encoded bytes of a record from its record commitment
     fn get_raw_record(&self, record_commitment: String) -> Result<String, RpcError> {
         match self
@@ -19,6 +19,7 @@
 use crate::{
     rpc_trait::RpcFunctions,
     rpc_types::{Meta, RpcCredentials},
+    CatchUpMode,
     RpcImpl,
 };
 use snarkos_consensus::MerkleTreeLedger;
@@ -27,7 +28,7 @@ use snarkos_network::{Environment, Node};
 use jsonrpc_http_server::{cors::AccessControlAllowHeaders, hyper, ServerBuilder};
 use parking_lot::RwLock;
 
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
 /// Starts a local JSON-RPC HTTP server at rpc_port in a new thread.
 /// Rpc failures will error on the thread level but not affect the main network server.
@@ -41,6 +42,10 @@ pub async fn start_rpc_server(
     node_server: Node,
     username: Option<String>,
     password: Option<String>,
+    token: Option<String>,
+    catch_up_mode: CatchUpMode,
+    catch_up_throttle: Option<Duration>,
+    max_block_range: u32,
 ) {
     let rpc_server: SocketAddr = format!("0.0.0.0:{}", rpc_port).parse().unwrap();
 
@@ -49,7 +54,20 @@ pub async fn start_rpc_server(
         _ => None,
     };
 
-    let rpc_impl = RpcImpl::new(secondary_storage, storage_path, environment, credentials, node_server);
+    let mut rpc_impl = RpcImpl::new(
+        secondary_storage,
+        storage_path,
+        environment,
+        credentials,
+        token,
+        node_server,
+        tokio::runtime::Handle::current(),
+        catch_up_mode,
+    )
+    .with_max_block_range(max_block_range);
+    if let Some(interval) = catch_up_throttle {
+        rpc_impl = rpc_impl.with_catch_up_throttle(interval);
+    }
     let mut io = jsonrpc_core::MetaIoHandler::default();
 
     rpc_impl.add_protected(&mut io);
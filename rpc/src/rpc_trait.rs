@@ -27,10 +27,22 @@ pub trait RpcFunctions {
     #[rpc(name = "getblock")]
     fn get_block(&self, block_hash_string: String) -> Result<BlockInfo, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblocksinrange.md"))]
+    #[rpc(name = "getblocksinrange")]
+    fn get_blocks_in_range(&self, start_height: u32, end_height: u32) -> Result<Vec<BlockInfo>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getrawblock.md"))]
+    #[rpc(name = "getrawblock")]
+    fn get_raw_block(&self, block_hash_string: String) -> Result<String, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblockcount.md"))]
     #[rpc(name = "getblockcount")]
     fn get_block_count(&self) -> Result<u32, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getchainwork.md"))]
+    #[rpc(name = "getchainwork")]
+    fn get_chain_work(&self) -> Result<String, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getbestblockhash.md"))]
     #[rpc(name = "getbestblockhash")]
     fn get_best_block_hash(&self) -> Result<String, RpcError>;
@@ -47,13 +59,35 @@ pub trait RpcFunctions {
     #[rpc(name = "gettransactioninfo")]
     fn get_transaction_info(&self, transaction_id: String) -> Result<TransactionInfo, RpcError>;
 
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/public_endpoints/gettransactionlocation.md")
+    )]
+    #[rpc(name = "gettransactionlocation")]
+    fn get_transaction_location(&self, transaction_id: String) -> Result<Option<TxLocation>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getmempoolancestors.md"))]
+    #[rpc(name = "getmempoolancestors")]
+    fn get_mempool_ancestors(&self, transaction_id: String) -> Result<Vec<String>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getmempooldescendants.md"))]
+    #[rpc(name = "getmempooldescendants")]
+    fn get_mempool_descendants(&self, transaction_id: String) -> Result<Vec<String>, RpcError>;
+
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/public_endpoints/getmempoolfeehistogram.md")
+    )]
+    #[rpc(name = "getmempoolfeehistogram")]
+    fn get_mempool_fee_histogram(&self) -> Result<Vec<(u64, usize)>, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/decoderawtransaction.md"))]
     #[rpc(name = "decoderawtransaction")]
     fn decode_raw_transaction(&self, transaction_bytes: String) -> Result<TransactionInfo, RpcError>;
 
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/sendtransaction.md"))]
     #[rpc(name = "sendtransaction")]
-    fn send_raw_transaction(&self, transaction_bytes: String) -> Result<String, RpcError>;
+    fn send_raw_transaction(&self, transaction_bytes: String) -> Result<SubmitResult, RpcError>;
 
     #[cfg_attr(
         nightly,
@@ -62,6 +96,13 @@ pub trait RpcFunctions {
     #[rpc(name = "validaterawtransaction")]
     fn validate_raw_transaction(&self, transaction_bytes: String) -> Result<bool, RpcError>;
 
+    #[cfg_attr(
+        nightly,
+        doc(include = "../documentation/public_endpoints/checktransactionconflicts.md")
+    )]
+    #[rpc(name = "checktransactionconflicts")]
+    fn check_transaction_conflicts(&self, transaction_bytes: String) -> Result<bool, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getconnectioncount.md"))]
     #[rpc(name = "getconnectioncount")]
     fn get_connection_count(&self) -> Result<usize, RpcError>;
@@ -70,13 +111,57 @@ pub trait RpcFunctions {
     #[rpc(name = "getpeerinfo")]
     fn get_peer_info(&self) -> Result<PeerInfo, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getdetailedpeerinfo.md"))]
+    #[rpc(name = "getdetailedpeerinfo")]
+    fn get_detailed_peer_info(&self) -> Result<DetailedPeerInfo, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getdiscoverystats.md"))]
+    #[rpc(name = "getdiscoverystats")]
+    fn get_discovery_stats(&self) -> Result<DiscoveryStats, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getnodeinfo.md"))]
     #[rpc(name = "getnodeinfo")]
     fn get_node_info(&self) -> Result<NodeInfo, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getmininginfo.md"))]
+    #[rpc(name = "getmininginfo")]
+    fn get_mining_info(&self) -> Result<MiningInfo, RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getblocktemplate.md"))]
     #[rpc(name = "getblocktemplate")]
     fn get_block_template(&self) -> Result<BlockTemplate, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getdifficulty.md"))]
+    #[rpc(name = "getdifficulty")]
+    fn get_difficulty(&self) -> Result<u64, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/submitblock.md"))]
+    #[rpc(name = "submitblock")]
+    fn submit_block(&self, block_bytes: String) -> Result<String, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getpropagationstats.md"))]
+    #[rpc(name = "getpropagationstats")]
+    fn get_propagation_stats(&self) -> Result<PropagationStats, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getsyncstatus.md"))]
+    #[rpc(name = "getsyncstatus")]
+    fn get_sync_status(&self) -> Result<SyncStatus, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getheaderchain.md"))]
+    #[rpc(name = "getheaderchain")]
+    fn get_header_chain(&self, start_height: u32, count: u32) -> Result<String, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getcachestats.md"))]
+    #[rpc(name = "getcachestats")]
+    fn get_cache_stats(&self) -> Result<CacheStats, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/getpeerhealth.md"))]
+    #[rpc(name = "getpeerhealth")]
+    fn get_peer_health(&self) -> Result<PeerHealth, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/public_endpoints/validateaddress.md"))]
+    #[rpc(name = "validateaddress")]
+    fn validate_address(&self, address: String) -> Result<AddressValidation, RpcError>;
 }
 
 /// Definition of private RPC endpoints that require authentication.
@@ -108,6 +193,30 @@ pub trait ProtectedRpcFunctions {
     )]
     fn get_record_commitment_count(&self) -> Result<usize, RpcError>;
 
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getrecordsetatheight.md"))]
+    fn get_record_set_at_height(&self, block_height: u32, private_key: String) -> Result<Vec<String>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/resetsync.md"))]
+    fn reset_sync(&self) -> Result<(), RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/setsyncinterval.md"))]
+    fn set_sync_interval(&self, seconds: u64) -> Result<(), RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/benchmarkstorage.md"))]
+    fn benchmark_storage(&self, samples: u32) -> Result<StorageBenchmark, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/exportsnapshot.md"))]
+    fn export_snapshot(&self, path: String) -> Result<(), RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/pingpeers.md"))]
+    fn ping_peers(&self) -> Result<Vec<(String, Option<u64>)>, RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/setcoinbaseaddress.md"))]
+    fn set_coinbase_address(&self, address: String) -> Result<(), RpcError>;
+
+    #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/compactstorage.md"))]
+    fn compact_storage(&self) -> Result<(), RpcError>;
+
     #[cfg_attr(nightly, doc(include = "../documentation/private_endpoints/getrawrecord.md"))]
     fn get_raw_record(&self, record_commitment: String) -> Result<String, RpcError>;
 
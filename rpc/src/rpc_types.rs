@@ -38,6 +38,37 @@ pub struct Meta {
 
 impl Metadata for Meta {}
 
+/// Returned value for the `sendtransaction` rpc call; rejected transactions are reported as
+/// `RpcError::TransactionInvalid` or `RpcError::TransactionConflicts` errors instead
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SubmitResult {
+    /// The transaction was added to the memory pool
+    Accepted {
+        /// Transaction id
+        transaction_id: String,
+    },
+
+    /// The transaction is already in the memory pool
+    AlreadyInPool {
+        /// Transaction id
+        transaction_id: String,
+    },
+}
+
+/// Returned value for the `validateaddress` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AddressValidation {
+    /// The address that was validated
+    pub address: String,
+
+    /// Flag indicating whether the address is a well-formed account address
+    pub is_valid: bool,
+
+    /// The hex encoding of the address bytes, if the address is valid
+    pub address_bytes: Option<String>,
+}
+
 /// Returned value for the `getblock` rpc call
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct BlockInfo {
@@ -76,6 +107,10 @@ pub struct BlockInfo {
 
     /// List of transaction ids
     pub transactions: Vec<String>,
+
+    /// Flag indicating whether the secondary storage failed to catch up with the primary one, so
+    /// that the confirmations might be outdated
+    pub stale: bool,
 }
 
 /// Returned value for the `getblocktemplate` rpc call
@@ -120,13 +155,38 @@ pub struct DecryptRecordInput {
 }
 
 /// Returned value for the `getnodeinfo` rpc call
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct NodeInfo {
     /// Flag indicating if the node is operating as a miner
     pub is_miner: bool,
 
     /// Flag indicating if the node is currently syncing
     pub is_syncing: bool,
+
+    /// The fraction of the chain advertised by the connected peers that the node has synced, if known
+    pub sync_progress: Option<f64>,
+
+    /// The number of bootnodes found to be reachable on startup
+    pub bootnodes_reachable: usize,
+}
+
+/// Returned value for the `getmininginfo` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MiningInfo {
+    /// Flag indicating if the node is operating as a miner
+    pub is_miner: bool,
+
+    /// Flag indicating if any of the node's mining tasks is running
+    pub is_mining: bool,
+
+    /// The proof of work difficulty target of the next block
+    pub difficulty_target: u64,
+
+    /// The number of memory pool transactions that would be included in the next block
+    pub template_transactions: usize,
+
+    /// The number of blocks mined by the node since it was started
+    pub blocks_mined: u64,
 }
 
 /// Returned value for the `getpeerinfo` rpc call
@@ -136,6 +196,180 @@ pub struct PeerInfo {
     pub peers: Vec<SocketAddr>,
 }
 
+/// Returned value for the `getdetailedpeerinfo` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DetailedPeerInfo {
+    /// The details of the peers connected to this node
+    pub peers: Vec<PeerDetails>,
+}
+
+/// The details of a peer connected to this node
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerDetails {
+    /// The address of the peer
+    pub address: SocketAddr,
+
+    /// The client version advertised by the peer during the handshake
+    pub version: Option<String>,
+
+    /// The side that initiated the connection with the peer ("inbound" or "outbound")
+    pub direction: String,
+
+    /// The round-trip time to the peer in milliseconds
+    pub rtt_ms: u64,
+
+    /// The number of failures associated with the peer
+    pub failures: u8,
+
+    /// The number of failures associated with the peer across all of its connections with this node
+    pub cumulative_failures: u64,
+
+    /// The number of bytes sent to the peer
+    pub bytes_sent: u64,
+
+    /// The number of bytes received from the peer
+    pub bytes_received: u64,
+
+    /// The current throughput to the peer in bytes per second
+    pub send_rate: u64,
+
+    /// The current throughput from the peer in bytes per second
+    pub recv_rate: u64,
+
+    /// The country the peer is located in, if known
+    pub country: Option<String>,
+
+    /// The autonomous system the peer belongs to, if known
+    pub asn: Option<u32>,
+
+    /// Is the node currently syncing blocks from the peer?
+    pub is_syncing_blocks: bool,
+
+    /// The number of blocks yet to be received from the peer in the current sync batch
+    pub remaining_sync_blocks: u16,
+}
+
+/// Returned value for the `getdiscoverystats` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryStats {
+    /// The number of peers discovered as bootnodes
+    pub bootnode_peers: usize,
+
+    /// The number of peers discovered through other peers
+    pub gossip_peers: usize,
+
+    /// The number of peers added explicitly
+    pub manual_peers: usize,
+
+    /// The number of connections attempted by the node
+    pub dial_attempts: u64,
+
+    /// The number of attempted connections that completed a handshake
+    pub successful_dials: u64,
+
+    /// The ratio of successful dials to dial attempts
+    pub success_rate: f64,
+}
+
+/// Returned value for the `getpropagationstats` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PropagationStats {
+    /// The number of recent blocks the statistics are computed over
+    pub samples: usize,
+
+    /// The mean block propagation latency in milliseconds
+    pub mean_ms: u64,
+
+    /// The median block propagation latency in milliseconds
+    pub median_ms: u64,
+
+    /// The 95th percentile block propagation latency in milliseconds
+    pub p95_ms: u64,
+}
+
+/// Returned value for the `getsyncstatus` rpc call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    /// Flag indicating if the node is currently syncing blocks
+    pub is_syncing: bool,
+
+    /// The number of previously unknown, valid blocks received during the current sync
+    pub new_blocks: u64,
+
+    /// The number of already known or invalid blocks received during the current sync
+    pub wasted_blocks: u64,
+
+    /// The ratio of new blocks to all the blocks received during the current sync, if any were received
+    pub efficiency: Option<f64>,
+}
+
+/// Returned value for the `getpeerhealth` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PeerHealth {
+    /// The number of connected peers
+    pub peers: usize,
+
+    /// The lowest RTT of a connected peer in milliseconds
+    pub min_rtt_ms: Option<u64>,
+
+    /// The median RTT of the connected peers in milliseconds
+    pub median_rtt_ms: Option<u64>,
+
+    /// The highest RTT of a connected peer in milliseconds
+    pub max_rtt_ms: Option<u64>,
+
+    /// The total number of failures of the connected peers
+    pub total_failures: u64,
+}
+
+/// The size of an in-memory cache
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CacheStat {
+    /// The number of entries in the cache
+    pub entries: usize,
+
+    /// The approximate size of the cache in bytes
+    pub size_in_bytes: usize,
+}
+
+/// Returned value for the `getcachestats` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// The transactions in the memory pool
+    pub memory_pool: CacheStat,
+
+    /// The blocks currently requested from peers
+    pub requested_blocks: CacheStat,
+
+    /// The recently propagated blocks
+    pub propagated_blocks: CacheStat,
+
+    /// The compact blocks awaiting their missing transactions
+    pub pending_compact_blocks: CacheStat,
+}
+
+/// Returned value for the `benchmarkstorage` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StorageBenchmark {
+    /// The number of random blocks read from storage
+    pub block_reads: u32,
+
+    /// The mean latency of a block read in microseconds
+    pub block_mean_us: u64,
+
+    /// The 95th percentile latency of a block read in microseconds
+    pub block_p95_us: u64,
+
+    /// The number of random transactions read from storage
+    pub transaction_reads: u32,
+
+    /// The mean latency of a transaction read in microseconds
+    pub transaction_mean_us: u64,
+
+    /// The 95th percentile latency of a transaction read in microseconds
+    pub transaction_p95_us: u64,
+}
+
 /// Record payload data
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct RPCRecordPayload {
@@ -261,6 +495,19 @@ pub struct TransactionMetadata {
     pub block_number: Option<u32>,
 }
 
+/// Returned value for the `gettransactionlocation` rpc call
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TxLocation {
+    /// The hash of the block containing the transaction
+    pub block_hash: String,
+
+    /// The height of the block containing the transaction
+    pub block_height: u32,
+
+    /// The index of the transaction within the block
+    pub index: u32,
+}
+
 /// Recipient of a transaction
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TransactionRecipient {
@@ -0,0 +1,119 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+//! A pool of secondary storage instances that allows RPC reads to proceed concurrently.
+
+use snarkos_consensus::MerkleTreeLedger;
+use snarkos_storage::error::StorageError;
+
+use parking_lot::Mutex;
+use std::{
+    ops::{Deref, DerefMut},
+    path::PathBuf,
+    sync::atomic::AtomicBool,
+    time::Instant,
+};
+
+/// A secondary storage instance along with the state of its catch-up with the primary one.
+pub struct PooledStorage {
+    /// The secondary storage instance.
+    pub ledger: MerkleTreeLedger,
+
+    /// Set once the instance has successfully caught up with the primary storage.
+    pub has_caught_up: AtomicBool,
+
+    /// The time of the last successful catch-up with the primary storage.
+    pub last_catch_up: Option<Instant>,
+}
+
+/// A pool of secondary storage instances; each instance is used by a single reader at a time.
+pub struct StoragePool {
+    /// The path to the primary storage.
+    storage_path: PathBuf,
+
+    /// The maximum number of idle instances kept open.
+    capacity: usize,
+
+    /// The instances that are currently not in use.
+    idle: Mutex<Vec<PooledStorage>>,
+}
+
+impl StoragePool {
+    /// Creates a new pool for the primary storage at the given path; instances are opened on demand.
+    pub fn new(storage_path: PathBuf, capacity: usize) -> Self {
+        Self {
+            storage_path,
+            capacity,
+            idle: Default::default(),
+        }
+    }
+
+    /// Borrows an idle instance, or opens a new one if all the instances are in use.
+    pub fn get(&self) -> Result<StorageGuard<'_>, StorageError> {
+        let idle_storage = self.idle.lock().pop();
+
+        let storage = match idle_storage {
+            Some(storage) => storage,
+            None => PooledStorage {
+                ledger: MerkleTreeLedger::open_secondary_at_path(self.storage_path.clone())?,
+                has_caught_up: Default::default(),
+                last_catch_up: None,
+            },
+        };
+
+        Ok(StorageGuard {
+            pool: self,
+            storage: Some(storage),
+        })
+    }
+
+    /// Returns the number of instances that are currently not in use.
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().len()
+    }
+}
+
+/// A borrowed secondary storage instance; it returns to its pool once dropped, unless the pool is full.
+pub struct StorageGuard<'a> {
+    pool: &'a StoragePool,
+    storage: Option<PooledStorage>,
+}
+
+impl Deref for StorageGuard<'_> {
+    type Target = PooledStorage;
+
+    fn deref(&self) -> &Self::Target {
+        self.storage.as_ref().expect("the storage is only taken on drop")
+    }
+}
+
+impl DerefMut for StorageGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.storage.as_mut().expect("the storage is only taken on drop")
+    }
+}
+
+impl Drop for StorageGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(storage) = self.storage.take() {
+            let mut idle = self.pool.idle.lock();
+
+            if idle.len() < self.pool.capacity {
+                idle.push(storage);
+            }
+        }
+    }
+}
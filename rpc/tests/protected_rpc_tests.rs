@@ -36,6 +36,7 @@ mod protected_rpc_tests {
         AccountViewKey,
         Record,
     };
+    use snarkvm_objects::Transaction;
     use snarkvm_utilities::{
         bytes::{FromBytes, ToBytes},
         to_bytes,
@@ -44,10 +45,15 @@ mod protected_rpc_tests {
     use jsonrpc_core::MetaIoHandler;
     use parking_lot::RwLock;
     use serde_json::Value;
-    use std::{str::FromStr, sync::Arc};
+    use std::{
+        str::FromStr,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
 
     const TEST_USERNAME: &str = "TEST_USERNAME";
     const TEST_PASSWORD: &str = "TEST_PASSWORD";
+    const TEST_TOKEN: &str = "TEST_TOKEN";
 
     fn invalid_authentication() -> Meta {
         let basic_auth_encoding = format!(
@@ -80,6 +86,10 @@ mod protected_rpc_tests {
     }
 
     async fn initialize_test_rpc(storage: Arc<RwLock<MerkleTreeLedger>>) -> MetaIoHandler<Meta> {
+        initialize_test_rpc_and_node(storage).await.0
+    }
+
+    async fn initialize_test_rpc_and_node(storage: Arc<RwLock<MerkleTreeLedger>>) -> (MetaIoHandler<Meta>, Node) {
         let credentials = RpcCredentials {
             username: TEST_USERNAME.to_string(),
             password: TEST_PASSWORD.to_string(),
@@ -92,12 +102,21 @@ mod protected_rpc_tests {
 
         let storage_path = storage.read().storage.db.path().to_path_buf();
 
-        let rpc_impl = RpcImpl::new(storage, storage_path, environment, Some(credentials), node);
+        let rpc_impl = RpcImpl::new(
+            storage,
+            storage_path,
+            environment,
+            Some(credentials),
+            Some(TEST_TOKEN.to_string()),
+            node.clone(),
+            tokio::runtime::Handle::current(),
+            CatchUpMode::Eager,
+        );
         let mut io = jsonrpc_core::MetaIoHandler::default();
 
         rpc_impl.add_protected(&mut io);
 
-        io
+        (io, node)
     }
 
     #[tokio::test]
@@ -119,6 +138,41 @@ mod protected_rpc_tests {
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_token_authentication() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let method = "getrecordcommitmentcount".to_string();
+        let request = format!("{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\" }}", method);
+
+        let meta = Meta {
+            auth: Some(format!("Bearer {}", TEST_TOKEN)),
+        };
+        let response = rpc.handle_request_sync(&request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], 0);
+
+        let meta = Meta {
+            auth: Some("Bearer INVALID_TOKEN".to_string()),
+        };
+        let response = rpc.handle_request_sync(&request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        let expected_result = Value::String("Authentication Error".to_string());
+        assert_eq!(extracted["error"]["message"], expected_result);
+
+        // The token isn't accepted in place of the basic auth credentials.
+        let meta = Meta {
+            auth: Some(format!("Basic {}", TEST_TOKEN)),
+        };
+        let response = rpc.handle_request_sync(&request, meta).unwrap();
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["error"]["message"], expected_result);
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
     #[tokio::test]
     async fn test_rpc_fetch_record_commitment_count() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -163,6 +217,59 @@ mod protected_rpc_tests {
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_record_set_at_height() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let meta = authentication();
+
+        storage.read().insert_and_commit(&DATA.block_1).unwrap();
+        storage.read().insert_and_commit(&DATA.block_2).unwrap();
+        storage.read().store_records(&DATA.records_1).unwrap();
+        storage.read().store_records(&DATA.records_2).unwrap();
+
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let [miner_acc, _, _] = FIXTURE_VK.test_accounts.clone();
+
+        // The records created by the coinbase transaction of block 1 are spent in block 2.
+        let spent_commitments: Vec<String> = DATA
+            .records_1
+            .iter()
+            .map(|record| hex::encode(to_bytes![record.commitment()].unwrap()))
+            .collect();
+
+        for height in 0..=2 {
+            let mut expected_record_set = vec![];
+            for block_height in 0..=height {
+                let block_hash = storage.read().get_block_hash(block_height).unwrap();
+                for transaction in storage.read().get_block_transactions(&block_hash).unwrap().0 {
+                    for commitment in transaction.new_commitments() {
+                        let commitment = hex::encode(to_bytes![commitment].unwrap());
+                        if height < 2 || !spent_commitments.contains(&commitment) {
+                            expected_record_set.push(Value::String(commitment));
+                        }
+                    }
+                }
+            }
+
+            let method = "getrecordsetatheight";
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [{}, \"{}\"] }}",
+                method,
+                height,
+                miner_acc.private_key.to_string()
+            );
+            let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+
+            let extracted: Value = serde_json::from_str(&response).unwrap();
+
+            assert_eq!(extracted["result"], Value::Array(expected_record_set));
+        }
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
     #[tokio::test]
     async fn test_rpc_get_raw_record() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -472,4 +579,197 @@ mod protected_rpc_tests {
         drop(rpc);
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
+
+    #[tokio::test]
+    async fn test_rpc_reset_sync() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let meta = authentication();
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone()).await;
+
+        // Simulate a sync that got stuck waiting for a block.
+        let consensus = node.consensus().unwrap();
+        let block_hash = DATA.block_1.header.get_hash();
+        consensus.register_block_sync_attempt();
+        consensus.register_block_requests(&[block_hash.clone()]);
+
+        assert!(consensus.is_syncing_blocks());
+        assert!(consensus.is_block_requested(&block_hash));
+        assert!(!consensus.should_sync_blocks());
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"resetsync\" }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], Value::Null);
+
+        assert!(!consensus.is_syncing_blocks());
+        assert!(!consensus.is_block_requested(&block_hash));
+        assert!(consensus.should_sync_blocks());
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_set_sync_interval() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let meta = authentication();
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone()).await;
+
+        // A sync was just attempted, so the long default interval prevents another one.
+        let consensus = node.consensus().unwrap();
+        consensus.register_block_sync_attempt();
+        consensus.finished_syncing_blocks();
+        assert!(!consensus.should_sync_blocks());
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"setsyncinterval\", \"params\": [1] }";
+        let response = rpc.handle_request_sync(request, meta.clone()).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], Value::Null);
+        assert_eq!(consensus.block_sync_interval(), Duration::from_secs(1));
+
+        // The shorter interval is picked up by the next sync check.
+        std::thread::sleep(Duration::from_millis(1100));
+        assert!(consensus.should_sync_blocks());
+
+        // An interval of 0 is rejected.
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"setsyncinterval\", \"params\": [0] }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert!(extracted["error"].is_object());
+        assert_eq!(consensus.block_sync_interval(), Duration::from_secs(1));
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_set_coinbase_address() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let meta = authentication();
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone()).await;
+
+        let consensus = node.consensus().unwrap();
+        assert!(consensus.coinbase_address().is_none());
+
+        let [_, _, recipient] = &FIXTURE_VK.test_accounts;
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"setcoinbaseaddress\", \"params\": [\"{}\"] }}",
+            recipient.address
+        );
+        let response = rpc.handle_request_sync(&request, meta.clone()).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], Value::Null);
+        assert_eq!(consensus.coinbase_address(), Some(recipient.address.clone()));
+
+        // An invalid address is rejected.
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"setcoinbaseaddress\", \"params\": [\"aleo1\"] }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert!(extracted["error"].is_object());
+        assert_eq!(consensus.coinbase_address(), Some(recipient.address.clone()));
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_benchmark_storage() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let meta = authentication();
+
+        storage.read().insert_and_commit(&DATA.block_1).unwrap();
+        storage.read().insert_and_commit(&DATA.block_2).unwrap();
+
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"benchmarkstorage\", \"params\": [50] }";
+        let start = Instant::now();
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        let benchmark: StorageBenchmark = serde_json::from_value(extracted["result"].clone()).unwrap();
+
+        // Every block in the fixture chain contains at least a coinbase transaction.
+        assert_eq!(benchmark.block_reads, 50);
+        assert_eq!(benchmark.transaction_reads, 50);
+        assert!(benchmark.block_p95_us < 1_000_000);
+        assert!(benchmark.transaction_p95_us < 1_000_000);
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_compact_storage() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let meta = authentication();
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone()).await;
+
+        // Populate the node's storage, leaving some deleted data behind.
+        let ledger = node.consensus().unwrap().storage().clone();
+        ledger.insert_and_commit(&DATA.block_1).unwrap();
+        ledger.insert_and_commit(&DATA.block_2).unwrap();
+        ledger.remove_latest_block().unwrap();
+
+        let request = "{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"compactstorage\" }";
+        let response = rpc.handle_request_sync(request, meta).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], Value::Null);
+
+        // Reads are served while the compaction runs, and once it's done.
+        for _ in 0..2 {
+            assert_eq!(ledger.get_current_block_height(), 1);
+            let block_1 = ledger.get_block(&DATA.block_1.header.get_hash()).unwrap();
+            assert_eq!(block_1.header.get_hash(), DATA.block_1.header.get_hash());
+            assert!(!ledger.is_canon(&DATA.block_2.header.get_hash()));
+
+            ledger.storage.compact();
+        }
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_export_snapshot() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let meta = authentication();
+
+        storage.read().insert_and_commit(&DATA.block_1).unwrap();
+        storage.read().insert_and_commit(&DATA.block_2).unwrap();
+
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let mut snapshot_path = std::env::temp_dir();
+        snapshot_path.push(format!("{}.snapshot", random_storage_path()));
+        let snapshot_path = snapshot_path.to_str().unwrap().to_string();
+
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"exportsnapshot\", \"params\": [\"{}\"] }}",
+            snapshot_path
+        );
+        let response = rpc.handle_request_sync(&request, meta).unwrap();
+
+        let extracted: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(extracted["result"], Value::Null);
+
+        // The snapshot can be re-read into a fresh ledger, which ends up with the same tip.
+        let imported = FIXTURE_VK.ledger();
+        let snapshot = std::fs::File::open(&snapshot_path).unwrap();
+        assert_eq!(imported.import_snapshot(std::io::BufReader::new(snapshot)).unwrap(), 2);
+        assert_eq!(imported.get_current_block_height(), 2);
+        assert_eq!(imported.get_block_hash(2).unwrap(), DATA.block_2.header.get_hash());
+
+        std::fs::remove_file(&snapshot_path).unwrap();
+        kill_storage_sync(imported);
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
 }
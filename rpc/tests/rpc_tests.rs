@@ -16,26 +16,29 @@
 
 /// Tests for public RPC endpoints
 mod rpc_tests {
-    use snarkos_consensus::{get_block_reward, MerkleTreeLedger};
-    use snarkos_network::Node;
-    use snarkos_rpc::*;
+    use snarkos_consensus::{get_block_reward, memory_pool::Entry, MerkleTreeLedger};
+    use snarkos_network::{message::CompactBlock, ConnectionDirection, DiscoverySource, Node};
+    use snarkos_rpc::{error::RpcError, *};
+    use snarkos_storage::{block_work, COL_META, KEY_BEST_BLOCK_NUMBER};
     use snarkos_testing::{
         consensus::*,
         network::{test_consensus, test_environment, ConsensusSetup, TestSetup},
         storage::*,
+        wait_until,
     };
     use snarkvm_dpc::base_dpc::instantiated::Tx;
-    use snarkvm_objects::Transaction;
+    use snarkvm_objects::{Block, BlockHeader, BlockHeaderHash, Transaction};
     use snarkvm_utilities::{
         bytes::{FromBytes, ToBytes},
         serialize::CanonicalSerialize,
         to_bytes,
     };
 
+    use chrono::Utc;
     use jsonrpc_test::Rpc;
     use parking_lot::RwLock;
     use serde_json::Value;
-    use std::{net::SocketAddr, sync::Arc};
+    use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
     fn unwrap_arc_rwlock<T>(x: Arc<RwLock<T>>) -> T {
         if let Ok(lock) = Arc::try_unwrap(x) {
@@ -46,14 +49,81 @@ mod rpc_tests {
     }
 
     async fn initialize_test_rpc(storage: Arc<RwLock<MerkleTreeLedger>>) -> Rpc {
+        initialize_test_rpc_and_node(storage, ConsensusSetup::default()).await.0
+    }
+
+    async fn initialize_test_rpc_and_node(
+        storage: Arc<RwLock<MerkleTreeLedger>>,
+        consensus_setup: ConsensusSetup,
+    ) -> (Rpc, Node) {
         let environment = test_environment(TestSetup::default());
         let mut node = Node::new(environment.clone()).await.unwrap();
-        let consensus = test_consensus(ConsensusSetup::default(), node.clone());
+        let consensus = test_consensus(consensus_setup, node.clone());
         node.set_consensus(consensus);
 
         let storage_path = storage.read().storage.db.path().to_path_buf();
 
-        Rpc::new(RpcImpl::new(storage, storage_path, environment, None, node).to_delegate())
+        let rpc = Rpc::new(
+            RpcImpl::new(
+                storage,
+                storage_path,
+                environment,
+                None,
+                None,
+                node.clone(),
+                tokio::runtime::Handle::current(),
+                CatchUpMode::Eager,
+            )
+            .to_delegate(),
+        );
+
+        (rpc, node)
+    }
+
+    async fn initialize_test_rpc_with_catch_up_mode(
+        secondary: Arc<RwLock<MerkleTreeLedger>>,
+        storage_path: PathBuf,
+        catch_up_mode: CatchUpMode,
+    ) -> Rpc {
+        let environment = test_environment(TestSetup::default());
+        let node = Node::new(environment.clone()).await.unwrap();
+
+        Rpc::new(
+            RpcImpl::new(
+                secondary,
+                storage_path,
+                environment,
+                None,
+                None,
+                node,
+                tokio::runtime::Handle::current(),
+                catch_up_mode,
+            )
+            .to_delegate(),
+        )
+    }
+
+    /// Sends a transaction, returning its outcome or the code and message of the error it was rejected with.
+    fn send_transaction(rpc: &Rpc, transaction_bytes: &[u8]) -> Result<SubmitResult, (i64, String)> {
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"sendtransaction\", \"params\": [\"{}\"] }}",
+            hex::encode(transaction_bytes)
+        );
+        let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap();
+
+        match response.get("error") {
+            Some(error) => Err((
+                error["code"].as_i64().unwrap(),
+                error["message"].as_str().unwrap().to_string(),
+            )),
+            None => Ok(serde_json::from_value(response["result"].clone()).unwrap()),
+        }
+    }
+
+    /// Returns the code and message the given error is reported with.
+    fn rpc_error(error: RpcError) -> (i64, String) {
+        let error = jsonrpc_core::Error::from(error);
+        (error.code.code(), error.message)
     }
 
     fn verify_transaction_info(transaction_bytes: Vec<u8>, transaction_info: Value) {
@@ -157,6 +227,99 @@ mod rpc_tests {
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_block_malformed_hash() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let request_error = |block_hash: String| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getblock\", \"params\": [\"{}\"] }}",
+                block_hash
+            );
+            let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap();
+            response["error"].clone()
+        };
+
+        // A too short or too long hash is rejected instead of crashing the handler.
+        let short_hash = hex::encode(&GENESIS_BLOCK_HEADER_HASH[..16]);
+        let error = request_error(short_hash.clone());
+        assert_eq!(error["code"], -32002);
+        assert_eq!(error["message"], format!("invalid block hash: {}", short_hash));
+        assert_eq!(request_error(hex::encode([0u8; 33]))["code"], -32002);
+
+        // The node keeps serving requests afterwards.
+        let response = rpc.request("getblock", &[hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec())]);
+        let block_response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(hex::encode(genesis().header.get_hash().0), block_response["hash"]);
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_blocks_in_range() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let blocks = vec![
+            genesis(),
+            Block::<Tx>::read(&BLOCK_1[..]).unwrap(),
+            Block::<Tx>::read(&BLOCK_2[..]).unwrap(),
+        ];
+        for block in &blocks[1..] {
+            storage.write().insert_and_commit(block).unwrap();
+        }
+
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let block_infos: Vec<BlockInfo> = serde_json::from_str(&rpc.request("getblocksinrange", &[0u32, 2])).unwrap();
+
+        assert_eq!(block_infos.len(), blocks.len());
+        for (height, (block_info, block)) in block_infos.iter().zip(&blocks).enumerate() {
+            assert_eq!(block_info.hash, hex::encode(block.header.get_hash().0));
+            assert_eq!(block_info.height, Some(height as u32));
+            assert_eq!(block_info.transactions.len(), block.transactions.len());
+        }
+
+        // Reversed and oversized ranges are rejected.
+        let request_error = |start: u32, end: u32| {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getblocksinrange\", \"params\": [{}, {}] }}",
+                start, end
+            );
+            let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap();
+            response["error"].is_object()
+        };
+        assert!(request_error(2, 1));
+        assert!(request_error(0, MAX_BLOCK_RANGE));
+        assert!(!request_error(1, 1));
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_raw_block() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let response = rpc.request("getrawblock", &[hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec())]);
+        let block_bytes: String = serde_json::from_str(&response).unwrap();
+        let block = Block::<Tx>::deserialize(&hex::decode(block_bytes).unwrap()).unwrap();
+
+        assert_eq!(block, genesis());
+
+        // An unknown block hash is rejected.
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"getrawblock\", \"params\": [\"{}\"] }}",
+            hex::encode([0u8; 32])
+        );
+        let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap();
+        assert!(response["error"].is_object());
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
     #[tokio::test]
     async fn test_rpc_get_block_count() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -172,6 +335,245 @@ mod rpc_tests {
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_chain_work() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let chain_work = |rpc: &Rpc| {
+            let result = make_request_no_params(rpc, "getchainwork".to_string());
+            u128::from_str_radix(result.as_str().unwrap(), 16).unwrap()
+        };
+
+        // Only the genesis block contributes to the work at first.
+        let genesis_header = storage
+            .read()
+            .get_block_header(&storage.read().get_block_hash(0).unwrap())
+            .unwrap();
+        let mut previous_work = chain_work(&rpc);
+        assert_eq!(previous_work, block_work(genesis_header.difficulty_target));
+
+        // The work increases with every block.
+        for block in &[&DATA.block_1, &DATA.block_2] {
+            storage.read().insert_and_commit(block).unwrap();
+
+            let work = chain_work(&rpc);
+            assert_eq!(work, previous_work + block_work(block.header.difficulty_target));
+            assert!(work > previous_work);
+            previous_work = work;
+        }
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_lazy_catch_up() {
+        let primary = FIXTURE_VK.ledger();
+        let storage_path = primary.storage.db.path().to_path_buf();
+        let secondary = Arc::new(RwLock::new(
+            MerkleTreeLedger::open_secondary_at_path(&storage_path).unwrap(),
+        ));
+
+        let catch_up_mode = CatchUpMode::Lazy(Duration::from_secs(1));
+        let rpc = initialize_test_rpc_with_catch_up_mode(secondary.clone(), storage_path.clone(), catch_up_mode).await;
+
+        primary
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+
+        // The read doesn't catch up with the primary storage on its own...
+        let result = make_request_no_params(&rpc, "getblockcount".to_string());
+        assert_eq!(result.as_u64().unwrap(), 1u64);
+
+        // ...but the background task eventually does.
+        wait_until!(
+            5,
+            make_request_no_params(&rpc, "getblockcount".to_string()).as_u64() == Some(2),
+            100
+        );
+
+        drop(rpc);
+        drop(secondary);
+        kill_storage_sync(primary);
+
+        let mut secondary_path = storage_path.into_os_string();
+        secondary_path.push("_secondary");
+        let _ = std::fs::remove_dir_all(secondary_path);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_throttled_catch_up() {
+        let primary = FIXTURE_VK.ledger();
+        let storage_path = primary.storage.db.path().to_path_buf();
+        let secondary = Arc::new(RwLock::new(
+            MerkleTreeLedger::open_secondary_at_path(&storage_path).unwrap(),
+        ));
+
+        let environment = test_environment(TestSetup::default());
+        let node = Node::new(environment.clone()).await.unwrap();
+        let throttle = Duration::from_secs(2);
+        let rpc = Rpc::new(
+            RpcImpl::new(
+                secondary.clone(),
+                storage_path.clone(),
+                environment,
+                None,
+                None,
+                node,
+                tokio::runtime::Handle::current(),
+                CatchUpMode::Eager,
+            )
+            .with_catch_up_throttle(throttle)
+            .to_delegate(),
+        );
+
+        // The first read catches up with the primary storage.
+        let result = make_request_no_params(&rpc, "getblockcount".to_string());
+        assert_eq!(result.as_u64().unwrap(), 1u64);
+
+        primary
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+
+        // The reads within the interval reuse the recently synced state...
+        let result = make_request_no_params(&rpc, "getblockcount".to_string());
+        assert_eq!(result.as_u64().unwrap(), 1u64);
+
+        // ...while the first one after it catches up again.
+        tokio::time::sleep(throttle).await;
+        let result = make_request_no_params(&rpc, "getblockcount".to_string());
+        assert_eq!(result.as_u64().unwrap(), 2u64);
+
+        drop(rpc);
+        drop(secondary);
+        kill_storage_sync(primary);
+
+        let mut secondary_path = storage_path.into_os_string();
+        secondary_path.push("_secondary");
+        let _ = std::fs::remove_dir_all(secondary_path);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_tolerant_catch_up() {
+        let primary = FIXTURE_VK.ledger();
+        let storage_path = primary.storage.db.path().to_path_buf();
+        let secondary = Arc::new(RwLock::new(
+            MerkleTreeLedger::open_secondary_at_path(&storage_path).unwrap(),
+        ));
+
+        let genesis_hash = hex::encode(GENESIS_BLOCK_HEADER_HASH.to_vec());
+        let request = |rpc: &Rpc, method: &str, params: &str| -> Value {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [{}] }}",
+                method, params
+            );
+            serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap()
+        };
+        let get_block = |rpc: &Rpc| request(rpc, "getblock", &format!("\"{}\"", genesis_hash));
+
+        let tolerant_rpc =
+            initialize_test_rpc_with_catch_up_mode(secondary.clone(), storage_path.clone(), CatchUpMode::Tolerant)
+                .await;
+        assert_eq!(get_block(&tolerant_rpc)["result"]["stale"], false);
+        assert_eq!(request(&tolerant_rpc, "getblockcount", "")["result"], 1);
+
+        // Simulate a catch-up failure by removing the best block number from the primary storage.
+        let db = &primary.storage.db;
+        db.delete_cf(
+            db.cf_handle(&format!("col{}", COL_META)).unwrap(),
+            KEY_BEST_BLOCK_NUMBER.as_bytes(),
+        )
+        .unwrap();
+        assert!(secondary.read().catch_up_secondary(false).is_err());
+
+        // The secondary storage instances caught up before, so a block is served, flagged as stale...
+        let response = get_block(&tolerant_rpc);
+        assert_eq!(response["result"]["hash"], genesis_hash);
+        assert_eq!(response["result"]["stale"], true);
+
+        // ...while the endpoints that can't flag their data report the failure...
+        assert!(request(&tolerant_rpc, "getblockcount", "")["error"].is_object());
+
+        // ...as do all of them in the eager mode...
+        let eager_rpc =
+            initialize_test_rpc_with_catch_up_mode(secondary.clone(), storage_path.clone(), CatchUpMode::Eager).await;
+        assert!(request(&eager_rpc, "getblockcount", "")["error"].is_object());
+
+        // ...and if the secondary storage has never caught up.
+        let never_synced_rpc =
+            initialize_test_rpc_with_catch_up_mode(secondary.clone(), storage_path.clone(), CatchUpMode::Tolerant)
+                .await;
+        assert!(request(&never_synced_rpc, "getblockcount", "")["error"].is_object());
+
+        drop(tolerant_rpc);
+        drop(eager_rpc);
+        drop(never_synced_rpc);
+        drop(secondary);
+        kill_storage_sync(primary);
+
+        let mut secondary_path = storage_path.into_os_string();
+        secondary_path.push("_secondary");
+        let _ = std::fs::remove_dir_all(secondary_path);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_rpc_concurrent_get_block() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let blocks = vec![
+            genesis(),
+            Block::<Tx>::read(&BLOCK_1[..]).unwrap(),
+            Block::<Tx>::read(&BLOCK_2[..]).unwrap(),
+        ];
+        for block in &blocks[1..] {
+            storage.write().insert_and_commit(block).unwrap();
+        }
+
+        let rpc = Arc::new(initialize_test_rpc(storage.clone()).await);
+
+        let mut handles = Vec::new();
+        for i in 0..64 {
+            let rpc = rpc.clone();
+            let expected_height = i % blocks.len();
+            let expected_confirmations = blocks.len() - 1 - expected_height;
+            let block_hash = hex::encode(blocks[expected_height].header.get_hash().0);
+
+            handles.push(tokio::task::spawn_blocking(move || {
+                let block_info: BlockInfo = serde_json::from_str(&rpc.request("getblock", &[&block_hash])).unwrap();
+                assert_eq!(block_info.hash, block_hash);
+                assert_eq!(block_info.height, Some(expected_height as u32));
+                assert_eq!(block_info.confirmations, expected_confirmations as u32);
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[test]
+    fn test_storage_pool_capacity() {
+        let storage = FIXTURE_VK.ledger();
+        let pool = StoragePool::new(storage.storage.db.path().to_path_buf(), 2);
+
+        // Instances are only opened when all the idle ones are in use...
+        let guards = (0..3).map(|_| pool.get().unwrap()).collect::<Vec<_>>();
+        assert_eq!(pool.idle_count(), 0);
+
+        // ...and only up to the capacity of the pool is kept open once they are returned.
+        drop(guards);
+        assert_eq!(pool.idle_count(), 2);
+
+        let guard = pool.get().unwrap();
+        assert_eq!(pool.idle_count(), 1);
+
+        drop(guard);
+        drop(pool);
+        kill_storage_sync(storage);
+    }
+
     #[tokio::test]
     async fn test_rpc_get_best_block_hash() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -243,6 +645,38 @@ mod rpc_tests {
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_transaction_location() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        // The coinbase transaction of the genesis block is confirmed.
+        let genesis_block = genesis();
+        let transaction_id = hex::encode(genesis_block.transactions.0[0].transaction_id().unwrap());
+
+        let location: Option<TxLocation> =
+            serde_json::from_str(&rpc.request("gettransactionlocation", &[transaction_id])).unwrap();
+        assert_eq!(
+            location,
+            Some(TxLocation {
+                block_hash: hex::encode(genesis_block.header.get_hash().0),
+                block_height: 0,
+                index: 0,
+            })
+        );
+
+        // A transaction that isn't in the ledger is unconfirmed.
+        let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
+        let transaction_id = hex::encode(transaction.transaction_id().unwrap());
+
+        let location: Option<TxLocation> =
+            serde_json::from_str(&rpc.request("gettransactionlocation", &[transaction_id])).unwrap();
+        assert_eq!(location, None);
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
     #[tokio::test]
     async fn test_rpc_decode_raw_transaction() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -254,10 +688,68 @@ mod rpc_tests {
 
         verify_transaction_info(TRANSACTION_1.to_vec(), transaction_info);
 
+        // Truncated transaction bytes are rejected with an error.
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"decoderawtransaction\", \"params\": [\"{}\"] }}",
+            hex::encode(&TRANSACTION_1[..TRANSACTION_1.len() / 2])
+        );
+        let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap();
+        assert!(response["error"].is_object());
+
         drop(rpc);
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_check_transaction_conflicts() {
+        let transaction_bytes = hex::encode(TRANSACTION_1.to_vec());
+
+        // A transaction that isn't in the ledger doesn't conflict with it.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        assert_eq!(
+            rpc.request("checktransactionconflicts", &[transaction_bytes.clone()]),
+            "false"
+        );
+        // The memory pool is left untouched.
+        assert!(node.expect_consensus().memory_pool().lock().transactions.is_empty());
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+
+        // A transaction that is already in the ledger conflicts with it.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        storage
+            .read()
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        assert_eq!(rpc.request("checktransactionconflicts", &[transaction_bytes]), "true");
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[test]
+    fn test_rpc_error_codes() {
+        let code = |error: RpcError| rpc_error(error).0;
+
+        assert_eq!(code(RpcError::NoConsensus), -32001);
+        assert_eq!(code(RpcError::InvalidBlockHash("00".into())), -32002);
+        assert_eq!(code(RpcError::TransactionInvalid("00".into(), "reason".into())), -32003);
+        assert_eq!(code(RpcError::TransactionConflicts("00".into())), -32004);
+        assert_eq!(code(RpcError::Message("message".into())), -32000);
+
+        // The message describes the error.
+        assert_eq!(
+            rpc_error(RpcError::TransactionInvalid("00".into(), "reason".into())).1,
+            "transaction 00 was rejected: reason"
+        );
+    }
+
     #[tokio::test]
     async fn test_rpc_send_raw_transaction() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -266,11 +758,223 @@ mod rpc_tests {
         let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
 
         assert_eq!(
-            rpc.request("sendtransaction", &[hex::encode(TRANSACTION_1.to_vec())]),
-            format![r#""{}""#, hex::encode(transaction.transaction_id().unwrap())]
+            send_transaction(&rpc, &TRANSACTION_1),
+            Ok(SubmitResult::Accepted {
+                transaction_id: hex::encode(transaction.transaction_id().unwrap())
+            })
+        );
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_send_raw_transaction_outcomes() {
+        let transaction_id = hex::encode(Tx::read(&TRANSACTION_1[..]).unwrap().transaction_id().unwrap());
+
+        // Sending the same transaction twice finds it in the memory pool the second time.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_1),
+            Ok(SubmitResult::Accepted {
+                transaction_id: transaction_id.clone()
+            })
+        );
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_1),
+            Ok(SubmitResult::AlreadyInPool {
+                transaction_id: transaction_id.clone()
+            })
+        );
+        assert_eq!(node.expect_consensus().memory_pool().lock().transactions.len(), 1);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+
+        // A transaction that is already in the ledger conflicts with it.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        storage
+            .read()
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_1),
+            Err(rpc_error(RpcError::TransactionConflicts(transaction_id.clone())))
+        );
+        assert!(node.expect_consensus().memory_pool().lock().transactions.is_empty());
+
+        // The error has its own code.
+        let (code, _) = send_transaction(&rpc, &TRANSACTION_1).unwrap_err();
+        assert_eq!(code, -32004);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_send_raw_transaction_program_allowlist() {
+        let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
+        let program_commitment = to_bytes![transaction.program_commitment].unwrap();
+
+        // A transaction with a program commitment that isn't allowlisted is rejected.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let consensus_setup = ConsensusSetup {
+            authorized_program_commitments: vec![vec![0u8; program_commitment.len()]],
+            ..Default::default()
+        };
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup).await;
+
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_1),
+            Err(rpc_error(RpcError::TransactionInvalid(
+                hex::encode(transaction.transaction_id().unwrap()),
+                "Transaction uses an unauthorized program".into()
+            )))
+        );
+        assert!(node.expect_consensus().memory_pool().lock().transactions.is_empty());
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+
+        // A transaction with an allowlisted program commitment is accepted.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let consensus_setup = ConsensusSetup {
+            authorized_program_commitments: vec![program_commitment],
+            ..Default::default()
+        };
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup).await;
+
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_1),
+            Ok(SubmitResult::Accepted {
+                transaction_id: hex::encode(transaction.transaction_id().unwrap())
+            })
+        );
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_send_raw_transaction_min_relay_fee() {
+        let transaction = Tx::read(&TRANSACTION_1[..]).unwrap();
+        let fee = transaction.value_balance.0;
+
+        // A transaction paying less than the minimum relay fee is rejected.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let consensus_setup = ConsensusSetup {
+            min_relay_fee: Some(fee + 1),
+            ..Default::default()
+        };
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup).await;
+
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_1),
+            Err(rpc_error(RpcError::TransactionInvalid(
+                hex::encode(transaction.transaction_id().unwrap()),
+                format!("Transaction fee is below the minimum relay fee of {}", fee + 1)
+            )))
         );
+        assert!(node.expect_consensus().memory_pool().lock().transactions.is_empty());
 
         drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+
+        // A transaction paying exactly the minimum relay fee is accepted.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let consensus_setup = ConsensusSetup {
+            min_relay_fee: Some(fee),
+            ..Default::default()
+        };
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup).await;
+
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_1),
+            Ok(SubmitResult::Accepted {
+                transaction_id: hex::encode(transaction.transaction_id().unwrap())
+            })
+        );
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_send_raw_transaction_fee_sniping() {
+        // TRANSACTION_2 spends the coinbase outputs of block 1 (DATA.records_1).
+        let transaction_2 = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let transaction_id = hex::encode(transaction_2.transaction_id().unwrap());
+        let consensus_setup = ConsensusSetup {
+            fee_sniping_depth: Some(1),
+            ..Default::default()
+        };
+
+        // A transaction that isn't known to spend recent coinbase outputs passes under the policy, even
+        // though it's anchored to the latest ledger state.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        storage
+            .read()
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup.clone()).await;
+
+        assert!(!node
+            .expect_consensus()
+            .is_fee_sniping(&transaction_2, &storage.read())
+            .unwrap());
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_2),
+            Ok(SubmitResult::Accepted {
+                transaction_id: transaction_id.clone()
+            })
+        );
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+
+        // Once it's known to spend the coinbase outputs of the latest block, the same transaction is rejected.
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        storage
+            .read()
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup).await;
+
+        node.expect_consensus()
+            .register_spent_records(&DATA.records_1, &transaction_2, &storage.read())
+            .unwrap();
+        assert_eq!(
+            send_transaction(&rpc, &TRANSACTION_2),
+            Err(rpc_error(RpcError::TransactionInvalid(
+                transaction_id,
+                "Transaction spends too recent coinbase outputs (possible fee sniping)".into()
+            )))
+        );
+        assert!(node.expect_consensus().memory_pool().lock().transactions.is_empty());
+
+        // Once the outputs are buried deep enough, the policy no longer applies.
+        storage
+            .read()
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_2[..]).unwrap())
+            .unwrap();
+        assert!(!node
+            .expect_consensus()
+            .is_fee_sniping(&transaction_2, &storage.read())
+            .unwrap());
+
+        drop(rpc);
+        drop(node);
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
@@ -322,6 +1026,270 @@ mod rpc_tests {
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_detailed_peer_info() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let method = "getdetailedpeerinfo".to_string();
+
+        let peer_info: DetailedPeerInfo = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+        assert!(peer_info.peers.is_empty());
+
+        let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
+        node.peer_book
+            .write()
+            .set_connected(address, None, ConnectionDirection::Inbound)
+            .unwrap();
+        node.peer_book.read().register_bytes_sent(address, 1000);
+        node.peer_book.read().register_bytes_received(address, 500);
+        node.peer_book.read().register_failure(address);
+
+        let peer_info: DetailedPeerInfo = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+
+        assert_eq!(peer_info.peers.len(), 1);
+        assert_eq!(peer_info.peers[0].address, address);
+        // the peer didn't go through a handshake
+        assert_eq!(peer_info.peers[0].version, None);
+        assert_eq!(peer_info.peers[0].direction, "inbound");
+        assert_eq!(peer_info.peers[0].bytes_sent, 1000);
+        assert_eq!(peer_info.peers[0].bytes_received, 500);
+        assert_eq!(peer_info.peers[0].failures, 1);
+        assert_eq!(peer_info.peers[0].cumulative_failures, 1);
+        assert_eq!(
+            peer_info.peers[0].send_rate,
+            1000 / snarkos_network::THROUGHPUT_WINDOW_SECS
+        );
+        assert_eq!(
+            peer_info.peers[0].recv_rate,
+            500 / snarkos_network::THROUGHPUT_WINDOW_SECS
+        );
+        // no GeoIP database is configured
+        assert_eq!(peer_info.peers[0].country, None);
+        // no blocks are being synced from the peer
+        assert!(!peer_info.peers[0].is_syncing_blocks);
+        assert_eq!(peer_info.peers[0].remaining_sync_blocks, 0);
+
+        node.peer_book.read().expecting_sync_blocks(address, 5);
+
+        let peer_info: DetailedPeerInfo = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+
+        assert!(peer_info.peers[0].is_syncing_blocks);
+        assert_eq!(peer_info.peers[0].remaining_sync_blocks, 5);
+
+        let location = snarkos_network::PeerLocation {
+            country: Some("AQ".into()),
+            asn: Some(64512),
+        };
+        node.peer_book.write().set_location(address, Some(location));
+
+        let peer_info: DetailedPeerInfo = serde_json::from_value(make_request_no_params(&rpc, method)).unwrap();
+
+        assert_eq!(peer_info.peers[0].country, Some("AQ".into()));
+        assert_eq!(peer_info.peers[0].asn, Some(64512));
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_peer_health() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let method = "getpeerhealth".to_string();
+
+        let health: PeerHealth = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+        assert_eq!(health, PeerHealth {
+            peers: 0,
+            min_rtt_ms: None,
+            median_rtt_ms: None,
+            max_rtt_ms: None,
+            total_failures: 0,
+        });
+
+        // Connect peers with RTTs of 300ms, 100ms, 500ms and 200ms.
+        for (i, rtt_ms) in [300u64, 100, 500, 200].iter().enumerate() {
+            let address: SocketAddr = format!("127.0.0.1:{}", 4130 + i).parse().unwrap();
+            node.peer_book
+                .write()
+                .set_connected(address, None, ConnectionDirection::Outbound)
+                .unwrap();
+            let peer_book = node.peer_book.read();
+            let quality = &peer_book.connected_peers()[&address].quality;
+            quality.rtt_ms.store(*rtt_ms, std::sync::atomic::Ordering::SeqCst);
+            peer_book.register_failure(address);
+        }
+
+        let health: PeerHealth = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+        assert_eq!(health, PeerHealth {
+            peers: 4,
+            min_rtt_ms: Some(100),
+            median_rtt_ms: Some(250),
+            max_rtt_ms: Some(500),
+            total_failures: 4,
+        });
+
+        // With an odd number of peers, the median is the middle RTT.
+        let address: SocketAddr = "127.0.0.1:4140".parse().unwrap();
+        node.peer_book
+            .write()
+            .set_connected(address, None, ConnectionDirection::Outbound)
+            .unwrap();
+        node.peer_book.read().connected_peers()[&address]
+            .quality
+            .rtt_ms
+            .store(400, std::sync::atomic::Ordering::SeqCst);
+
+        let health: PeerHealth = serde_json::from_value(make_request_no_params(&rpc, method)).unwrap();
+        assert_eq!(health.median_rtt_ms, Some(300));
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_validate_address() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        let address = "aleo1faksgtpmculyzt6tgaq26fe4fgdjtwualyljjvfn2q6k42ydegzspfz9uh";
+        let validation: AddressValidation = serde_json::from_str(&rpc.request("validateaddress", &[address])).unwrap();
+        assert_eq!(validation.address, address);
+        assert!(validation.is_valid);
+        assert!(validation.address_bytes.is_some());
+
+        // A malformed checksum, a wrong prefix, and garbage are all rejected.
+        let invalid_addresses = [
+            "aleo1faksgtpmculyzt6tgaq26fe4fgdjtwualyljjvfn2q6k42ydegzspfz9uj",
+            "btc1faksgtpmculyzt6tgaq26fe4fgdjtwualyljjvfn2q6k42ydegzspfz9uh",
+            "not an address",
+        ];
+        for address in invalid_addresses.iter() {
+            let validation: AddressValidation =
+                serde_json::from_str(&rpc.request("validateaddress", &[address])).unwrap();
+            assert_eq!(validation, AddressValidation {
+                address: address.to_string(),
+                is_valid: false,
+                address_bytes: None,
+            });
+        }
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_discovery_stats() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let method = "getdiscoverystats".to_string();
+
+        {
+            let mut peer_book = node.peer_book.write();
+            peer_book.register_discovery("127.0.0.1:4130".parse().unwrap(), DiscoverySource::Bootnode);
+            peer_book.add_peer_from("127.0.0.1:4131".parse().unwrap(), DiscoverySource::Gossip);
+            peer_book.add_peer_from("127.0.0.1:4132".parse().unwrap(), DiscoverySource::Gossip);
+            peer_book.add_peer("127.0.0.1:4133".parse().unwrap());
+            for _ in 0..4 {
+                peer_book.register_dial_attempt();
+            }
+            peer_book.register_successful_dial();
+        }
+
+        let stats: DiscoveryStats = serde_json::from_value(make_request_no_params(&rpc, method)).unwrap();
+
+        assert_eq!(stats.bootnode_peers, 1);
+        assert_eq!(stats.gossip_peers, 2);
+        assert_eq!(stats.manual_peers, 1);
+        assert_eq!(stats.dial_attempts, 4);
+        assert_eq!(stats.successful_dials, 1);
+        assert!((stats.success_rate - 0.25).abs() < f64::EPSILON);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_sync_status() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let method = "getsyncstatus".to_string();
+
+        let status: SyncStatus = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+        assert!(!status.is_syncing);
+        assert_eq!(status.efficiency, None);
+
+        let consensus = node.expect_consensus();
+        consensus.register_block_sync_attempt();
+        consensus.register_sync_block(true);
+        consensus.register_sync_block(false);
+        consensus.register_sync_block(false);
+        consensus.register_sync_block(true);
+
+        let status: SyncStatus = serde_json::from_value(make_request_no_params(&rpc, method)).unwrap();
+        assert!(status.is_syncing);
+        assert_eq!(status.new_blocks, 2);
+        assert_eq!(status.wasted_blocks, 2);
+        assert_eq!(status.efficiency, Some(0.5));
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_cache_stats() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let method = "getcachestats".to_string();
+
+        let stats: CacheStats = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+        assert_eq!(stats.memory_pool, CacheStat {
+            entries: 0,
+            size_in_bytes: 0
+        });
+        assert_eq!(stats.requested_blocks.entries, 0);
+        assert_eq!(stats.propagated_blocks.entries, 0);
+        assert_eq!(stats.pending_compact_blocks.entries, 0);
+
+        // Populate the caches.
+        let consensus = node.expect_consensus();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+        };
+        consensus.memory_pool().lock().insert(&storage.read(), entry).unwrap();
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_2 = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+        consensus.register_block_requests(&[block_1.header.get_hash(), block_2.header.get_hash()]);
+        consensus.register_propagated_block(block_1.header.get_hash());
+        consensus.register_pending_compact_block(CompactBlock::new(&block_1).unwrap());
+
+        let stats: CacheStats = serde_json::from_value(make_request_no_params(&rpc, method)).unwrap();
+        assert_eq!(stats.memory_pool, CacheStat {
+            entries: 1,
+            size_in_bytes: TRANSACTION_2.len()
+        });
+        assert_eq!(stats.requested_blocks.entries, 2);
+        assert!(stats.requested_blocks.size_in_bytes > 0);
+        assert_eq!(stats.propagated_blocks.entries, 1);
+        assert!(stats.propagated_blocks.size_in_bytes > 0);
+        assert_eq!(stats.pending_compact_blocks.entries, 1);
+        assert!(stats.pending_compact_blocks.size_in_bytes > 0);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
     #[tokio::test]
     async fn test_rpc_get_node_info() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -335,11 +1303,119 @@ mod rpc_tests {
 
         assert_eq!(peer_info.is_miner, false);
         assert_eq!(peer_info.is_syncing, false);
+        assert_eq!(peer_info.sync_progress, None);
+        // no bootnodes are configured
+        assert_eq!(peer_info.bootnodes_reachable, 0);
 
         drop(rpc);
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
 
+    #[tokio::test]
+    async fn test_rpc_get_node_info_is_syncing() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let get_is_syncing = || {
+            let node_info: NodeInfo =
+                serde_json::from_value(make_request_no_params(&rpc, "getnodeinfo".into())).unwrap();
+            node_info.is_syncing
+        };
+
+        assert!(!get_is_syncing());
+
+        // Blocks requested from peers are in flight.
+        let block_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+        node.expect_consensus().register_block_requests(&[block_hash.clone()]);
+        assert!(get_is_syncing());
+
+        node.expect_consensus().received_requested_block(&block_hash);
+        assert!(!get_is_syncing());
+
+        // A peer advertises a higher block height than the node's.
+        let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
+        node.peer_book
+            .write()
+            .set_connected(address, None, ConnectionDirection::Outbound)
+            .unwrap();
+        node.peer_book.read().register_advertised_height(address, 1);
+        assert!(get_is_syncing());
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_node_info_sync_progress() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        // A peer advertises a chain much longer than the node's.
+        let address: SocketAddr = "127.0.0.1:4130".parse().unwrap();
+        node.peer_book
+            .write()
+            .set_connected(address, None, ConnectionDirection::Outbound)
+            .unwrap();
+        node.peer_book.read().register_advertised_height(address, 100);
+
+        let node_info: NodeInfo = serde_json::from_value(make_request_no_params(&rpc, "getnodeinfo".into())).unwrap();
+        let sync_progress = node_info.sync_progress.unwrap();
+        assert!(sync_progress < 1.0);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_mining_info() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let consensus_setup = ConsensusSetup {
+            is_miner: true,
+            ..Default::default()
+        };
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup).await;
+        let consensus = node.expect_consensus();
+
+        let get_mining_info = || -> MiningInfo {
+            serde_json::from_value(make_request_no_params(&rpc, "getmininginfo".to_string())).unwrap()
+        };
+
+        // The node is a miner, but its miner isn't running yet.
+        let mining_info = get_mining_info();
+        assert!(mining_info.is_miner);
+        assert!(!mining_info.is_mining);
+        assert_eq!(mining_info.template_transactions, 0);
+        assert_eq!(mining_info.blocks_mined, 0);
+
+        // The miner starts, mines a block and has a transaction to include in the next one.
+        consensus.register_miner_started();
+        consensus.register_mined_block();
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction: Tx::read(&TRANSACTION_2[..]).unwrap(),
+        };
+        consensus.memory_pool().lock().insert(&storage.read(), entry).unwrap();
+
+        let mining_info = get_mining_info();
+        assert!(mining_info.is_mining);
+        assert_eq!(mining_info.template_transactions, 1);
+        assert_eq!(mining_info.blocks_mined, 1);
+
+        let template: BlockTemplate =
+            serde_json::from_value(make_request_no_params(&rpc, "getblocktemplate".to_string())).unwrap();
+        assert_eq!(mining_info.difficulty_target, template.difficulty_target);
+
+        // The miner stops.
+        consensus.register_miner_stopped();
+        assert!(!get_mining_info().is_mining);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
     #[tokio::test]
     async fn test_rpc_get_block_template() {
         let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
@@ -368,4 +1444,261 @@ mod rpc_tests {
         drop(rpc);
         kill_storage_sync(unwrap_arc_rwlock(storage));
     }
+
+    #[tokio::test]
+    async fn test_rpc_get_difficulty() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+        let consensus = node.expect_consensus().consensus_parameters().clone();
+
+        // The genesis block is the only one in the chain.
+        let difficulty: u64 =
+            serde_json::from_value(make_request_no_params(&rpc, "getdifficulty".to_string())).unwrap();
+        let genesis_header = storage.read().get_latest_block().unwrap().header;
+        assert_eq!(
+            difficulty,
+            consensus.get_block_difficulty(&genesis_header, Utc::now().timestamp())
+        );
+
+        storage
+            .write()
+            .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+            .unwrap();
+
+        let difficulty: u64 =
+            serde_json::from_value(make_request_no_params(&rpc, "getdifficulty".to_string())).unwrap();
+        let latest_header = storage.read().get_latest_block().unwrap().header;
+        assert_eq!(
+            difficulty,
+            consensus.get_block_difficulty(&latest_header, Utc::now().timestamp())
+        );
+
+        let template: BlockTemplate =
+            serde_json::from_value(make_request_no_params(&rpc, "getblocktemplate".to_string())).unwrap();
+        assert_eq!(difficulty, template.difficulty_target);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_submit_block() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let block_1 = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+        let block_1_hash = block_1.header.get_hash();
+
+        assert_eq!(rpc.request("submitblock", &[hex::encode(BLOCK_1.to_vec())]), format![
+            r#""{}""#,
+            hex::encode(&block_1_hash.0)
+        ]);
+        assert!(node.expect_consensus().storage().block_hash_exists(&block_1_hash));
+        assert_eq!(node.expect_consensus().storage().get_current_block_height(), 1);
+
+        // The same block can't be submitted twice.
+        let request = format!(
+            "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"submitblock\", \"params\": [\"{}\"] }}",
+            hex::encode(BLOCK_1.to_vec())
+        );
+        let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap();
+        assert!(response["error"].is_object());
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_mempool_descendants() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        // The child (TRANSACTION_2) spends the coinbase records of block 1, created by the parent (TRANSACTION_1).
+        let consensus = node.expect_consensus();
+        let mut ids = vec![];
+        for transaction_bytes in &[&TRANSACTION_1, &TRANSACTION_2] {
+            let transaction = Tx::read(&transaction_bytes[..]).unwrap();
+            ids.push(hex::encode(transaction.transaction_id().unwrap()));
+            let entry = Entry {
+                size_in_bytes: transaction_bytes.len(),
+                transaction,
+            };
+            consensus
+                .memory_pool()
+                .lock()
+                .insert(&storage.read(), entry)
+                .unwrap()
+                .unwrap();
+        }
+        let (parent_id, child_id) = (&ids[0], &ids[1]);
+
+        // The spent records are only known once they're registered, as is the case for the node's own transactions.
+        let relatives = |method: &str, transaction_id: &str| -> Vec<String> {
+            serde_json::from_str(&rpc.request(method, &[transaction_id])).unwrap()
+        };
+        assert!(relatives("getmempooldescendants", parent_id).is_empty());
+        assert!(relatives("getmempoolancestors", child_id).is_empty());
+
+        consensus
+            .register_spent_records(&DATA.records_1, &Tx::read(&TRANSACTION_2[..]).unwrap(), &storage.read())
+            .unwrap();
+        assert_eq!(relatives("getmempooldescendants", parent_id), vec![child_id.clone()]);
+        assert_eq!(relatives("getmempoolancestors", child_id), vec![parent_id.clone()]);
+        assert!(relatives("getmempoolancestors", parent_id).is_empty());
+        assert!(relatives("getmempooldescendants", child_id).is_empty());
+
+        // A transaction that isn't in the memory pool is rejected.
+        for method in &["getmempoolancestors", "getmempooldescendants"] {
+            let request = format!(
+                "{{ \"jsonrpc\":\"2.0\", \"id\": 1, \"method\": \"{}\", \"params\": [\"{}\"] }}",
+                method,
+                hex::encode([0u8; 32])
+            );
+            let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(&request).unwrap()).unwrap();
+            assert!(response["error"].is_object());
+        }
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_mempool_fee_histogram() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        // An empty memory pool has no fee buckets.
+        let histogram: Vec<(u64, usize)> =
+            serde_json::from_value(make_request_no_params(&rpc, "getmempoolfeehistogram".to_string())).unwrap();
+        assert!(histogram.is_empty());
+
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let fee = transaction.value_balance().0.max(0) as u64;
+        let entry = Entry {
+            size_in_bytes: TRANSACTION_2.len(),
+            transaction,
+        };
+        node.expect_consensus()
+            .memory_pool()
+            .lock()
+            .insert(&storage.read(), entry)
+            .unwrap();
+
+        let bucket = *MEMPOOL_FEE_HISTOGRAM_BUCKETS
+            .iter()
+            .rev()
+            .find(|&&bound| bound <= fee)
+            .unwrap();
+        let histogram: Vec<(u64, usize)> =
+            serde_json::from_value(make_request_no_params(&rpc, "getmempoolfeehistogram".to_string())).unwrap();
+        assert_eq!(histogram, vec![(bucket, 1)]);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_block_template_respects_size_target() {
+        let transaction = Tx::read(&TRANSACTION_2[..]).unwrap();
+        let transaction_string = hex::encode(TRANSACTION_2.to_vec());
+
+        for (block_size_target, is_included) in vec![(None, true), (Some(TRANSACTION_2.len()), false)] {
+            let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+            let consensus_setup = ConsensusSetup {
+                block_size_target,
+                ..Default::default()
+            };
+            let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), consensus_setup).await;
+
+            let entry = Entry {
+                size_in_bytes: TRANSACTION_2.len(),
+                transaction: transaction.clone(),
+            };
+            node.expect_consensus()
+                .memory_pool()
+                .lock()
+                .insert(&storage.read(), entry)
+                .unwrap();
+
+            let result = make_request_no_params(&rpc, "getblocktemplate".to_string());
+            let template: BlockTemplate = serde_json::from_value(result).unwrap();
+
+            assert_eq!(template.transactions.contains(&transaction_string), is_included);
+
+            drop(rpc);
+            drop(node);
+            kill_storage_sync(unwrap_arc_rwlock(storage));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_propagation_stats() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let (rpc, node) = initialize_test_rpc_and_node(storage.clone(), ConsensusSetup::default()).await;
+
+        let method = "getpropagationstats".to_string();
+
+        let stats: PropagationStats = serde_json::from_value(make_request_no_params(&rpc, method.clone())).unwrap();
+        assert_eq!(stats.samples, 0);
+        assert_eq!(stats.mean_ms, 0);
+
+        // Register blocks received 100ms, 200ms, ..., 2000ms after they were mined.
+        let consensus = node.expect_consensus();
+        for delta in (1..=20u64).rev() {
+            consensus.register_block_propagation(Duration::from_millis(delta * 100));
+        }
+
+        let stats: PropagationStats = serde_json::from_value(make_request_no_params(&rpc, method)).unwrap();
+
+        assert_eq!(stats.samples, 20);
+        assert_eq!(stats.mean_ms, 1050);
+        assert_eq!(stats.median_ms, 1050);
+        assert_eq!(stats.p95_ms, 1900);
+
+        drop(rpc);
+        drop(node);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_get_header_chain() {
+        let storage = Arc::new(RwLock::new(FIXTURE_VK.ledger()));
+        let rpc = initialize_test_rpc(storage.clone()).await;
+
+        {
+            let storage = storage.read();
+            storage
+                .insert_and_commit(&Block::<Tx>::read(&BLOCK_1[..]).unwrap())
+                .unwrap();
+            storage
+                .insert_and_commit(&Block::<Tx>::read(&BLOCK_2[..]).unwrap())
+                .unwrap();
+        }
+
+        // Request more headers than there are blocks; the range is capped at the chain tip.
+        let request = r#"{ "jsonrpc":"2.0", "id": 1, "method": "getheaderchain", "params": [0, 10] }"#;
+        let response: Value = serde_json::from_str(&rpc.io.handle_request_sync(request).unwrap()).unwrap();
+        let header_chain = hex::decode(response["result"].as_str().unwrap()).unwrap();
+
+        // Parse the stream sequentially.
+        let mut reader = &header_chain[..];
+        let mut headers = vec![];
+        while !reader.is_empty() {
+            headers.push(BlockHeader::read(&mut reader).unwrap());
+        }
+
+        assert_eq!(headers.len(), 3);
+        for (height, header) in headers.iter().enumerate() {
+            let storage = storage.read();
+            let block_hash = storage.get_block_hash(height as u32).unwrap();
+            assert_eq!(*header, storage.get_block_header(&block_hash).unwrap());
+        }
+
+        drop(rpc);
+        kill_storage_sync(unwrap_arc_rwlock(storage));
+    }
 }
@@ -21,6 +21,16 @@ use crate::{
     update::UpdateCLI,
 };
 
+use snarkos_consensus::{memory_pool::DEFAULT_MAX_MEMORY_POOL_SIZE, SelectionPolicy, MAX_FUTURE_BLOCK_TIME};
+use snarkos_network::{
+    LISTEN_BACKLOG,
+    MAX_CONCURRENT_TRANSACTION_VERIFICATIONS,
+    MAX_DIAL_ADDRESSES,
+    MAX_MESSAGE_SIZE,
+    SHARED_PEER_COUNT,
+};
+use snarkos_rpc::MAX_BLOCK_RANGE;
+
 use clap::ArgMatches;
 use dirs::home_dir;
 use serde::{Deserialize, Serialize};
@@ -67,6 +77,29 @@ pub struct JsonRPC {
     pub port: u16,
     pub username: Option<String>,
     pub password: Option<String>,
+    /// If set, guarded endpoints also accept this static token in an `Authorization: Bearer` header.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// If set, the secondary storage used by the RPC is caught up with the primary one in the
+    /// background at this interval in milliseconds, instead of on every read.
+    #[serde(default)]
+    pub catch_up_interval: Option<u64>,
+    /// If set, `getblock` serves blocks from the last synced state of the secondary storage used by the RPC
+    /// when it fails to catch up with the primary one, flagging them as stale, instead of failing; the other
+    /// endpoints still fail.
+    #[serde(default)]
+    pub tolerate_stale_reads: bool,
+    /// If set, the secondary storage used by the RPC catches up with the primary one on reads at most
+    /// once per this interval in milliseconds; it has no effect if `catch_up_interval` is set.
+    #[serde(default)]
+    pub catch_up_throttle: Option<u64>,
+    /// The maximum number of blocks returned by a single request for a range of blocks.
+    #[serde(default = "default_max_block_range")]
+    pub max_block_range: u32,
+}
+
+fn default_max_block_range() -> u32 {
+    MAX_BLOCK_RANGE
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -77,12 +110,32 @@ pub struct Node {
     pub ip: String,
     pub port: u16,
     pub verbose: u8,
+    /// The maximum number of seconds the timestamp of a received block may be ahead of the local
+    /// time; blocks further into the future are rejected and their senders penalized.
+    #[serde(default = "default_max_future_block_time")]
+    pub max_future_block_time: i64,
+}
+
+fn default_max_future_block_time() -> i64 {
+    MAX_FUTURE_BLOCK_TIME
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Miner {
     pub is_miner: bool,
+    /// The address receiving the coinbase rewards; it can be changed at runtime over RPC.
     pub miner_address: String,
+    /// The soft target for the size of mined blocks in bytes; defaults to the maximum block size.
+    #[serde(default)]
+    pub block_size_target: Option<usize>,
+    /// The policy for selecting the memory pool transactions included in mined blocks; one of
+    /// `highest_fee` (the default), `fifo` or `dependency_ordered`.
+    #[serde(default = "default_selection_policy")]
+    pub selection_policy: String,
+}
+
+fn default_selection_policy() -> String {
+    SelectionPolicy::default().name().into()
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -94,6 +147,80 @@ pub struct P2P {
     pub peer_sync_interval: u16,
     pub min_peers: u16,
     pub max_peers: u16,
+    /// The hex-encoded program commitments permitted in the memory pool; if empty, all are permitted.
+    #[serde(default)]
+    pub authorized_program_commitments: Vec<String>,
+    /// The maximum size of the memory pool in bytes.
+    #[serde(default = "default_mempool_max_size")]
+    pub mempool_max_size: usize,
+    /// The geographic regions or autonomous systems (ASNs) whose peers are preferred.
+    #[serde(default)]
+    pub preferred_regions: Vec<String>,
+    /// The path to a MaxMind (e.g. GeoLite2) database used to resolve the country and ASN of peers;
+    /// it's only used if snarkOS is built with the `geoip` feature.
+    #[serde(default)]
+    pub geoip_database: Option<PathBuf>,
+    /// The maximum number of connected peers from a single autonomous system; it's only enforced if
+    /// the ASNs of peers can be resolved using the `geoip_database`.
+    #[serde(default)]
+    pub max_peers_per_asn: Option<usize>,
+    /// The addresses of the peers that are never evicted or banned, and are dialed first.
+    #[serde(default)]
+    pub allowlisted_peers: Vec<String>,
+    /// The minimum fee (value balance) a transaction must pay to be accepted into the memory pool.
+    #[serde(default)]
+    pub min_relay_fee: Option<i64>,
+    /// If set, transactions known to spend the coinbase outputs of one of this many most recent blocks
+    /// are rejected as possible fee-sniping constructions; a heuristic.
+    #[serde(default)]
+    pub fee_sniping_depth: Option<u32>,
+    /// The maximum number of transactions received from peers that are verified concurrently.
+    #[serde(default = "default_max_concurrent_transaction_verifications")]
+    pub max_concurrent_transaction_verifications: usize,
+    /// The maximum size of a message accepted from a peer in bytes.
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
+    /// The maximum number of peer addresses shared in response to a request for peers.
+    #[serde(default = "default_max_peers_in_response")]
+    pub max_peers_in_response: usize,
+    /// The maximum number of known addresses of a single peer dialed before giving up on it.
+    #[serde(default = "default_max_dial_addresses")]
+    pub max_dial_addresses: usize,
+    /// If set, peers whose moving average RTT exceeds this many milliseconds over several consecutive
+    /// measurements are disconnected from.
+    #[serde(default)]
+    pub max_peer_rtt_ms: Option<u64>,
+    /// The maximum number of inbound connections waiting to be accepted.
+    #[serde(default = "default_listen_backlog")]
+    pub listen_backlog: u32,
+    /// If set, at most this many inbound connections are accepted per second; during a connection flood,
+    /// the excess ones wait in the listen backlog.
+    #[serde(default)]
+    pub max_inbound_connections_per_sec: Option<u32>,
+}
+
+fn default_mempool_max_size() -> usize {
+    DEFAULT_MAX_MEMORY_POOL_SIZE
+}
+
+fn default_max_concurrent_transaction_verifications() -> usize {
+    MAX_CONCURRENT_TRANSACTION_VERIFICATIONS
+}
+
+fn default_max_message_size() -> usize {
+    MAX_MESSAGE_SIZE
+}
+
+fn default_max_peers_in_response() -> usize {
+    SHARED_PEER_COUNT
+}
+
+fn default_max_dial_addresses() -> usize {
+    MAX_DIAL_ADDRESSES
+}
+
+fn default_listen_backlog() -> u32 {
+    LISTEN_BACKLOG
 }
 
 impl Default for Config {
@@ -107,10 +234,13 @@ impl Default for Config {
                 ip: "0.0.0.0".into(),
                 port: 4131,
                 verbose: 3,
+                max_future_block_time: default_max_future_block_time(),
             },
             miner: Miner {
                 is_miner: false,
                 miner_address: "".into(),
+                block_size_target: None,
+                selection_policy: default_selection_policy(),
             },
             rpc: JsonRPC {
                 json_rpc: true,
@@ -118,6 +248,11 @@ impl Default for Config {
                 // TODO (raychu86) Establish a random username and password for the node operator by default
                 username: Some("Username".into()),
                 password: Some("Password".into()),
+                token: None,
+                catch_up_interval: None,
+                tolerate_stale_reads: false,
+                catch_up_throttle: None,
+                max_block_range: MAX_BLOCK_RANGE,
             },
             p2p: P2P {
                 bootnodes: TESTNET_BOOTNODES
@@ -129,6 +264,21 @@ impl Default for Config {
                 block_sync_interval: 10,
                 min_peers: 7,
                 max_peers: 25,
+                authorized_program_commitments: vec![],
+                mempool_max_size: DEFAULT_MAX_MEMORY_POOL_SIZE,
+                preferred_regions: vec![],
+                geoip_database: None,
+                max_peers_per_asn: None,
+                allowlisted_peers: vec![],
+                min_relay_fee: None,
+                fee_sniping_depth: None,
+                max_concurrent_transaction_verifications: MAX_CONCURRENT_TRANSACTION_VERIFICATIONS,
+                max_message_size: MAX_MESSAGE_SIZE,
+                max_peers_in_response: SHARED_PEER_COUNT,
+                max_dial_addresses: MAX_DIAL_ADDRESSES,
+                max_peer_rtt_ms: None,
+                listen_backlog: LISTEN_BACKLOG,
+                max_inbound_connections_per_sec: None,
             },
         }
     }
@@ -201,6 +351,7 @@ impl Config {
             "rpc-port" => self.rpc_port(clap::value_t!(arguments.value_of(*option), u16).ok()),
             "rpc-username" => self.rpc_username(arguments.value_of(option)),
             "rpc-password" => self.rpc_password(arguments.value_of(option)),
+            "rpc-token" => self.rpc_token(arguments.value_of(option)),
             "verbose" => self.verbose(clap::value_t!(arguments.value_of(*option), u8).ok()),
             _ => (),
         });
@@ -315,6 +466,12 @@ impl Config {
         }
     }
 
+    fn rpc_token(&mut self, argument: Option<&str>) {
+        if let Some(token) = argument {
+            self.rpc.token = Some(token.to_string());
+        }
+    }
+
     fn verbose(&mut self, argument: Option<u8>) {
         if let Some(verbose) = argument {
             self.node.verbose = verbose
@@ -364,6 +521,7 @@ impl CLI for ConfigCli {
         option::RPC_PORT,
         option::RPC_USERNAME,
         option::RPC_PASSWORD,
+        option::RPC_TOKEN,
         option::VERBOSE,
     ];
     const SUBCOMMANDS: &'static [SubCommandType] = &[subcommand::UPDATE];
@@ -387,6 +545,7 @@ impl CLI for ConfigCli {
             "rpc-port",
             "rpc-username",
             "rpc-password",
+            "rpc-token",
             "verbose",
         ]);
 
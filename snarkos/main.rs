@@ -26,7 +26,7 @@ use snarkos::{
 };
 use snarkos_consensus::{ConsensusParameters, MemoryPool, MerkleTreeLedger};
 use snarkos_network::{environment::Environment, Consensus, Node};
-use snarkos_rpc::start_rpc_server;
+use snarkos_rpc::{start_rpc_server, CatchUpMode};
 use snarkvm_algorithms::{CRH, SNARK};
 use snarkvm_dpc::{
     base_dpc::{instantiated::Components, parameters::PublicParameters, BaseDPCComponents},
@@ -101,16 +101,37 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
         // Set sync intervals for peers, blocks and transactions (memory pool).
         Duration::from_secs(config.p2p.peer_sync_interval.into()),
     )?;
+    environment.set_preferred_regions(config.p2p.preferred_regions.clone());
+    environment.set_geoip_database(config.p2p.geoip_database.clone());
+    environment.set_max_message_size(config.p2p.max_message_size);
+    environment.set_max_peers_in_response(config.p2p.max_peers_in_response);
+    environment.set_max_dial_addresses(config.p2p.max_dial_addresses);
+    environment.set_max_peer_rtt(config.p2p.max_peer_rtt_ms.map(Duration::from_millis));
+    environment.set_listen_backlog(config.p2p.listen_backlog);
+    environment.set_max_inbound_connections_per_sec(config.p2p.max_inbound_connections_per_sec);
 
     // Construct the node instance. Note this does not start the network services.
     // This is done early on, so that the local address can be discovered
     // before any other object (miner, RPC) needs to use it.
     let mut node = Node::new(environment.clone()).await?;
 
+    node.peer_book
+        .write()
+        .set_max_peers_per_asn(config.p2p.max_peers_per_asn);
+
+    for peer in &config.p2p.allowlisted_peers {
+        match peer.parse() {
+            Ok(addr) => node.peer_book.write().allowlist_peer(addr),
+            Err(_) => warn!("Ignoring the invalid allowlisted peer address {}", peer),
+        }
+    }
+
     // Enable the consensus layer if the node is not a bootstrapper.
     if !config.node.is_bootnode {
         let storage = Arc::new(MerkleTreeLedger::open_at_path(path.clone())?);
-        let memory_pool = Arc::new(Mutex::new(MemoryPool::from_storage(&storage)?));
+        let memory_pool = Arc::new(Mutex::new(
+            MemoryPool::load(&storage)?.with_max_size(config.p2p.mempool_max_size),
+        ));
 
         info!("Loading Aleo parameters...");
         let dpc_parameters = Arc::new(PublicParameters::<Components>::load(!config.miner.is_miner)?);
@@ -127,15 +148,27 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
         let authorized_inner_snark_ids = vec![to_bytes![inner_snark_id]?];
 
         // Set the initial consensus parameters.
+        let max_block_size = 1_000_000_000usize;
         let consensus_params = Arc::new(ConsensusParameters {
-            max_block_size: 1_000_000_000usize,
+            max_block_size,
+            block_size_target: config.miner.block_size_target.unwrap_or(max_block_size),
+            selection_policy: config.miner.selection_policy.parse()?,
             max_nonce: u32::max_value(),
             target_block_time: 10i64,
+            max_future_block_time: config.node.max_future_block_time,
             network_id: Network::from_network_id(config.aleo.network_id),
             verifier: PoswMarlin::verify_only().expect("could not instantiate PoSW verifier"),
             authorized_inner_snark_ids,
         });
 
+        // Fetch the set of program commitments permitted in the memory pool.
+        let authorized_program_commitments = config
+            .p2p
+            .authorized_program_commitments
+            .iter()
+            .map(hex::decode)
+            .collect::<Result<Vec<_>, _>>()?;
+
         let consensus = Consensus::new(
             node.clone(),
             storage,
@@ -145,6 +178,10 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
             config.miner.is_miner,
             Duration::from_secs(config.p2p.block_sync_interval.into()),
             Duration::from_secs(config.p2p.mempool_interval.into()),
+            authorized_program_commitments,
+            config.p2p.min_relay_fee,
+            config.p2p.fee_sniping_depth,
+            config.p2p.max_concurrent_transaction_verifications,
         );
 
         node.set_consensus(consensus);
@@ -171,6 +208,12 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
         // Open a secondary storage instance to prevent resource sharing and bottle-necking.
         let secondary_storage = Arc::new(RwLock::new(MerkleTreeLedger::open_secondary_at_path(path.clone())?));
 
+        let catch_up_mode = match config.rpc.catch_up_interval {
+            Some(interval) => CatchUpMode::Lazy(Duration::from_millis(interval)),
+            None if config.rpc.tolerate_stale_reads => CatchUpMode::Tolerant,
+            None => CatchUpMode::Eager,
+        };
+
         start_rpc_server(
             config.rpc.port,
             secondary_storage,
@@ -179,6 +222,10 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
             node.clone(),
             config.rpc.username,
             config.rpc.password,
+            config.rpc.token,
+            catch_up_mode,
+            config.rpc.catch_up_throttle.map(Duration::from_millis),
+            config.rpc.max_block_range,
         )
         .await;
     }
@@ -186,7 +233,13 @@ async fn start_server(config: Config) -> anyhow::Result<()> {
     // Start the network services
     node.start_services().await;
 
-    std::future::pending::<()>().await;
+    // Run until the node is interrupted, then persist the memory pool before exiting.
+    tokio::signal::ctrl_c().await?;
+    info!("Shutting down...");
+
+    if let Some(consensus) = node.consensus() {
+        consensus.store_memory_pool()?;
+    }
 
     Ok(())
 }
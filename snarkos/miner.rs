@@ -43,23 +43,45 @@ impl MinerInstance {
     /// Spawns a new miner on a new thread using MinerInstance parameters.
     /// Once a block is found, A block message is sent to all peers.
     /// Calling this function multiple times will spawn additional listeners on separate threads.
-    /// Miner threads are asynchronous so the only way to stop them is to kill the runtime they were started in. This may be changed in the future.
-    pub fn spawn(self) {
+    /// Miner tasks exit once `Consensus::stop_mining` is called and the block they're currently mining is done.
+    pub fn spawn(self) -> task::JoinHandle<()> {
         task::spawn(async move {
             let local_address = self.environment.local_address().unwrap();
             info!("Initializing Aleo miner - Your miner address is {}", self.miner_address);
-            let miner = Miner::new(
+            let mut miner = Miner::new(
                 self.miner_address.clone(),
                 Arc::clone(self.node.expect_consensus().consensus_parameters()),
             );
             info!("Miner instantiated; starting to mine blocks");
 
+            // The configured address receives the coinbase rewards until it's changed over RPC.
+            if self.node.expect_consensus().coinbase_address().is_none() {
+                self.node
+                    .expect_consensus()
+                    .set_coinbase_address(self.miner_address.clone());
+            }
+
             let mut mining_failure_count = 0;
             let mining_failure_threshold = 10;
 
+            self.node.expect_consensus().register_miner_started();
+
             loop {
-                info!("Starting to mine the next block");
                 let consensus = self.node.expect_consensus();
+                if consensus.is_mining_stopped() {
+                    info!("Mining was stopped; shutting down miner");
+                    break;
+                }
+
+                // A changed coinbase address only applies to the blocks mined from now on.
+                if let Some(coinbase_address) = consensus.coinbase_address() {
+                    if &coinbase_address != miner.address() {
+                        info!("The coinbase rewards now go to {}", coinbase_address);
+                        miner.set_address(coinbase_address);
+                    }
+                }
+
+                info!("Starting to mine the next block");
 
                 let (block, _coinbase_records) = match miner
                     .mine_block(consensus.dpc_parameters(), consensus.storage(), consensus.memory_pool())
@@ -86,6 +108,7 @@ impl MinerInstance {
                 };
 
                 info!("Mined a new block: {:?}", hex::encode(block.header.get_hash().0));
+                consensus.register_mined_block();
                 let peers = self.node.peer_book.read().connected_peers().clone();
                 let serialized_block = if let Ok(block) = block.serialize() {
                     block
@@ -99,6 +122,8 @@ impl MinerInstance {
                     .propagate_block(serialized_block, local_address, &peers)
                     .await;
             }
-        });
+
+            self.node.expect_consensus().register_miner_stopped();
+        })
     }
 }
@@ -100,6 +100,13 @@ pub const RPC_PASSWORD: OptionType = (
     &["rpc-username"],
 );
 
+pub const RPC_TOKEN: OptionType = (
+    "[rpc-token] --rpc-token=[rpc-token] 'Specify a bearer token for rpc authentication'",
+    &["no-jsonrpc"],
+    &[],
+    &[],
+);
+
 pub const VERBOSE: OptionType = (
     "[verbose] --verbose=[verbose] 'Specify the verbosity (default = 1) of the node'",
     &[],
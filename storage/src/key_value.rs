@@ -34,6 +34,7 @@ pub const NUM_COLS: u32 = 11;
 pub const KEY_BEST_BLOCK_NUMBER: &str = "BEST_BLOCK_NUMBER";
 pub const KEY_MEMORY_POOL: &str = "MEMORY_POOL";
 pub const KEY_PEER_BOOK: &str = "PEER_BOOK";
+pub const KEY_CHAIN_WORK: &str = "CHAIN_WORK"; // followed by the block hash
 
 pub const KEY_CURR_CM_INDEX: &str = "CURRENT_CM_INDEX";
 pub const KEY_CURR_SN_INDEX: &str = "CURRENT_SN_INDEX";
@@ -0,0 +1,76 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{error::StorageError, *};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_objects::{BlockHeader, BlockHeaderHash, Transaction};
+
+use std::convert::TryInto;
+
+/// Returns the work represented by a block with the given difficulty target, i.e. the expected number
+/// of proofs that need to be computed to find one whose hash doesn't exceed the target.
+pub fn block_work(difficulty_target: u64) -> u128 {
+    (1u128 << 64) / (difficulty_target as u128 + 1)
+}
+
+/// Returns the key under which the total work of the chain ending at the given block is stored.
+pub(crate) fn chain_work_key(block_hash: &BlockHeaderHash) -> Vec<u8> {
+    [KEY_CHAIN_WORK.as_bytes(), &block_hash.0[..]].concat()
+}
+
+impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+    /// Returns the total work of the chain ending at the given block, including the genesis block.
+    ///
+    /// The work is stored when a block is committed and kept if it's decommitted later; for the blocks
+    /// it wasn't stored for (e.g. the ones that were never canon), it's computed from their ancestors.
+    pub fn get_chain_work(&self, block_hash: &BlockHeaderHash) -> Result<u128, StorageError> {
+        let mut uncounted_work = 0;
+        let mut block_hash = block_hash.clone();
+
+        loop {
+            if let Some(chain_work) = self.storage.get(COL_META, &chain_work_key(&block_hash))? {
+                let chain_work = chain_work[..]
+                    .try_into()
+                    .map_err(|_| StorageError::Message("invalid chain work".into()))?;
+
+                return Ok(uncounted_work + u128::from_le_bytes(chain_work));
+            }
+
+            let block_header = self.get_block_header(&block_hash)?;
+            uncounted_work += block_work(block_header.difficulty_target);
+
+            if block_header.previous_block_hash == BlockHeaderHash([0u8; 32]) {
+                return Ok(uncounted_work);
+            }
+            block_hash = block_header.previous_block_hash;
+        }
+    }
+
+    /// Returns the total work of the chain ending at a block with the given header.
+    pub(crate) fn header_chain_work(&self, block_header: &BlockHeader) -> Result<u128, StorageError> {
+        let mut chain_work = block_work(block_header.difficulty_target);
+        if block_header.previous_block_hash != BlockHeaderHash([0u8; 32]) {
+            chain_work += self.get_chain_work(&block_header.previous_block_hash)?;
+        }
+
+        Ok(chain_work)
+    }
+
+    /// Returns the total work of the canon chain.
+    pub fn get_best_chain_work(&self) -> Result<u128, StorageError> {
+        self.get_chain_work(&self.get_block_hash(self.get_current_block_height())?)
+    }
+}
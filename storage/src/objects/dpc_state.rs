@@ -67,6 +67,14 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         Ok(digests)
     }
 
+    /// Get the height of the block that resulted in the given ledger digest, if it's known.
+    pub fn get_digest_block_height(&self, digest_bytes: &[u8]) -> Result<Option<BlockHeight>, StorageError> {
+        match self.storage.get(COL_DIGEST, digest_bytes)? {
+            Some(block_height_bytes) => Ok(Some(bytes_to_u32(block_height_bytes))),
+            None => Ok(None),
+        }
+    }
+
     /// Get serial number index.
     pub fn get_sn_index(&self, sn_bytes: &[u8]) -> Result<Option<usize>, StorageError> {
         match self.storage.get(COL_SERIAL_NUMBER, sn_bytes)? {
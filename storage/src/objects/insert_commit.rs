@@ -256,6 +256,14 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
             value: new_best_block_number.to_le_bytes().to_vec(),
         });
 
+        // Record the total work of the chain ending at the block
+
+        database_transaction.push(Op::Insert {
+            col: COL_META,
+            key: chain_work_key(&block_header_hash),
+            value: self.header_chain_work(&block.header)?.to_le_bytes().to_vec(),
+        });
+
         // Update the block location
 
         database_transaction.push(Op::Insert {
@@ -318,15 +326,14 @@ impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
         self.is_canon(&block_header.previous_block_hash)
     }
 
-    /// Revert the chain to the state before the fork.
+    /// Revert the chain to the state before the fork; the caller is responsible
+    /// for determining that the side chain is superior to the canon chain.
     pub fn revert_for_fork(&self, side_chain_path: &SideChainPath) -> Result<(), StorageError> {
         let current_block_height = self.get_current_block_height();
 
-        if side_chain_path.new_block_number > current_block_height {
-            // Decommit all blocks on canon chain up to the shared block number with the side chain.
-            for _ in (side_chain_path.shared_block_number)..current_block_height {
-                self.decommit_latest_block()?;
-            }
+        // Decommit all blocks on canon chain up to the shared block number with the side chain.
+        for _ in (side_chain_path.shared_block_number)..current_block_height {
+            self.decommit_latest_block()?;
         }
 
         Ok(())
@@ -24,6 +24,9 @@ pub use block_header::*;
 pub mod block_path;
 pub use block_path::*;
 
+pub mod chain_work;
+pub use chain_work::*;
+
 pub mod dpc_state;
 pub use dpc_state::*;
 
@@ -39,5 +42,8 @@ pub use memory_pool::*;
 pub mod records;
 pub use records::*;
 
+pub mod snapshot;
+pub use snapshot::*;
+
 pub mod transaction;
 pub use transaction::*;
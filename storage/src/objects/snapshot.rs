@@ -0,0 +1,111 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{error::StorageError, *};
+use snarkvm_algorithms::traits::LoadableMerkleParameters;
+use snarkvm_objects::{Block, BlockHeaderHash, Transaction};
+use snarkvm_utilities::{to_bytes, FromBytes, ToBytes};
+
+use std::io::{Read, Write};
+
+/// The bytes that every ledger snapshot starts with.
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"SNARKSNP";
+
+/// A snapshot consists of the magic bytes, the height and hash of its tip, followed by every canon
+/// block above the genesis one, each prefixed with its length as a little-endian `u32`.
+impl<T: Transaction, P: LoadableMerkleParameters> Ledger<T, P> {
+    /// Writes a snapshot of the canon chain up to the current block height and returns its height.
+    ///
+    /// The canon chain is verified to be linked while it is being written, so that a snapshot taken
+    /// from a secondary instance that caught up mid-reorg is rejected instead of being inconsistent.
+    pub fn export_snapshot<W: Write>(&self, mut writer: W) -> Result<u32, StorageError> {
+        let tip_height = self.get_current_block_height();
+        let tip_hash = self.get_block_hash(tip_height)?;
+
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&tip_height.to_le_bytes())?;
+        writer.write_all(&tip_hash.0)?;
+
+        let mut previous_hash = self.get_block_hash(0)?;
+        for block_number in 1..=tip_height {
+            let block = self.get_block_from_block_number(block_number)?;
+
+            if block.header.previous_block_hash != previous_hash {
+                return Err(StorageError::Message(format!(
+                    "the canon chain is not linked at height {}; a reorg may be in progress",
+                    block_number
+                )));
+            }
+            previous_hash = block.header.get_hash();
+
+            let block_bytes = to_bytes![block]?;
+            writer.write_all(&(block_bytes.len() as u32).to_le_bytes())?;
+            writer.write_all(&block_bytes)?;
+        }
+
+        if previous_hash != tip_hash {
+            return Err(StorageError::Message(
+                "the canon chain changed while the snapshot was written".into(),
+            ));
+        }
+
+        writer.flush()?;
+
+        Ok(tip_height)
+    }
+
+    /// Reads a snapshot written by `export_snapshot` and commits its blocks, returning its height.
+    ///
+    /// This is meant for offline use (e.g. bootstrapping a fresh ledger with the same genesis block
+    /// before the node is started); the blocks are trusted and aren't verified by consensus.
+    pub fn import_snapshot<R: Read>(&self, mut reader: R) -> Result<u32, StorageError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if magic != SNAPSHOT_MAGIC {
+            return Err(StorageError::Message("the file is not a ledger snapshot".into()));
+        }
+
+        let mut height_bytes = [0u8; 4];
+        reader.read_exact(&mut height_bytes)?;
+        let tip_height = u32::from_le_bytes(height_bytes);
+
+        let mut tip_hash = [0u8; 32];
+        reader.read_exact(&mut tip_hash)?;
+        let tip_hash = BlockHeaderHash::new(tip_hash.to_vec());
+
+        for _ in 1..=tip_height {
+            let mut length_bytes = [0u8; 4];
+            reader.read_exact(&mut length_bytes)?;
+
+            let mut block_bytes = vec![0u8; u32::from_le_bytes(length_bytes) as usize];
+            reader.read_exact(&mut block_bytes)?;
+            let block = Block::<T>::read(&block_bytes[..])?;
+
+            // Blocks that are already canon (e.g. from an earlier, interrupted import) are skipped.
+            if !self.is_canon(&block.header.get_hash()) {
+                self.insert_and_commit(&block)?;
+            }
+        }
+
+        if self.get_current_block_height() != tip_height || self.get_block_hash(tip_height)? != tip_hash {
+            return Err(StorageError::Message(
+                "the imported chain doesn't match the tip of the snapshot".into(),
+            ));
+        }
+
+        Ok(tip_height)
+    }
+}
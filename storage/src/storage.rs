@@ -138,6 +138,16 @@ impl Storage {
         }
     }
 
+    /// Compacts every column family, discarding the space taken up by deleted and overwritten values.
+    /// This can take a while on large databases, but it doesn't block reads or writes.
+    pub fn compact(&self) {
+        for cf_name in &self.cf_names {
+            if let Some(cf) = self.db.cf_handle(cf_name) {
+                self.db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+            }
+        }
+    }
+
     /// Returns `Ok(())` after destroying the storage
     /// If RocksDB fails to destroy storage, returns [StorageError](snarkvm_errors::storage::StorageError).
     pub fn destroy(&self) -> Result<(), StorageError> {
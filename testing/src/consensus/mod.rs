@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use snarkos_consensus::ConsensusParameters;
+use snarkos_consensus::{ConsensusParameters, SelectionPolicy, MAX_FUTURE_BLOCK_TIME};
 use snarkvm_algorithms::CRH;
 use snarkvm_dpc::{instantiated::Components, DPCComponents};
 use snarkvm_objects::{Network, Transaction, TransactionError};
@@ -46,8 +46,11 @@ pub static TEST_CONSENSUS: Lazy<ConsensusParameters> = Lazy::new(|| {
 
     ConsensusParameters {
         max_block_size: 1_000_000usize,
+        block_size_target: 1_000_000usize,
+        selection_policy: SelectionPolicy::default(),
         max_nonce: u32::max_value(),
         target_block_time: 2i64, //unix seconds
+        max_future_block_time: MAX_FUTURE_BLOCK_TIME,
         network_id: Network::Mainnet,
         verifier: PoswMarlin::verify_only().unwrap(),
         authorized_inner_snark_ids: vec![inner_snark_id],
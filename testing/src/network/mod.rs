@@ -20,6 +20,9 @@ pub use blocks::*;
 #[cfg(test)]
 pub mod encryption;
 
+#[cfg(test)]
+pub mod payload_fuzzing;
+
 #[cfg(test)]
 pub mod sync;
 
@@ -74,6 +77,12 @@ pub struct ConsensusSetup {
     pub is_miner: bool,
     pub block_sync_interval: u64,
     pub tx_sync_interval: u64,
+    pub authorized_program_commitments: Vec<Vec<u8>>,
+    pub min_relay_fee: Option<i64>,
+    pub fee_sniping_depth: Option<u32>,
+    pub block_size_target: Option<usize>,
+    pub max_future_block_time: Option<i64>,
+    pub max_concurrent_transaction_verifications: usize,
 }
 
 impl ConsensusSetup {
@@ -82,6 +91,12 @@ impl ConsensusSetup {
             is_miner,
             block_sync_interval,
             tx_sync_interval,
+            authorized_program_commitments: vec![],
+            min_relay_fee: None,
+            fee_sniping_depth: None,
+            block_size_target: None,
+            max_future_block_time: None,
+            max_concurrent_transaction_verifications: snarkos_network::MAX_CONCURRENT_TRANSACTION_VERIFICATIONS,
         }
     }
 }
@@ -92,6 +107,12 @@ impl Default for ConsensusSetup {
             is_miner: false,
             block_sync_interval: 600,
             tx_sync_interval: 600,
+            authorized_program_commitments: vec![],
+            min_relay_fee: None,
+            fee_sniping_depth: None,
+            block_size_target: None,
+            max_future_block_time: None,
+            max_concurrent_transaction_verifications: snarkos_network::MAX_CONCURRENT_TRANSACTION_VERIFICATIONS,
         }
     }
 }
@@ -105,6 +126,10 @@ pub struct TestSetup {
     pub max_peers: u16,
     pub is_bootnode: bool,
     pub bootnodes: Vec<String>,
+    pub max_message_size: usize,
+    pub handshake_timeout_secs: u64,
+    pub max_inbound_connections_per_sec: Option<u32>,
+    pub rng_seed: Option<u64>,
 }
 
 impl TestSetup {
@@ -125,6 +150,10 @@ impl TestSetup {
             max_peers,
             is_bootnode,
             bootnodes,
+            max_message_size: snarkos_network::MAX_MESSAGE_SIZE,
+            handshake_timeout_secs: snarkos_network::HANDSHAKE_TIMEOUT_SECS,
+            max_inbound_connections_per_sec: None,
+            rng_seed: None,
         }
     }
 }
@@ -139,26 +168,42 @@ impl Default for TestSetup {
             max_peers: 100,
             is_bootnode: false,
             bootnodes: vec![],
+            max_message_size: snarkos_network::MAX_MESSAGE_SIZE,
+            handshake_timeout_secs: snarkos_network::HANDSHAKE_TIMEOUT_SECS,
+            max_inbound_connections_per_sec: None,
+            rng_seed: None,
         }
     }
 }
 
 pub fn test_consensus(setup: ConsensusSetup, node: Node) -> Consensus {
+    let mut consensus_parameters = TEST_CONSENSUS.clone();
+    if let Some(block_size_target) = setup.block_size_target {
+        consensus_parameters.block_size_target = block_size_target;
+    }
+    if let Some(max_future_block_time) = setup.max_future_block_time {
+        consensus_parameters.max_future_block_time = max_future_block_time;
+    }
+
     Consensus::new(
         node,
         Arc::new(FIXTURE_VK.ledger()),
         Arc::new(Mutex::new(snarkos_consensus::MemoryPool::new())),
-        Arc::new(TEST_CONSENSUS.clone()),
+        Arc::new(consensus_parameters),
         Arc::new(FIXTURE.parameters.clone()),
         setup.is_miner,
         Duration::from_secs(setup.block_sync_interval),
         Duration::from_secs(setup.tx_sync_interval),
+        setup.authorized_program_commitments,
+        setup.min_relay_fee,
+        setup.fee_sniping_depth,
+        setup.max_concurrent_transaction_verifications,
     )
 }
 
 /// Returns an `Environment` struct with given arguments
 pub fn test_environment(setup: TestSetup) -> Environment {
-    Environment::new(
+    let mut environment = Environment::new(
         setup.socket_address,
         setup.min_peers,
         setup.max_peers,
@@ -166,7 +211,15 @@ pub fn test_environment(setup: TestSetup) -> Environment {
         setup.is_bootnode,
         Duration::from_secs(setup.peer_sync_interval),
     )
-    .unwrap()
+    .unwrap();
+    environment.set_max_message_size(setup.max_message_size);
+    environment.set_handshake_timeout(Duration::from_secs(setup.handshake_timeout_secs));
+    environment.set_max_inbound_connections_per_sec(setup.max_inbound_connections_per_sec);
+    if let Some(seed) = setup.rng_seed {
+        environment.set_rng_seed(seed);
+    }
+
+    environment
 }
 
 /// Starts a node with the specified bootnodes.
@@ -201,7 +254,13 @@ impl FakeNode {
         let noise = Arc::new(Mutex::new(noise));
         let (reader, writer) = stream.into_split();
 
-        let reader = ConnReader::new(peer_addr, reader, buffer.clone(), noise.clone());
+        let reader = ConnReader::new(
+            peer_addr,
+            reader,
+            buffer.clone(),
+            noise.clone(),
+            snarkos_network::MAX_MESSAGE_SIZE,
+        );
 
         let writer = ConnWriter::new(peer_addr, writer, buffer, noise);
 
@@ -210,7 +269,7 @@ impl FakeNode {
 
     pub async fn read_payload(&mut self) -> Result<Payload, NetworkError> {
         let message = match self.reader.read_message().await {
-            Ok(msg) => {
+            Ok((msg, _)) => {
                 debug!("read a {}", msg.payload);
                 msg
             }
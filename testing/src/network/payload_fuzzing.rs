@@ -0,0 +1,155 @@
+// Copyright (C) 2019-2021 Aleo Systems Inc.
+// This file is part of the snarkOS library.
+
+// The snarkOS library is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// The snarkOS library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::consensus::{
+    BLOCK_1,
+    BLOCK_1_HEADER_HASH,
+    BLOCK_2,
+    BLOCK_2_HEADER_HASH,
+    DATA,
+    TRANSACTION_1,
+    TRANSACTION_2,
+};
+
+use snarkos_network::message::*;
+
+use snarkvm_objects::{block_header_hash::BlockHeaderHash, Transaction};
+
+use rand::{distributions::Standard, Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::{net::SocketAddr, panic};
+
+const ITERATIONS: usize = 10000;
+
+/// A corpus of valid payloads of every type, serialized; used as seeds for the mutations.
+fn seed_corpus() -> Vec<Vec<u8>> {
+    let hashes = vec![
+        BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec()),
+        BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec()),
+    ];
+    let addrs: Vec<SocketAddr> = ["127.0.0.1:4141", "[::1]:4131"]
+        .iter()
+        .map(|addr| addr.parse().unwrap())
+        .collect();
+    let compact_block = CompactBlock::new(&DATA.block_2).unwrap().serialize().unwrap();
+    let tx_id = DATA.block_2.transactions.0[0].transaction_id().unwrap();
+
+    let payloads = vec![
+        Payload::Block(BLOCK_1.to_vec()),
+        Payload::BlockTxn(vec![TRANSACTION_2.to_vec()]),
+        Payload::CompactBlock(compact_block),
+        Payload::FilterAdd(tx_id.to_vec()),
+        Payload::FilterClear,
+        Payload::FilterLoad(vec![0xa5; 64], 5, 0xdead_beef),
+        Payload::GetBlockTxn(vec![tx_id]),
+        Payload::GetBlocks(hashes.clone()),
+        Payload::GetMemoryPool,
+        Payload::GetPeers,
+        Payload::GetSync(hashes.clone()),
+        Payload::MemoryPool(vec![TRANSACTION_1.to_vec(), TRANSACTION_2.to_vec()]),
+        Payload::Peers(addrs),
+        Payload::Ping(2, 0xdead_beef),
+        Payload::Pong(0xdead_beef),
+        Payload::Sync(hashes),
+        Payload::SyncBlock(BLOCK_2.to_vec()),
+        Payload::Transaction(TRANSACTION_1.to_vec()),
+    ];
+
+    payloads.iter().map(|payload| payload.serialize().unwrap()).collect()
+}
+
+/// Feeds the given bytes to the deserializers; any result is fine, as long as there is no panic.
+fn fuzz_target(bytes: &[u8]) {
+    let result = panic::catch_unwind(|| {
+        let _ = Payload::deserialize(bytes);
+        let _ = Version::deserialize(bytes);
+    });
+
+    assert!(result.is_ok(), "deserialization panicked on input {:?}", bytes);
+}
+
+fn mutate<R: Rng>(rng: &mut R, seed: &[u8]) -> Vec<u8> {
+    let mut bytes = seed.to_vec();
+
+    for _ in 0..rng.gen_range(1..=8) {
+        match rng.gen_range(0..4) {
+            // flip a random byte
+            0 if !bytes.is_empty() => {
+                let idx = rng.gen_range(0..bytes.len());
+                bytes[idx] = rng.gen();
+            }
+            // truncate
+            1 if !bytes.is_empty() => {
+                let len = rng.gen_range(0..bytes.len());
+                bytes.truncate(len);
+            }
+            // insert a random byte
+            2 => {
+                let idx = rng.gen_range(0..=bytes.len());
+                bytes.insert(idx, rng.gen());
+            }
+            // remove a random byte
+            3 if !bytes.is_empty() => {
+                let idx = rng.gen_range(0..bytes.len());
+                bytes.remove(idx);
+            }
+            _ => {}
+        }
+    }
+
+    bytes
+}
+
+#[test]
+fn seed_corpus_deserializes() {
+    for seed in seed_corpus() {
+        let payload = Payload::deserialize(&seed).unwrap();
+        assert_eq!(payload.serialize().unwrap(), seed);
+    }
+}
+
+#[test]
+fn fuzzing_mutated_payloads() {
+    let mut rng = XorShiftRng::seed_from_u64(1231275789u64);
+    let corpus = seed_corpus();
+
+    for _ in 0..ITERATIONS {
+        let seed = &corpus[rng.gen_range(0..corpus.len())];
+        fuzz_target(&mutate(&mut rng, seed));
+    }
+}
+
+#[test]
+fn fuzzing_random_payloads() {
+    let mut rng = XorShiftRng::seed_from_u64(1231275789u64);
+
+    for _ in 0..ITERATIONS {
+        let len = rng.gen_range(0..256);
+        let bytes: Vec<u8> = (&mut rng).sample_iter(Standard).take(len).collect();
+        fuzz_target(&bytes);
+    }
+}
+
+#[test]
+fn garbage_is_rejected() {
+    assert!(Payload::deserialize(&[]).is_err());
+    assert!(Payload::deserialize(&[0xff; 64]).is_err());
+
+    // a valid payload cut short can't be read
+    for seed in seed_corpus() {
+        assert!(Payload::deserialize(&seed[..seed.len() / 2]).is_err());
+    }
+}
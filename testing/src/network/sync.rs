@@ -14,22 +14,31 @@
 // You should have received a copy of the GNU General Public License
 // along with the snarkOS library. If not, see <https://www.gnu.org/licenses/>.
 
-use tokio::time::sleep;
+use tokio::time::{sleep, timeout};
 
 use crate::{
-    consensus::{BLOCK_1, BLOCK_1_HEADER_HASH, BLOCK_2, BLOCK_2_HEADER_HASH, TRANSACTION_1, TRANSACTION_2},
-    network::{handshaken_node_and_peer, test_node, ConsensusSetup, TestSetup},
+    consensus::{BLOCK_1, BLOCK_1_HEADER_HASH, BLOCK_2, BLOCK_2_HEADER_HASH, FIXTURE, TRANSACTION_1, TRANSACTION_2},
+    network::{handshaken_node_and_peer, handshaken_peer, test_node, ConsensusSetup, TestSetup},
     wait_until,
 };
 
 use snarkos_consensus::memory_pool::Entry;
-use snarkos_network::message::*;
+use snarkos_network::{message::*, BlockOutcome, Consensus, DisconnectReason};
 
-use snarkvm_dpc::instantiated::Tx;
+use snarkvm_algorithms::CRH;
+use snarkvm_dpc::{
+    base_dpc::{
+        instantiated::{Components, InstantiatedDPC, SerialNumberNonce, Tx},
+        record_payload::RecordPayload,
+    },
+    DPCComponents,
+    Program,
+};
 use snarkvm_objects::block_header_hash::BlockHeaderHash;
 #[cfg(test)]
-use snarkvm_utilities::FromBytes;
+use snarkvm_utilities::{to_bytes, FromBytes, ToBytes};
 
+use rand::Rng;
 use std::time::Duration;
 
 #[tokio::test]
@@ -48,12 +57,12 @@ async fn block_initiator_side() {
     sleep(Duration::from_secs(1)).await;
 
     // trigger the full node to request synchronization by sending it a higher block_height than it has
-    let ping = Payload::Ping(2u32);
+    let ping = Payload::Ping(2u32, 7u64);
     peer.write_message(&ping).await;
 
-    // read the Pong
+    // read the Pong, which echoes the nonce of the Ping
     let payload = peer.read_payload().await.unwrap();
-    assert!(matches!(payload, Payload::Pong));
+    assert!(matches!(payload, Payload::Pong(7u64)));
 
     // check if a GetSync message was received
     let payload = peer.read_payload().await.unwrap();
@@ -151,6 +160,481 @@ async fn block_responder_side() {
     assert_eq!(block, block_struct_1);
 }
 
+#[tokio::test]
+async fn repeated_block_requests_are_served_from_cache() {
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // insert block into node
+    let block_struct_1 = snarkvm_objects::Block::deserialize(&BLOCK_1).unwrap();
+    node.expect_consensus()
+        .consensus_parameters()
+        .receive_block(
+            node.expect_consensus().dpc_parameters(),
+            &node.expect_consensus().storage(),
+            &mut node.expect_consensus().memory_pool().lock(),
+            &block_struct_1,
+        )
+        .unwrap();
+
+    // request the same block twice
+    let get_block = Payload::GetBlocks(vec![block_struct_1.header.get_hash()]);
+    for _ in 0..2 {
+        peer.write_message(&get_block).await;
+
+        let block = timeout(Duration::from_secs(5), async {
+            loop {
+                if let Payload::SyncBlock(block) = peer.read_payload().await.unwrap() {
+                    return block;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(snarkvm_objects::Block::deserialize(&block).unwrap(), block_struct_1);
+    }
+
+    // only the first request hit the storage
+    assert_eq!(node.expect_consensus().block_cache_hits(), 1);
+}
+
+#[tokio::test]
+async fn block_locator_is_cached_until_the_tip_changes() {
+    let node = test_node(TestSetup::default()).await;
+    let consensus = node.expect_consensus();
+
+    // the locator is computed once and reused while the tip is unchanged
+    let genesis_locator = consensus.block_locator_hashes().unwrap();
+    assert_eq!(consensus.block_locator_hashes().unwrap(), genesis_locator);
+    assert_eq!(consensus.block_locator_hits(), 1);
+
+    // insert block into node
+    let block_struct_1 = snarkvm_objects::Block::deserialize(&BLOCK_1).unwrap();
+    consensus
+        .consensus_parameters()
+        .receive_block(
+            consensus.dpc_parameters(),
+            &consensus.storage(),
+            &mut consensus.memory_pool().lock(),
+            &block_struct_1,
+        )
+        .unwrap();
+
+    // the new tip causes the locator to be refreshed
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let locator = consensus.block_locator_hashes().unwrap();
+    assert_eq!(locator.first(), Some(&block_1_header_hash));
+    assert_eq!(consensus.block_locator_hits(), 1);
+
+    assert_eq!(consensus.block_locator_hashes().unwrap(), locator);
+    assert_eq!(consensus.block_locator_hits(), 2);
+}
+
+#[tokio::test]
+async fn oversized_block_locator_is_rejected() {
+    use std::sync::atomic::Ordering;
+
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+
+    // insert block into node
+    let block_struct_1 = snarkvm_objects::Block::deserialize(&BLOCK_1).unwrap();
+    node.expect_consensus()
+        .consensus_parameters()
+        .receive_block(
+            node.expect_consensus().dpc_parameters(),
+            &node.expect_consensus().storage(),
+            &mut node.expect_consensus().memory_pool().lock(),
+            &block_struct_1,
+        )
+        .unwrap();
+
+    let quality = node
+        .peer_book
+        .read()
+        .connected_peers()
+        .values()
+        .next()
+        .unwrap()
+        .quality
+        .clone();
+
+    // send a GetSync with a locator exceeding the limit; if it was processed, the response
+    // would contain the hash of block 1
+    let oversized_locator = (0..=snarkos_network::MAX_LOCATOR_HASHES)
+        .map(|i| BlockHeaderHash::new(vec![(i % 256) as u8, (i / 256) as u8 + 1].repeat(16)))
+        .collect();
+    peer.write_message(&Payload::GetSync(oversized_locator)).await;
+    wait_until!(1, quality.failures.load(Ordering::SeqCst) >= 1);
+
+    // send a valid GetSync whose response is empty, as the node has no blocks past block 1
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    peer.write_message(&Payload::GetSync(vec![block_1_header_hash])).await;
+
+    // the only response is the one to the valid request
+    let payload = peer.read_payload().await.unwrap();
+    assert!(matches!(payload, Payload::Sync(hashes) if hashes.is_empty()));
+}
+
+#[tokio::test]
+async fn oversized_block_request_is_bounded() {
+    use std::sync::atomic::Ordering;
+
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+
+    // insert block into node
+    let block_struct_1 = snarkvm_objects::Block::deserialize(&BLOCK_1).unwrap();
+    node.expect_consensus()
+        .consensus_parameters()
+        .receive_block(
+            node.expect_consensus().dpc_parameters(),
+            &node.expect_consensus().storage(),
+            &mut node.expect_consensus().memory_pool().lock(),
+            &block_struct_1,
+        )
+        .unwrap();
+
+    let quality = node
+        .peer_book
+        .read()
+        .connected_peers()
+        .values()
+        .next()
+        .unwrap()
+        .quality
+        .clone();
+
+    // request more blocks than can be served at once
+    let max_blocks = snarkos_network::MAX_BLOCK_SYNC_COUNT as usize;
+    let oversized_request = vec![block_struct_1.header.get_hash(); max_blocks + 10];
+    peer.write_message(&Payload::GetBlocks(oversized_request)).await;
+    wait_until!(1, quality.failures.load(Ordering::SeqCst) >= 1);
+
+    // follow up with a GetSync, whose response marks the end of the served blocks
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    peer.write_message(&Payload::GetSync(vec![block_1_header_hash])).await;
+
+    let served_blocks = timeout(Duration::from_secs(10), async {
+        let mut served_blocks = 0;
+        loop {
+            match peer.read_payload().await.unwrap() {
+                Payload::SyncBlock(_) => served_blocks += 1,
+                Payload::Sync(_) => return served_blocks,
+                _ => {}
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(served_blocks, max_blocks);
+}
+
+#[tokio::test]
+async fn block_requests_are_deduplicated() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    let block_2_header_hash = BlockHeaderHash::new(BLOCK_2_HEADER_HASH.to_vec());
+
+    // the first sync requests block 1
+    let sync = Payload::Sync(vec![block_1_header_hash.clone()]);
+    peer.write_message(&sync).await;
+
+    let payload = peer.read_payload().await.unwrap();
+    let block_hashes = if let Payload::GetBlocks(block_hashes) = payload {
+        block_hashes
+    } else {
+        unreachable!();
+    };
+    assert_eq!(block_hashes, vec![block_1_header_hash.clone()]);
+
+    // an overlapping sync only requests the block that isn't already in flight
+    let sync = Payload::Sync(vec![block_1_header_hash.clone(), block_2_header_hash.clone()]);
+    peer.write_message(&sync).await;
+
+    let payload = peer.read_payload().await.unwrap();
+    let block_hashes = if let Payload::GetBlocks(block_hashes) = payload {
+        block_hashes
+    } else {
+        unreachable!();
+    };
+    assert_eq!(block_hashes, vec![block_2_header_hash.clone()]);
+
+    // the arrival of a block clears its in-flight request
+    assert!(node.expect_consensus().is_block_requested(&block_1_header_hash));
+
+    let block_1 = Payload::SyncBlock(BLOCK_1.to_vec());
+    peer.write_message(&block_1).await;
+
+    wait_until!(1, !node.expect_consensus().is_block_requested(&block_1_header_hash));
+    assert!(node.expect_consensus().is_block_requested(&block_2_header_hash));
+}
+
+#[tokio::test]
+async fn sync_efficiency_reflects_new_blocks() {
+    // handshake between a fake node and a full node
+    let (node, peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    let consensus = node.expect_consensus();
+
+    // start a sync session
+    consensus.register_block_sync_attempt();
+    assert_eq!(consensus.sync_efficiency(), None);
+
+    // the sync node sends the first block twice, the second time it's already known
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+    peer.write_message(&Payload::SyncBlock(BLOCK_2.to_vec())).await;
+
+    wait_until!(1, consensus.sync_block_counts() == (2, 1));
+    assert!((consensus.sync_efficiency().unwrap() - 2.0 / 3.0).abs() < f64::EPSILON);
+
+    // a new sync session starts from scratch
+    consensus.register_block_sync_attempt();
+    assert_eq!(consensus.sync_block_counts(), (0, 0));
+}
+
+#[tokio::test]
+async fn compact_block_reconstructed_from_memory_pool() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    let block_1 = snarkvm_objects::Block::<Tx>::deserialize(&BLOCK_1).unwrap();
+    let block_1_header_hash = block_1.header.get_hash();
+
+    // share the block's non-coinbase transactions with the node's memory pool
+    {
+        let mut memory_pool = node.expect_consensus().memory_pool().lock();
+        let storage = node.expect_consensus().storage();
+
+        for transaction in block_1.transactions.iter() {
+            if !transaction.value_balance.is_negative() {
+                let entry = Entry {
+                    size_in_bytes: to_bytes![transaction].unwrap().len(),
+                    transaction: transaction.clone(),
+                };
+                memory_pool.insert(&storage, entry).unwrap();
+            }
+        }
+    }
+
+    // send the block in its compact form
+    let compact_block = CompactBlock::new(&block_1).unwrap();
+    let payload = Payload::CompactBlock(compact_block.serialize().unwrap());
+    peer.write_message(&payload).await;
+
+    // the block is reconstructed without fetching any transaction bodies
+    wait_until!(
+        1,
+        node.expect_consensus()
+            .storage()
+            .block_hash_exists(&block_1_header_hash)
+    );
+    assert!(!node.expect_consensus().is_compact_block_pending(&block_1_header_hash));
+}
+
+#[tokio::test]
+async fn compact_block_missing_transactions_are_fetched() {
+    // handshake between a fake node and a full node
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    let block_1 = snarkvm_objects::Block::<Tx>::deserialize(&BLOCK_1).unwrap();
+    let block_1_header_hash = block_1.header.get_hash();
+
+    // withhold all the transactions, so that none of them can be found in the node's memory pool
+    let mut compact_block = CompactBlock::new(&block_1).unwrap();
+    compact_block.prefilled_transactions.clear();
+    let payload = Payload::CompactBlock(compact_block.serialize().unwrap());
+    peer.write_message(&payload).await;
+
+    // the node requests the missing transactions
+    let payload = peer.read_payload().await.unwrap();
+    let transaction_ids = if let Payload::GetBlockTxn(transaction_ids) = payload {
+        transaction_ids
+    } else {
+        unreachable!();
+    };
+    assert_eq!(transaction_ids, compact_block.transaction_ids);
+    assert!(node.expect_consensus().is_compact_block_pending(&block_1_header_hash));
+
+    // respond with the missing transactions
+    let transactions = block_1
+        .transactions
+        .iter()
+        .map(|transaction| to_bytes![transaction].unwrap())
+        .collect();
+    peer.write_message(&Payload::BlockTxn(transactions)).await;
+
+    // check the block has been added to the node's chain
+    wait_until!(
+        1,
+        node.expect_consensus()
+            .storage()
+            .block_hash_exists(&block_1_header_hash)
+    );
+    assert!(!node.expect_consensus().is_compact_block_pending(&block_1_header_hash));
+}
+
+#[tokio::test]
+async fn duplicate_blocks_are_propagated_once() {
+    let node = test_node(TestSetup::default()).await;
+    let node_listener = node.local_address().unwrap();
+
+    // connect three fake peers to the node
+    let mut peers = vec![
+        handshaken_peer(node_listener).await,
+        handshaken_peer(node_listener).await,
+        handshaken_peer(node_listener).await,
+    ];
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 3);
+
+    // all the peers send the same block in quick succession
+    for peer in &peers {
+        peer.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+    }
+
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    wait_until!(
+        1,
+        node.expect_consensus()
+            .storage()
+            .block_hash_exists(&block_1_header_hash)
+    );
+
+    // count the propagated blocks received by the peers
+    let mut propagated = 0;
+    for peer in peers.iter_mut() {
+        while let Ok(Ok(payload)) = timeout(Duration::from_millis(500), peer.read_payload()).await {
+            if matches!(payload, Payload::Block(..) | Payload::CompactBlock(..)) {
+                propagated += 1;
+            }
+        }
+    }
+
+    // the block is only propagated once, to the two peers other than its first sender
+    assert_eq!(propagated, 2);
+}
+
+#[tokio::test]
+async fn recently_received_blocks_are_not_processed_again() {
+    let node = test_node(TestSetup::default()).await;
+    let node_listener = node.local_address().unwrap();
+
+    let mut first_sender = handshaken_peer(node_listener).await;
+    let second_sender = handshaken_peer(node_listener).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+
+    // the first peer delivers a new block, which is processed and propagated to the second one
+    first_sender.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+
+    let consensus = node.expect_consensus();
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    wait_until!(1, consensus.storage().block_hash_exists(&block_1_header_hash));
+    assert_eq!(consensus.duplicate_blocks(), 0);
+
+    // the second peer delivers it too, which is a no-op
+    second_sender.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+    wait_until!(1, consensus.duplicate_blocks() == 1);
+
+    // the block isn't propagated back to the first peer
+    while let Ok(Ok(payload)) = timeout(Duration::from_millis(500), first_sender.read_payload()).await {
+        assert!(!matches!(payload, Payload::Block(..) | Payload::CompactBlock(..)));
+    }
+    assert_eq!(node.peer_book.read().number_of_connected_peers(), 2);
+}
+
+#[tokio::test]
+async fn gossiped_blocks_are_propagated_during_sync() {
+    let node = test_node(TestSetup::default()).await;
+    let node_listener = node.local_address().unwrap();
+
+    let (sender, mut receiver) = (
+        handshaken_peer(node_listener).await,
+        handshaken_peer(node_listener).await,
+    );
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+
+    // the node is syncing blocks with some other peer
+    node.expect_consensus().register_block_sync_attempt();
+
+    // a gossiped block is propagated regardless
+    sender.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+    let propagated = timeout(Duration::from_secs(5), async {
+        loop {
+            if matches!(
+                receiver.read_payload().await.unwrap(),
+                Payload::Block(..) | Payload::CompactBlock(..)
+            ) {
+                return true;
+            }
+        }
+    })
+    .await;
+    assert!(propagated.is_ok());
+}
+
+#[tokio::test]
+async fn sync_blocks_are_never_propagated() {
+    let node = test_node(TestSetup::default()).await;
+    let node_listener = node.local_address().unwrap();
+
+    let (sender, mut receiver) = (
+        handshaken_peer(node_listener).await,
+        handshaken_peer(node_listener).await,
+    );
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+
+    // a block received as part of a sync is processed...
+    sender.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+    let block_1_header_hash = BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec());
+    wait_until!(
+        1,
+        node.expect_consensus()
+            .storage()
+            .block_hash_exists(&block_1_header_hash)
+    );
+
+    // ...but not propagated
+    while let Ok(Ok(payload)) = timeout(Duration::from_millis(500), receiver.read_payload()).await {
+        assert!(!matches!(payload, Payload::Block(..) | Payload::CompactBlock(..)));
+    }
+}
+
+#[tokio::test]
+async fn block_propagation_reaches_all_peers() {
+    let node = test_node(TestSetup::default()).await;
+    let node_listener = node.local_address().unwrap();
+
+    // connect three fake peers to the node
+    let mut peers = vec![
+        handshaken_peer(node_listener).await,
+        handshaken_peer(node_listener).await,
+        handshaken_peer(node_listener).await,
+    ];
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 3);
+
+    // propagate a block as if it was mined by the node
+    let connected_peers = node.peer_book.read().connected_peers().clone();
+    let peers_reached = node
+        .expect_consensus()
+        .propagate_block(BLOCK_1.to_vec(), node_listener, &connected_peers)
+        .await;
+    assert_eq!(peers_reached, 3);
+
+    // all the peers received the block
+    let mut received = 0;
+    for peer in peers.iter_mut() {
+        while let Ok(Ok(payload)) = timeout(Duration::from_millis(500), peer.read_payload()).await {
+            if matches!(payload, Payload::CompactBlock(..)) {
+                received += 1;
+                break;
+            }
+        }
+    }
+    assert_eq!(received, 3);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[ignore]
 async fn block_propagation() {
@@ -162,13 +646,33 @@ async fn block_propagation() {
         ..Default::default()
     };
 
-    let (_node, mut peer) = handshaken_node_and_peer(setup).await;
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
 
     let payload = peer.read_payload().await.unwrap();
-    assert!(matches!(payload, Payload::Block(..)));
+    assert!(matches!(payload, Payload::CompactBlock(..)));
 
-    // TODO: shutdown the miner task, currently there is no good way to do this. This test will
-    // currently hang after the assertion.
+    // stop the miner task so that the runtime can shut down
+    node.expect_consensus().stop_mining();
+}
+
+#[tokio::test]
+async fn miner_task_exits_once_stopped() {
+    use snarkos::miner::MinerInstance;
+
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup::default()),
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+
+    let miner_address = crate::consensus::FIXTURE.test_accounts[0].address.clone();
+    let miner = MinerInstance::new(miner_address, node.environment.clone(), node.clone()).spawn();
+
+    node.expect_consensus().stop_mining();
+    assert!(node.expect_consensus().is_mining_stopped());
+
+    // the task exits at the latest after mining the block it might have already started on
+    timeout(Duration::from_secs(120), miner).await.unwrap().unwrap();
 }
 
 #[tokio::test]
@@ -295,6 +799,397 @@ async fn transaction_responder_side() {
     assert!(txs.contains(&TRANSACTION_2.to_vec()));
 }
 
+#[tokio::test]
+async fn duplicate_transactions_skip_verification() {
+    let (node, peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+
+    // the transaction is already in the memory pool
+    let entry = Entry {
+        size_in_bytes: TRANSACTION_1.len(),
+        transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+    };
+    let consensus = node.expect_consensus();
+    consensus
+        .memory_pool()
+        .lock()
+        .insert(&consensus.storage(), entry)
+        .unwrap()
+        .unwrap();
+
+    // every further arrival of it is skipped right away
+    for i in 1..=3 {
+        peer.write_message(&Payload::Transaction(TRANSACTION_1.to_vec())).await;
+        wait_until!(5, consensus.duplicate_transactions() == i);
+    }
+    assert_eq!(consensus.memory_pool().lock().transactions.len(), 1);
+}
+
+/// Creates a valid transaction that spends dummy records and creates zero-value ones, so that any number of
+/// them can land in the memory pool without conflicting with each other.
+fn zero_value_transaction<R: Rng>(consensus: &Consensus, rng: &mut R) -> Vec<u8> {
+    let parameters = consensus.dpc_parameters();
+    let owner = &FIXTURE.test_accounts[0];
+    let program_id = FIXTURE.program.into_compact_repr();
+
+    let old_records = (0..Components::NUM_INPUT_RECORDS)
+        .map(|_| {
+            let sn_nonce =
+                SerialNumberNonce::hash(&parameters.system_parameters.serial_number_nonce, &rng.gen::<[u8; 4]>())
+                    .unwrap();
+
+            InstantiatedDPC::generate_record(
+                &parameters.system_parameters,
+                sn_nonce,
+                owner.address.clone(),
+                true, // The input record is dummy
+                0,
+                RecordPayload::default(),
+                program_id.clone(),
+                program_id.clone(),
+                rng,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let (_, transaction) = consensus
+        .consensus_parameters()
+        .create_transaction(
+            parameters,
+            old_records,
+            vec![owner.private_key.clone(); Components::NUM_INPUT_RECORDS],
+            vec![owner.address.clone(); Components::NUM_OUTPUT_RECORDS],
+            vec![program_id.clone(); Components::NUM_OUTPUT_RECORDS],
+            vec![program_id; Components::NUM_OUTPUT_RECORDS],
+            vec![true; Components::NUM_OUTPUT_RECORDS],
+            vec![0; Components::NUM_OUTPUT_RECORDS],
+            vec![RecordPayload::default(); Components::NUM_OUTPUT_RECORDS],
+            [0u8; 32],
+            consensus.storage(),
+            rng,
+        )
+        .unwrap();
+
+    to_bytes![transaction].unwrap()
+}
+
+#[tokio::test]
+async fn concurrently_received_transactions_are_processed() {
+    use std::collections::HashSet;
+
+    const MAX_CONCURRENT_VERIFICATIONS: usize = 2;
+
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            max_concurrent_transaction_verifications: MAX_CONCURRENT_VERIFICATIONS,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let node = test_node(setup).await;
+    let node_listener = node.local_address().unwrap();
+
+    let mut peers = vec![];
+    for _ in 0..4 {
+        peers.push(handshaken_peer(node_listener).await);
+    }
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 4);
+
+    // more distinct valid transactions than can be verified at once
+    let consensus = node.expect_consensus();
+    let mut rng = FIXTURE.rng.clone();
+    let transactions = (0..MAX_CONCURRENT_VERIFICATIONS * 2 + 1)
+        .map(|_| zero_value_transaction(consensus, &mut rng))
+        .collect::<Vec<_>>();
+    let valid_ids = transactions
+        .iter()
+        .map(|transaction| Tx::read(&transaction[..]).unwrap().transaction_id().unwrap().to_vec())
+        .collect::<HashSet<_>>();
+    assert_eq!(valid_ids.len(), transactions.len());
+
+    // every peer sends every transaction in a burst
+    for peer in &peers {
+        for transaction in &transactions {
+            peer.write_message(&Payload::Transaction(transaction.clone())).await;
+        }
+    }
+
+    // all the transactions land in the memory pool, once
+    let pool_ids = || {
+        consensus
+            .memory_pool()
+            .lock()
+            .transactions
+            .keys()
+            .cloned()
+            .collect::<HashSet<_>>()
+    };
+    wait_until!(30, pool_ids() == valid_ids);
+    sleep(Duration::from_millis(500)).await;
+    assert_eq!(pool_ids(), valid_ids);
+}
+
+#[tokio::test]
+async fn memory_pool_responses_are_bounded() {
+    use snarkos_network::{MAX_MEMORY_POOL_RESPONSE_SIZE, MAX_MEMORY_POOL_RESPONSE_TRANSACTIONS};
+
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // fill the memory pool with more transactions than fit in a single response
+    let pool_size = MAX_MEMORY_POOL_RESPONSE_TRANSACTIONS + 10;
+    {
+        let mut memory_pool = node.expect_consensus().memory_pool().lock();
+        for i in 0..pool_size {
+            let entry = Entry {
+                size_in_bytes: TRANSACTION_1.len(),
+                transaction: Tx::read(&TRANSACTION_1[..]).unwrap(),
+            };
+            memory_pool
+                .transactions
+                .insert((i as u64).to_le_bytes().to_vec(), entry);
+        }
+    }
+
+    // the pool is sent in bounded batches over subsequent requests
+    let mut received = 0;
+    while received < pool_size {
+        peer.write_message(&Payload::GetMemoryPool).await;
+
+        let txs = timeout(Duration::from_secs(5), async {
+            loop {
+                if let Payload::MemoryPool(txs) = peer.read_payload().await.unwrap() {
+                    return txs;
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert!(!txs.is_empty());
+        assert!(txs.len() <= MAX_MEMORY_POOL_RESPONSE_TRANSACTIONS);
+        assert!(txs.iter().map(|tx| tx.len()).sum::<usize>() <= MAX_MEMORY_POOL_RESPONSE_SIZE);
+        received += txs.len();
+    }
+    assert_eq!(received, pool_size);
+
+    // once the whole pool was sent, there's nothing left to send
+    peer.write_message(&Payload::GetMemoryPool).await;
+    let payload = timeout(Duration::from_millis(500), async {
+        loop {
+            if let Payload::MemoryPool(txs) = peer.read_payload().await.unwrap() {
+                return txs;
+            }
+        }
+    })
+    .await;
+    assert!(payload.is_err());
+}
+
+#[tokio::test]
+async fn oversized_message_drops_peer() {
+    // handshake between a fake node and a full node with a small inbound message limit
+    let setup = TestSetup {
+        max_message_size: 1024,
+        ..Default::default()
+    };
+    let (node, peer) = handshaken_node_and_peer(setup).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+
+    // send a MemoryPool message exceeding the limit
+    let memory_pool = Payload::MemoryPool(vec![TRANSACTION_1.to_vec(); 10]);
+    peer.write_message(&memory_pool).await;
+
+    // check the peer was disconnected
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 0);
+
+    // check the disconnect was recorded as a protocol violation
+    let peer_book = node.peer_book.read();
+    let peer_info = peer_book.disconnected_peers().values().next().unwrap();
+    assert_eq!(
+        peer_info.last_disconnect_reason(),
+        Some(DisconnectReason::ProtocolViolation)
+    );
+}
+
+#[tokio::test]
+async fn orphan_parent_is_requested_from_sender() {
+    let (_node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+
+    // send block 2 to a node that doesn't have its parent
+    peer.write_message(&Payload::Block(BLOCK_2.to_vec())).await;
+
+    // check that the missing parent is requested right away, skipping any unrelated messages
+    let block_hashes = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Payload::GetBlocks(block_hashes) = peer.read_payload().await.unwrap() {
+                return block_hashes;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(block_hashes, vec![BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec())]);
+}
+
+#[tokio::test]
+async fn orphan_parent_reply_is_not_a_sync_batch() {
+    let (node, mut peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+    let peer_addr = *node.peer_book.read().connected_peers().keys().next().unwrap();
+
+    // send block 2 to a node that doesn't have its parent
+    peer.write_message(&Payload::Block(BLOCK_2.to_vec())).await;
+
+    // wait for the request for the missing parent
+    timeout(Duration::from_secs(5), async {
+        loop {
+            if let Payload::GetBlocks(_) = peer.read_payload().await.unwrap() {
+                return;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    // answer it the way a requested block is answered
+    peer.write_message(&Payload::SyncBlock(BLOCK_1.to_vec())).await;
+    wait_until!(5, node.expect_consensus().current_block_height() >= 1);
+
+    // the reply doesn't leave the peer looking like it's in the middle of a sync batch
+    assert!(!node.peer_book.read().is_syncing_blocks(peer_addr));
+    assert_eq!(node.expect_consensus().sync_block_counts(), (0, 0));
+}
+
+#[tokio::test]
+async fn invalid_blocks_drop_peer() {
+    use snarkvm_objects::Block;
+    use std::sync::atomic::Ordering;
+
+    let (node, peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+
+    let quality = node
+        .peer_book
+        .read()
+        .connected_peers()
+        .values()
+        .next()
+        .unwrap()
+        .quality
+        .clone();
+
+    // a block with a timestamp preceding its parent's fails verification
+    let mut invalid_block = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+    invalid_block.header.time = 0;
+    let invalid_block = Payload::Block(to_bytes![invalid_block].unwrap());
+
+    // the peer is penalized for every invalid block...
+    for i in 1..snarkos_network::MAX_INVALID_BLOCKS {
+        peer.write_message(&invalid_block).await;
+        wait_until!(5, quality.invalid_blocks.load(Ordering::SeqCst) == i);
+        assert!(quality.failures.load(Ordering::SeqCst) >= i);
+        assert_eq!(node.peer_book.read().number_of_connected_peers(), 1);
+    }
+
+    // ...and dropped once it reaches the threshold
+    peer.write_message(&invalid_block).await;
+    wait_until!(5, node.peer_book.read().number_of_connected_peers() == 0);
+    assert_eq!(
+        quality.invalid_blocks.load(Ordering::SeqCst),
+        snarkos_network::MAX_INVALID_BLOCKS
+    );
+}
+
+#[tokio::test]
+async fn received_block_outcomes_are_counted() {
+    use snarkvm_objects::Block;
+
+    let (node, peer) = handshaken_node_and_peer(TestSetup::default()).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+    let consensus = node.expect_consensus();
+    let counts = || {
+        (
+            consensus.received_blocks(BlockOutcome::Connected),
+            consensus.received_blocks(BlockOutcome::Orphan),
+            consensus.received_blocks(BlockOutcome::Invalid),
+        )
+    };
+
+    // block 2 arrives before its parent, so it's an orphan
+    peer.write_message(&Payload::Block(BLOCK_2.to_vec())).await;
+    wait_until!(5, counts() == (0, 1, 0));
+
+    // block 1 connects to the genesis block
+    peer.write_message(&Payload::Block(BLOCK_1.to_vec())).await;
+    wait_until!(5, counts() == (1, 1, 0));
+
+    // a block with a timestamp preceding its parent's fails validation
+    let mut invalid_block = Block::<Tx>::read(&BLOCK_1[..]).unwrap();
+    invalid_block.header.time = 0;
+    peer.write_message(&Payload::Block(to_bytes![invalid_block].unwrap()))
+        .await;
+    wait_until!(5, counts() == (1, 1, 1));
+}
+
+#[tokio::test]
+async fn far_future_blocks_are_rejected() {
+    use snarkvm_objects::Block;
+    use std::{
+        sync::atomic::Ordering,
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup {
+            max_future_block_time: Some(60),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (node, mut peer) = handshaken_node_and_peer(setup).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+
+    let quality = node
+        .peer_book
+        .read()
+        .connected_peers()
+        .values()
+        .next()
+        .unwrap()
+        .quality
+        .clone();
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    // block 2 is an orphan here, so only its timestamp can be checked right away
+    let block_with_time = |time: i64| {
+        let mut block = Block::<Tx>::read(&BLOCK_2[..]).unwrap();
+        block.header.time = time;
+        Payload::Block(to_bytes![block].unwrap())
+    };
+
+    // a block from far in the future is rejected and its sender penalized...
+    peer.write_message(&block_with_time(now + 3600)).await;
+    wait_until!(5, quality.invalid_blocks.load(Ordering::SeqCst) == 1);
+
+    // ...while one within the allowed skew is accepted as an orphan, and its parent requested
+    peer.write_message(&block_with_time(now + 30)).await;
+    let block_hashes = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Payload::GetBlocks(block_hashes) = peer.read_payload().await.unwrap() {
+                return block_hashes;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(block_hashes, vec![BlockHeaderHash::new(BLOCK_1_HEADER_HASH.to_vec())]);
+    assert_eq!(quality.invalid_blocks.load(Ordering::SeqCst), 1);
+}
+
 #[tokio::test]
 async fn transaction_two_node() {
     use snarkos_consensus::memory_pool::Entry;
@@ -334,3 +1229,103 @@ async fn transaction_two_node() {
     // check transaction is present in bob's memory pool
     wait_until!(5, node_bob.expect_consensus().memory_pool().lock().contains(&entry));
 }
+
+#[tokio::test]
+async fn bloom_filter_suppresses_non_matching_transactions() {
+    use snarkvm_objects::Transaction;
+
+    let node = test_node(TestSetup::default()).await;
+    let node_listener = node.local_address().unwrap();
+
+    let mut filtered_peer = handshaken_peer(node_listener).await;
+    let mut unfiltered_peer = handshaken_peer(node_listener).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+
+    // one of the peers is only interested in the second transaction
+    let tx_2_id = Tx::read(&TRANSACTION_2[..]).unwrap().transaction_id().unwrap();
+    filtered_peer
+        .write_message(&Payload::FilterLoad(vec![0u8; 64], 5, 0xdead_beef))
+        .await;
+    filtered_peer.write_message(&Payload::FilterAdd(tx_2_id.to_vec())).await;
+    wait_until!(
+        1,
+        node.peer_book
+            .read()
+            .connected_peers()
+            .values()
+            .any(|peer| matches!(&*peer.quality.bloom_filter.read(), Some(filter) if filter.contains(&tx_2_id)))
+    );
+
+    // relay both transactions as if they were received by the node
+    let connected_peers = node.peer_book.read().connected_peers().clone();
+    for transaction in vec![TRANSACTION_1.to_vec(), TRANSACTION_2.to_vec()] {
+        node.expect_consensus()
+            .propagate_transaction(transaction, node_listener, &connected_peers)
+            .await
+            .unwrap();
+    }
+
+    async fn read_transactions(peer: &mut crate::network::FakeNode) -> Vec<Vec<u8>> {
+        let mut transactions = vec![];
+        while let Ok(Ok(payload)) = timeout(Duration::from_millis(500), peer.read_payload()).await {
+            if let Payload::Transaction(transaction) = payload {
+                transactions.push(transaction);
+            }
+        }
+        transactions
+    }
+
+    // the peer without a filter receives both transactions, while the filtered one only gets the matching one
+    assert_eq!(read_transactions(&mut unfiltered_peer).await, vec![
+        TRANSACTION_1.to_vec(),
+        TRANSACTION_2.to_vec()
+    ]);
+    assert_eq!(
+        read_transactions(&mut filtered_peer).await,
+        vec![TRANSACTION_2.to_vec()]
+    );
+}
+
+#[tokio::test]
+async fn sync_node_is_kept_until_it_disconnects_or_degrades() {
+    let setup = TestSetup {
+        consensus_setup: Some(ConsensusSetup::default()),
+        ..Default::default()
+    };
+    let (node, first_peer) = handshaken_node_and_peer(setup).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 1);
+    let consensus = node.expect_consensus();
+
+    // a peer is only selected once it's known to be ahead
+    let first_addr = *node.peer_book.read().connected_peers().keys().next().unwrap();
+    assert_eq!(consensus.sync_node(), None);
+    node.peer_book.read().register_advertised_height(first_addr, 10);
+    assert_eq!(consensus.sync_node(), Some(first_addr));
+
+    // the selection is kept even though another peer becomes available
+    let second_peer = handshaken_peer(node.local_address().unwrap()).await;
+    wait_until!(1, node.peer_book.read().number_of_connected_peers() == 2);
+    let second_addr = *node
+        .peer_book
+        .read()
+        .connected_peers()
+        .keys()
+        .find(|addr| **addr != first_addr)
+        .unwrap();
+    node.peer_book.read().register_advertised_height(second_addr, 10);
+    for _ in 0..10 {
+        assert_eq!(consensus.sync_node(), Some(first_addr));
+    }
+
+    // once the sync node degrades, the other peer takes its place
+    node.peer_book.read().register_failure(first_addr);
+    for _ in 0..10 {
+        assert_eq!(consensus.sync_node(), Some(second_addr));
+    }
+
+    // once the sync node disconnects, the remaining peer takes its place
+    drop(second_peer);
+    wait_until!(5, node.peer_book.read().number_of_connected_peers() == 1);
+    assert_eq!(consensus.sync_node(), Some(first_addr));
+    drop(first_peer);
+}